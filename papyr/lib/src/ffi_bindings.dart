@@ -33,14 +33,68 @@ class PapyrScanEventType {
   static const int pageData = 1;
   static const int pageComplete = 2;
   static const int jobComplete = 3;
+  static const int progress = 4; // bytes, expected and percent are set
+  static const int status = 5; // code holds a PapyrScanStatus
+  static const int warning = 6; // code holds a PapyrWarningCode, message the text
+  static const int cancelled = 7; // last event of a cancelled session
+  static const int error = 8; // code holds a PapyrErrorCode, message the text
+}
+
+// Session states reported by status events
+class PapyrScanStatus {
+  static const int waitingForDevice = 0;
+  static const int warmingUp = 1;
+  static const int scanning = 2;
+}
+
+// Recoverable problems reported by warning events
+class PapyrWarningCode {
+  static const int configAdjusted = 0;
+  static const int optionNotApplied = 1;
+  static const int cleanupFailed = 2;
+  static const int backendFallback = 3;
+}
+
+// How a scanner is attached
+class PapyrConnectionKind {
+  static const int unknown = 0;
+  static const int usb = 1;
+  static const int network = 2;
+  static const int virtual = 3;
 }
 
 // Native structures
+final class PapyrDeviceRoute extends Struct {
+  external Pointer<Utf8> id;
+  @Int32()
+  external int backend;
+}
+
+// Must match PapyrScannerInfo in papyr_core.h field for field: scanner
+// lists are stepped through by this struct's size
 final class PapyrScannerInfo extends Struct {
   external Pointer<Utf8> id;
   external Pointer<Utf8> name;
   @Int32()
   external int backend;
+  // Details below are nullptr when the backend doesn't know them
+  external Pointer<Utf8> manufacturer;
+  external Pointer<Utf8> model;
+  external Pointer<Utf8> serial;
+  @Int32()
+  external int connection; // PapyrConnectionKind
+  external Pointer<Utf8> address;
+  external Pointer<Utf8> deviceType;
+  external Pointer<Utf8> location;
+  external Pointer<Utf8> adminUrl;
+  external Pointer<Utf8> iconUrl;
+  external Pointer<Uint8> icon;
+  @Size()
+  external int iconLen;
+  // Every backend reaching this scanner, most preferred first
+  external Pointer<PapyrDeviceRoute> routes;
+  @Size()
+  external int routeCount;
 }
 
 final class PapyrScannerInfoList extends Struct {
@@ -63,6 +117,7 @@ final class PapyrCapabilities extends Struct {
   external int supportsDuplex;
 }
 
+// The original scan config, for papyr_start_scan
 final class PapyrScanConfig extends Struct {
   @Int32()
   external int source;
@@ -78,12 +133,54 @@ final class PapyrScanConfig extends Struct {
   external int pageHeightMm;
 }
 
+// The scan config for papyr_start_scan_v2; must match PapyrScanConfigV2
+// in papyr_core.h field for field
+final class PapyrScanConfigV2 extends Struct {
+  @Int32()
+  external int source;
+  @Int32()
+  external int duplex;
+  @Int32()
+  external int dpi;
+  @Int32()
+  external int colorMode;
+  @Double()
+  external double pageWidthMm;
+  @Double()
+  external double pageHeightMm;
+  @Int32()
+  external int dpiY; // 0 = same as dpi
+  @Int32()
+  external int bitDepth; // 0 = mode default
+  @Int32()
+  external int maxPages; // 0 = no limit
+  @Int32()
+  external int hasArea; // 1 to scan only the area below
+  @Double()
+  external double areaXMm;
+  @Double()
+  external double areaYMm;
+  @Double()
+  external double areaWidthMm;
+  @Double()
+  external double areaHeightMm;
+}
+
 final class PapyrScanEvent extends Struct {
   @Int32()
   external int eventType;
   external Pointer<Void> data;
   @Size()
   external int dataSize;
+  @Uint64()
+  external int bytes; // progress: bytes of the page so far
+  @Int64()
+  external int expected; // progress: page size in bytes, -1 if unknown
+  @Float()
+  external double percent; // progress: 0-100, -1 if unknown
+  @Int32()
+  external int code; // status, warning or error code
+  external Pointer<Utf8> message; // warning or error text, nullptr otherwise
 }
 
 // Function signatures
@@ -102,6 +199,11 @@ typedef PapyrStartScanNative = Int32 Function(
     Pointer<Utf8>, Pointer<PapyrScanConfig>);
 typedef PapyrStartScan = int Function(Pointer<Utf8>, Pointer<PapyrScanConfig>);
 
+typedef PapyrStartScanV2Native = Int32 Function(
+    Pointer<Utf8>, Pointer<PapyrScanConfigV2>, Int32);
+typedef PapyrStartScanV2 = int Function(
+    Pointer<Utf8>, Pointer<PapyrScanConfigV2>, int);
+
 typedef PapyrNextScanEventNative = Pointer<PapyrScanEvent> Function(Int32);
 typedef PapyrNextScanEvent = Pointer<PapyrScanEvent> Function(int);
 
@@ -128,6 +230,7 @@ class PapyrCoreFFI {
   late final PapyrListScanners papyrListScanners;
   late final PapyrGetCapabilities papyrGetCapabilities;
   late final PapyrStartScan papyrStartScan;
+  late final PapyrStartScanV2 papyrStartScanV2;
   late final PapyrNextScanEvent papyrNextScanEvent;
   late final PapyrFreeScannerList papyrFreeScannerList;
   late final PapyrFreeCapabilities papyrFreeCapabilities;
@@ -187,6 +290,10 @@ class PapyrCoreFFI {
         .lookup<NativeFunction<PapyrStartScanNative>>('papyr_start_scan')
         .asFunction();
     
+    papyrStartScanV2 = _lib
+        .lookup<NativeFunction<PapyrStartScanV2Native>>('papyr_start_scan_v2')
+        .asFunction();
+    
     papyrNextScanEvent = _lib
         .lookup<NativeFunction<PapyrNextScanEventNative>>('papyr_next_scan_event')
         .asFunction();
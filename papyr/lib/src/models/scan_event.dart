@@ -27,6 +27,13 @@ class ScanEvent with _$ScanEvent {
 
   /// Scan was cancelled.
   const factory ScanEvent.cancelled() = ScanCancelled;
+
+  /// A recoverable problem, such as a setting adjusted to fit the scanner;
+  /// the scan goes on.
+  const factory ScanEvent.warning({
+    required String message,
+    required int code,
+  }) = ScanWarning;
 }
 
 /// Result of a completed scan operation.
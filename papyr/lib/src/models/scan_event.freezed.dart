@@ -24,6 +24,7 @@ mixin _$ScanEvent {
     required TResult Function(ScanResult result) completed,
     required TResult Function(String message, int code) error,
     required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
   }) => throw _privateConstructorUsedError;
   @optionalTypeArgs
   TResult? whenOrNull<TResult extends Object?>({
@@ -32,6 +33,7 @@ mixin _$ScanEvent {
     TResult? Function(ScanResult result)? completed,
     TResult? Function(String message, int code)? error,
     TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
   }) => throw _privateConstructorUsedError;
   @optionalTypeArgs
   TResult maybeWhen<TResult extends Object?>({
@@ -40,6 +42,7 @@ mixin _$ScanEvent {
     TResult Function(ScanResult result)? completed,
     TResult Function(String message, int code)? error,
     TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
     required TResult orElse(),
   }) => throw _privateConstructorUsedError;
   @optionalTypeArgs
@@ -49,6 +52,7 @@ mixin _$ScanEvent {
     required TResult Function(ScanCompleted value) completed,
     required TResult Function(ScanError value) error,
     required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
   }) => throw _privateConstructorUsedError;
   @optionalTypeArgs
   TResult? mapOrNull<TResult extends Object?>({
@@ -57,6 +61,7 @@ mixin _$ScanEvent {
     TResult? Function(ScanCompleted value)? completed,
     TResult? Function(ScanError value)? error,
     TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
   }) => throw _privateConstructorUsedError;
   @optionalTypeArgs
   TResult maybeMap<TResult extends Object?>({
@@ -65,6 +70,7 @@ mixin _$ScanEvent {
     TResult Function(ScanCompleted value)? completed,
     TResult Function(ScanError value)? error,
     TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
     required TResult orElse(),
   }) => throw _privateConstructorUsedError;
 }
@@ -137,6 +143,7 @@ class _$ScanStartedImpl implements ScanStarted {
     required TResult Function(ScanResult result) completed,
     required TResult Function(String message, int code) error,
     required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
   }) {
     return started();
   }
@@ -149,6 +156,7 @@ class _$ScanStartedImpl implements ScanStarted {
     TResult? Function(ScanResult result)? completed,
     TResult? Function(String message, int code)? error,
     TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
   }) {
     return started?.call();
   }
@@ -161,6 +169,7 @@ class _$ScanStartedImpl implements ScanStarted {
     TResult Function(ScanResult result)? completed,
     TResult Function(String message, int code)? error,
     TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
     required TResult orElse(),
   }) {
     if (started != null) {
@@ -177,6 +186,7 @@ class _$ScanStartedImpl implements ScanStarted {
     required TResult Function(ScanCompleted value) completed,
     required TResult Function(ScanError value) error,
     required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
   }) {
     return started(this);
   }
@@ -189,6 +199,7 @@ class _$ScanStartedImpl implements ScanStarted {
     TResult? Function(ScanCompleted value)? completed,
     TResult? Function(ScanError value)? error,
     TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
   }) {
     return started?.call(this);
   }
@@ -201,6 +212,7 @@ class _$ScanStartedImpl implements ScanStarted {
     TResult Function(ScanCompleted value)? completed,
     TResult Function(ScanError value)? error,
     TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
     required TResult orElse(),
   }) {
     if (started != null) {
@@ -298,6 +310,7 @@ class _$ScanProgressImpl implements ScanProgress {
     required TResult Function(ScanResult result) completed,
     required TResult Function(String message, int code) error,
     required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
   }) {
     return progress(bytesScanned, totalBytes);
   }
@@ -310,6 +323,7 @@ class _$ScanProgressImpl implements ScanProgress {
     TResult? Function(ScanResult result)? completed,
     TResult? Function(String message, int code)? error,
     TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
   }) {
     return progress?.call(bytesScanned, totalBytes);
   }
@@ -322,6 +336,7 @@ class _$ScanProgressImpl implements ScanProgress {
     TResult Function(ScanResult result)? completed,
     TResult Function(String message, int code)? error,
     TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
     required TResult orElse(),
   }) {
     if (progress != null) {
@@ -338,6 +353,7 @@ class _$ScanProgressImpl implements ScanProgress {
     required TResult Function(ScanCompleted value) completed,
     required TResult Function(ScanError value) error,
     required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
   }) {
     return progress(this);
   }
@@ -350,6 +366,7 @@ class _$ScanProgressImpl implements ScanProgress {
     TResult? Function(ScanCompleted value)? completed,
     TResult? Function(ScanError value)? error,
     TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
   }) {
     return progress?.call(this);
   }
@@ -362,6 +379,7 @@ class _$ScanProgressImpl implements ScanProgress {
     TResult Function(ScanCompleted value)? completed,
     TResult Function(ScanError value)? error,
     TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
     required TResult orElse(),
   }) {
     if (progress != null) {
@@ -474,6 +492,7 @@ class _$ScanCompletedImpl implements ScanCompleted {
     required TResult Function(ScanResult result) completed,
     required TResult Function(String message, int code) error,
     required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
   }) {
     return completed(result);
   }
@@ -486,6 +505,7 @@ class _$ScanCompletedImpl implements ScanCompleted {
     TResult? Function(ScanResult result)? completed,
     TResult? Function(String message, int code)? error,
     TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
   }) {
     return completed?.call(result);
   }
@@ -498,6 +518,7 @@ class _$ScanCompletedImpl implements ScanCompleted {
     TResult Function(ScanResult result)? completed,
     TResult Function(String message, int code)? error,
     TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
     required TResult orElse(),
   }) {
     if (completed != null) {
@@ -514,6 +535,7 @@ class _$ScanCompletedImpl implements ScanCompleted {
     required TResult Function(ScanCompleted value) completed,
     required TResult Function(ScanError value) error,
     required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
   }) {
     return completed(this);
   }
@@ -526,6 +548,7 @@ class _$ScanCompletedImpl implements ScanCompleted {
     TResult? Function(ScanCompleted value)? completed,
     TResult? Function(ScanError value)? error,
     TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
   }) {
     return completed?.call(this);
   }
@@ -538,6 +561,7 @@ class _$ScanCompletedImpl implements ScanCompleted {
     TResult Function(ScanCompleted value)? completed,
     TResult Function(ScanError value)? error,
     TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
     required TResult orElse(),
   }) {
     if (completed != null) {
@@ -642,6 +666,7 @@ class _$ScanErrorImpl implements ScanError {
     required TResult Function(ScanResult result) completed,
     required TResult Function(String message, int code) error,
     required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
   }) {
     return error(message, code);
   }
@@ -654,6 +679,7 @@ class _$ScanErrorImpl implements ScanError {
     TResult? Function(ScanResult result)? completed,
     TResult? Function(String message, int code)? error,
     TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
   }) {
     return error?.call(message, code);
   }
@@ -666,6 +692,7 @@ class _$ScanErrorImpl implements ScanError {
     TResult Function(ScanResult result)? completed,
     TResult Function(String message, int code)? error,
     TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
     required TResult orElse(),
   }) {
     if (error != null) {
@@ -682,6 +709,7 @@ class _$ScanErrorImpl implements ScanError {
     required TResult Function(ScanCompleted value) completed,
     required TResult Function(ScanError value) error,
     required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
   }) {
     return error(this);
   }
@@ -694,6 +722,7 @@ class _$ScanErrorImpl implements ScanError {
     TResult? Function(ScanCompleted value)? completed,
     TResult? Function(ScanError value)? error,
     TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
   }) {
     return error?.call(this);
   }
@@ -706,6 +735,7 @@ class _$ScanErrorImpl implements ScanError {
     TResult Function(ScanCompleted value)? completed,
     TResult Function(ScanError value)? error,
     TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
     required TResult orElse(),
   }) {
     if (error != null) {
@@ -779,6 +809,7 @@ class _$ScanCancelledImpl implements ScanCancelled {
     required TResult Function(ScanResult result) completed,
     required TResult Function(String message, int code) error,
     required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
   }) {
     return cancelled();
   }
@@ -791,6 +822,7 @@ class _$ScanCancelledImpl implements ScanCancelled {
     TResult? Function(ScanResult result)? completed,
     TResult? Function(String message, int code)? error,
     TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
   }) {
     return cancelled?.call();
   }
@@ -803,6 +835,7 @@ class _$ScanCancelledImpl implements ScanCancelled {
     TResult Function(ScanResult result)? completed,
     TResult Function(String message, int code)? error,
     TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
     required TResult orElse(),
   }) {
     if (cancelled != null) {
@@ -819,6 +852,7 @@ class _$ScanCancelledImpl implements ScanCancelled {
     required TResult Function(ScanCompleted value) completed,
     required TResult Function(ScanError value) error,
     required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
   }) {
     return cancelled(this);
   }
@@ -831,6 +865,7 @@ class _$ScanCancelledImpl implements ScanCancelled {
     TResult? Function(ScanCompleted value)? completed,
     TResult? Function(ScanError value)? error,
     TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
   }) {
     return cancelled?.call(this);
   }
@@ -843,6 +878,7 @@ class _$ScanCancelledImpl implements ScanCancelled {
     TResult Function(ScanCompleted value)? completed,
     TResult Function(ScanError value)? error,
     TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
     required TResult orElse(),
   }) {
     if (cancelled != null) {
@@ -856,6 +892,183 @@ abstract class ScanCancelled implements ScanEvent {
   const factory ScanCancelled() = _$ScanCancelledImpl;
 }
 
+/// @nodoc
+abstract class _$$ScanWarningImplCopyWith<$Res> {
+  factory _$$ScanWarningImplCopyWith(
+    _$ScanWarningImpl value,
+    $Res Function(_$ScanWarningImpl) then,
+  ) = __$$ScanWarningImplCopyWithImpl<$Res>;
+  @useResult
+  $Res call({String message, int code});
+}
+
+/// @nodoc
+class __$$ScanWarningImplCopyWithImpl<$Res>
+    extends _$ScanEventCopyWithImpl<$Res, _$ScanWarningImpl>
+    implements _$$ScanWarningImplCopyWith<$Res> {
+  __$$ScanWarningImplCopyWithImpl(
+    _$ScanWarningImpl _value,
+    $Res Function(_$ScanWarningImpl) _then,
+  ) : super(_value, _then);
+
+  /// Create a copy of ScanEvent
+  /// with the given fields replaced by the non-null parameter values.
+  @pragma('vm:prefer-inline')
+  @override
+  $Res call({Object? message = null, Object? code = null}) {
+    return _then(
+      _$ScanWarningImpl(
+        message: null == message
+            ? _value.message
+            : message // ignore: cast_nullable_to_non_nullable
+                  as String,
+        code: null == code
+            ? _value.code
+            : code // ignore: cast_nullable_to_non_nullable
+                  as int,
+      ),
+    );
+  }
+}
+
+/// @nodoc
+
+class _$ScanWarningImpl implements ScanWarning {
+  const _$ScanWarningImpl({required this.message, required this.code});
+
+  @override
+  final String message;
+  @override
+  final int code;
+
+  @override
+  String toString() {
+    return 'ScanEvent.warning(message: $message, code: $code)';
+  }
+
+  @override
+  bool operator ==(Object other) {
+    return identical(this, other) ||
+        (other.runtimeType == runtimeType &&
+            other is _$ScanWarningImpl &&
+            (identical(other.message, message) || other.message == message) &&
+            (identical(other.code, code) || other.code == code));
+  }
+
+  @override
+  int get hashCode => Object.hash(runtimeType, message, code);
+
+  /// Create a copy of ScanEvent
+  /// with the given fields replaced by the non-null parameter values.
+  @JsonKey(includeFromJson: false, includeToJson: false)
+  @override
+  @pragma('vm:prefer-inline')
+  _$$ScanWarningImplCopyWith<_$ScanWarningImpl> get copyWith =>
+      __$$ScanWarningImplCopyWithImpl<_$ScanWarningImpl>(this, _$identity);
+
+  @override
+  @optionalTypeArgs
+  TResult when<TResult extends Object?>({
+    required TResult Function() started,
+    required TResult Function(int bytesScanned, int? totalBytes) progress,
+    required TResult Function(ScanResult result) completed,
+    required TResult Function(String message, int code) error,
+    required TResult Function() cancelled,
+    required TResult Function(String message, int code) warning,
+  }) {
+    return warning(message, code);
+  }
+
+  @override
+  @optionalTypeArgs
+  TResult? whenOrNull<TResult extends Object?>({
+    TResult? Function()? started,
+    TResult? Function(int bytesScanned, int? totalBytes)? progress,
+    TResult? Function(ScanResult result)? completed,
+    TResult? Function(String message, int code)? error,
+    TResult? Function()? cancelled,
+    TResult? Function(String message, int code)? warning,
+  }) {
+    return warning?.call(message, code);
+  }
+
+  @override
+  @optionalTypeArgs
+  TResult maybeWhen<TResult extends Object?>({
+    TResult Function()? started,
+    TResult Function(int bytesScanned, int? totalBytes)? progress,
+    TResult Function(ScanResult result)? completed,
+    TResult Function(String message, int code)? error,
+    TResult Function()? cancelled,
+    TResult Function(String message, int code)? warning,
+    required TResult orElse(),
+  }) {
+    if (warning != null) {
+      return warning(message, code);
+    }
+    return orElse();
+  }
+
+  @override
+  @optionalTypeArgs
+  TResult map<TResult extends Object?>({
+    required TResult Function(ScanStarted value) started,
+    required TResult Function(ScanProgress value) progress,
+    required TResult Function(ScanCompleted value) completed,
+    required TResult Function(ScanError value) error,
+    required TResult Function(ScanCancelled value) cancelled,
+    required TResult Function(ScanWarning value) warning,
+  }) {
+    return warning(this);
+  }
+
+  @override
+  @optionalTypeArgs
+  TResult? mapOrNull<TResult extends Object?>({
+    TResult? Function(ScanStarted value)? started,
+    TResult? Function(ScanProgress value)? progress,
+    TResult? Function(ScanCompleted value)? completed,
+    TResult? Function(ScanError value)? error,
+    TResult? Function(ScanCancelled value)? cancelled,
+    TResult? Function(ScanWarning value)? warning,
+  }) {
+    return warning?.call(this);
+  }
+
+  @override
+  @optionalTypeArgs
+  TResult maybeMap<TResult extends Object?>({
+    TResult Function(ScanStarted value)? started,
+    TResult Function(ScanProgress value)? progress,
+    TResult Function(ScanCompleted value)? completed,
+    TResult Function(ScanError value)? error,
+    TResult Function(ScanCancelled value)? cancelled,
+    TResult Function(ScanWarning value)? warning,
+    required TResult orElse(),
+  }) {
+    if (warning != null) {
+      return warning(this);
+    }
+    return orElse();
+  }
+}
+
+abstract class ScanWarning implements ScanEvent {
+  const factory ScanWarning({
+    required final String message,
+    required final int code,
+  }) = _$ScanWarningImpl;
+
+  String get message;
+  int get code;
+
+  /// Create a copy of ScanEvent
+  /// with the given fields replaced by the non-null parameter values.
+  @JsonKey(includeFromJson: false, includeToJson: false)
+  _$$ScanWarningImplCopyWith<_$ScanWarningImpl> get copyWith =>
+      throw _privateConstructorUsedError;
+}
+
 /// @nodoc
 mixin _$ScanResult {
   /// The scanned data as bytes (if outputPath was null).
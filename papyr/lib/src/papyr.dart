@@ -98,8 +98,9 @@ class Papyr {
   /// The stream will emit:
   /// - [ScanStarted] when scanning begins
   /// - [ScanProgress] events during scanning (if supported)
+  /// - [ScanWarning] for problems the scan carries on after
   /// - [ScanCompleted] with the result when successful
-  /// - [ScanError] if an error occurs
+  /// - [ScanError] with the papyr error code if an error occurs
   /// - [ScanCancelled] if the scan is cancelled
   ///
   /// Example:
@@ -117,6 +118,7 @@ class Papyr {
   ///     completed: (result) => print('Done: ${result.filePath}'),
   ///     error: (msg, code) => print('Error: $msg'),
   ///     cancelled: () => print('Cancelled'),
+  ///     warning: (msg, code) => print('Warning: $msg'),
   ///   );
   /// }
   /// ```
@@ -126,16 +128,20 @@ class Papyr {
     _ensureInitialized();
 
     final deviceIdPtr = config.scanner.id.toNativeUtf8();
-    final configPtr = malloc<PapyrScanConfig>();
+    final configPtr = malloc<PapyrScanConfigV2>();
 
     configPtr.ref.source = _scanSourceToInt(config.source);
     configPtr.ref.duplex = config.useDuplex ? 1 : 0;
     configPtr.ref.dpi = config.dpi;
     configPtr.ref.colorMode = _colorModeToInt(config.colorMode);
-    configPtr.ref.pageWidthMm = 216; // Letter width
-    configPtr.ref.pageHeightMm = 279; // Letter height
+    configPtr.ref.pageWidthMm = 215.9; // Letter width
+    configPtr.ref.pageHeightMm = 279.4; // Letter height
+    configPtr.ref.dpiY = 0; // Same as dpi
+    configPtr.ref.bitDepth = 0; // Mode default
+    configPtr.ref.maxPages = 0; // No limit
+    configPtr.ref.hasArea = 0; // Whole page
 
-    final sessionId = _ffi.papyrStartScan(deviceIdPtr, configPtr);
+    final sessionId = _ffi.papyrStartScanV2(deviceIdPtr, configPtr, 0);
 
     malloc.free(deviceIdPtr);
     malloc.free(configPtr);
@@ -152,6 +158,7 @@ class Papyr {
 
     // Poll for events
     int totalBytes = 0;
+    int pageCount = 0;
     final pageData = <List<int>>[];
 
     while (true) {
@@ -168,16 +175,48 @@ class Papyr {
           break;
 
         case PapyrScanEventType.pageData:
-          final data = event.data.cast<Uint8>().asTypedList(event.dataSize);
-          pageData.add(List<int>.from(data));
+          if (event.data != nullptr) {
+            final data = event.data.cast<Uint8>().asTypedList(event.dataSize);
+            pageData.add(List<int>.from(data));
+          }
           totalBytes += event.dataSize;
-          yield ScanEvent.progress(bytesScanned: totalBytes);
           break;
 
         case PapyrScanEventType.pageComplete:
           // Page completed, continue to next page or job complete
+          pageCount++;
+          break;
+
+        case PapyrScanEventType.progress:
+          yield ScanEvent.progress(
+            bytesScanned: event.bytes,
+            totalBytes: event.expected >= 0 ? event.expected : null,
+          );
+          break;
+
+        case PapyrScanEventType.status:
+          // Waiting, warming up or scanning; nothing to report yet
+          break;
+
+        case PapyrScanEventType.warning:
+          yield ScanEvent.warning(
+            message: _eventMessage(event),
+            code: event.code,
+          );
           break;
 
+        case PapyrScanEventType.cancelled:
+          _ffi.papyrFreeScanEvent(eventPtr);
+          yield const ScanEvent.cancelled();
+          return;
+
+        case PapyrScanEventType.error:
+          final message = _eventMessage(event);
+          final code = event.code;
+          _ffi.papyrFreeScanEvent(eventPtr);
+          yield ScanEvent.error(message: message, code: code);
+          return;
+
         case PapyrScanEventType.jobComplete:
           // Flatten all page data
           final allData = pageData.expand((page) => page).toList();
@@ -193,7 +232,7 @@ class Papyr {
             result: ScanResult(
               data: config.outputPath == null ? allData : null,
               filePath: filePath,
-              pageCount: 1, // TODO: Track actual page count
+              pageCount: pageCount,
               totalBytes: totalBytes,
             ),
           );
@@ -226,6 +265,10 @@ class Papyr {
 
   // Conversion helpers
 
+  String _eventMessage(PapyrScanEvent event) {
+    return event.message == nullptr ? '' : event.message.toDartString();
+  }
+
   ScannerBackend _convertBackend(int backend) {
     switch (backend) {
       case PapyrBackend.wia:
@@ -1,4 +1,7 @@
+import 'dart:ffi';
+
 import 'package:papyr/papyr.dart';
+import 'package:papyr/src/ffi_bindings.dart';
 import 'package:test/test.dart';
 
 void main() {
@@ -131,5 +134,27 @@ void main() {
         contains('does not support source'),
       );
     });
+
+    test('ScanEvent.warning carries message and code', () {
+      const event = ScanEvent.warning(
+        message: 'dpi 250 -> 300',
+        code: 0,
+      );
+
+      final text = event.maybeWhen(
+        warning: (message, code) => '$code: $message',
+        orElse: () => 'other',
+      );
+      expect(text, equals('0: dpi 250 -> 300'));
+      expect(event, isA<ScanWarning>());
+    });
+
+    test('PapyrScannerInfo matches the C layout', () {
+      // papyr_core.h: 12 pointers, 2 ints padded to pointer alignment and
+      // 2 size_t; the route is a pointer and an int
+      final pointer = sizeOf<IntPtr>();
+      expect(sizeOf<PapyrScannerInfo>(), equals(16 * pointer));
+      expect(sizeOf<PapyrDeviceRoute>(), equals(2 * pointer));
+    });
   });
 }
@@ -0,0 +1,253 @@
+/*
+ *  papyr_core
+ *  examples/plugin/example_plugin.c - Example backend plugin for the C plugin ABI
+ *
+ *  Created by Ngonidzashe Mangudya on 2025/10/22.
+ *  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+ *
+ *  A plugin with three made-up check scanners, showing the whole of
+ *  include/papyr_plugin.h. Build it as a shared library and put it in a
+ *  plugin directory:
+ *
+ *    cc -shared -fPIC -I include -o plugins/libexample_plugin.so \
+ *        examples/plugin/example_plugin.c
+ *
+ *    check    feeder scanner, 3 gray or color sheets of the configured size
+ *             or scan area, with independent X/Y resolution
+ *    jammed   jams while feeding the second sheet
+ *    stalled  never delivers a page; only cancelling ends the scan
+ *
+ *  tests/plugin_test.rs builds and loads it. -DEXAMPLE_ABI_VERSION=n builds
+ *  it for another ABI version and -DEXAMPLE_VTABLE_SIZE=n with a truncated
+ *  vtable, both of which papyr refuses.
+ */
+
+#define _POSIX_C_SOURCE 199309L /* nanosleep */
+
+#include <stdatomic.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+#include "papyr_plugin.h"
+
+#ifndef EXAMPLE_ABI_VERSION
+#define EXAMPLE_ABI_VERSION PAPYR_PLUGIN_ABI_VERSION
+#endif
+#ifndef EXAMPLE_VTABLE_SIZE
+#define EXAMPLE_VTABLE_SIZE sizeof(PapyrPluginVTable)
+#endif
+
+#define SHEETS 3
+#define ROWS_PER_EVENT 64
+
+enum { STAGE_START, STAGE_DATA, STAGE_COMPLETE, STAGE_DONE };
+
+static const PapyrPluginDevice devices[] = {
+    {"check", "Example Check Scanner", "Example", "CS-100", "EX0001", NULL, CONNECTION_USB},
+    {"jammed", "Example Jamming Scanner", "Example", "CS-100", "EX0002", NULL, CONNECTION_USB},
+    {"stalled", "Example Stalled Scanner", "Example", "CS-100", "EX0003", NULL, CONNECTION_USB},
+};
+#define DEVICE_COUNT (sizeof(devices) / sizeof(devices[0]))
+
+static int sources[] = {SOURCE_ADF};
+static int dpis[] = {100, 200, 300};
+static int color_modes[] = {COLOR_MODE_COLOR, COLOR_MODE_GRAY};
+
+typedef struct {
+    const PapyrPluginDevice *device;
+    PapyrPluginScanConfig config;
+    int sheets;
+    int page;
+    int stage;
+    unsigned width, height, channels, row;
+    uint8_t *rows;
+    atomic_int cancelled;
+} Session;
+
+static void set_error(PapyrPluginError *error, const char *message) {
+    snprintf(error->message, sizeof(error->message), "%s", message);
+}
+
+static const PapyrPluginDevice *find_device(const char *id) {
+    for (size_t i = 0; i < DEVICE_COUNT; i++) {
+        if (strcmp(devices[i].id, id) == 0) {
+            return &devices[i];
+        }
+    }
+    return NULL;
+}
+
+static int example_enumerate(void *context, const PapyrPluginDevice **list, size_t *count,
+                             PapyrPluginError *error) {
+    (void)context;
+    (void)error;
+    *list = devices;
+    *count = DEVICE_COUNT;
+    return PAPYR_OK;
+}
+
+static int example_capabilities(void *context, const char *device_id,
+                                PapyrPluginCapabilities *caps, PapyrPluginError *error) {
+    (void)context;
+    if (!find_device(device_id)) {
+        set_error(error, "no such device");
+        return PAPYR_ERROR_NOT_FOUND;
+    }
+    caps->sources = sources;
+    caps->sources_count = sizeof(sources) / sizeof(sources[0]);
+    caps->dpis = dpis;
+    caps->dpis_count = sizeof(dpis) / sizeof(dpis[0]);
+    caps->color_modes = color_modes;
+    caps->color_modes_count = sizeof(color_modes) / sizeof(color_modes[0]);
+    caps->supports_duplex = 0;
+    caps->independent_dpi = 1;
+    return PAPYR_OK;
+}
+
+static int example_start_scan(void *context, const char *device_id,
+                              const PapyrPluginScanConfig *config, void **session,
+                              PapyrPluginError *error) {
+    (void)context;
+    const PapyrPluginDevice *device = find_device(device_id);
+    if (!device) {
+        set_error(error, "no such device");
+        return PAPYR_ERROR_NOT_FOUND;
+    }
+    if (config->color_mode == COLOR_MODE_BW) {
+        set_error(error, "black and white scanning");
+        return PAPYR_ERROR_UNSUPPORTED;
+    }
+    if (config->has_brightness || config->has_contrast) {
+        set_error(error, "brightness and contrast");
+        return PAPYR_ERROR_UNSUPPORTED;
+    }
+
+    Session *s = calloc(1, sizeof(Session));
+    if (!s) {
+        set_error(error, "out of memory");
+        return PAPYR_ERROR_OTHER;
+    }
+    s->device = device;
+    s->config = *config;
+    s->sheets = config->max_pages > 0 && config->max_pages < SHEETS ? config->max_pages : SHEETS;
+    s->channels = config->color_mode == COLOR_MODE_COLOR ? 3 : 1;
+    double width_mm = config->has_area ? config->area_width_mm : config->page_width_mm;
+    double height_mm = config->has_area ? config->area_height_mm : config->page_height_mm;
+    int dpi_y = config->dpi_y > 0 ? config->dpi_y : config->dpi;
+    s->width = (unsigned)(width_mm * config->dpi / 25.4);
+    s->height = (unsigned)(height_mm * dpi_y / 25.4);
+    s->rows = malloc((size_t)s->width * s->channels * ROWS_PER_EVENT);
+    if (!s->rows) {
+        free(s);
+        set_error(error, "out of memory");
+        return PAPYR_ERROR_OTHER;
+    }
+    atomic_init(&s->cancelled, 0);
+    *session = s;
+    return PAPYR_OK;
+}
+
+/* A diagonal gradient with the page number in the top-left corner's shade. */
+static size_t fill_rows(Session *s) {
+    unsigned rows = s->height - s->row < ROWS_PER_EVENT ? s->height - s->row : ROWS_PER_EVENT;
+    uint8_t *out = s->rows;
+    for (unsigned y = s->row; y < s->row + rows; y++) {
+        for (unsigned x = 0; x < s->width; x++) {
+            for (unsigned c = 0; c < s->channels; c++) {
+                *out++ = (x < 32 && y < 32) ? (uint8_t)(s->page * 64) : (uint8_t)(x + y + c * 85);
+            }
+        }
+    }
+    s->row += rows;
+    return (size_t)(out - s->rows);
+}
+
+static int example_next_event(void *session, PapyrPluginEvent *event, PapyrPluginError *error) {
+    Session *s = session;
+
+    if (strcmp(s->device->id, "stalled") == 0) {
+        struct timespec pause = {0, 1000000};
+        while (!atomic_load(&s->cancelled)) {
+            nanosleep(&pause, NULL);
+        }
+    }
+    if (atomic_load(&s->cancelled)) {
+        event->event_type = SCAN_EVENT_CANCELLED;
+        s->stage = STAGE_DONE;
+        return PAPYR_OK;
+    }
+
+    switch (s->stage) {
+    case STAGE_START:
+        if (s->page == s->sheets) {
+            event->event_type = SCAN_EVENT_JOB_COMPLETE;
+            s->stage = STAGE_DONE;
+            return PAPYR_OK;
+        }
+        if (s->page == 1 && strcmp(s->device->id, "jammed") == 0) {
+            set_error(error, "sheet 2 is stuck in the feeder");
+            return PAPYR_ERROR_PAPER_JAM;
+        }
+        event->event_type = SCAN_EVENT_PAGE_STARTED;
+        event->page = (uint32_t)s->page;
+        s->row = 0;
+        s->stage = STAGE_DATA;
+        return PAPYR_OK;
+    case STAGE_DATA:
+        event->event_type = SCAN_EVENT_PAGE_DATA;
+        event->data = s->rows;
+        event->data_size = fill_rows(s);
+        if (s->row == s->height) {
+            s->stage = STAGE_COMPLETE;
+        }
+        return PAPYR_OK;
+    case STAGE_COMPLETE:
+        event->event_type = SCAN_EVENT_PAGE_COMPLETE;
+        event->page = (uint32_t)s->page;
+        event->width_px = s->width;
+        event->height_px = s->height;
+        event->dpi = s->config.dpi;
+        event->dpi_y = s->config.dpi_y;
+        event->color_mode = s->config.color_mode;
+        event->bit_depth = 8;
+        s->page++;
+        s->stage = STAGE_START;
+        return PAPYR_OK;
+    default:
+        set_error(error, "the scan is over");
+        return PAPYR_ERROR_OTHER;
+    }
+}
+
+static void example_cancel(void *session) {
+    Session *s = session;
+    atomic_store(&s->cancelled, 1);
+}
+
+static void example_free_session(void *session) {
+    Session *s = session;
+    free(s->rows);
+    free(s);
+}
+
+static const PapyrPluginVTable vtable = {
+    .abi_version = EXAMPLE_ABI_VERSION,
+    .size = EXAMPLE_VTABLE_SIZE,
+    .name = "example",
+    .context = NULL,
+    .enumerate = example_enumerate,
+    .capabilities = example_capabilities,
+    .start_scan = example_start_scan,
+    .next_event = example_next_event,
+    .cancel = example_cancel,
+    .free_session = example_free_session,
+    .destroy = NULL,
+};
+
+const PapyrPluginVTable *papyr_plugin_entry(uint32_t host_abi_version) {
+    /* Refusing here is an option too, for hosts too old for the plugin */
+    (void)host_abi_version;
+    return &vtable;
+}
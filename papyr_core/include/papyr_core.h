@@ -18,9 +18,14 @@ extern "C" {
 
 // Backend types
 typedef enum {
-    BACKEND_WIA = 0,      // Windows Image Acquisition
-    BACKEND_SANE = 1,     // Scanner Access Now Easy (Linux)
-    BACKEND_ICA = 2,      // Image Capture Architecture (macOS)
+    BACKEND_TWAIN = 0,    // TWAIN (Windows/macOS)
+    BACKEND_WIA = 1,      // Windows Image Acquisition
+    BACKEND_SANE = 2,     // Scanner Access Now Easy (Linux)
+    BACKEND_ICA = 3,      // Image Capture Architecture (macOS)
+    BACKEND_ESCL = 4,     // eSCL/AirScan (network)
+    BACKEND_SANE_NET = 5, // SANE network protocol (remote saned)
+    BACKEND_PLUGIN = 6,   // Third-party plugin (see papyr_plugin.h)
+    BACKEND_VIRTUAL = 7,  // Simulated scanners (feature "virtual")
     BACKEND_UNKNOWN = 99
 } PapyrBackend;
 
@@ -43,14 +48,129 @@ typedef enum {
     SCAN_EVENT_PAGE_STARTED = 0,
     SCAN_EVENT_PAGE_DATA = 1,
     SCAN_EVENT_PAGE_COMPLETE = 2,
-    SCAN_EVENT_JOB_COMPLETE = 3
+    SCAN_EVENT_JOB_COMPLETE = 3,
+    SCAN_EVENT_PROGRESS = 4,  // bytes, expected and percent are set
+    SCAN_EVENT_STATUS = 5,    // code holds a PapyrScanStatus
+    SCAN_EVENT_WARNING = 6,   // code holds a PapyrWarningCode, message the text
+    SCAN_EVENT_CANCELLED = 7, // last event of a cancelled session
+    SCAN_EVENT_ERROR = 8      // code holds a PapyrErrorCode, message the text
 } PapyrScanEventType;
 
+// Session states reported by SCAN_EVENT_STATUS
+typedef enum {
+    SCAN_STATUS_WAITING_FOR_DEVICE = 0, // device busy, retrying
+    SCAN_STATUS_WARMING_UP = 1,
+    SCAN_STATUS_SCANNING = 2
+} PapyrScanStatus;
+
+// Recoverable problems reported by SCAN_EVENT_WARNING
+typedef enum {
+    WARNING_CONFIG_ADJUSTED = 0,     // a setting was changed to fit the device
+    WARNING_OPTION_NOT_APPLIED = 1,  // a device option couldn't be set
+    WARNING_CLEANUP_FAILED = 2,
+    WARNING_BACKEND_FALLBACK = 3     // scanning through the scanner's next backend
+} PapyrWarningCode;
+
+// Button and sensor event types
+typedef enum {
+    SENSOR_EVENT_BUTTON_PRESSED = 0, // name holds the button ("scan", "email", ...)
+    SENSOR_EVENT_PAPER_LOADED = 1,
+    SENSOR_EVENT_PAPER_REMOVED = 2,
+    SENSOR_EVENT_COVER_OPENED = 3,
+    SENSOR_EVENT_COVER_CLOSED = 4
+} PapyrSensorEventType;
+
+// Device option value types
+typedef enum {
+    OPTION_TYPE_BOOL = 0,
+    OPTION_TYPE_INT = 1,
+    OPTION_TYPE_FIXED = 2,  // fractional number
+    OPTION_TYPE_STRING = 3,
+    OPTION_TYPE_BUTTON = 4  // action; any value triggers it
+} PapyrOptionType;
+
+// Device option units
+typedef enum {
+    OPTION_UNIT_NONE = 0,
+    OPTION_UNIT_PIXEL = 1,
+    OPTION_UNIT_BIT = 2,
+    OPTION_UNIT_MM = 3,
+    OPTION_UNIT_DPI = 4,
+    OPTION_UNIT_PERCENT = 5,
+    OPTION_UNIT_MICROSECOND = 6
+} PapyrOptionUnit;
+
+// Device option constraint kinds
+typedef enum {
+    OPTION_CONSTRAINT_NONE = 0,
+    OPTION_CONSTRAINT_RANGE = 1, // range_min..range_max, step range_step (0 = any)
+    OPTION_CONSTRAINT_LIST = 2   // one of list_values
+} PapyrOptionConstraint;
+
+// How a scanner is attached
+typedef enum {
+    CONNECTION_UNKNOWN = 0,
+    CONNECTION_USB = 1,
+    CONNECTION_NETWORK = 2,
+    CONNECTION_VIRTUAL = 3  // software device, e.g. SANE's test backend
+} PapyrConnectionKind;
+
+// How a backend fared in papyr_discover_scanners()
+typedef enum {
+    DISCOVERY_OK = 0,
+    DISCOVERY_TIMED_OUT = 1,  // still enumerating at its timeout, cancelled
+    DISCOVERY_FAILED = 2,     // enumeration panicked, message says why
+    DISCOVERY_UNAVAILABLE = 3 // not usable on this machine, message says why
+} PapyrDiscoveryStatus;
+
+// Error codes reported by papyr_last_error_code()
+typedef enum {
+    PAPYR_OK = 0,
+    PAPYR_ERROR_NOT_FOUND = 1,
+    PAPYR_ERROR_INVALID_CONFIG = 2,
+    PAPYR_ERROR_BACKEND = 3,
+    PAPYR_ERROR_DEVICE_BUSY = 4,    // retryable
+    PAPYR_ERROR_FEEDER_EMPTY = 5,
+    PAPYR_ERROR_PAPER_JAM = 6,
+    PAPYR_ERROR_COVER_OPEN = 7,
+    PAPYR_ERROR_TIMEOUT = 8,        // retryable
+    PAPYR_ERROR_CANCELLED = 9,
+    PAPYR_ERROR_UNAUTHORIZED = 10,
+    PAPYR_ERROR_UNSUPPORTED = 11,
+    PAPYR_ERROR_IO = 12,            // retryable
+    PAPYR_ERROR_PROTOCOL = 13,
+    PAPYR_ERROR_NOT_IMPLEMENTED = 14,
+    PAPYR_ERROR_OTHER = 15
+} PapyrErrorCode;
+
 // Structures
 typedef struct {
+    char* id;
+    int backend;        // PapyrBackend
+} PapyrDeviceRoute;
+
+typedef struct {
+    // "<backend>_<local id>", e.g. "escl_HP_OfficeJet"; stable, so it can
+    // be saved and passed back later. Malformed ids are PAPYR_ERROR_NOT_FOUND.
     char* id;
     char* name;
     int backend;
+    // Details below are NULL when the backend doesn't know them
+    char* manufacturer;
+    char* model;
+    char* serial;       // serial number, or UUID for network devices
+    int connection;     // PapyrConnectionKind
+    char* address;      // host or IP of network devices, with port where known
+    char* device_type;  // e.g. "flatbed scanner"
+    char* location;
+    char* admin_url;
+    char* icon_url;
+    uint8_t* icon;      // icon image bytes, icon_len long
+    size_t icon_len;
+    // Every backend reaching this scanner, most preferred first; the first
+    // is id itself. Scans fall back along this list.
+    PapyrDeviceRoute* routes;
+    size_t route_count;
 } PapyrScannerInfo;
 
 typedef struct {
@@ -58,6 +178,26 @@ typedef struct {
     size_t count;
 } PapyrScannerInfoList;
 
+typedef struct {
+    int backend;         // PapyrBackend
+    int status;          // PapyrDiscoveryStatus
+    size_t device_count;
+    uint64_t elapsed_ms;
+    char* message;       // reason for FAILED and UNAVAILABLE, NULL otherwise
+} PapyrBackendDiscovery;
+
+typedef struct {
+    PapyrScannerInfoList* scanners;
+    PapyrBackendDiscovery* backends; // one per registered backend
+    size_t backend_count;
+} PapyrDiscovery;
+
+typedef struct {
+    int min;
+    int max;
+    int step;
+} PapyrDpiRange;
+
 typedef struct {
     int* sources;
     size_t sources_count;
@@ -66,8 +206,14 @@ typedef struct {
     int* color_modes;
     size_t color_modes_count;
     int supports_duplex; // 0 = false, 1 = true
+    PapyrDpiRange* dpi_ranges; // accepted besides dpis, empty for discrete devices
+    size_t dpi_ranges_count;
+    int independent_dpi; // 1 if dpi_y may differ from dpi
+    int* bit_depths;     // bits per sample for color and gray (8, 16)
+    size_t bit_depths_count;
 } PapyrCapabilities;
 
+// The original scan config; settings added since are in PapyrScanConfigV2
 typedef struct {
     int source;
     int duplex;            // 0 = false, 1 = true
@@ -77,12 +223,94 @@ typedef struct {
     int page_height_mm;
 } PapyrScanConfig;
 
+// For papyr_start_scan_v2() and papyr_get_default_scan_config_v2()
+typedef struct {
+    int source;
+    int duplex;            // 0 = false, 1 = true
+    int dpi;
+    int color_mode;
+    double page_width_mm;  // sub-millimetre, e.g. 215.9 for Letter
+    double page_height_mm;
+    int dpi_y;             // vertical resolution, 0 = same as dpi
+    int bit_depth;         // bits per sample (16 for 48-bit color), 0 = default
+    int max_pages;         // stop after this many pages, 0 = no limit
+    int has_area;          // 1 to scan only the area below
+    double area_x_mm;      // from the top-left corner of the page
+    double area_y_mm;
+    double area_width_mm;
+    double area_height_mm;
+} PapyrScanConfigV2;
+
+typedef struct {
+    char* name;         // identifier for papyr_set_device_option()
+    char* title;
+    char* description;
+    char* group;        // NULL when ungrouped
+    int value_type;     // PapyrOptionType
+    int unit;           // PapyrOptionUnit
+    int constraint_type; // PapyrOptionConstraint
+    double range_min;
+    double range_max;
+    double range_step;
+    char** list_values; // values as text
+    size_t list_count;
+    char* value;        // current value as text, NULL if unknown
+    int settable;       // 0 = false, 1 = true
+    int advanced;       // 0 = false, 1 = true
+} PapyrDeviceOption;
+
+typedef struct {
+    PapyrDeviceOption* options;
+    size_t count;
+} PapyrDeviceOptionList;
+
 typedef struct {
-    int event_type;
+    int event_type;     // PapyrScanEventType
     void* data;
     size_t data_size;
+    uint64_t bytes;     // progress: bytes of the page so far
+    int64_t expected;   // progress: page size in bytes, -1 if unknown
+    float percent;      // progress: 0-100, -1 if unknown
+    int code;           // status, warning or error code, by event type
+    char* message;      // warning or error text, NULL otherwise
 } PapyrScanEvent;
 
+typedef struct {
+    int event_type; // PapyrSensorEventType
+    char* name;     // button name, NULL for other events
+} PapyrSensorEvent;
+
+/**
+ * Credentials callback.
+ * Fill username/password as NUL-terminated strings of at most the given
+ * lengths and return non-zero, or return 0 to decline (the operation then
+ * fails as unauthorized). Resources with an MD5 challenge are passed
+ * without the "$MD5$salt" suffix; hashing is done by papyr.
+ */
+typedef int (*PapyrCredentialsCallback)(const char* resource,
+                                        char* username, size_t username_len,
+                                        char* password, size_t password_len);
+
+// Log levels, most severe first
+typedef enum {
+    PAPYR_LOG_OFF = 0,
+    PAPYR_LOG_ERROR = 1,
+    PAPYR_LOG_WARN = 2,
+    PAPYR_LOG_INFO = 3,
+    PAPYR_LOG_DEBUG = 4,
+    PAPYR_LOG_TRACE = 5
+} PapyrLogLevel;
+
+/**
+ * Log callback.
+ * level is a PapyrLogLevel, target the emitting module (e.g.
+ * "papyr_core::backends::escl"), message one line prefixed with the
+ * enclosing spans and followed by structured fields:
+ *   scan{device=escl_a1}:page{index=0}: page complete width=2550 height=3300
+ * Both strings are only valid during the call.
+ */
+typedef void (*PapyrLogCallback)(int level, const char* target, const char* message);
+
 // Function declarations
 
 /**
@@ -93,12 +321,83 @@ typedef struct {
 int papyr_init(void);
 
 /**
- * Get list of available scanners.
+ * Initialize with the backends and settings of a config file, instead of
+ * papyr_init(). The file may enable, disable and order backends and set
+ * the discovery timeout, static eSCL and saned hosts, the libsane path and
+ * default scan settings, and turn on virtual scanners ("virtual" feature).
+ * @param config_path Path of a .toml or .json file
+ * @return 0 on success, negative with PAPYR_ERROR_INVALID_CONFIG if the
+ *         file can't be read or parsed
+ */
+int papyr_init_with_config(const char* config_path);
+
+/**
+ * papyr_init_with_config() with the config given as JSON text.
+ * @param config_json e.g. {"disabled": ["Escl"], "saned": {"hosts": ["scanhost"]}}
+ * @return 0 on success, negative on error
+ */
+int papyr_init_with_config_json(const char* config_json);
+
+/**
+ * Get list of available scanners. Scanners discovered within the cache TTL
+ * (5 minutes by default) are returned without discovering again.
  * @return Pointer to scanner list, or NULL on error.
  *         Must be freed with papyr_free_scanner_list()
  */
 PapyrScannerInfoList* papyr_list_scanners(void);
 
+/**
+ * papyr_list_scanners() that stops discovery once the token is cancelled.
+ * @param cancel_token_id Token from papyr_cancel_token_new(), or 0 for none
+ * @return As papyr_list_scanners(); NULL with PAPYR_ERROR_CANCELLED when
+ *         cancelled
+ */
+PapyrScannerInfoList* papyr_list_scanners_cancellable(int cancel_token_id);
+
+/**
+ * Discover scanners again, replacing the cached list. Capabilities and
+ * scans are routed by the cached list, so a scanner connected since the
+ * last discovery is PAPYR_ERROR_NOT_FOUND until this is called or the
+ * cache expires.
+ * @param cancel_token_id Token from papyr_cancel_token_new(), or 0 for none
+ * @return As papyr_list_scanners_cancellable()
+ */
+PapyrScannerInfoList* papyr_refresh_scanners(int cancel_token_id);
+
+/**
+ * Set how long discovered scanners are reused.
+ * @param ttl_ms Cache lifetime in milliseconds; 0 discovers every time
+ * @return 0 on success, negative if papyr_init() has not been called
+ */
+int papyr_set_device_cache_ttl(int ttl_ms);
+
+/**
+ * Discover scanners on every backend at once, replacing the cached list.
+ * A backend that times out or fails is reported in backends instead of
+ * failing the call, so network scanners can be shown as still searching.
+ * @param cancel_token_id Token from papyr_cancel_token_new(), or 0 for none
+ * @return Discovery result, or NULL on error (PAPYR_ERROR_CANCELLED when
+ *         cancelled). Must be freed with papyr_free_discovery()
+ */
+PapyrDiscovery* papyr_discover_scanners(int cancel_token_id);
+
+/**
+ * Set how long a backend may take to enumerate (45 seconds by default).
+ * @param backend PapyrBackend to set it for, or -1 for every backend
+ * @param timeout_ms Timeout in milliseconds
+ * @return 0 on success, negative if papyr_init() has not been called
+ */
+int papyr_set_enumeration_timeout(int backend, int timeout_ms);
+
+/**
+ * Load and register the backend plugins in a directory: its .so, .dylib
+ * or .dll files, see papyr_plugin.h. Plugins that fail to load are skipped.
+ * @param dir Plugin directory
+ * @return Number of plugins registered, or -1 on error
+ *         (PAPYR_ERROR_INVALID_CONFIG if the directory can't be read)
+ */
+int papyr_load_plugins(const char* dir);
+
 /**
  * Get capabilities of a specific scanner.
  * @param device_id Scanner device ID
@@ -107,6 +406,38 @@ PapyrScannerInfoList* papyr_list_scanners(void);
  */
 PapyrCapabilities* papyr_get_capabilities(const char* device_id);
 
+/**
+ * papyr_get_capabilities() that fails with PAPYR_ERROR_CANCELLED once the
+ * token is cancelled.
+ * @param cancel_token_id Token from papyr_cancel_token_new(), or 0 for none
+ */
+PapyrCapabilities* papyr_get_capabilities_cancellable(const char* device_id,
+                                                      int cancel_token_id);
+
+/**
+ * Choose how papyr_start_scan treats settings the scanner doesn't support.
+ * By default the scan fails with PAPYR_ERROR_INVALID_CONFIG; with coercion
+ * the resolution snaps to the nearest supported value, duplex falls back to
+ * simplex and the page is clamped to the largest size the scanner takes.
+ * @param enabled Non-zero to adjust settings, 0 to reject them
+ * @return 0 on success, negative if papyr_init() has not been called
+ */
+int papyr_set_config_coercion(int enabled);
+
+/**
+ * Get the configured default scan settings (300 dpi color A4 flatbed
+ * unless the config says otherwise).
+ * @param config Filled on success
+ * @return 0 on success, negative on error
+ */
+int papyr_get_default_scan_config(PapyrScanConfig* config);
+
+/**
+ * papyr_get_default_scan_config() for the v2 config, with the page size
+ * unrounded and max_pages included.
+ */
+int papyr_get_default_scan_config_v2(PapyrScanConfigV2* config);
+
 /**
  * Start a scan session.
  * @param device_id Scanner device ID
@@ -115,26 +446,181 @@ PapyrCapabilities* papyr_get_capabilities(const char* device_id);
  */
 int papyr_start_scan(const char* device_id, const PapyrScanConfig* config);
 
+/**
+ * papyr_start_scan() that the token cancels: while starting the call fails
+ * with PAPYR_ERROR_CANCELLED, afterwards it acts like papyr_cancel_scan().
+ * @param cancel_token_id Token from papyr_cancel_token_new(), or 0 for none
+ */
+int papyr_start_scan_cancellable(const char* device_id, const PapyrScanConfig* config,
+                                 int cancel_token_id);
+
+/**
+ * Start a scan session with the v2 config, for sub-millimetre page sizes, a
+ * scan area, separate X/Y resolution, bit depth and a page limit.
+ * @param cancel_token_id Token as for papyr_start_scan_cancellable(), or 0
+ * @return Session ID (positive integer) on success, negative on error
+ */
+int papyr_start_scan_v2(const char* device_id, const PapyrScanConfigV2* config,
+                        int cancel_token_id);
+
+/**
+ * Set timeouts for scan sessions started afterwards. A session waiting
+ * longer than idle_timeout_ms for its next event, or running longer than
+ * job_timeout_ms in total, is cancelled and ends with a SCAN_EVENT_ERROR
+ * carrying PAPYR_ERROR_TIMEOUT. 0 turns a timeout off (the default).
+ * @return 0 on success, negative on error
+ */
+int papyr_set_scan_timeouts(int idle_timeout_ms, int job_timeout_ms);
+
+/**
+ * Cancel a scan session. Safe from any thread, including while another
+ * thread is blocked in papyr_next_scan_event(); that call then returns a
+ * SCAN_EVENT_CANCELLED event once the scanner has stopped (network jobs are
+ * deleted, SANE scans cancelled).
+ * @return 0 on success, -1 for an unknown session
+ */
+int papyr_cancel_scan(int session_id);
+
+/**
+ * Create a cancel token for the *_cancellable calls. A token stays
+ * cancelled, so use a new one per operation.
+ * @return Token ID (positive integer), negative on error
+ */
+int papyr_cancel_token_new(void);
+
+/**
+ * Cancel every call and scan using the token. Safe from any thread.
+ * @return 0 on success, -1 for an unknown token
+ */
+int papyr_cancel_token_cancel(int token_id);
+
+/**
+ * Release a cancel token. Calls already using it are unaffected.
+ */
+void papyr_cancel_token_free(int token_id);
+
 /**
  * Get next scan event from a session.
  * @param session_id Session ID from papyr_start_scan()
- * @return Pointer to scan event, or NULL when session is complete or on error.
- *         Must be freed with papyr_free_scan_event()
+ * A failure that ends the job arrives as a SCAN_EVENT_ERROR event (also
+ * recorded for papyr_last_error_code()), after which NULL is returned.
+ * @return Pointer to scan event, or NULL when the session is complete or
+ *         unknown. Must be freed with papyr_free_scan_event()
  */
 PapyrScanEvent* papyr_next_scan_event(int session_id);
 
+/**
+ * Get backend-specific options of a scanner (lamp timeout, double-feed
+ * detection, gamma, ...) with their current values.
+ * @param device_id Scanner device ID
+ * @return Pointer to option list, or NULL on error or if the backend has
+ *         no options. Must be freed with papyr_free_device_options()
+ */
+PapyrDeviceOptionList* papyr_get_device_options(const char* device_id);
+
+/**
+ * Set a scanner option. The value is given as text ("true", "300",
+ * "1.8", "Photo") and parsed against the option's type. The setting is
+ * kept and applied to later scans of the device.
+ * @param device_id Scanner device ID
+ * @param name Option name from PapyrDeviceOption.name
+ * @param value New value as text
+ * @return 0 on success, negative on error
+ */
+int papyr_set_device_option(const char* device_id, const char* name, const char* value);
+
+/**
+ * Start polling a scanner's hardware buttons and sensors.
+ * @param device_id Scanner device ID
+ * @param interval_ms Poll interval in milliseconds, 0 for the default (500)
+ * @return Monitor ID (positive integer) on success, negative on error or if
+ *         the device exposes no buttons or sensors
+ */
+int papyr_start_sensor_monitor(const char* device_id, int interval_ms);
+
+/**
+ * Wait for the next button or sensor event.
+ * @param monitor_id Monitor ID from papyr_start_sensor_monitor()
+ * @param timeout_ms Longest time to wait, 0 to only check
+ * @return Pointer to event, or NULL on timeout or error.
+ *         Must be freed with papyr_free_sensor_event()
+ */
+PapyrSensorEvent* papyr_next_sensor_event(int monitor_id, int timeout_ms);
+
+/**
+ * Stop a sensor monitor.
+ * @param monitor_id Monitor ID from papyr_start_sensor_monitor()
+ */
+void papyr_stop_sensor_monitor(int monitor_id);
+
+/**
+ * Get the error code of the last failed call on this thread.
+ * Every call that can fail resets it on entry.
+ * @return PapyrErrorCode, PAPYR_OK if the last call succeeded
+ */
+int papyr_last_error_code(void);
+
+/**
+ * Get a description of the last failed call on this thread.
+ * @return Message owned by the library and valid until the next papyr call
+ *         on this thread, or NULL if the last call succeeded
+ */
+const char* papyr_last_error_message(void);
+
+/**
+ * Check whether the last failure on this thread is transient (device busy,
+ * timeout, I/O error) and the call may succeed if repeated.
+ * @return 1 if retryable, 0 otherwise
+ */
+int papyr_last_error_retryable(void);
+
+/**
+ * Set the callback asked for credentials when a scanner or saned host
+ * requires authorization. Pass NULL to clear.
+ * @param callback Credentials callback, may be called from any thread
+ */
+void papyr_set_credentials_callback(PapyrCredentialsCallback callback);
+
+/**
+ * Set the callback receiving papyr's log lines, or clear it with NULL.
+ * Can be called again to change the callback or level.
+ * @param callback Log callback, may be called from any thread
+ * @param max_level Least severe PapyrLogLevel passed to the callback
+ * @return 0 on success, -1 on error (invalid level, or the host process
+ *         already installed a Rust tracing subscriber)
+ */
+int papyr_set_log_callback(PapyrLogCallback callback, int max_level);
+
 /**
  * Free scanner list memory.
  * @param list Scanner list to free
  */
 void papyr_free_scanner_list(PapyrScannerInfoList* list);
 
+/**
+ * Free a discovery result, including its scanner list.
+ * @param discovery Discovery result to free
+ */
+void papyr_free_discovery(PapyrDiscovery* discovery);
+
 /**
  * Free capabilities memory.
  * @param caps Capabilities to free
  */
 void papyr_free_capabilities(PapyrCapabilities* caps);
 
+/**
+ * Free device option list memory.
+ * @param list Option list to free
+ */
+void papyr_free_device_options(PapyrDeviceOptionList* list);
+
+/**
+ * Free sensor event memory.
+ * @param event Sensor event to free
+ */
+void papyr_free_sensor_event(PapyrSensorEvent* event);
+
 /**
  * Free scan event memory.
  * @param event Scan event to free
@@ -0,0 +1,177 @@
+//
+//  papyr_core
+//  include/papyr_plugin.h - C ABI for third-party backend plugins
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+//  A plugin is a shared library exporting papyr_plugin_entry(). papyr loads
+//  it with papyr_load_plugins() (or BackendRegistry::load_plugins) and
+//  lists its devices as BACKEND_PLUGIN devices with ids
+//  "plugin_<plugin name>/<device id>"; the plugin only ever sees its own
+//  device ids. See examples/plugin/example_plugin.c.
+//
+//  Threading: papyr calls enumerate, capabilities and start_scan one at a
+//  time. A session's next_event and free_session are called from one
+//  thread at a time, but cancel may be called from any thread while
+//  next_event is blocked.
+//
+//  Memory: everything a plugin hands back (device lists, capability arrays,
+//  page data, messages) stays owned by the plugin, and only has to stay
+//  valid until the next call on the same context or session.
+//
+//  The structures here are the plugin ABI's own and only change with
+//  PAPYR_PLUGIN_ABI_VERSION; papyr_core.h is used for its constants only.
+//
+
+#ifndef PAPYR_PLUGIN_H
+#define PAPYR_PLUGIN_H
+
+#include "papyr_core.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Bumped on any incompatible change to the structures below. A plugin
+// built for another version is refused when loading.
+#define PAPYR_PLUGIN_ABI_VERSION 2
+
+typedef struct {
+    const char* id;           // unique within the plugin, stable across runs
+    const char* name;
+    // Details below may be NULL when unknown
+    const char* manufacturer;
+    const char* model;
+    const char* serial;
+    const char* address;
+    int connection;           // PapyrConnectionKind
+} PapyrPluginDevice;
+
+typedef struct {
+    int event_type;           // PapyrScanEventType; failures are reported by
+                              // next_event's return value, not SCAN_EVENT_ERROR
+    uint32_t page;            // PAGE_STARTED, PAGE_COMPLETE: index from 0
+    const uint8_t* data;      // PAGE_DATA
+    size_t data_size;
+    uint32_t width_px;        // PAGE_COMPLETE
+    uint32_t height_px;
+    int dpi;
+    int dpi_y;                // 0 = same as dpi
+    int color_mode;           // PapyrColorMode
+    int bit_depth;            // bits per sample, 0 = mode default
+    uint64_t bytes;           // PROGRESS: bytes of the page so far
+    int64_t expected;         // PROGRESS: page size in bytes, -1 if unknown
+    int code;                 // STATUS: PapyrScanStatus, WARNING: PapyrWarningCode
+    const char* message;      // WARNING text
+} PapyrPluginEvent;
+
+typedef struct {
+    int min;
+    int max;
+    int step;                 // 0 = any value in between
+} PapyrPluginDpiRange;
+
+typedef struct {
+    const int* sources;       // PapyrScanSource
+    size_t sources_count;
+    const int* dpis;
+    size_t dpis_count;
+    const int* color_modes;   // PapyrColorMode
+    size_t color_modes_count;
+    int supports_duplex;      // 0 = false, 1 = true
+    const PapyrPluginDpiRange* dpi_ranges; // accepted besides dpis
+    size_t dpi_ranges_count;
+    int independent_dpi;      // 1 if dpi_y may differ from dpi
+    const int* bit_depths;    // bits per sample for color and gray, empty = 8
+    size_t bit_depths_count;
+} PapyrPluginCapabilities;
+
+// A plugin that can't apply a setting fails start_scan with
+// PAPYR_ERROR_UNSUPPORTED rather than ignoring it
+typedef struct {
+    int source;               // PapyrScanSource
+    int duplex;               // 0 = false, 1 = true
+    int dpi;
+    int dpi_y;                // 0 = same as dpi
+    int color_mode;           // PapyrColorMode
+    int bit_depth;            // bits per sample, 0 = mode default
+    double page_width_mm;
+    double page_height_mm;
+    int has_area;             // 1 to scan only the area below
+    double area_x_mm;         // from the top-left corner of the page
+    double area_y_mm;
+    double area_width_mm;
+    double area_height_mm;
+    int has_brightness;       // 1 if brightness is set
+    int brightness;           // device-specific range
+    int has_contrast;         // 1 if contrast is set
+    int contrast;             // device-specific range
+    int max_pages;            // stop after this many pages, 0 = no limit
+} PapyrPluginScanConfig;
+
+// Filled in by a function returning an error code other than PAPYR_OK
+typedef struct {
+    char message[256];        // NUL-terminated
+} PapyrPluginError;
+
+typedef struct {
+    uint32_t abi_version;     // PAPYR_PLUGIN_ABI_VERSION
+    uint32_t size;            // sizeof(PapyrPluginVTable)
+    const char* name;         // plugin name, no '/'; part of its device ids
+    void* context;            // passed to every call below
+
+    /**
+     * List the plugin's devices.
+     * @return PAPYR_OK with *devices and *count set, or a PapyrErrorCode
+     */
+    int (*enumerate)(void* context, const PapyrPluginDevice** devices, size_t* count,
+                     PapyrPluginError* error);
+
+    /**
+     * Fill in a device's capabilities; the arrays stay owned by the plugin.
+     * @return PAPYR_OK, or a PapyrErrorCode (PAPYR_ERROR_NOT_FOUND for
+     *         unknown devices)
+     */
+    int (*capabilities)(void* context, const char* device_id, PapyrPluginCapabilities* caps,
+                        PapyrPluginError* error);
+
+    /**
+     * Start a scan; *session is passed to the session functions below.
+     * @return PAPYR_OK, or a PapyrErrorCode
+     */
+    int (*start_scan)(void* context, const char* device_id,
+                      const PapyrPluginScanConfig* config, void** session,
+                      PapyrPluginError* error);
+
+    /**
+     * Fill in the session's next event. A session ends after
+     * SCAN_EVENT_JOB_COMPLETE, SCAN_EVENT_CANCELLED or an error.
+     * @return PAPYR_OK, or the PapyrErrorCode that ended the job
+     */
+    int (*next_event)(void* session, PapyrPluginEvent* event, PapyrPluginError* error);
+
+    // Optional (NULL): abort the session; next_event then reports
+    // SCAN_EVENT_CANCELLED
+    void (*cancel)(void* session);
+
+    // Release a session, finished or not
+    void (*free_session)(void* session);
+
+    // Optional (NULL): release the context when the plugin is unloaded
+    void (*destroy)(void* context);
+} PapyrPluginVTable;
+
+/**
+ * The entry point every plugin exports.
+ * @param host_abi_version PAPYR_PLUGIN_ABI_VERSION of the loading papyr
+ * @return The plugin's vtable, valid until destroy, or NULL to refuse
+ *         loading
+ */
+const PapyrPluginVTable* papyr_plugin_entry(uint32_t host_abi_version);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // PAPYR_PLUGIN_H
@@ -0,0 +1,128 @@
+//
+//  papyr_core
+//  async_api.rs - Async scanning on tokio (`async` feature)
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::*;
+use crate::session_guard::{guarded_stream, SessionLimits};
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+// Events a blocking session may read ahead of a slow consumer
+const EVENT_BUFFER: usize = 16;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A scan as a stream of events. A failure that ends the job is the last
+/// item; dropping the stream abandons the scan.
+pub type ScanEventStream = Pin<Box<dyn Stream<Item = Result<ScanEvent>> + Send>>;
+
+/// Runs `f` on tokio's blocking pool.
+pub async fn spawn_blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| PapyrError::Backend(format!("Blocking task failed: {}", e)))?
+}
+
+/// Devices of one backend, discovered on the blocking pool.
+pub async fn enumerate(provider: Arc<dyn BackendProvider>) -> Vec<ScannerInfo> {
+    spawn_blocking(move || Ok(provider.enumerate()))
+        .await
+        .unwrap_or_default()
+}
+
+pub async fn capabilities(
+    provider: Arc<dyn BackendProvider>,
+    device_id: &DeviceId,
+) -> Result<Capabilities> {
+    let device_id = device_id.clone();
+    spawn_blocking(move || provider.capabilities(&device_id)).await
+}
+
+/// Starts a scan on `provider`, natively async where the backend supports
+/// it (eSCL) and otherwise with the session running on the blocking pool.
+/// The stream is guarded by `config.max_pages` and `limits` as registry
+/// sessions are.
+pub async fn start_scan(
+    provider: Arc<dyn BackendProvider>,
+    device_id: &DeviceId,
+    config: ScanConfig,
+    limits: SessionLimits,
+) -> Result<ScanEventStream> {
+    let backend = provider.kind();
+    let max_pages = config.max_pages;
+    let events = match provider.start_scan_async(device_id, &config) {
+        Some(scan) => scan.await?,
+        None => {
+            let device_id = device_id.clone();
+            let session = spawn_blocking(move || provider.start_scan(&device_id, config)).await?;
+            blocking_stream(session)
+        }
+    };
+    Ok(guarded_stream(events, backend, max_pages, limits))
+}
+
+/// Drives a blocking session on tokio's blocking pool and streams its
+/// events. Dropping the stream cancels the session if it can be cancelled.
+/// Must be called from within a tokio runtime.
+pub fn blocking_stream(mut session: Box<dyn ScanSession>) -> ScanEventStream {
+    let cancel = session.cancel_handle();
+    let (tx, events) = mpsc::channel(EVENT_BUFFER);
+
+    tokio::task::spawn_blocking(move || loop {
+        let (event, last) = match session.next_event() {
+            Ok(Some(event)) => (Ok(event), false),
+            Ok(None) => break,
+            Err(e) => (Err(e), true),
+        };
+        // A closed channel means the stream was dropped
+        if tx.blocking_send(event).is_err() || last {
+            break;
+        }
+    });
+
+    Box::pin(BlockingEvents {
+        events,
+        cancel,
+        done: false,
+    })
+}
+
+struct BlockingEvents {
+    events: mpsc::Receiver<Result<ScanEvent>>,
+    cancel: Option<CancelHandle>,
+    done: bool,
+}
+
+impl Stream for BlockingEvents {
+    type Item = Result<ScanEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.events.poll_recv(cx);
+        if let Poll::Ready(None) = poll {
+            self.done = true;
+        }
+        poll
+    }
+}
+
+impl Drop for BlockingEvents {
+    fn drop(&mut self) {
+        if !self.done {
+            if let Some(cancel) = &self.cancel {
+                cancel.cancel();
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//
+//  papyr_core
+//  auth.rs - Credentials for backends that ask the user to authorize a resource
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use std::sync::{Arc, RwLock};
+
+/// Marker SANE puts between a resource name and its MD5 challenge salt.
+const MD5_MARKER: &str = "$MD5$";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+}
+
+/// Supplies credentials when a backend asks to authorize `resource`
+/// (e.g. a SANE device name or a protected saned host).
+///
+/// Returning `None` declines; the backend then fails with
+/// `PapyrError::Unauthorized`.
+pub trait CredentialsProvider: Send + Sync {
+    fn credentials(&self, resource: &str) -> Option<Credentials>;
+}
+
+impl<F> CredentialsProvider for F
+where
+    F: Fn(&str) -> Option<Credentials> + Send + Sync,
+{
+    fn credentials(&self, resource: &str) -> Option<Credentials> {
+        self(resource)
+    }
+}
+
+/// Same credentials for every resource.
+impl CredentialsProvider for Credentials {
+    fn credentials(&self, _resource: &str) -> Option<Credentials> {
+        Some(self.clone())
+    }
+}
+
+static PROVIDER: RwLock<Option<Arc<dyn CredentialsProvider>>> = RwLock::new(None);
+
+/// Installs the process-wide provider consulted by backends that have no
+/// provider of their own. libsane only accepts one callback per process,
+/// so this is global rather than per registry.
+pub fn set_credentials_provider(provider: Option<Arc<dyn CredentialsProvider>>) {
+    if let Ok(mut guard) = PROVIDER.write() {
+        *guard = provider;
+    }
+}
+
+/// Asks the process-wide provider for `resource`.
+pub fn credentials_for(resource: &str) -> Option<Credentials> {
+    let provider = PROVIDER.read().ok()?.clone()?;
+    provider.credentials(resource)
+}
+
+/// Splits `device$MD5$salt` into the resource name and challenge salt.
+pub fn split_md5_resource(resource: &str) -> (&str, Option<&str>) {
+    match resource.find(MD5_MARKER) {
+        Some(pos) => (&resource[..pos], Some(&resource[pos + MD5_MARKER.len()..])),
+        None => (resource, None),
+    }
+}
+
+/// Password to send for `resource`: the plain password, or for MD5
+/// challenges `$MD5$` followed by the hex digest of salt + password.
+pub fn encode_password(resource: &str, password: &str) -> String {
+    match split_md5_resource(resource) {
+        (_, Some(salt)) => format!(
+            "{}{:x}",
+            MD5_MARKER,
+            md5::compute(format!("{}{}", salt, password))
+        ),
+        (_, None) => password.to_string(),
+    }
+}
@@ -6,11 +6,17 @@
 //  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
 //
 
+#[cfg(feature = "async")]
+use crate::async_api::{BoxFuture, ScanEventStream};
+use crate::backends::OptionOverrides;
 use crate::models::*;
 use mdns_sd::{ScopedIp, ServiceDaemon, ServiceEvent};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, trace, warn, Span};
 
 // Multiple eSCL service types
 const ESCL_SERVICES: &[&str] = &[
@@ -22,21 +28,60 @@ const ESCL_SERVICES: &[&str] = &[
 // Extended discovery timeout
 const DISCOVERY_TIMEOUT_SECS: u64 = 10;
 
+// How often discovery checks whether it has been cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Size of the PageData events a downloading document is split into
+const DOCUMENT_CHUNK_SIZE: usize = 64 * 1024;
+
+// How long to keep retrying while the scanner answers 503 (busy)
+pub(crate) const BUSY_RETRY_SECS: u64 = 30;
+pub(crate) const BUSY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Intents offered when the device doesn't list its own
+const DEFAULT_INTENTS: &[&str] = &["Document", "TextAndGraphic", "Photo", "Preview"];
+
+// Ranged ScanSettings elements: (element, title, advanced). The device
+// advertises each as `<element>Support` with Min/Max/Normal/Step.
+const RANGE_SETTINGS: &[(&str, &str, bool)] = &[
+    ("Brightness", "Brightness", false),
+    ("Contrast", "Contrast", false),
+    ("Sharpen", "Sharpen", false),
+    ("Threshold", "Black & white threshold", false),
+    ("Gamma", "Gamma", true),
+    ("Highlight", "Highlight", true),
+    ("Shadow", "Shadow", true),
+    ("NoiseRemoval", "Noise removal", true),
+    ("CompressionFactor", "Compression factor", true),
+];
+
+// Boolean ScanSettings elements advertised as `<element>true</element>`
+const FLAG_SETTINGS: &[(&str, &str)] = &[
+    ("BlankPageDetection", "Detect blank pages"),
+    ("BlankPageDetectionAndRemoval", "Remove blank pages"),
+];
+
 pub struct EsclBackend {
-    discovered_scanners: Arc<Mutex<HashMap<String, EsclDevice>>>,
+    discovered_scanners: Arc<Mutex<HashMap<DeviceId, EsclDevice>>>,
+    // Configured scanners, listed whether or not mDNS finds them
+    static_devices: Vec<EsclDevice>,
+    mdns: bool,
+    overrides: OptionOverrides,
 }
 
 #[derive(Clone, Debug)]
-struct EsclDevice {
-    id: String,
+pub(crate) struct EsclDevice {
+    id: DeviceId,
     name: String,
     host: String,
     port: u16,
     use_https: bool,
+    /// TXT record, keys lowercased.
+    txt: HashMap<String, String>,
 }
 
 impl EsclDevice {
-    fn base_url(&self) -> String {
+    pub(crate) fn base_url(&self) -> String {
         let protocol = if self.use_https { "https" } else { "http" };
         // Handle IPv6 addresses properly
         let host = if self.host.contains(':') && !self.host.starts_with('[') {
@@ -56,11 +101,128 @@ impl EsclDevice {
     }
 }
 
+impl EsclDevice {
+    /// A configured scanner: `host[:port]`, or a URL whose `https` scheme
+    /// selects TLS. `None` if the entry can't be parsed.
+    fn from_host(entry: &str) -> Option<Self> {
+        let entry = entry.trim().trim_end_matches('/');
+        let (use_https, address) = match entry.split_once("://") {
+            Some(("https", address)) => (true, address),
+            Some(("http", address)) => (false, address),
+            Some(_) => return None,
+            None => (false, entry),
+        };
+        // Anything after the authority is ignored: the eSCL root is fixed
+        let address = address.split('/').next()?;
+        let default_port = if use_https { 443 } else { 80 };
+
+        let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+            // [IPv6]:port
+            let (host, port) = rest.split_once(']')?;
+            let port = match port.strip_prefix(':') {
+                Some(port) => port.parse().ok()?,
+                None => default_port,
+            };
+            (host.to_string(), port)
+        } else {
+            match address.rsplit_once(':') {
+                Some((host, port)) if !host.contains(':') => (host.to_string(), port.parse().ok()?),
+                _ => (address.to_string(), default_port),
+            }
+        };
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            id: DeviceId::new(
+                Backend::Escl,
+                format!("static_{}_{}", host.replace(['.', ':'], "_"), port),
+            ),
+            name: host.clone(),
+            host,
+            port,
+            use_https,
+            txt: HashMap::new(),
+        })
+    }
+}
+
 impl EsclBackend {
     pub fn new() -> Self {
         Self {
             discovered_scanners: Arc::new(Mutex::new(HashMap::new())),
+            static_devices: Vec::new(),
+            mdns: true,
+            overrides: OptionOverrides::default(),
+        }
+    }
+
+    /// Also lists scanners at fixed addresses (`host[:port]` or an
+    /// `http(s)://` URL), for networks where mDNS doesn't reach them.
+    /// Entries that can't be parsed are skipped.
+    pub fn with_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for host in hosts {
+            match EsclDevice::from_host(host.as_ref()) {
+                Some(device) => self.static_devices.push(device),
+                None => warn!(host = host.as_ref(), "ignoring invalid eSCL host"),
+            }
+        }
+        self
+    }
+
+    /// Turns mDNS discovery on or off; with it off only the configured
+    /// hosts are listed.
+    pub fn with_mdns(mut self, enabled: bool) -> Self {
+        self.mdns = enabled;
+        self
+    }
+
+    fn device(&self, device_id: &DeviceId) -> Result<EsclDevice> {
+        let discovered = self
+            .discovered_scanners
+            .lock()
+            .map_err(|_| PapyrError::Backend("Failed to lock discovered scanners".into()))?;
+
+        discovered
+            .get(device_id)
+            .cloned()
+            .ok_or_else(|| PapyrError::NotFound(format!("Device {} not found", device_id)))
+    }
+
+    fn fetch_capabilities(&self, device: &EsclDevice) -> Result<String> {
+        // Use blocking client to fetch actual capabilities
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| PapyrError::Backend(format!("Failed to create HTTP client: {}", e)))?;
+
+        let url = format!("{}/ScannerCapabilities", device.base_url());
+        debug!(%url, "fetching capabilities");
+
+        let response = client
+            .get(&url)
+            .send()
+            .map_err(|e| request_error("Failed to fetch capabilities", e))?;
+        if !response.status().is_success() {
+            return Err(error_for_status(
+                "Failed to fetch capabilities",
+                response.status().as_u16(),
+                "",
+                None,
+            ));
         }
+
+        let xml = response
+            .text()
+            .map_err(|e| request_error("Failed to read capabilities", e))?;
+        debug!(bytes = xml.len(), "capabilities received");
+        Ok(xml)
     }
 
     fn is_valid_address(&self, addr: &ScopedIp) -> bool {
@@ -68,14 +230,55 @@ impl EsclBackend {
         !addr.to_string().is_empty()
     }
 
-    fn discover_scanners(&self) -> Result<Vec<ScannerInfo>> {
+    /// Browses mDNS (if enabled) and adds the configured hosts. Cancelling
+    /// keeps the devices found so far and leaves earlier discoveries in
+    /// place.
+    fn discover_scanners(&self, cancel: &CancelToken) -> Result<Vec<ScannerInfo>> {
+        let mut devices = if self.mdns {
+            self.browse_mdns(cancel)?
+        } else {
+            Vec::new()
+        };
+        devices.extend(self.static_devices.iter().cloned());
+
+        // Store discovered devices
+        if let Ok(mut discovered) = self.discovered_scanners.lock() {
+            if !cancel.is_cancelled() {
+                discovered.clear();
+            }
+            for device in &devices {
+                discovered.insert(device.id.clone(), device.clone());
+            }
+        }
+
+        // Convert to ScannerInfo
+        let scanners: Vec<ScannerInfo> = devices
+            .into_iter()
+            .map(|device| {
+                let address = if device.port == 80 || device.port == 443 {
+                    device.host.clone()
+                } else {
+                    format!("{}:{}", device.host, device.port)
+                };
+                scanner_info_from_txt(device.id, &device.name, &address, &device.txt)
+            })
+            .collect();
+
+        Ok(scanners)
+    }
+
+    /// Browses mDNS for every eSCL service type.
+    fn browse_mdns(&self, cancel: &CancelToken) -> Result<Vec<EsclDevice>> {
         use std::sync::mpsc::channel;
         use std::thread;
 
         let (tx, rx) = channel();
+        let thread_cancel = cancel.clone();
+        let span = Span::current();
 
         // Spawn discovery thread without capturing self
         thread::spawn(move || {
+            let _entered = span.enter();
             let rt = match tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()
@@ -91,22 +294,27 @@ impl EsclBackend {
                 let mdns = match ServiceDaemon::new() {
                     Ok(mdns) => mdns,
                     Err(e) => {
-                        println!("Failed to create mDNS daemon: {:?}", e);
+                        warn!("failed to create mDNS daemon: {}", e);
                         return Vec::new();
                     }
                 };
 
-                println!("🌐 Starting eSCL discovery for multiple service types...");
+                debug!("starting mDNS discovery");
                 let mut all_scanners = Vec::new();
 
                 // Discover each service type
                 for service_type in ESCL_SERVICES {
-                    println!("🔍 Browsing for service: {}", service_type);
+                    if thread_cancel.is_cancelled() {
+                        debug!("mDNS discovery cancelled");
+                        break;
+                    }
+                    debug!(service = service_type, "browsing");
 
                     match mdns.browse(service_type) {
                         Ok(receiver) => {
                             let timeout = tokio::time::sleep(Duration::from_secs(DISCOVERY_TIMEOUT_SECS));
                             tokio::pin!(timeout);
+                            let mut cancel_poll = tokio::time::interval(CANCEL_POLL_INTERVAL);
 
                             let mut service_scanners = Vec::new();
 
@@ -115,33 +323,40 @@ impl EsclBackend {
                                     event = receiver.recv_async() => {
                                         match event {
                                             Ok(ServiceEvent::ServiceResolved(info)) => {
-                                                println!("📡 Found service: {}", info.get_fullname());
 
                                                 let name = info.get_fullname()
                                                     .trim_end_matches('.').to_string();
 
                                                 // Get the best available address
                                                 let addresses: Vec<_> = info.get_addresses().iter()
-                                                    .cloned()
                                                     .filter(|addr| !addr.to_string().is_empty())
+                                                    .cloned()
                                                     .collect();
 
-                                                println!("Available addresses: {:?}", addresses);
+                                                trace!(
+                                                    service = info.get_fullname(),
+                                                    ?addresses,
+                                                    "service resolved"
+                                                );
 
                                                 if let Some(addr) = addresses.first() {
                                                     let host = addr.to_string();
                                                     let port = info.get_port();
                                                     let use_https = service_type.contains("uscans") || service_type.contains("airscan");
+                                                    let txt = info.get_properties().iter()
+                                                        .map(|property| (property.key().to_lowercase(), property.val_str().to_string()))
+                                                        .collect();
 
                                                     let device = EsclDevice {
-                                                        id: format!("escl_{}", name.replace('.', "_")),
+                                                        id: DeviceId::new(Backend::Escl, name.replace('.', "_")),
                                                         name: name.clone(),
                                                         host,
                                                         port,
                                                         use_https,
+                                                        txt,
                                                     };
 
-                                                    println!("✅ Added device: {} at {}", device.name, device.base_url());
+                                                    debug!(name = %device.name, url = %device.base_url(), "found device");
                                                     service_scanners.push(device);
                                                 }
                                             },
@@ -150,21 +365,26 @@ impl EsclBackend {
                                         }
                                     }
                                     _ = &mut timeout => {
-                                        println!("🕒 Discovery timeout for {}", service_type);
+                                        trace!(service = service_type, "browse timed out");
                                         break;
                                     }
+                                    _ = cancel_poll.tick() => {
+                                        if thread_cancel.is_cancelled() {
+                                            break;
+                                        }
+                                    }
                                 }
                             }
 
                             all_scanners.extend(service_scanners);
                         },
                         Err(e) => {
-                            println!("Failed to browse {}: {:?}", service_type, e);
+                            warn!(service = service_type, "failed to browse: {}", e);
                         }
                     }
                 }
 
-                println!("🎯 Total eSCL devices discovered: {}", all_scanners.len());
+                debug!(devices = all_scanners.len(), "mDNS discovery finished");
                 all_scanners
             });
 
@@ -172,128 +392,571 @@ impl EsclBackend {
         });
 
         // Wait for discovery to complete
-        let devices = rx
-            .recv()
-            .map_err(|_| PapyrError::Backend("eSCL discovery thread failed".into()))?;
+        rx.recv()
+            .map_err(|_| PapyrError::Backend("eSCL discovery thread failed".into()))
+    }
+}
 
-        // Store discovered devices
-        if let Ok(mut discovered) = self.discovered_scanners.lock() {
-            discovered.clear();
-            for device in &devices {
-                discovered.insert(device.id.clone(), device.clone());
+impl BackendProvider for EsclBackend {
+    fn name(&self) -> &'static str {
+        "eSCL (AirPrint/AirScan)"
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::Escl
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        self.enumerate_cancellable(&CancelToken::new())
+    }
+
+    fn enumerate_cancellable(&self, cancel: &CancelToken) -> Vec<ScannerInfo> {
+        self.discover_scanners(cancel).unwrap_or_default()
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        self.capabilities_cancellable(device_id, &CancelToken::new())
+    }
+
+    fn capabilities_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cancel: &CancelToken,
+    ) -> Result<Capabilities> {
+        let device = self.device(device_id)?;
+        cancel.check()?;
+
+        let fetched = self.fetch_capabilities(&device);
+        cancel.check()?;
+        match fetched {
+            Ok(xml) => Ok(capabilities_from_xml(&xml)),
+            Err(e) => {
+                warn!(device = %device_id, "{}, using default capabilities", e);
+                Ok(self.default_capabilities())
             }
         }
+    }
 
-        // Convert to ScannerInfo
-        let scanners: Vec<ScannerInfo> = devices
+    fn start_scan(&self, device_id: &DeviceId, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        let device = self.device(device_id)?;
+        let options = self.overrides.get(device_id);
+
+        Ok(Box::new(EsclScanSession::new(device, config, options)?))
+    }
+
+    #[cfg(feature = "async")]
+    fn start_scan_async(
+        &self,
+        device_id: &DeviceId,
+        config: &ScanConfig,
+    ) -> Option<BoxFuture<'static, Result<ScanEventStream>>> {
+        let device = self.device(device_id);
+        let options = self.overrides.get(device_id);
+        let config = config.clone();
+        Some(Box::pin(async move {
+            super::escl_async::scan_stream(device?, config, options)
+        }))
+    }
+
+    fn options(&self, device_id: &DeviceId) -> Result<Vec<DeviceOption>> {
+        let device = self.device(device_id)?;
+        let xml = self.fetch_capabilities(&device)?;
+
+        let mut options = options_from_capabilities(&xml);
+        for (name, value) in self.overrides.get(device_id) {
+            if let Some(option) = options.iter_mut().find(|o| o.name == name) {
+                option.value = Some(value);
+            }
+        }
+        Ok(options)
+    }
+
+    fn set_option(
+        &self,
+        device_id: &DeviceId,
+        name: &str,
+        value: OptionValue,
+    ) -> Result<DeviceOption> {
+        let mut option = self
+            .options(device_id)?
             .into_iter()
-            .map(|device| ScannerInfo {
-                id: device.id.clone(),
-                name: device.name.clone(),
-                backend: Backend::Escl,
-            })
-            .collect();
+            .find(|o| o.name == name)
+            .ok_or_else(|| PapyrError::InvalidConfig(format!("unknown option {}", name)))?;
 
-        Ok(scanners)
+        let value = option.coerce(value)?;
+        self.overrides.set(device_id, name, value.clone());
+        option.value = Some(value);
+        Ok(option)
     }
+}
 
-    fn parse_capabilities(&self, xml: &str) -> Result<Capabilities> {
-        // Simple XML parsing for now
-        let dpis = vec![75, 150, 300, 600];
-        let color_modes = vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw];
-        let mut sources = vec![ScanSource::Flatbed];
+/// Inner text of every element named `tag` in `xml`, with or without a
+/// namespace prefix.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
 
-        // Look for ADF support
-        if xml.contains("ADF") || xml.contains("DocumentFeeder") || xml.contains("Feeder") {
-            sources.push(ScanSource::Adf);
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let element = &rest[..end];
+        if element.starts_with(['/', '?', '!']) || element.ends_with('/') {
+            continue;
+        }
+        let qname = element.split_whitespace().next().unwrap_or(element);
+        if qname.rsplit(':').next() != Some(tag) {
+            continue;
         }
 
-        // Look for duplex support
-        let supports_duplex = xml.contains("Duplex") || xml.contains("TwoSided");
-
-        Ok(Capabilities {
-            sources,
-            dpis,
-            color_modes,
-            page_sizes: vec![
-                PageSize {
-                    width_mm: 216,
-                    height_mm: 279,
-                }, // Letter
-                PageSize {
-                    width_mm: 210,
-                    height_mm: 297,
-                }, // A4
-            ],
-            supports_duplex,
-        })
+        let body = &rest[end + 1..];
+        if let Some(close) = body.find(&format!("</{}>", qname)) {
+            found.push(body[..close].trim());
+        }
     }
+
+    found
 }
 
-impl BackendProvider for EsclBackend {
-    fn name(&self) -> &'static str {
-        "eSCL (AirPrint/AirScan)"
+fn xml_number(xml: &str, tag: &str) -> Option<f64> {
+    xml_elements(xml, tag).first()?.parse().ok()
+}
+
+// Offered from within a ResolutionRange, as for SANE range devices
+const COMMON_DPIS: &[u32] = &[75, 100, 150, 200, 300, 600, 1200, 2400];
+
+/// Scan capabilities read from a `ScannerCapabilities` document.
+pub fn capabilities_from_xml(xml: &str) -> Capabilities {
+    let mut dpis: Vec<u32> = xml_elements(xml, "DiscreteResolution")
+        .into_iter()
+        .filter_map(|resolution| xml_number(resolution, "XResolution"))
+        .map(|dpi| dpi.round() as u32)
+        .collect();
+
+    // Listed once per input source
+    let mut dpi_ranges: Vec<DpiRange> = Vec::new();
+    for range in xml_elements(xml, "XResolutionRange") {
+        let (Some(min), Some(max)) = (xml_number(range, "Min"), xml_number(range, "Max")) else {
+            continue;
+        };
+        let range = DpiRange {
+            min: min.round() as u32,
+            max: max.round() as u32,
+            step: xml_number(range, "Step").map_or(1, |step| (step.round() as u32).max(1)),
+        };
+        if !dpi_ranges.contains(&range) {
+            dpi_ranges.push(range);
+        }
+    }
+    for range in &dpi_ranges {
+        dpis.extend(COMMON_DPIS.iter().filter(|dpi| range.contains(**dpi)));
+    }
+    dpis.sort_unstable();
+    dpis.dedup();
+    if dpis.is_empty() && dpi_ranges.is_empty() {
+        dpis = vec![75, 150, 300, 600];
     }
 
-    fn kind(&self) -> Backend {
-        Backend::Escl
+    // Discrete pairs tie X to Y unless some pair differs
+    let independent_dpi = !xml_elements(xml, "YResolutionRange").is_empty()
+        || xml_elements(xml, "DiscreteResolution")
+            .into_iter()
+            .any(|resolution| {
+                let x = xml_number(resolution, "XResolution");
+                let y = xml_number(resolution, "YResolution");
+                matches!((x, y), (Some(x), Some(y)) if x != y)
+            });
+
+    let mut color_modes = Vec::new();
+    let mut bit_depths = Vec::new();
+    for mode in xml_elements(xml, "ColorMode") {
+        let (color_mode, depth) = match mode {
+            "RGB24" => (ColorMode::Color, Some(8)),
+            "RGB48" => (ColorMode::Color, Some(16)),
+            "Grayscale8" => (ColorMode::Gray, Some(8)),
+            "Grayscale16" => (ColorMode::Gray, Some(16)),
+            "BlackAndWhite1" => (ColorMode::Bw, None),
+            _ => continue,
+        };
+        if !color_modes.contains(&color_mode) {
+            color_modes.push(color_mode);
+        }
+        if let Some(depth) = depth.filter(|depth| !bit_depths.contains(depth)) {
+            bit_depths.push(depth);
+        }
+    }
+    if color_modes.is_empty() {
+        color_modes = vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw];
+    }
+    bit_depths.sort_unstable();
+    if bit_depths.is_empty() {
+        bit_depths.push(8);
     }
 
-    fn enumerate(&self) -> Vec<ScannerInfo> {
-        self.discover_scanners().unwrap_or_default()
+    let mut sources = vec![ScanSource::Flatbed];
+
+    // Look for ADF support (eSCL spells the element `Adf`)
+    if xml.contains("ADF")
+        || xml.contains("Adf")
+        || xml.contains("DocumentFeeder")
+        || xml.contains("Feeder")
+    {
+        sources.push(ScanSource::Adf);
     }
 
-    fn capabilities(&self, device_id: &str) -> Result<Capabilities> {
-        let discovered = self
-            .discovered_scanners
-            .lock()
-            .map_err(|_| PapyrError::Backend("Failed to lock discovered scanners".into()))?;
+    // Look for duplex support
+    let supports_duplex = xml.contains("Duplex") || xml.contains("TwoSided");
+    if supports_duplex && sources.contains(&ScanSource::Adf) {
+        sources.push(ScanSource::AdfDuplex);
+    }
 
-        let device = discovered
-            .values()
-            .find(|d| d.id == device_id)
-            .ok_or_else(|| PapyrError::NotFound(format!("Device {} not found", device_id)))?;
+    // Input caps give the maximum area in 1/300 inch
+    let mut max_areas = Vec::new();
+    for (element, source) in [
+        ("PlatenInputCaps", ScanSource::Flatbed),
+        ("AdfSimplexInputCaps", ScanSource::Adf),
+        ("AdfDuplexInputCaps", ScanSource::AdfDuplex),
+    ] {
+        let Some(caps) = xml_elements(xml, element).into_iter().next() else {
+            continue;
+        };
+        if let (Some(width), Some(height)) =
+            (xml_number(caps, "MaxWidth"), xml_number(caps, "MaxHeight"))
+        {
+            max_areas.push(SourceArea {
+                source,
+                max: PageSize::from_three_hundredths(width.round() as u32, height.round() as u32),
+            });
+        }
+    }
+    // Duplex pages go through the same feeder
+    if let Some(adf) = max_areas
+        .iter()
+        .find(|area| area.source == ScanSource::Adf)
+        .copied()
+    {
+        if sources.contains(&ScanSource::AdfDuplex)
+            && !max_areas
+                .iter()
+                .any(|area| area.source == ScanSource::AdfDuplex)
+        {
+            max_areas.push(SourceArea {
+                source: ScanSource::AdfDuplex,
+                ..adf
+            });
+        }
+    }
 
-        // Use blocking client to fetch actual capabilities
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .danger_accept_invalid_certs(true)
-            .build()
-            .map_err(|e| PapyrError::Backend(format!("Failed to create HTTP client: {}", e)))?;
+    Capabilities {
+        sources,
+        dpis,
+        dpi_ranges,
+        independent_dpi,
+        color_modes,
+        bit_depths,
+        page_sizes: page_formats_for(&max_areas),
+        max_areas,
+        supports_duplex,
+    }
+}
 
-        let url = format!("{}/ScannerCapabilities", device.base_url());
-        println!("🔍 Fetching capabilities from: {}", url);
+/// ScannerInfo for a device announced as the mDNS service `fullname` at
+/// `address`, from its TXT record (keys lowercased): `ty`, `usb_MFG`,
+/// `usb_MDL`, `UUID`, `note`, `adminurl` and `representation`.
+pub fn scanner_info_from_txt(
+    id: DeviceId,
+    fullname: &str,
+    address: &str,
+    txt: &HashMap<String, String>,
+) -> ScannerInfo {
+    let value = |key: &str| {
+        txt.get(key)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    // "HP OfficeJet Pro 9010._uscan._tcp.local" -> "HP OfficeJet Pro 9010"
+    let instance = fullname.split("._").next().unwrap_or(fullname);
+    let make_and_model = value("ty");
+
+    let manufacturer = value("usb_mfg").or_else(|| value("mfg")).or_else(|| {
+        // The make is conventionally the first word of `ty`
+        make_and_model
+            .as_deref()
+            .and_then(|ty| ty.split_whitespace().next())
+            .map(str::to_string)
+    });
+    let model = value("usb_mdl").or_else(|| value("mdl")).or_else(|| {
+        let ty = make_and_model.as_deref()?;
+        let model = match &manufacturer {
+            Some(make) => ty.strip_prefix(make.as_str()).unwrap_or(ty).trim(),
+            None => ty,
+        };
+        (!model.is_empty()).then(|| model.to_string())
+    });
+
+    ScannerInfo {
+        manufacturer,
+        model,
+        serial: value("uuid"),
+        connection: ConnectionKind::Network,
+        address: Some(address.to_string()),
+        location: value("note"),
+        admin_url: value("adminurl"),
+        icon_url: value("representation"),
+        ..ScannerInfo::new(id, instance)
+    }
+}
 
-        match client.get(&url).send() {
-            Ok(response) if response.status().is_success() => match response.text() {
-                Ok(xml) => {
-                    println!("📄 Capabilities XML received ({} bytes)", xml.len());
-                    self.parse_capabilities(&xml)
-                }
-                Err(_) => {
-                    println!("⚠️  Failed to read capabilities, using defaults");
-                    Ok(self.default_capabilities())
-                }
+/// eSCL `ColorMode` for a config, e.g. RGB48 for 16-bit colour.
+pub(crate) fn color_mode_setting(config: &ScanConfig) -> &'static str {
+    match (config.color_mode, config.bits_per_sample()) {
+        (ColorMode::Color, 16) => "RGB48",
+        (ColorMode::Color, _) => "RGB24",
+        (ColorMode::Gray, 16) => "Grayscale16",
+        (ColorMode::Gray, _) => "Grayscale8",
+        (ColorMode::Bw, _) => "BlackAndWhite1",
+    }
+}
+
+/// Extra ScanSettings a device supports, read from its
+/// `ScannerCapabilities` document.
+pub fn options_from_capabilities(xml: &str) -> Vec<DeviceOption> {
+    let mut options = Vec::new();
+
+    // Listed once per input source, so collect without duplicates
+    let mut intents: Vec<&str> = Vec::new();
+    for list in xml_elements(xml, "SupportedIntents") {
+        let listed = xml_elements(list, "Intent")
+            .into_iter()
+            .chain(xml_elements(list, "SupportedIntent"));
+        for intent in listed {
+            if !intents.contains(&intent) {
+                intents.push(intent);
+            }
+        }
+    }
+    if intents.is_empty() {
+        intents = DEFAULT_INTENTS.to_vec();
+    }
+    options.push(DeviceOption {
+        name: "Intent".into(),
+        title: "Scan intent".into(),
+        description: "Tunes image processing for the kind of original".into(),
+        group: Some("Scan".into()),
+        value_type: OptionType::String,
+        unit: OptionUnit::None,
+        constraint: OptionConstraint::List(
+            intents
+                .iter()
+                .map(|i| OptionValue::String(i.to_string()))
+                .collect(),
+        ),
+        value: Some(OptionValue::String(
+            if intents.contains(&"Document") {
+                "Document"
+            } else {
+                intents[0]
+            }
+            .to_string(),
+        )),
+        settable: true,
+        advanced: false,
+    });
+
+    for (element, title, advanced) in RANGE_SETTINGS {
+        let Some(support) = xml_elements(xml, &format!("{}Support", element))
+            .into_iter()
+            .next()
+        else {
+            continue;
+        };
+        let (Some(min), Some(max)) = (xml_number(support, "Min"), xml_number(support, "Max"))
+        else {
+            continue;
+        };
+
+        options.push(DeviceOption {
+            name: element.to_string(),
+            title: title.to_string(),
+            description: String::new(),
+            group: Some("Image".into()),
+            value_type: OptionType::Int,
+            unit: OptionUnit::None,
+            constraint: OptionConstraint::Range {
+                min,
+                max,
+                step: xml_number(support, "Step"),
             },
-            _ => {
-                println!("⚠️  Failed to fetch capabilities, using defaults");
-                Ok(self.default_capabilities())
+            value: xml_number(support, "Normal").map(|n| OptionValue::Int(n.round() as i32)),
+            settable: true,
+            advanced: *advanced,
+        });
+    }
+
+    for (element, title) in FLAG_SETTINGS {
+        if xml_elements(xml, element).contains(&"true") {
+            options.push(DeviceOption {
+                name: element.to_string(),
+                title: title.to_string(),
+                description: String::new(),
+                group: Some("Feeder".into()),
+                value_type: OptionType::Bool,
+                unit: OptionUnit::None,
+                constraint: OptionConstraint::None,
+                value: Some(OptionValue::Bool(false)),
+                settable: true,
+                advanced: false,
+            });
+        }
+    }
+
+    options
+}
+
+/// Error for a failed HTTP request to the scanner.
+pub(crate) fn request_error(context: &str, e: reqwest::Error) -> PapyrError {
+    let backend = Backend::Escl;
+    let message = format!("{}: {}", context, e);
+    if e.is_timeout() {
+        PapyrError::Timeout { backend, message }
+    } else if e.is_decode() {
+        PapyrError::Protocol { backend, message }
+    } else {
+        PapyrError::Io { backend, message }
+    }
+}
+
+/// Error for a failed read of a document body.
+fn read_error(e: std::io::Error) -> PapyrError {
+    let backend = Backend::Escl;
+    let message = format!("Failed to read document data: {}", e);
+    if e.kind() == std::io::ErrorKind::TimedOut {
+        PapyrError::Timeout { backend, message }
+    } else {
+        PapyrError::Io { backend, message }
+    }
+}
+
+/// Error for an unexpected HTTP status. `scanner_status` is the device's
+/// `ScannerStatus` document, consulted to explain a refused job (most
+/// scanners answer 409 or 503 for an empty, jammed or open feeder).
+pub fn error_for_status(
+    context: &str,
+    status: u16,
+    body: &str,
+    scanner_status: Option<&str>,
+) -> PapyrError {
+    let backend = Backend::Escl;
+    let message = format!("{}: HTTP {} {}", context, status, body.trim())
+        .trim_end()
+        .to_string();
+
+    if matches!(status, 409 | 503) {
+        if let Some(error) = scanner_status.and_then(|xml| scanner_state_error(xml, &message)) {
+            return error;
+        }
+    }
+
+    match status {
+        400 => PapyrError::InvalidConfig(message),
+        401 | 403 => PapyrError::Unauthorized { backend, message },
+        409 | 503 => PapyrError::DeviceBusy { backend, message },
+        _ => PapyrError::Protocol { backend, message },
+    }
+}
+
+/// Maps the ADF state of a `ScannerStatus` document to an error, if it
+/// reports a problem.
+fn scanner_state_error(xml: &str, message: &str) -> Option<PapyrError> {
+    let backend = Backend::Escl;
+    let message = |state: &str| format!("{} ({})", message, state);
+
+    for state in xml_elements(xml, "AdfState") {
+        match state {
+            "ScannerAdfEmpty" => {
+                return Some(PapyrError::FeederEmpty {
+                    backend,
+                    message: message(state),
+                })
+            }
+            "ScannerAdfJam"
+            | "ScannerAdfMispick"
+            | "ScannerAdfMultipickDetected"
+            | "ScannerAdfDuplexPageTooShort"
+            | "ScannerAdfDuplexPageTooLong" => {
+                return Some(PapyrError::PaperJam {
+                    backend,
+                    message: message(state),
+                })
+            }
+            "ScannerAdfHatchOpen" => {
+                return Some(PapyrError::CoverOpen {
+                    backend,
+                    message: message(state),
+                })
             }
+            _ => {}
         }
     }
 
-    fn start_scan(&self, device_id: &str, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
-        let discovered = self
-            .discovered_scanners
-            .lock()
-            .map_err(|_| PapyrError::Backend("Failed to lock discovered scanners".into()))?;
+    None
+}
 
-        let device = discovered
-            .get(device_id)
-            .ok_or_else(|| PapyrError::NotFound(format!("Device {} not found", device_id)))?
-            .clone();
+/// The `ScanSettings` document for a job.
+pub(crate) fn scan_settings_xml(config: &ScanConfig, options: &[(String, OptionValue)]) -> String {
+    let input_source = match config.source {
+        ScanSource::Flatbed => "Platen",
+        ScanSource::Adf | ScanSource::AdfDuplex => "Feeder",
+    };
+
+    let color_mode = color_mode_setting(config);
+
+    let mut intent = "Document".to_string();
+    let mut extra = String::new();
+    for (name, value) in options {
+        if name == "Intent" {
+            intent = value.to_string();
+        } else {
+            extra.push_str(&format!("\n    <scan:{0}>{1}</scan:{0}>", name, value));
+        }
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<scan:ScanSettings xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03" xmlns:pwg="http://www.pwg.org/schemas/2010/12/sm">
+    <pwg:Version>2.1</pwg:Version>
+    <scan:Intent>{}</scan:Intent>
+    <scan:InputSource>{}</scan:InputSource>
+    <scan:ColorMode>{}</scan:ColorMode>
+    <scan:XResolution>{}</scan:XResolution>
+    <scan:YResolution>{}</scan:YResolution>
+    <scan:DocumentFormat>image/jpeg</scan:DocumentFormat>{}
+</scan:ScanSettings>"#,
+        intent,
+        input_source,
+        color_mode,
+        config.dpi,
+        config.vertical_dpi(),
+        extra
+    )
+}
 
-        Ok(Box::new(EsclScanSession::new(device, config)?))
+/// Metadata for page `index`. The scanner doesn't report the image size,
+/// so it is worked out from the configured page size.
+pub(crate) fn page_meta(config: &ScanConfig, index: u32) -> PageMeta {
+    let dpi_y = config.vertical_dpi();
+    let (width_px, height_px) = config.page_size.to_pixels(config.dpi, dpi_y);
+    PageMeta {
+        index,
+        width_px,
+        height_px,
+        dpi: config.dpi,
+        dpi_y,
+        color_mode: config.color_mode,
+        bit_depth: config.bits_per_sample(),
     }
 }
 
@@ -302,17 +965,12 @@ impl EsclBackend {
         Capabilities {
             sources: vec![ScanSource::Flatbed, ScanSource::Adf],
             dpis: vec![75, 150, 300, 600],
+            dpi_ranges: vec![],
+            independent_dpi: false,
             color_modes: vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw],
-            page_sizes: vec![
-                PageSize {
-                    width_mm: 216,
-                    height_mm: 279,
-                }, // Letter
-                PageSize {
-                    width_mm: 210,
-                    height_mm: 297,
-                }, // A4
-            ],
+            bit_depths: vec![8],
+            page_sizes: DEFAULT_PAGE_FORMATS.to_vec(),
+            max_areas: vec![],
             supports_duplex: false,
         }
     }
@@ -321,10 +979,23 @@ impl EsclBackend {
 pub struct EsclScanSession {
     device: EsclDevice,
     config: ScanConfig,
+    options: Vec<(String, OptionValue)>,
     client: reqwest::blocking::Client,
     job_url: Option<String>,
+    // Body of the document being downloaded
+    document: Option<reqwest::blocking::Response>,
     page_index: u32,
+    page_bytes: u64,
+    page_expected: Option<u64>,
+    status: Option<ScanStatus>,
+    // End of the retry window while the scanner answers busy
+    busy_deadline: Option<Instant>,
+    pending: VecDeque<ScanEvent>,
+    // Reported once the events queued before it have been delivered
+    failure: Option<PapyrError>,
     state: ScanState,
+    // Set by cancel handles, checked between requests and chunks
+    cancelled: Arc<AtomicBool>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -336,7 +1007,11 @@ enum ScanState {
 }
 
 impl EsclScanSession {
-    pub fn new(device: EsclDevice, config: ScanConfig) -> Result<Self> {
+    fn new(
+        device: EsclDevice,
+        config: ScanConfig,
+        options: Vec<(String, OptionValue)>,
+    ) -> Result<Self> {
         let client = reqwest::blocking::Client::builder()
             .timeout(Duration::from_secs(120)) // Long timeout for scanning
             .danger_accept_invalid_certs(true)
@@ -346,46 +1021,28 @@ impl EsclScanSession {
         Ok(Self {
             device,
             config,
+            options,
             client,
             job_url: None,
+            document: None,
             page_index: 0,
+            page_bytes: 0,
+            page_expected: None,
+            status: None,
+            busy_deadline: None,
+            pending: VecDeque::new(),
+            failure: None,
             state: ScanState::NotStarted,
+            cancelled: Arc::new(AtomicBool::new(false)),
         })
     }
 
-    fn create_scan_settings_xml(&self) -> String {
-        let input_source = match self.config.source {
-            ScanSource::Flatbed => "Platen",
-            ScanSource::Adf | ScanSource::AdfDuplex => "Feeder",
-        };
-
-        let color_mode = match self.config.color_mode {
-            ColorMode::Color => "RGB24",
-            ColorMode::Gray => "Grayscale8",
-            ColorMode::Bw => "BlackAndWhite1",
-        };
-
-        format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<scan:ScanSettings xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03" xmlns:pwg="http://www.pwg.org/schemas/2010/12/sm">
-    <pwg:Version>2.1</pwg:Version>
-    <scan:Intent>Document</scan:Intent>
-    <scan:InputSource>{}</scan:InputSource>
-    <scan:ColorMode>{}</scan:ColorMode>
-    <scan:XResolution>{}</scan:XResolution>
-    <scan:YResolution>{}</scan:YResolution>
-    <scan:DocumentFormat>image/jpeg</scan:DocumentFormat>
-</scan:ScanSettings>"#,
-            input_source, color_mode, self.config.dpi, self.config.dpi
-        )
-    }
-
     fn create_job(&mut self) -> Result<()> {
         let url = format!("{}/ScanJobs", self.device.base_url());
-        let scan_xml = self.create_scan_settings_xml();
+        let scan_xml = scan_settings_xml(&self.config, &self.options);
 
-        println!("🖨️  Creating scan job at: {}", url);
-        println!("📄 Settings:\n{}", scan_xml);
+        debug!(%url, "creating scan job");
+        trace!(settings = %scan_xml, "scan settings");
 
         let response = self
             .client
@@ -393,10 +1050,10 @@ impl EsclScanSession {
             .header("Content-Type", "text/xml")
             .body(scan_xml)
             .send()
-            .map_err(|e| PapyrError::Backend(format!("Failed to create scan job: {}", e)))?;
+            .map_err(|e| request_error("Failed to create scan job", e))?;
 
         let status = response.status();
-        println!("📥 Response status: {}", status);
+        debug!(%url, status = status.as_u16(), "scan job response");
 
         if status.as_u16() == 201 {
             if let Some(location) = response.headers().get("Location") {
@@ -405,7 +1062,7 @@ impl EsclScanSession {
                     .map_err(|_| PapyrError::Backend("Invalid job location header".into()))?
                     .to_string();
 
-                println!("✅ Scan job created: {}", job_url);
+                info!(job = %job_url, "scan job created");
                 self.job_url = Some(job_url);
                 self.state = ScanState::JobCreated;
                 Ok(())
@@ -414,126 +1071,220 @@ impl EsclScanSession {
             }
         } else {
             let body = response.text().unwrap_or_default();
-            Err(PapyrError::Backend(format!(
-                "Scan job creation failed: HTTP {} - {}",
-                status, body
-            )))
+            Err(error_for_status(
+                "Scan job creation failed",
+                status.as_u16(),
+                &body,
+                self.scanner_status().as_deref(),
+            ))
         }
     }
 
-    fn fetch_next_document(&mut self) -> Result<Option<Vec<u8>>> {
+    /// Requests the next document; `None` once the job has no more pages.
+    fn fetch_next_document(&mut self) -> Result<Option<reqwest::blocking::Response>> {
         let job_url = self
             .job_url
             .as_ref()
             .ok_or_else(|| PapyrError::Backend("No active scan job".into()))?;
 
         let document_url = format!("{}/NextDocument", job_url);
-        println!("📥 Fetching document from: {}", document_url);
 
         let response = self
             .client
             .get(&document_url)
             .header("Accept", "image/jpeg,image/png,application/pdf")
             .send()
-            .map_err(|e| PapyrError::Backend(format!("Failed to fetch document: {}", e)))?;
+            .map_err(|e| request_error("Failed to fetch document", e))?;
 
         let status = response.status();
-        println!("📥 Document response status: {}", status);
+        debug!(url = %document_url, status = status.as_u16(), "document response");
 
         match status.as_u16() {
-            200 => {
-                let bytes = response
-                    .bytes()
-                    .map_err(|e| {
-                        PapyrError::Backend(format!("Failed to read document data: {}", e))
-                    })?
-                    .to_vec();
-
-                println!("✅ Downloaded document: {} bytes", bytes.len());
-                Ok(Some(bytes))
-            }
+            200 => Ok(Some(response)),
             404 => {
-                println!("✅ No more documents (HTTP 404)");
+                debug!("no more documents");
                 Ok(None)
             }
             _ => {
                 let body = response.text().unwrap_or_default();
-                Err(PapyrError::Backend(format!(
-                    "Document fetch failed: HTTP {} - {}",
-                    status, body
-                )))
+                Err(error_for_status(
+                    "Document fetch failed",
+                    status.as_u16(),
+                    &body,
+                    self.scanner_status().as_deref(),
+                ))
             }
         }
     }
 
+    /// Fetches the `ScannerStatus` document, used to explain a refused
+    /// request; `None` if the scanner doesn't answer.
+    fn scanner_status(&self) -> Option<String> {
+        let url = format!("{}/ScannerStatus", self.device.base_url());
+        let response = self.client.get(&url).send().ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.text().ok()
+    }
+
+    /// Deletes the job on the scanner, if one was created.
     fn delete_job(&mut self) -> Result<()> {
-        if let Some(job_url) = &self.job_url {
-            println!("🗑️  Deleting scan job: {}", job_url);
+        let Some(job_url) = self.job_url.take() else {
+            return Ok(());
+        };
+
+        let response = self
+            .client
+            .delete(&job_url)
+            .send()
+            .map_err(|e| request_error("Failed to delete scan job", e))?;
+        debug!(job = %job_url, status = response.status().as_u16(), "scan job deleted");
+        Ok(())
+    }
+
+    /// Ends the session with `error`, after any events already queued.
+    fn fail(&mut self, error: PapyrError) -> Result<Option<ScanEvent>> {
+        self.document = None;
+        let _ = self.delete_job();
+        self.state = ScanState::Completed;
+        self.failure = Some(error);
+        self.next_event()
+    }
+
+    /// Queues a `Status` event if the status changed.
+    fn set_status(&mut self, status: ScanStatus) {
+        if self.status != Some(status) {
+            self.status = Some(status);
+            self.pending.push_back(ScanEvent::Status(status));
+        }
+    }
+
+    /// While the scanner answers busy, queues `WaitingForDevice` and turns
+    /// the refusal into `None` (so the status is delivered before the next
+    /// attempt) until the retry window closes.
+    fn retry_if_busy<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        if let Err(PapyrError::DeviceBusy { .. }) = &result {
+            let deadline = *self
+                .busy_deadline
+                .get_or_insert_with(|| Instant::now() + Duration::from_secs(BUSY_RETRY_SECS));
+            if Instant::now() < deadline {
+                self.set_status(ScanStatus::WaitingForDevice);
+                return Ok(None);
+            }
+        }
+        self.busy_deadline = None;
+        result.map(Some)
+    }
+
+    /// Runs `request`, pausing first if the scanner refused the last one.
+    fn retry_while_busy<T>(
+        &mut self,
+        request: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<Option<T>> {
+        if self.busy_deadline.is_some() {
+            std::thread::sleep(BUSY_POLL_INTERVAL);
+        }
+        let result = request(self);
+        self.retry_if_busy(result)
+    }
 
-            match self.client.delete(job_url).send() {
-                Ok(response) => {
-                    println!("✅ Job deleted: HTTP {}", response.status());
+    /// Queues the events for the next step of the job: the next chunk of the
+    /// current document, the start of the next one, or the end of the job.
+    fn advance(&mut self) -> Result<()> {
+        let Some(document) = self.document.as_mut() else {
+            match self.retry_while_busy(Self::fetch_next_document)? {
+                None => {}
+                Some(Some(response)) => {
+                    self.set_status(ScanStatus::Scanning);
+                    self.page_bytes = 0;
+                    self.page_expected = response.content_length();
+                    self.document = Some(response);
+                    self.pending
+                        .push_back(ScanEvent::PageStarted(self.page_index));
                 }
-                Err(e) => {
-                    println!("⚠️  Failed to delete job: {}", e);
+                Some(None) => {
+                    if let Err(e) = self.delete_job() {
+                        self.pending.push_back(ScanEvent::warning(
+                            WarningCode::CleanupFailed,
+                            e.to_string(),
+                        ));
+                    }
+                    self.pending.push_back(ScanEvent::JobComplete);
+                    self.state = ScanState::Completed;
                 }
             }
+            return Ok(());
+        };
+
+        let mut chunk = vec![0u8; DOCUMENT_CHUNK_SIZE];
+        let len = document.read(&mut chunk).map_err(read_error)?;
+        if len == 0 {
+            debug!(bytes = self.page_bytes, "document downloaded");
+            self.document = None;
+            self.pending.push_back(ScanEvent::PageComplete(page_meta(
+                &self.config,
+                self.page_index,
+            )));
+            self.page_index += 1;
+            return Ok(());
         }
+
+        chunk.truncate(len);
+        self.page_bytes += len as u64;
+        self.pending.push_back(ScanEvent::PageData(chunk));
+        self.pending
+            .push_back(ScanEvent::progress(self.page_bytes, self.page_expected));
         Ok(())
     }
 }
 
 impl ScanSession for EsclScanSession {
     fn next_event(&mut self) -> Result<Option<ScanEvent>> {
-        match self.state {
-            ScanState::NotStarted => {
-                // Create the scan job
-                self.create_job()?;
-                self.state = ScanState::Scanning;
-
-                // Return PageStarted for first page
-                Ok(Some(ScanEvent::PageStarted(self.page_index)))
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            match self.failure.take() {
+                Some(PapyrError::Cancelled) => return Ok(Some(ScanEvent::Cancelled)),
+                Some(e) => return Err(e),
+                None => {}
             }
 
-            ScanState::JobCreated | ScanState::Scanning => {
-                // Fetch next document
-                match self.fetch_next_document()? {
-                    Some(data) => {
-                        let page_meta = PageMeta {
-                            index: self.page_index,
-                            width_px: (8.5 * self.config.dpi as f32) as u32,
-                            height_px: (11.0 * self.config.dpi as f32) as u32,
-                            dpi: self.config.dpi,
-                            color_mode: self.config.color_mode,
-                        };
-
-                        self.page_index += 1;
-
-                        // Return page data, then complete, then start next page on next call
-                        if data.is_empty() {
-                            Ok(Some(ScanEvent::PageComplete(page_meta)))
-                        } else {
-                            Ok(Some(ScanEvent::PageData(data)))
-                        }
-                    }
-                    None => {
-                        // No more documents
-                        self.delete_job()?;
-                        self.state = ScanState::Completed;
-                        Ok(Some(ScanEvent::JobComplete))
-                    }
-                }
+            if self.state != ScanState::Completed && self.cancelled.load(Ordering::SeqCst) {
+                debug!("eSCL scan cancelled");
+                return self.fail(PapyrError::Cancelled);
             }
 
-            ScanState::Completed => Ok(None),
+            let step = match self.state {
+                ScanState::NotStarted => self.retry_while_busy(Self::create_job).map(|created| {
+                    if created.is_some() {
+                        self.set_status(ScanStatus::Scanning);
+                        self.state = ScanState::Scanning;
+                    }
+                }),
+                ScanState::JobCreated | ScanState::Scanning => self.advance(),
+                ScanState::Completed => return Ok(None),
+            };
+            if let Err(e) = step {
+                return self.fail(e);
+            }
         }
     }
+
+    /// Takes effect between requests and downloaded chunks; the job is
+    /// then deleted on the scanner.
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancelled = Arc::clone(&self.cancelled);
+        Some(CancelHandle::new(move || {
+            cancelled.store(true, Ordering::SeqCst)
+        }))
+    }
 }
 
 impl Drop for EsclScanSession {
     fn drop(&mut self) {
-        if self.state != ScanState::Completed {
-            let _ = self.delete_job();
-        }
+        self.document = None;
+        let _ = self.delete_job();
     }
 }
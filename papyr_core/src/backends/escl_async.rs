@@ -0,0 +1,317 @@
+//
+//  papyr_core
+//  backends/escl_async.rs - eSCL scan sessions on async reqwest (`async` feature)
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::async_api::ScanEventStream;
+use crate::backends::escl::{
+    error_for_status, page_meta, request_error, scan_settings_xml, EsclDevice, BUSY_POLL_INTERVAL,
+    BUSY_RETRY_SECS,
+};
+use crate::models::*;
+use futures_util::stream;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Starts an eSCL scan as a stream of events. The job is created when the
+/// stream is first polled; dropping the stream deletes it.
+pub(crate) fn scan_stream(
+    device: EsclDevice,
+    config: ScanConfig,
+    options: Vec<(String, OptionValue)>,
+) -> Result<ScanEventStream> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(120)) // Long timeout for scanning
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| PapyrError::Backend(format!("Failed to create HTTP client: {}", e)))?;
+
+    let job = AsyncEsclJob {
+        device,
+        config,
+        options,
+        client,
+        job_url: None,
+        document: None,
+        page_index: 0,
+        page_bytes: 0,
+        page_expected: None,
+        status: None,
+        busy_deadline: None,
+        pending: VecDeque::new(),
+        failure: None,
+        state: JobState::NotStarted,
+    };
+
+    Ok(Box::pin(stream::unfold(job, |mut job| async move {
+        let event = job.next_event().await.transpose()?;
+        Some((event, job))
+    })))
+}
+
+#[derive(Debug, PartialEq)]
+enum JobState {
+    NotStarted,
+    Scanning,
+    Completed,
+}
+
+/// The async counterpart of `EsclScanSession`, producing the same events.
+struct AsyncEsclJob {
+    device: EsclDevice,
+    config: ScanConfig,
+    options: Vec<(String, OptionValue)>,
+    client: reqwest::Client,
+    job_url: Option<String>,
+    // Body of the document being downloaded
+    document: Option<reqwest::Response>,
+    page_index: u32,
+    page_bytes: u64,
+    page_expected: Option<u64>,
+    status: Option<ScanStatus>,
+    // End of the retry window while the scanner answers busy
+    busy_deadline: Option<Instant>,
+    pending: VecDeque<ScanEvent>,
+    // Reported once the events queued before it have been delivered
+    failure: Option<PapyrError>,
+    state: JobState,
+}
+
+impl AsyncEsclJob {
+    async fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            match self.failure.take() {
+                Some(PapyrError::Cancelled) => return Ok(Some(ScanEvent::Cancelled)),
+                Some(e) => return Err(e),
+                None => {}
+            }
+
+            let step = match self.state {
+                JobState::NotStarted => {
+                    self.wait_if_busy().await;
+                    let result = self.create_job().await;
+                    self.retry_if_busy(result).map(|created| {
+                        if created.is_some() {
+                            self.set_status(ScanStatus::Scanning);
+                            self.state = JobState::Scanning;
+                        }
+                    })
+                }
+                JobState::Scanning => self.advance().await,
+                JobState::Completed => return Ok(None),
+            };
+            if let Err(e) = step {
+                self.document = None;
+                let _ = self.delete_job().await;
+                self.state = JobState::Completed;
+                self.failure = Some(e);
+            }
+        }
+    }
+
+    /// Queues a `Status` event if the status changed.
+    fn set_status(&mut self, status: ScanStatus) {
+        if self.status != Some(status) {
+            self.status = Some(status);
+            self.pending.push_back(ScanEvent::Status(status));
+        }
+    }
+
+    /// Creates the job on the scanner.
+    async fn create_job(&mut self) -> Result<()> {
+        let url = format!("{}/ScanJobs", self.device.base_url());
+        debug!(%url, "creating scan job");
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "text/xml")
+            .body(scan_settings_xml(&self.config, &self.options))
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to create scan job", e))?;
+
+        let status = response.status().as_u16();
+        if status != 201 {
+            let body = response.text().await.unwrap_or_default();
+            return Err(error_for_status(
+                "Scan job creation failed",
+                status,
+                &body,
+                self.scanner_status().await.as_deref(),
+            ));
+        }
+
+        let job_url = response
+            .headers()
+            .get("Location")
+            .ok_or_else(|| PapyrError::Backend("No Location header in response".into()))?
+            .to_str()
+            .map_err(|_| PapyrError::Backend("Invalid job location header".into()))?
+            .to_string();
+        debug!(job = %job_url, "scan job created");
+        self.job_url = Some(job_url);
+        Ok(())
+    }
+
+    /// Requests the next document; `None` once the job has no more pages.
+    async fn fetch_next_document(&mut self) -> Result<Option<reqwest::Response>> {
+        let job_url = self
+            .job_url
+            .as_ref()
+            .ok_or_else(|| PapyrError::Backend("No active scan job".into()))?;
+        let document_url = format!("{}/NextDocument", job_url);
+
+        let response = self
+            .client
+            .get(&document_url)
+            .header("Accept", "image/jpeg,image/png,application/pdf")
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to fetch document", e))?;
+
+        let status = response.status().as_u16();
+        match status {
+            200 => Ok(Some(response)),
+            404 => {
+                debug!("no more documents");
+                Ok(None)
+            }
+            _ => {
+                let body = response.text().await.unwrap_or_default();
+                Err(error_for_status(
+                    "Document fetch failed",
+                    status,
+                    &body,
+                    self.scanner_status().await.as_deref(),
+                ))
+            }
+        }
+    }
+
+    /// Pauses before retrying a request the scanner refused as busy.
+    async fn wait_if_busy(&self) {
+        if self.busy_deadline.is_some() {
+            tokio::time::sleep(BUSY_POLL_INTERVAL).await;
+        }
+    }
+
+    /// While the scanner answers busy, queues `WaitingForDevice` and turns
+    /// the refusal into `None` (so the status is delivered before the next
+    /// attempt) until the retry window closes.
+    fn retry_if_busy<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        if let Err(PapyrError::DeviceBusy { .. }) = &result {
+            let deadline = *self
+                .busy_deadline
+                .get_or_insert_with(|| Instant::now() + Duration::from_secs(BUSY_RETRY_SECS));
+            if Instant::now() < deadline {
+                self.set_status(ScanStatus::WaitingForDevice);
+                return Ok(None);
+            }
+        }
+        self.busy_deadline = None;
+        result.map(Some)
+    }
+
+    /// Fetches the `ScannerStatus` document, used to explain a refused
+    /// request; `None` if the scanner doesn't answer.
+    async fn scanner_status(&self) -> Option<String> {
+        let url = format!("{}/ScannerStatus", self.device.base_url());
+        let response = self.client.get(&url).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.text().await.ok()
+    }
+
+    /// Deletes the job on the scanner, if one was created.
+    async fn delete_job(&mut self) -> Result<()> {
+        let Some(job_url) = self.job_url.take() else {
+            return Ok(());
+        };
+
+        let response = self
+            .client
+            .delete(&job_url)
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to delete scan job", e))?;
+        debug!(job = %job_url, status = response.status().as_u16(), "scan job deleted");
+        Ok(())
+    }
+
+    /// Queues the events for the next step of the job: the next chunk of the
+    /// current document, the start of the next one, or the end of the job.
+    async fn advance(&mut self) -> Result<()> {
+        let Some(document) = self.document.as_mut() else {
+            self.wait_if_busy().await;
+            let result = self.fetch_next_document().await;
+            match self.retry_if_busy(result)? {
+                None => {}
+                Some(Some(response)) => {
+                    self.set_status(ScanStatus::Scanning);
+                    self.page_bytes = 0;
+                    self.page_expected = response.content_length();
+                    self.document = Some(response);
+                    self.pending
+                        .push_back(ScanEvent::PageStarted(self.page_index));
+                }
+                Some(None) => {
+                    if let Err(e) = self.delete_job().await {
+                        self.pending.push_back(ScanEvent::warning(
+                            WarningCode::CleanupFailed,
+                            e.to_string(),
+                        ));
+                    }
+                    self.pending.push_back(ScanEvent::JobComplete);
+                    self.state = JobState::Completed;
+                }
+            }
+            return Ok(());
+        };
+
+        let chunk = document
+            .chunk()
+            .await
+            .map_err(|e| request_error("Failed to read document data", e))?;
+        match chunk {
+            Some(chunk) => {
+                self.page_bytes += chunk.len() as u64;
+                self.pending.push_back(ScanEvent::PageData(chunk.to_vec()));
+                self.pending
+                    .push_back(ScanEvent::progress(self.page_bytes, self.page_expected));
+            }
+            None => {
+                debug!(bytes = self.page_bytes, "document downloaded");
+                self.document = None;
+                self.pending.push_back(ScanEvent::PageComplete(page_meta(
+                    &self.config,
+                    self.page_index,
+                )));
+                self.page_index += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AsyncEsclJob {
+    fn drop(&mut self) {
+        // Drop can't wait for the DELETE, so it is left to run on the runtime
+        if let (Some(job_url), Ok(runtime)) =
+            (self.job_url.take(), tokio::runtime::Handle::try_current())
+        {
+            let request = self.client.delete(job_url).send();
+            runtime.spawn(async move {
+                let _ = request.await;
+            });
+        }
+    }
+}
@@ -12,15 +12,21 @@
 #[cfg(target_os = "macos")]
 use std::process::Command;
 
+#[cfg(target_os = "macos")]
+use crate::backends::escl::color_mode_setting;
+#[cfg(target_os = "macos")]
+use crate::backends::sane_common::connection_for_device;
+
 use crate::models::{
-    Backend, BackendProvider, Capabilities, ColorMode, PageMeta, PageSize, PapyrError, Result,
-    ScanConfig, ScanEvent, ScanSession, ScanSource, ScannerInfo,
+    Backend, BackendProvider, Capabilities, ColorMode, DeviceId, PageFormat, PageMeta, PapyrError,
+    Result, ScanConfig, ScanEvent, ScanSession, ScanSource, ScannerInfo,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
 
 pub struct IcaBackend {
-    device_names: Arc<Mutex<HashMap<String, DeviceInfo>>>,
+    device_names: Arc<Mutex<HashMap<DeviceId, DeviceInfo>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -41,37 +47,37 @@ impl IcaBackend {
     fn enumerate_devices(&self) -> Result<Vec<ScannerInfo>> {
         let mut scanners = Vec::new();
 
-        println!("🔍 Searching for scanners using multiple methods...");
+        debug!("searching for scanners");
 
         let printer_scanners = self.try_printer_scanner_discovery()?;
-        println!(
-            "   🖨️  printer scanners found: {} devices",
-            printer_scanners.len()
+        debug!(
+            devices = printer_scanners.len(),
+            "printer discovery finished"
         );
         scanners.extend(printer_scanners);
 
         let profiler_scanners = self.try_system_profiler_discovery()?;
-        println!(
-            "   🔍 system_profiler found: {} devices",
-            profiler_scanners.len()
+        debug!(
+            devices = profiler_scanners.len(),
+            "system_profiler discovery finished"
         );
         scanners.extend(profiler_scanners);
 
         let ioreg_scanners = self.try_ioreg_discovery()?;
-        println!("   🔌 ioreg found: {} devices", ioreg_scanners.len());
+        debug!(devices = ioreg_scanners.len(), "ioreg discovery finished");
         scanners.extend(ioreg_scanners);
 
         let imagecapture_scanners = self.try_imagecapture_discovery()?;
-        println!(
-            "   📷 imagecapture found: {} devices",
-            imagecapture_scanners.len()
+        debug!(
+            devices = imagecapture_scanners.len(),
+            "imagecapture discovery finished"
         );
         scanners.extend(imagecapture_scanners);
 
         // Remove duplicates
         scanners.dedup_by(|a, b| a.name == b.name || a.id == b.id);
 
-        println!("🎯 Total unique scanners found: {}", scanners.len());
+        debug!(devices = scanners.len(), "ICA discovery finished");
 
         Ok(scanners)
     }
@@ -97,10 +103,12 @@ impl IcaBackend {
                         && !line.contains("Status:")
                     {
                         if !current_printer.is_empty() && supports_scanning {
-                            println!("   🖨️  Found MFP with scanning: {}", current_printer);
+                            debug!(printer = %current_printer, "found printer with scanning");
 
-                            let device_id =
-                                format!("ica_{}", current_printer.to_lowercase().replace(" ", "_"));
+                            let device_id = DeviceId::new(
+                                Backend::Ica,
+                                current_printer.to_lowercase().replace(" ", "_"),
+                            );
 
                             if let Ok(mut names) = self.device_names.lock() {
                                 names.insert(
@@ -114,9 +122,8 @@ impl IcaBackend {
                             }
 
                             scanners.push(ScannerInfo {
-                                id: device_id,
-                                name: current_printer.clone(),
-                                backend: Backend::Ica,
+                                device_type: Some("multi-function peripheral".into()),
+                                ..ScannerInfo::new(device_id, current_printer.clone())
                             });
                         }
 
@@ -130,10 +137,12 @@ impl IcaBackend {
                 }
 
                 if !current_printer.is_empty() && supports_scanning {
-                    println!("   🖨️  Found MFP with scanning: {}", current_printer);
+                    debug!(printer = %current_printer, "found printer with scanning");
 
-                    let device_id =
-                        format!("ica_{}", current_printer.to_lowercase().replace(" ", "_"));
+                    let device_id = DeviceId::new(
+                        Backend::Ica,
+                        current_printer.to_lowercase().replace(" ", "_"),
+                    );
 
                     if let Ok(mut names) = self.device_names.lock() {
                         names.insert(
@@ -147,9 +156,8 @@ impl IcaBackend {
                     }
 
                     scanners.push(ScannerInfo {
-                        id: device_id,
-                        name: current_printer,
-                        backend: Backend::Ica,
+                        device_type: Some("multi-function peripheral".into()),
+                        ..ScannerInfo::new(device_id, current_printer)
                     });
                 }
 
@@ -181,7 +189,11 @@ impl IcaBackend {
                                         if let Some(end) = line[start..].find(char::is_whitespace) {
                                             let url = &line[start..start + end];
                                             info.ippusb_url = Some(url.to_string());
-                                            println!("   🔗 Mapped {} to {}", info.name, url);
+                                            debug!(
+                                                device = %info.name,
+                                                %url,
+                                                "mapped device to IPP-USB"
+                                            );
                                         }
                                     }
                                 }
@@ -219,7 +231,8 @@ impl IcaBackend {
                             if let Some(end) = line.find('\'') {
                                 let device_spec = &line[start + 1..end];
                                 if let Some(colon_pos) = device_spec.find(':') {
-                                    let device_id = format!("ica_{}", &device_spec[..colon_pos]);
+                                    let device_id =
+                                        DeviceId::new(Backend::Ica, &device_spec[..colon_pos]);
                                     let device_model = &device_spec[colon_pos + 1..];
 
                                     if let Ok(mut names) = self.device_names.lock() {
@@ -235,10 +248,10 @@ impl IcaBackend {
                                         );
                                     }
 
+                                    let info = ScannerInfo::new(device_id, device_model);
                                     scanners.push(ScannerInfo {
-                                        id: device_id,
-                                        name: device_model.to_string(),
-                                        backend: Backend::Ica,
+                                        connection: connection_for_device(device_spec),
+                                        ..info
                                     });
                                 }
                             }
@@ -254,8 +267,8 @@ impl IcaBackend {
     }
 
     #[cfg(target_os = "macos")]
-    fn query_device_capabilities(&self, device_id: &str) -> Result<Capabilities> {
-        println!("🔍 Querying capabilities for device: {}", device_id);
+    fn query_device_capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        debug!(device = %device_id, "querying capabilities");
 
         let mut sources = vec![ScanSource::Flatbed];
         let mut dpis = vec![75, 150, 300];
@@ -266,7 +279,7 @@ impl IcaBackend {
             if let Some(info) = names.get(device_id) {
                 // Detect MFP based on IPP-USB URL presence
                 if info.ippusb_url.is_some() {
-                    println!("   📄 Detected MFP device (has IPP-USB) - adding ADF support");
+                    debug!("IPP-USB device, adding ADF support");
                     sources.push(ScanSource::Adf);
                     dpis.extend(vec![600, 1200]);
                     supports_duplex = true;
@@ -288,40 +301,31 @@ impl IcaBackend {
             supports_duplex = caps.supports_duplex;
         }
 
-        println!(
-            "   ✅ Capabilities: {} sources, {} DPIs, {} color modes, duplex: {}",
-            sources.len(),
-            dpis.len(),
-            color_modes.len(),
-            supports_duplex
+        debug!(
+            sources = sources.len(),
+            dpis = dpis.len(),
+            color_modes = color_modes.len(),
+            duplex = supports_duplex,
+            "capabilities"
         );
 
         Ok(Capabilities {
             sources,
             dpis,
+            dpi_ranges: vec![],
+            independent_dpi: false,
             color_modes,
-            page_sizes: vec![
-                PageSize {
-                    width_mm: 216,
-                    height_mm: 279,
-                }, // Letter
-                PageSize {
-                    width_mm: 210,
-                    height_mm: 297,
-                }, // A4
-                PageSize {
-                    width_mm: 148,
-                    height_mm: 210,
-                }, // A5
-            ],
+            bit_depths: vec![8],
+            page_sizes: vec![PageFormat::Letter, PageFormat::IsoA(4), PageFormat::IsoA(5)],
+            max_areas: vec![],
             supports_duplex,
         })
     }
 
     #[cfg(target_os = "macos")]
-    fn try_scanimage_capabilities(&self, device_id: &str) -> Result<Capabilities> {
+    fn try_scanimage_capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
         // Extract actual scanimage device name
-        let sane_device = device_id.strip_prefix("ica_").unwrap_or(device_id);
+        let sane_device = device_id.local();
 
         let output = Command::new("scanimage")
             .arg("-d")
@@ -377,8 +381,12 @@ impl IcaBackend {
                 return Ok(Capabilities {
                     sources,
                     dpis,
+                    dpi_ranges: vec![],
+                    independent_dpi: false,
                     color_modes,
+                    bit_depths: vec![],
                     page_sizes: vec![],
+                    max_areas: vec![],
                     supports_duplex,
                 });
             }
@@ -412,7 +420,7 @@ impl BackendProvider for IcaBackend {
         vec![]
     }
 
-    fn capabilities(&self, device_id: &str) -> Result<Capabilities> {
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
         #[cfg(target_os = "macos")]
         {
             self.query_device_capabilities(device_id)
@@ -422,7 +430,7 @@ impl BackendProvider for IcaBackend {
         Err(PapyrError::Backend("ICA only supported on macOS".into()))
     }
 
-    fn start_scan(&self, device_id: &str, cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+    fn start_scan(&self, device_id: &DeviceId, cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
         #[cfg(target_os = "macos")]
         {
             let device_info = if let Ok(names) = self.device_names.lock() {
@@ -442,7 +450,7 @@ impl BackendProvider for IcaBackend {
 
 pub struct IcaScanSession {
     #[cfg(target_os = "macos")]
-    device_id: String,
+    device_id: DeviceId,
     #[cfg(target_os = "macos")]
     device_info: Option<DeviceInfo>,
     #[cfg(target_os = "macos")]
@@ -456,18 +464,20 @@ pub struct IcaScanSession {
 enum IcaScanState {
     NotStarted,
     Scanning,
+    // The scanned image, waiting to be passed on
+    Scanned(Vec<u8>),
     Completed,
 }
 
 impl IcaScanSession {
     #[cfg(target_os = "macos")]
     pub fn new(
-        device_id: &str,
+        device_id: &DeviceId,
         device_info: Option<DeviceInfo>,
         config: ScanConfig,
     ) -> Result<Self> {
         Ok(IcaScanSession {
-            device_id: device_id.to_string(),
+            device_id: device_id.clone(),
             device_info,
             config,
             state: IcaScanState::NotStarted,
@@ -476,7 +486,7 @@ impl IcaScanSession {
 
     #[cfg(not(target_os = "macos"))]
     pub fn new(
-        _device_id: &str,
+        _device_id: &DeviceId,
         _device_info: Option<DeviceInfo>,
         _config: ScanConfig,
     ) -> Result<Self> {
@@ -488,34 +498,32 @@ impl ScanSession for IcaScanSession {
     fn next_event(&mut self) -> Result<Option<ScanEvent>> {
         #[cfg(target_os = "macos")]
         {
-            match self.state {
+            match std::mem::replace(&mut self.state, IcaScanState::Completed) {
                 IcaScanState::NotStarted => {
                     self.state = IcaScanState::Scanning;
 
-                    println!("🖨️  Attempting to scan from device: {}", self.device_id);
+                    info!(device = %self.device_id, "starting scan");
 
                     // Try actual scan
                     match self.try_actual_scan() {
                         Ok(Some(data)) => {
-                            self.state = IcaScanState::Completed;
-                            Ok(Some(ScanEvent::PageData(data)))
+                            // The image is one page, started before its data
+                            self.state = IcaScanState::Scanned(data);
+                            Ok(Some(ScanEvent::PageStarted(0)))
                         }
                         Ok(None) => {
                             self.state = IcaScanState::Completed;
                             Ok(Some(ScanEvent::JobComplete))
                         }
                         Err(e) => {
-                            println!("⚠️  Scan failed: {:?}", e);
+                            debug!("scan failed: {}", e);
                             self.state = IcaScanState::Completed;
                             Err(e)
                         }
                     }
                 }
-                IcaScanState::Scanning => {
-                    self.state = IcaScanState::Completed;
-                    Ok(Some(ScanEvent::JobComplete))
-                }
-                IcaScanState::Completed => Ok(None),
+                IcaScanState::Scanned(data) => Ok(Some(ScanEvent::PageData(data))),
+                IcaScanState::Scanning | IcaScanState::Completed => Ok(None),
             }
         }
 
@@ -548,7 +556,7 @@ impl IcaScanSession {
     }
 
     fn try_ippusb_escl_scan(&self, ippusb_url: &str) -> Result<Vec<u8>> {
-        println!("🔌 Attempting IPP-USB eSCL scan via: {}", ippusb_url);
+        debug!(url = %ippusb_url, "scanning through IPP-USB eSCL");
 
         // Convert ippusb:// to http:// for eSCL access
         // Format: ippusb://HP%20LaserJet.../_tcp.local.?uuid=xxx
@@ -567,7 +575,7 @@ impl IcaScanSession {
             return Err(PapyrError::Backend("Not an IPP-USB URL".into()));
         };
 
-        println!("📡 Converted to HTTP base: {}", http_base);
+        debug!(url = %http_base, "IPP-USB base URL");
 
         // Use blocking reqwest client for eSCL
         let client = reqwest::blocking::Client::builder()
@@ -580,7 +588,7 @@ impl IcaScanSession {
         let scan_xml = self.create_escl_scan_xml();
         let scan_url = format!("{}/eSCL/ScanJobs", http_base);
 
-        println!("📤 POSTing scan job to: {}", scan_url);
+        debug!(url = %scan_url, "creating scan job");
 
         let response = client
             .post(&scan_url)
@@ -602,14 +610,14 @@ impl IcaScanSession {
             .and_then(|h| h.to_str().ok())
             .ok_or_else(|| PapyrError::Backend("No Location header".into()))?;
 
-        println!("✅ Scan job created: {}", job_url);
+        info!(job = %job_url, "scan job created");
 
         // Wait a moment for scan to start
         std::thread::sleep(std::time::Duration::from_secs(2));
 
         // Fetch document
         let doc_url = format!("{}/NextDocument", job_url);
-        println!("📥 Fetching document from: {}", doc_url);
+        debug!(url = %doc_url, "fetching document");
 
         let doc_response = client
             .get(&doc_url)
@@ -628,7 +636,7 @@ impl IcaScanSession {
             .map_err(|e| PapyrError::Backend(format!("Failed to read document: {}", e)))?
             .to_vec();
 
-        println!("✅ Downloaded {} bytes", data.len());
+        debug!(bytes = data.len(), "document downloaded");
 
         // Clean up job
         let _ = client.delete(job_url).send();
@@ -642,11 +650,7 @@ impl IcaScanSession {
             ScanSource::Adf | ScanSource::AdfDuplex => "Feeder",
         };
 
-        let color_mode = match self.config.color_mode {
-            ColorMode::Color => "RGB24",
-            ColorMode::Gray => "Grayscale8",
-            ColorMode::Bw => "BlackAndWhite1",
-        };
+        let color_mode = color_mode_setting(&self.config);
 
         format!(
             r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -659,7 +663,10 @@ impl IcaScanSession {
     <scan:YResolution>{}</scan:YResolution>
     <scan:DocumentFormat>image/jpeg</scan:DocumentFormat>
 </scan:ScanSettings>"#,
-            input_source, color_mode, self.config.dpi, self.config.dpi
+            input_source,
+            color_mode,
+            self.config.dpi,
+            self.config.vertical_dpi()
         )
     }
 
@@ -668,15 +675,11 @@ impl IcaScanSession {
     }
 
     fn try_scanimage_scan(&self) -> Result<Vec<u8>> {
-        let sane_device = self
-            .device_id
-            .strip_prefix("ica_")
-            .unwrap_or(&self.device_id);
-        self.do_scanimage_scan(sane_device)
+        self.do_scanimage_scan(self.device_id.local())
     }
 
     fn do_scanimage_scan(&self, sane_device: &str) -> Result<Vec<u8>> {
-        println!("🔧 Attempting scan via scanimage command");
+        debug!("scanning through scanimage");
 
         let temp_file = format!("/tmp/papyr_scan_{}.pnm", std::process::id());
 
@@ -692,9 +695,13 @@ impl IcaScanSession {
             ScanSource::AdfDuplex => "ADF Duplex",
         };
 
-        println!(
-            "📤 Executing: scanimage -d {} --resolution {} --mode {} --source \"{}\" -o {}",
-            sane_device, self.config.dpi, mode, source, temp_file
+        debug!(
+            device = %sane_device,
+            dpi = self.config.dpi,
+            mode,
+            source,
+            output = %temp_file,
+            "running scanimage"
         );
 
         let output = Command::new("scanimage")
@@ -706,6 +713,14 @@ impl IcaScanSession {
             .arg(mode)
             .arg("--source")
             .arg(source)
+            .args(
+                self.config
+                    .bit_depth
+                    .filter(|_| self.config.color_mode != ColorMode::Bw)
+                    .map(|depth| ["--depth".to_string(), depth.to_string()])
+                    .into_iter()
+                    .flatten(),
+            )
             .arg("-o")
             .arg(&temp_file)
             .output()
@@ -723,7 +738,7 @@ impl IcaScanSession {
         // Clean up
         let _ = std::fs::remove_file(&temp_file);
 
-        println!("✅ Scan completed: {} bytes", data.len());
+        debug!(bytes = data.len(), "scanimage finished");
 
         Ok(data)
     }
@@ -9,6 +9,19 @@
 // eSCL is always available (cross-platform network scanning)
 pub mod escl;
 
+// eSCL scans on async reqwest, for the async API
+#[cfg(feature = "async")]
+pub mod escl_async;
+
+// SANE network protocol client (pure Rust, talks to a remote saned)
+pub mod saned;
+
+// Option/status/frame handling shared by the SANE backends
+pub mod sane_common;
+
+// Third-party backends loaded from shared libraries (include/papyr_plugin.h)
+pub mod plugin;
+
 // TWAIN is available on Windows and macOS
 #[cfg(any(target_os = "windows", target_os = "macos"))]
 pub mod twain;
@@ -22,3 +35,35 @@ pub mod ica;
 
 #[cfg(feature = "sane")]
 pub mod sane;
+
+// Simulated scanners for developing and testing without hardware
+#[cfg(feature = "virtual")]
+pub mod virtual_scanner;
+
+use crate::models::{DeviceId, OptionValue};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Device options set through `BackendProvider::set_option`, kept per device
+/// id so they can be re-applied whenever the device is opened again.
+#[derive(Default)]
+pub struct OptionOverrides(Mutex<HashMap<DeviceId, Vec<(String, OptionValue)>>>);
+
+impl OptionOverrides {
+    pub fn set(&self, device_id: &DeviceId, name: &str, value: OptionValue) {
+        if let Ok(mut overrides) = self.0.lock() {
+            let device = overrides.entry(device_id.clone()).or_default();
+            device.retain(|(existing, _)| existing != name);
+            device.push((name.to_string(), value));
+        }
+    }
+
+    /// Overrides for `device_id`, in the order they were set.
+    pub fn get(&self, device_id: &DeviceId) -> Vec<(String, OptionValue)> {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|overrides| overrides.get(device_id).cloned())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,639 @@
+//
+//  papyr_core
+//  backends/plugin.rs - Third-party backends loaded from shared libraries
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+// Backends outside this crate, loaded at runtime through the C ABI in
+// include/papyr_plugin.h. The structures below mirror that header; see it
+// for the rules on threading and memory. They are kept apart from the ones
+// in ffi.rs so the papyr_core.h API can change without breaking plugins.
+
+use crate::ffi::{color_mode_to_int, int_to_color_mode, int_to_scan_source, scan_source_to_int};
+use crate::models::*;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+/// `PAPYR_PLUGIN_ABI_VERSION` of this build. Plugins built for another
+/// version are refused.
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+
+const ENTRY_POINT: &[u8] = b"papyr_plugin_entry\0";
+
+#[repr(C)]
+pub struct PluginDevice {
+    pub id: *const c_char,
+    pub name: *const c_char,
+    // Details below may be NULL when unknown
+    pub manufacturer: *const c_char,
+    pub model: *const c_char,
+    pub serial: *const c_char,
+    pub address: *const c_char,
+    pub connection: c_int, // ConnectionKind as int
+}
+
+#[repr(C)]
+pub struct PluginEvent {
+    pub event_type: c_int, // ScanEvent type as int, as in CScanEvent
+    pub page: u32,
+    pub data: *const u8,
+    pub data_size: usize,
+    pub width_px: u32,
+    pub height_px: u32,
+    pub dpi: c_int,
+    pub dpi_y: c_int, // 0 = same as dpi
+    pub color_mode: c_int,
+    pub bit_depth: c_int, // 0 = mode default
+    pub bytes: u64,
+    pub expected: i64, // -1 if unknown
+    pub code: c_int,   // status or warning code
+    pub message: *const c_char,
+}
+
+#[repr(C)]
+pub struct PluginDpiRange {
+    pub min: c_int,
+    pub max: c_int,
+    pub step: c_int,
+}
+
+#[repr(C)]
+pub struct PluginCapabilities {
+    pub sources: *const c_int,
+    pub sources_count: usize,
+    pub dpis: *const c_int,
+    pub dpis_count: usize,
+    pub color_modes: *const c_int,
+    pub color_modes_count: usize,
+    pub supports_duplex: c_int, // bool as int
+    pub dpi_ranges: *const PluginDpiRange,
+    pub dpi_ranges_count: usize,
+    pub independent_dpi: c_int, // bool as int
+    pub bit_depths: *const c_int,
+    pub bit_depths_count: usize,
+}
+
+#[repr(C)]
+pub struct PluginScanConfig {
+    pub source: c_int,
+    pub duplex: c_int, // bool as int
+    pub dpi: c_int,
+    pub dpi_y: c_int, // 0 = same as dpi
+    pub color_mode: c_int,
+    pub bit_depth: c_int, // 0 = mode default
+    pub page_width_mm: f64,
+    pub page_height_mm: f64,
+    pub has_area: c_int, // bool as int
+    pub area_x_mm: f64,
+    pub area_y_mm: f64,
+    pub area_width_mm: f64,
+    pub area_height_mm: f64,
+    pub has_brightness: c_int, // bool as int
+    pub brightness: c_int,
+    pub has_contrast: c_int, // bool as int
+    pub contrast: c_int,
+    pub max_pages: c_int, // 0 = no limit
+}
+
+#[repr(C)]
+pub struct PluginError {
+    pub message: [c_char; 256],
+}
+
+type EnumerateFn = unsafe extern "C" fn(
+    context: *mut c_void,
+    devices: *mut *const PluginDevice,
+    count: *mut usize,
+    error: *mut PluginError,
+) -> c_int;
+type CapabilitiesFn = unsafe extern "C" fn(
+    context: *mut c_void,
+    device_id: *const c_char,
+    caps: *mut PluginCapabilities,
+    error: *mut PluginError,
+) -> c_int;
+type StartScanFn = unsafe extern "C" fn(
+    context: *mut c_void,
+    device_id: *const c_char,
+    config: *const PluginScanConfig,
+    session: *mut *mut c_void,
+    error: *mut PluginError,
+) -> c_int;
+type NextEventFn = unsafe extern "C" fn(
+    session: *mut c_void,
+    event: *mut PluginEvent,
+    error: *mut PluginError,
+) -> c_int;
+type SessionFn = unsafe extern "C" fn(session: *mut c_void);
+type DestroyFn = unsafe extern "C" fn(context: *mut c_void);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PluginVTable {
+    pub abi_version: u32,
+    pub size: u32,
+    pub name: *const c_char,
+    pub context: *mut c_void,
+    pub enumerate: Option<EnumerateFn>,
+    pub capabilities: Option<CapabilitiesFn>,
+    pub start_scan: Option<StartScanFn>,
+    pub next_event: Option<NextEventFn>,
+    pub cancel: Option<SessionFn>,
+    pub free_session: Option<SessionFn>,
+    pub destroy: Option<DestroyFn>,
+}
+
+type EntryFn = unsafe extern "C" fn(host_abi_version: u32) -> *const PluginVTable;
+
+/// A loaded plugin's entry points.
+///
+/// Shared between the backend and its sessions so the library stays loaded
+/// until the last session is gone.
+struct PluginLibrary {
+    name: &'static str,
+    context: *mut c_void,
+    enumerate: EnumerateFn,
+    capabilities: CapabilitiesFn,
+    start_scan: StartScanFn,
+    next_event: NextEventFn,
+    cancel: Option<SessionFn>,
+    free_session: SessionFn,
+    destroy: Option<DestroyFn>,
+    // Plugins get one context call at a time
+    calls: Mutex<()>,
+    // Must be dropped last: the function pointers above point into it
+    _lib: libloading::Library,
+}
+
+// The context is only used under `calls`
+unsafe impl Send for PluginLibrary {}
+unsafe impl Sync for PluginLibrary {}
+
+impl PluginLibrary {
+    fn load(path: &Path) -> std::result::Result<Self, String> {
+        let lib = unsafe { libloading::Library::new(path) }.map_err(|e| e.to_string())?;
+        let entry: EntryFn = unsafe { lib.get::<EntryFn>(ENTRY_POINT).map(|s| *s) }
+            .map_err(|e| format!("papyr_plugin_entry not found: {}", e))?;
+
+        let vtable = unsafe { entry(PLUGIN_ABI_VERSION) };
+        if vtable.is_null() {
+            return Err("the plugin refused to load".to_string());
+        }
+        // Nothing past the version is known to match until it's checked
+        let abi_version = unsafe { (*vtable).abi_version };
+        if abi_version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "built for plugin ABI {}, this build has {}",
+                abi_version, PLUGIN_ABI_VERSION
+            ));
+        }
+        // Nor is the rest there to copy until the size says so
+        let size = unsafe { (*vtable).size };
+        if (size as usize) < std::mem::size_of::<PluginVTable>() {
+            return Err(format!("vtable is {} bytes, too small", size));
+        }
+        let vtable = unsafe { *vtable };
+
+        let name = unsafe { optional_string(vtable.name) }.unwrap_or_default();
+        let missing = [
+            ("enumerate", vtable.enumerate.is_none()),
+            ("capabilities", vtable.capabilities.is_none()),
+            ("start_scan", vtable.start_scan.is_none()),
+            ("next_event", vtable.next_event.is_none()),
+            ("free_session", vtable.free_session.is_none()),
+        ]
+        .into_iter()
+        .find_map(|(function, missing)| missing.then_some(function));
+        let problem = if let Some(function) = missing {
+            Some(format!("{} is missing", function))
+        } else if name.is_empty() || name.contains('/') {
+            Some(format!("{:?} is not a plugin name", name))
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            if let Some(destroy) = vtable.destroy {
+                unsafe { destroy(vtable.context) };
+            }
+            return Err(problem);
+        }
+
+        Ok(Self {
+            // Plugins are loaded a handful of times per process, so the
+            // name can live as long as `BackendProvider::name` needs
+            name: Box::leak(name.into_boxed_str()),
+            context: vtable.context,
+            enumerate: vtable.enumerate.unwrap(),
+            capabilities: vtable.capabilities.unwrap(),
+            start_scan: vtable.start_scan.unwrap(),
+            next_event: vtable.next_event.unwrap(),
+            cancel: vtable.cancel,
+            free_session: vtable.free_session.unwrap(),
+            destroy: vtable.destroy,
+            calls: Mutex::new(()),
+            _lib: lib,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for PluginLibrary {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.destroy {
+            unsafe { destroy(self.context) };
+        }
+    }
+}
+
+/// A backend plugin. Its devices have ids `plugin_<name>/<plugin's id>`.
+pub struct PluginBackend {
+    lib: Arc<PluginLibrary>,
+}
+
+impl PluginBackend {
+    /// Loads the plugin at `path`; `InvalidConfig` if it isn't a plugin or
+    /// was built for another ABI version.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let lib = PluginLibrary::load(path).map_err(|e| {
+            PapyrError::InvalidConfig(format!("Can't load plugin {}: {}", path.display(), e))
+        })?;
+        info!(plugin = lib.name, path = %path.display(), "loaded plugin");
+        Ok(Self { lib: Arc::new(lib) })
+    }
+
+    /// The plugin's own id for `device_id`.
+    fn plugin_device_id(&self, device_id: &DeviceId) -> Result<std::ffi::CString> {
+        device_id
+            .local()
+            .strip_prefix(self.lib.name)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|_| device_id.backend() == Backend::Plugin)
+            .and_then(|id| std::ffi::CString::new(id).ok())
+            .ok_or_else(|| {
+                PapyrError::NotFound(format!("{} is not a {} device", device_id, self.lib.name))
+            })
+    }
+
+    fn scanner_info(&self, device: &PluginDevice) -> Option<ScannerInfo> {
+        let id = unsafe { optional_string(device.id) }?;
+        let name = unsafe { optional_string(device.name) }.unwrap_or_else(|| id.clone());
+        let id = DeviceId::new(Backend::Plugin, format!("{}/{}", self.lib.name, id));
+
+        unsafe {
+            Some(ScannerInfo {
+                manufacturer: optional_string(device.manufacturer),
+                model: optional_string(device.model),
+                serial: optional_string(device.serial),
+                address: optional_string(device.address),
+                connection: int_to_connection(device.connection),
+                ..ScannerInfo::new(id, name)
+            })
+        }
+    }
+}
+
+impl BackendProvider for PluginBackend {
+    fn name(&self) -> &'static str {
+        self.lib.name
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::Plugin
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        let mut devices = std::ptr::null();
+        let mut count = 0;
+        let mut error = PluginError::new();
+
+        let _calls = self.lib.lock();
+        let status =
+            unsafe { (self.lib.enumerate)(self.lib.context, &mut devices, &mut count, &mut error) };
+        if status != 0 {
+            warn!(
+                plugin = self.lib.name,
+                "couldn't list devices: {}",
+                error.to_papyr(status)
+            );
+            return Vec::new();
+        }
+        if devices.is_null() {
+            return Vec::new();
+        }
+
+        unsafe { std::slice::from_raw_parts(devices, count) }
+            .iter()
+            .filter_map(|device| self.scanner_info(device))
+            .collect()
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        let plugin_id = self.plugin_device_id(device_id)?;
+        let mut caps: PluginCapabilities = unsafe { std::mem::zeroed() };
+        let mut error = PluginError::new();
+
+        let _calls = self.lib.lock();
+        let status = unsafe {
+            (self.lib.capabilities)(self.lib.context, plugin_id.as_ptr(), &mut caps, &mut error)
+        };
+        if status != 0 {
+            return Err(error.to_papyr(status));
+        }
+
+        unsafe {
+            let ranges = c_slice(caps.dpi_ranges, caps.dpi_ranges_count);
+            let bit_depths: Vec<u8> = c_slice(caps.bit_depths, caps.bit_depths_count)
+                .iter()
+                .filter_map(|&depth| u8::try_from(depth).ok())
+                .collect();
+            Ok(Capabilities {
+                sources: c_slice(caps.sources, caps.sources_count)
+                    .iter()
+                    .map(|&source| int_to_scan_source(source))
+                    .collect(),
+                dpis: c_slice(caps.dpis, caps.dpis_count)
+                    .iter()
+                    .filter_map(|&dpi| u32::try_from(dpi).ok())
+                    .collect(),
+                dpi_ranges: ranges
+                    .iter()
+                    .map(|range: &PluginDpiRange| DpiRange {
+                        min: range.min.max(0) as u32,
+                        max: range.max.max(0) as u32,
+                        step: range.step.max(0) as u32,
+                    })
+                    .collect(),
+                independent_dpi: caps.independent_dpi != 0,
+                color_modes: c_slice(caps.color_modes, caps.color_modes_count)
+                    .iter()
+                    .map(|&mode| int_to_color_mode(mode))
+                    .collect(),
+                bit_depths: if bit_depths.is_empty() {
+                    vec![8]
+                } else {
+                    bit_depths
+                },
+                page_sizes: page_formats_for(&[]),
+                max_areas: Vec::new(),
+                supports_duplex: caps.supports_duplex != 0,
+            })
+        }
+    }
+
+    fn start_scan(&self, device_id: &DeviceId, cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        let plugin_id = self.plugin_device_id(device_id)?;
+        let area = cfg.area.unwrap_or(ScanArea {
+            x_mm: 0.0,
+            y_mm: 0.0,
+            width_mm: 0.0,
+            height_mm: 0.0,
+        });
+        let config = PluginScanConfig {
+            source: scan_source_to_int(cfg.source),
+            duplex: cfg.duplex as c_int,
+            dpi: cfg.dpi as c_int,
+            dpi_y: cfg.dpi_y.unwrap_or(0) as c_int,
+            color_mode: color_mode_to_int(cfg.color_mode),
+            bit_depth: cfg.bit_depth.unwrap_or(0) as c_int,
+            page_width_mm: cfg.page_size.width_mm,
+            page_height_mm: cfg.page_size.height_mm,
+            has_area: cfg.area.is_some() as c_int,
+            area_x_mm: area.x_mm,
+            area_y_mm: area.y_mm,
+            area_width_mm: area.width_mm,
+            area_height_mm: area.height_mm,
+            has_brightness: cfg.brightness.is_some() as c_int,
+            brightness: cfg.brightness.unwrap_or(0) as c_int,
+            has_contrast: cfg.contrast.is_some() as c_int,
+            contrast: cfg.contrast.unwrap_or(0) as c_int,
+            max_pages: cfg.max_pages.unwrap_or(0) as c_int,
+        };
+        let mut session = std::ptr::null_mut();
+        let mut error = PluginError::new();
+
+        let _calls = self.lib.lock();
+        let status = unsafe {
+            (self.lib.start_scan)(
+                self.lib.context,
+                plugin_id.as_ptr(),
+                &config,
+                &mut session,
+                &mut error,
+            )
+        };
+        if status != 0 {
+            return Err(error.to_papyr(status));
+        }
+        if session.is_null() {
+            return Err(PapyrError::Protocol {
+                backend: Backend::Plugin,
+                message: format!("plugin {} started a scan without a session", self.lib.name),
+            });
+        }
+
+        debug!(plugin = self.lib.name, device = %device_id, "scan started");
+        Ok(Box::new(PluginSession {
+            handle: Arc::new(SessionHandle {
+                lib: Arc::clone(&self.lib),
+                session: Mutex::new(SessionPtr(session)),
+            }),
+            done: false,
+        }))
+    }
+}
+
+struct SessionPtr(*mut c_void);
+
+// Plugins allow cancel from any thread, see papyr_plugin.h
+unsafe impl Send for SessionPtr {}
+
+/// A plugin session, shared with its cancel handle. The pointer is NULL
+/// once freed, so a late cancel is a no-op.
+struct SessionHandle {
+    lib: Arc<PluginLibrary>,
+    session: Mutex<SessionPtr>,
+}
+
+impl SessionHandle {
+    fn session(&self) -> std::sync::MutexGuard<'_, SessionPtr> {
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cancel(&self) {
+        let session = self.session();
+        if let (Some(cancel), false) = (self.lib.cancel, session.0.is_null()) {
+            unsafe { cancel(session.0) };
+        }
+    }
+}
+
+pub struct PluginSession {
+    handle: Arc<SessionHandle>,
+    done: bool,
+}
+
+impl ScanSession for PluginSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut event: PluginEvent = unsafe { std::mem::zeroed() };
+        let mut error = PluginError::new();
+
+        // Not held during the call, which may block until cancelled
+        let session = self.handle.session().0;
+        let status = unsafe { (self.handle.lib.next_event)(session, &mut event, &mut error) };
+        if status != 0 {
+            self.done = true;
+            return Err(error.to_papyr(status));
+        }
+
+        let event = unsafe { scan_event(&event) };
+        self.done = event.as_ref().map_or(true, ScanEvent::is_terminal);
+        event.map(Some)
+    }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        self.handle.lib.cancel?;
+        let handle = Arc::clone(&self.handle);
+        Some(CancelHandle::new(move || handle.cancel()))
+    }
+}
+
+impl Drop for PluginSession {
+    fn drop(&mut self) {
+        let mut session = self.handle.session();
+        if !session.0.is_null() {
+            unsafe { (self.handle.lib.free_session)(session.0) };
+            session.0 = std::ptr::null_mut();
+        }
+    }
+}
+
+impl PluginError {
+    fn new() -> Self {
+        Self { message: [0; 256] }
+    }
+
+    /// The error for a plugin's failure `code` (a `PapyrError::code`).
+    fn to_papyr(&self, code: c_int) -> PapyrError {
+        let bytes: Vec<u8> = self
+            .message
+            .iter()
+            .map(|&c| c as u8)
+            .take_while(|&b| b != 0)
+            .collect();
+        let message = match String::from_utf8_lossy(&bytes) {
+            text if text.trim().is_empty() => format!("plugin error {}", code),
+            text => text.into_owned(),
+        };
+
+        let backend = Backend::Plugin;
+        match code {
+            1 => PapyrError::NotFound(message),
+            2 => PapyrError::InvalidConfig(message),
+            4 => PapyrError::DeviceBusy { backend, message },
+            5 => PapyrError::FeederEmpty { backend, message },
+            6 => PapyrError::PaperJam { backend, message },
+            7 => PapyrError::CoverOpen { backend, message },
+            8 => PapyrError::Timeout { backend, message },
+            9 => PapyrError::Cancelled,
+            10 => PapyrError::Unauthorized { backend, message },
+            11 => PapyrError::Unsupported {
+                backend,
+                option: message,
+            },
+            12 => PapyrError::Io { backend, message },
+            13 => PapyrError::Protocol { backend, message },
+            14 => PapyrError::NotImplemented,
+            15 => PapyrError::Other(message),
+            _ => PapyrError::Backend(message),
+        }
+    }
+}
+
+/// The `ScanEvent` a plugin reported.
+unsafe fn scan_event(event: &PluginEvent) -> Result<ScanEvent> {
+    let invalid = |what: &str, value: c_int| PapyrError::Protocol {
+        backend: Backend::Plugin,
+        message: format!("plugin sent unknown {} {}", what, value),
+    };
+
+    Ok(match event.event_type {
+        0 => ScanEvent::PageStarted(event.page),
+        1 => ScanEvent::PageData(c_slice(event.data, event.data_size).to_vec()),
+        2 => {
+            let color_mode = int_to_color_mode(event.color_mode);
+            let bit_depth = match (color_mode, event.bit_depth) {
+                (ColorMode::Bw, _) => 1,
+                (_, depth) if depth > 0 => depth as u8,
+                _ => 8,
+            };
+            let dpi = event.dpi.max(0) as u32;
+            ScanEvent::PageComplete(PageMeta {
+                index: event.page,
+                width_px: event.width_px,
+                height_px: event.height_px,
+                dpi,
+                dpi_y: if event.dpi_y > 0 {
+                    event.dpi_y as u32
+                } else {
+                    dpi
+                },
+                color_mode,
+                bit_depth,
+            })
+        }
+        3 => ScanEvent::JobComplete,
+        4 => ScanEvent::progress(event.bytes, u64::try_from(event.expected).ok()),
+        5 => ScanEvent::Status(match event.code {
+            0 => ScanStatus::WaitingForDevice,
+            1 => ScanStatus::WarmingUp,
+            2 => ScanStatus::Scanning,
+            code => return Err(invalid("status", code)),
+        }),
+        6 => {
+            let code = match event.code {
+                0 => WarningCode::ConfigAdjusted,
+                1 => WarningCode::OptionNotApplied,
+                2 => WarningCode::CleanupFailed,
+                3 => WarningCode::BackendFallback,
+                code => return Err(invalid("warning", code)),
+            };
+            ScanEvent::warning(code, optional_string(event.message).unwrap_or_default())
+        }
+        7 => ScanEvent::Cancelled,
+        event_type => return Err(invalid("event type", event_type)),
+    })
+}
+
+fn int_to_connection(val: c_int) -> ConnectionKind {
+    match val {
+        1 => ConnectionKind::Usb,
+        2 => ConnectionKind::Network,
+        3 => ConnectionKind::Virtual,
+        _ => ConnectionKind::Unknown,
+    }
+}
+
+unsafe fn optional_string(value: *const c_char) -> Option<String> {
+    (!value.is_null()).then(|| CStr::from_ptr(value).to_string_lossy().into_owned())
+}
+
+/// `count` items at `items`; empty for NULL.
+unsafe fn c_slice<'a, T>(items: *const T, count: usize) -> &'a [T] {
+    if items.is_null() || count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(items, count)
+    }
+}
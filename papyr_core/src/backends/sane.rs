@@ -6,17 +6,26 @@
 //  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
 //
 
+use crate::auth;
+use crate::backends::sane_common::*;
+use crate::backends::OptionOverrides;
 use crate::models::*;
-use std::ffi::{CStr, CString};
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString, OsStr};
 use std::os::raw::{c_char, c_int, c_void};
 use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
 
-// SANE FFI bindings
-const SANE_STATUS_GOOD: c_int = 0;
-const SANE_STATUS_EOF: c_int = 5;
+const SANE_TRUE: c_int = 1;
 
-const SANE_FRAME_GRAY: c_int = 0;
-const SANE_FRAME_RGB: c_int = 1;
+/// How long a read may go without data before the scan is abandoned.
+pub const DEFAULT_SANE_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Longest single wait on the select fd, so cancellation is noticed promptly
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[repr(C)]
 // SAFETY: SaneHandle is just a wrapper around a C pointer that represents
@@ -44,7 +53,14 @@ struct SaneParameters {
 }
 
 #[repr(C)]
-struct SaneOptionDescriptor {
+struct SaneRange {
+    min: c_int,
+    max: c_int,
+    quant: c_int,
+}
+
+#[repr(C)]
+struct RawOptionDescriptor {
     name: *const c_char,
     title: *const c_char,
     desc: *const c_char,
@@ -53,57 +69,319 @@ struct SaneOptionDescriptor {
     size: c_int,
     cap: c_int,
     constraint_type: c_int,
-    // ... more fields would be needed for full implementation
+    // Union of string list, word list and range pointers
+    constraint: *const c_void,
 }
 
-#[link(name = "sane", kind = "dylib")]
-extern "C" {
-    fn sane_init(version_code: *mut c_int, authorize: *const c_void) -> c_int;
-    fn sane_exit();
-    fn sane_get_devices(device_list: *mut *const *const SaneDevice, local_only: c_int) -> c_int;
-    fn sane_open(devicename: *const c_char, handle: *mut SaneHandle) -> c_int;
-    fn sane_close(handle: SaneHandle);
-    fn sane_get_option_descriptor(handle: SaneHandle, option: c_int)
-        -> *const SaneOptionDescriptor;
-    fn sane_control_option(
-        handle: SaneHandle,
-        option: c_int,
-        action: c_int,
-        value: *mut c_void,
-        info: *mut c_int,
-    ) -> c_int;
-    fn sane_start(handle: SaneHandle) -> c_int;
-    fn sane_get_parameters(handle: SaneHandle, params: *mut SaneParameters) -> c_int;
-    fn sane_read(handle: SaneHandle, data: *mut u8, max_length: c_int, length: *mut c_int)
-        -> c_int;
-    fn sane_cancel(handle: SaneHandle);
+unsafe fn c_string(value: *const c_char) -> String {
+    if value.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(value).to_string_lossy().into_owned()
+    }
 }
 
-pub struct SaneBackend {
+impl RawOptionDescriptor {
+    /// Copies the descriptor out of libsane-owned memory.
+    unsafe fn to_owned(&self) -> SaneOptionDescriptor {
+        let constraint = match self.constraint_type {
+            _ if self.constraint.is_null() => SaneConstraint::None,
+            SANE_CONSTRAINT_RANGE => {
+                let range = &*(self.constraint as *const SaneRange);
+                SaneConstraint::Range {
+                    min: range.min,
+                    max: range.max,
+                    quant: range.quant,
+                }
+            }
+            SANE_CONSTRAINT_WORD_LIST => {
+                // The first word is the number of entries that follow
+                let words = self.constraint as *const c_int;
+                let len = (*words).max(0) as usize;
+                SaneConstraint::WordList(std::slice::from_raw_parts(words.add(1), len).to_vec())
+            }
+            SANE_CONSTRAINT_STRING_LIST => {
+                let mut strings = Vec::new();
+                let mut entry = self.constraint as *const *const c_char;
+                while !(*entry).is_null() {
+                    strings.push(c_string(*entry));
+                    entry = entry.add(1);
+                }
+                SaneConstraint::StringList(strings)
+            }
+            _ => SaneConstraint::None,
+        };
+
+        SaneOptionDescriptor {
+            name: c_string(self.name),
+            title: c_string(self.title),
+            desc: c_string(self.desc),
+            value_type: self.option_type,
+            unit: self.unit,
+            size: self.size,
+            cap: self.cap,
+            constraint,
+        }
+    }
+}
+
+// Buffer sizes libsane hands to the authorization callback
+const SANE_MAX_USERNAME_LEN: usize = 128;
+const SANE_MAX_PASSWORD_LEN: usize = 128;
+
+type SaneAuthCallback =
+    unsafe extern "C" fn(resource: *const c_char, username: *mut c_char, password: *mut c_char);
+
+type SaneInitFn =
+    unsafe extern "C" fn(version_code: *mut c_int, authorize: Option<SaneAuthCallback>) -> c_int;
+type SaneExitFn = unsafe extern "C" fn();
+type SaneGetDevicesFn =
+    unsafe extern "C" fn(device_list: *mut *const *const SaneDevice, local_only: c_int) -> c_int;
+type SaneOpenFn = unsafe extern "C" fn(devicename: *const c_char, handle: *mut SaneHandle) -> c_int;
+type SaneCloseFn = unsafe extern "C" fn(handle: SaneHandle);
+type SaneGetOptionDescriptorFn =
+    unsafe extern "C" fn(handle: SaneHandle, option: c_int) -> *const RawOptionDescriptor;
+type SaneControlOptionFn = unsafe extern "C" fn(
+    handle: SaneHandle,
+    option: c_int,
+    action: c_int,
+    value: *mut c_void,
+    info: *mut c_int,
+) -> c_int;
+type SaneStartFn = unsafe extern "C" fn(handle: SaneHandle) -> c_int;
+type SaneGetParametersFn =
+    unsafe extern "C" fn(handle: SaneHandle, params: *mut SaneParameters) -> c_int;
+type SaneReadFn = unsafe extern "C" fn(
+    handle: SaneHandle,
+    data: *mut u8,
+    max_length: c_int,
+    length: *mut c_int,
+) -> c_int;
+type SaneCancelFn = unsafe extern "C" fn(handle: SaneHandle);
+type SaneSetIoModeFn = unsafe extern "C" fn(handle: SaneHandle, non_blocking: c_int) -> c_int;
+type SaneGetSelectFdFn = unsafe extern "C" fn(handle: SaneHandle, fd: *mut c_int) -> c_int;
+
+/// Environment variable overriding the libsane location.
+pub const SANE_LIBRARY_ENV: &str = "PAPYR_SANE_LIBRARY";
+
+// Library names tried, in order, when no explicit path is configured
+#[cfg(target_os = "macos")]
+const DEFAULT_SANE_LIBRARIES: &[&str] = &[
+    "libsane.1.dylib",
+    "libsane.dylib",
+    "/opt/homebrew/lib/libsane.1.dylib",
+    "/usr/local/lib/libsane.1.dylib",
+];
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_SANE_LIBRARIES: &[&str] = &["libsane.so.1", "libsane.so"];
+
+/// Copies `value` into a libsane-owned buffer of `capacity` bytes, truncating
+/// and always NUL-terminating.
+unsafe fn copy_to_c_buffer(value: &str, buffer: *mut c_char, capacity: usize) {
+    let bytes = value.as_bytes();
+    let len = bytes.len().min(capacity - 1);
+    ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, buffer, len);
+    *buffer.add(len) = 0;
+}
+
+/// `SANE_Auth_Callback` handed to `sane_init`; answers from the
+/// process-wide credentials provider.
+unsafe extern "C" fn authorize_callback(
+    resource: *const c_char,
+    username: *mut c_char,
+    password: *mut c_char,
+) {
+    if resource.is_null() || username.is_null() || password.is_null() {
+        return;
+    }
+
+    let resource = CStr::from_ptr(resource).to_string_lossy();
+    let (name, _) = auth::split_md5_resource(&resource);
+
+    match auth::credentials_for(name) {
+        Some(credentials) => {
+            copy_to_c_buffer(&credentials.username, username, SANE_MAX_USERNAME_LEN);
+            copy_to_c_buffer(
+                &auth::encode_password(&resource, &credentials.password),
+                password,
+                SANE_MAX_PASSWORD_LEN,
+            );
+        }
+        None => {
+            info!(resource = %name, "no credentials available");
+            *username = 0;
+            *password = 0;
+        }
+    }
+}
+
+/// libsane entry points resolved at runtime.
+///
+/// Shared between the backend and its sessions so the library stays loaded
+/// (and `sane_exit` is deferred) until the last open handle is gone.
+struct SaneLibrary {
+    init: SaneInitFn,
+    exit: SaneExitFn,
+    get_devices: SaneGetDevicesFn,
+    open: SaneOpenFn,
+    close: SaneCloseFn,
+    get_option_descriptor: SaneGetOptionDescriptorFn,
+    control_option: SaneControlOptionFn,
+    start: SaneStartFn,
+    get_parameters: SaneGetParametersFn,
+    read: SaneReadFn,
+    cancel: SaneCancelFn,
+    set_io_mode: SaneSetIoModeFn,
+    get_select_fd: SaneGetSelectFdFn,
     initialized: bool,
+    // Must be dropped last: the function pointers above point into it
+    _lib: libloading::Library,
+}
+
+impl SaneLibrary {
+    fn load(path: &OsStr) -> std::result::Result<Self, String> {
+        let lib = unsafe { libloading::Library::new(path) }
+            .map_err(|e| format!("failed to load {}: {}", path.to_string_lossy(), e))?;
+
+        unsafe fn symbol<T: Copy>(
+            lib: &libloading::Library,
+            path: &OsStr,
+            name: &[u8],
+        ) -> std::result::Result<T, String> {
+            lib.get::<T>(name).map(|s| *s).map_err(|e| {
+                format!(
+                    "{} not found in {}: {}",
+                    String::from_utf8_lossy(&name[..name.len() - 1]),
+                    path.to_string_lossy(),
+                    e
+                )
+            })
+        }
+
+        unsafe {
+            Ok(Self {
+                init: symbol(&lib, path, b"sane_init\0")?,
+                exit: symbol(&lib, path, b"sane_exit\0")?,
+                get_devices: symbol(&lib, path, b"sane_get_devices\0")?,
+                open: symbol(&lib, path, b"sane_open\0")?,
+                close: symbol(&lib, path, b"sane_close\0")?,
+                get_option_descriptor: symbol(&lib, path, b"sane_get_option_descriptor\0")?,
+                control_option: symbol(&lib, path, b"sane_control_option\0")?,
+                start: symbol(&lib, path, b"sane_start\0")?,
+                get_parameters: symbol(&lib, path, b"sane_get_parameters\0")?,
+                read: symbol(&lib, path, b"sane_read\0")?,
+                cancel: symbol(&lib, path, b"sane_cancel\0")?,
+                set_io_mode: symbol(&lib, path, b"sane_set_io_mode\0")?,
+                get_select_fd: symbol(&lib, path, b"sane_get_select_fd\0")?,
+                initialized: false,
+                _lib: lib,
+            })
+        }
+    }
+
+    /// Loads the first library from `candidates` that resolves and initializes.
+    fn open_any<S: AsRef<OsStr>>(candidates: &[S]) -> std::result::Result<Self, String> {
+        let mut last_error = None;
+
+        for candidate in candidates {
+            match Self::load(candidate.as_ref()) {
+                Ok(mut lib) => {
+                    let mut version = 0;
+                    let status = unsafe { (lib.init)(&mut version, Some(authorize_callback)) };
+                    if status == SANE_STATUS_GOOD {
+                        lib.initialized = true;
+                        return Ok(lib);
+                    }
+                    last_error = Some(format!(
+                        "sane_init failed for {}: {}",
+                        candidate.as_ref().to_string_lossy(),
+                        status
+                    ));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| "no SANE library candidates configured".to_string()))
+    }
+}
+
+impl Drop for SaneLibrary {
+    fn drop(&mut self) {
+        if self.initialized {
+            unsafe {
+                (self.exit)();
+            }
+        }
+    }
+}
+
+pub struct SaneBackend {
+    lib: Option<Arc<SaneLibrary>>,
+    availability: BackendAvailability,
+    read_timeout: Duration,
+    overrides: OptionOverrides,
 }
 
 impl SaneBackend {
+    /// Loads libsane from `PAPYR_SANE_LIBRARY` if set, otherwise from the
+    /// platform's default library names.
     pub fn new() -> Self {
-        let mut version = 0;
-        let initialized = unsafe { sane_init(&mut version, ptr::null()) == SANE_STATUS_GOOD };
+        match std::env::var_os(SANE_LIBRARY_ENV) {
+            Some(path) => Self::with_library_path(path),
+            None => Self::from_result(SaneLibrary::open_any(DEFAULT_SANE_LIBRARIES)),
+        }
+    }
 
-        Self { initialized }
+    /// Loads libsane from an explicit path.
+    pub fn with_library_path<P: AsRef<OsStr>>(path: P) -> Self {
+        Self::from_result(SaneLibrary::open_any(&[path.as_ref()]))
     }
 
-    fn enumerate_devices(&self) -> Result<Vec<ScannerInfo>> {
-        if !self.initialized {
-            return Err(PapyrError::Backend("SANE not initialized".into()));
+    /// Sets how long a scan may go without receiving data before it fails.
+    ///
+    /// Only enforced for devices that support non-blocking I/O; other
+    /// devices can still be stopped through the session's cancel handle.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    fn from_result(lib: std::result::Result<SaneLibrary, String>) -> Self {
+        let (lib, availability) = match lib {
+            Ok(lib) => (Some(Arc::new(lib)), BackendAvailability::Available),
+            Err(reason) => (None, BackendAvailability::Unavailable(reason)),
+        };
+
+        Self {
+            lib,
+            availability,
+            read_timeout: DEFAULT_SANE_READ_TIMEOUT,
+            overrides: OptionOverrides::default(),
+        }
+    }
+
+    fn lib(&self) -> Result<&Arc<SaneLibrary>> {
+        match (&self.lib, &self.availability) {
+            (Some(lib), _) => Ok(lib),
+            (None, BackendAvailability::Unavailable(reason)) => Err(PapyrError::Backend(format!(
+                "SANE backend unavailable: {}",
+                reason
+            ))),
+            (None, BackendAvailability::Available) => {
+                Err(PapyrError::Backend("SANE not initialized".into()))
+            }
         }
+    }
+
+    fn enumerate_devices(&self) -> Result<Vec<ScannerInfo>> {
+        let lib = self.lib()?;
 
         let mut device_list: *const *const SaneDevice = ptr::null();
-        let status = unsafe { sane_get_devices(&mut device_list, 1) };
+        let status = unsafe { (lib.get_devices)(&mut device_list, 1) };
 
         if status != SANE_STATUS_GOOD {
-            return Err(PapyrError::Backend(format!(
-                "Failed to get devices: {}",
-                status
-            )));
+            return Err(status_error(Backend::Sane, "Failed to get devices", status));
         }
 
         let mut scanners = Vec::new();
@@ -131,11 +409,19 @@ impl SaneBackend {
                     "".to_string()
                 };
 
-                scanners.push(ScannerInfo {
-                    id: format!("sane_{}", name.replace(":", "_")),
-                    name: format!("{} {}", vendor, model).trim().to_string(),
-                    backend: Backend::Sane,
-                });
+                let kind = if !device.device_type.is_null() {
+                    CStr::from_ptr(device.device_type)
+                        .to_string_lossy()
+                        .into_owned()
+                } else {
+                    "".to_string()
+                };
+
+                let info = ScannerInfo::new(
+                    sane_device_id(&name),
+                    format!("{} {}", vendor, model).trim(),
+                );
+                scanners.push(describe_device(info, &name, &vendor, &model, &kind));
 
                 i += 1;
             }
@@ -144,82 +430,142 @@ impl SaneBackend {
         Ok(scanners)
     }
 
-    fn get_device_capabilities(&self, device_name: &str) -> Result<Capabilities> {
+    /// Opens `device_id`; the caller owns the returned handle.
+    fn open_device(&self, device_id: &DeviceId) -> Result<(&Arc<SaneLibrary>, SaneHandle)> {
+        let device_name = sane_device_name(device_id)
+            .ok_or_else(|| PapyrError::NotFound(format!("Device {} not found", device_id)))?;
+        let lib = self.lib()?;
+
         let device_name_c = CString::new(device_name)
             .map_err(|_| PapyrError::InvalidConfig("Invalid device name".into()))?;
 
         let mut handle = SaneHandle(ptr::null_mut());
-        let status = unsafe { sane_open(device_name_c.as_ptr(), &mut handle) };
+        let status = unsafe { (lib.open)(device_name_c.as_ptr(), &mut handle) };
 
         if status != SANE_STATUS_GOOD {
-            return Err(PapyrError::Backend(format!(
-                "Failed to open device: {}",
-                status
-            )));
-        }
-
-        // Parse SANE options to build capabilities
-        let mut dpis = Vec::new();
-        let mut color_modes = Vec::new();
-        let mut sources = vec![ScanSource::Flatbed]; // Default to flatbed
-
-        // Option indices vary by device, typically:
-        // 0-3: Standard options (count, group, etc.)
-        // 4+: Device-specific options
-        for i in 0..100 {
-            let desc = unsafe { sane_get_option_descriptor(handle, i) };
-            if desc.is_null() {
-                break;
-            }
+            return Err(status_error(Backend::Sane, "Failed to open device", status));
+        }
 
-            unsafe {
-                if !(*desc).name.is_null() {
-                    let name = CStr::from_ptr((*desc).name).to_string_lossy();
-
-                    match name.as_ref() {
-                        "resolution" => {
-                            // Typically supports 75, 150, 300, 600
-                            dpis = vec![75, 150, 300, 600];
-                        }
-                        "mode" => {
-                            color_modes = vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw];
-                        }
-                        "source" => {
-                            sources = vec![ScanSource::Flatbed, ScanSource::Adf];
-                        }
-                        _ => {}
-                    }
+        Ok((lib, handle))
+    }
+
+    /// Opens `device_id` with its remembered options applied, runs `f` and
+    /// closes the device again.
+    fn with_device<T>(
+        &self,
+        device_id: &DeviceId,
+        f: impl FnOnce(&mut LibDevice) -> Result<T>,
+    ) -> Result<T> {
+        let (lib, handle) = self.open_device(device_id)?;
+        let mut dev = LibDevice { lib, handle };
+
+        let result =
+            apply_overrides(&mut dev, &self.overrides.get(device_id)).and_then(|_| f(&mut dev));
+        unsafe {
+            (lib.close)(handle);
+        }
+        result
+    }
+}
+
+/// An open libsane handle.
+struct LibDevice<'a> {
+    lib: &'a SaneLibrary,
+    handle: SaneHandle,
+}
+
+impl SaneOptionAccess for LibDevice<'_> {
+    fn descriptors(&mut self) -> Result<Vec<SaneOptionDescriptor>> {
+        // Option 0 always exists and holds the number of options
+        let count_desc = unsafe { (self.lib.get_option_descriptor)(self.handle, 0) };
+        if count_desc.is_null() {
+            return Err(PapyrError::Backend("SANE device reports no options".into()));
+        }
+        let count_desc = unsafe { (*count_desc).to_owned() };
+        let count = match self.control(0, &count_desc, SANE_ACTION_GET_VALUE, None)? {
+            (_, Some(SaneValue::Int(count))) => count.max(1),
+            _ => 1,
+        };
+
+        let mut options = vec![count_desc];
+        for index in 1..count {
+            let desc = unsafe { (self.lib.get_option_descriptor)(self.handle, index) };
+            options.push(if desc.is_null() {
+                // Keep indices aligned with libsane's option numbers
+                SaneOptionDescriptor {
+                    name: String::new(),
+                    title: String::new(),
+                    desc: String::new(),
+                    value_type: SANE_TYPE_GROUP,
+                    unit: SANE_UNIT_NONE,
+                    size: 0,
+                    cap: SANE_CAP_INACTIVE,
+                    constraint: SaneConstraint::None,
                 }
+            } else {
+                unsafe { (*desc).to_owned() }
+            });
+        }
+
+        Ok(options)
+    }
+
+    fn control(
+        &mut self,
+        index: usize,
+        desc: &SaneOptionDescriptor,
+        action: i32,
+        value: Option<&SaneValue>,
+    ) -> Result<(i32, Option<SaneValue>)> {
+        // libsane reads the value from, and writes the result back to, a
+        // buffer of `size` bytes
+        let size = (desc.size.max(4) as usize).next_multiple_of(4);
+        let mut buffer = vec![0u8; size];
+        match value {
+            Some(SaneValue::String(s)) => {
+                let len = s.len().min(size - 1);
+                buffer[..len].copy_from_slice(&s.as_bytes()[..len]);
+            }
+            Some(value) => {
+                let word: c_int = match value {
+                    SaneValue::Bool(b) => *b as c_int,
+                    SaneValue::Int(i) => *i,
+                    SaneValue::Fixed(f) => f64_to_fixed(*f),
+                    SaneValue::String(_) => 0,
+                };
+                buffer[..4].copy_from_slice(&word.to_ne_bytes());
             }
+            None => {}
         }
 
-        unsafe {
-            sane_close(handle);
-        }
-
-        if dpis.is_empty() {
-            dpis = vec![75, 150, 300, 600];
-        }
-        if color_modes.is_empty() {
-            color_modes = vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw];
-        }
-
-        Ok(Capabilities {
-            sources,
-            dpis,
-            color_modes,
-            page_sizes: vec![
-                PageSize {
-                    width_mm: 216,
-                    height_mm: 279,
-                }, // Letter
-                PageSize {
-                    width_mm: 210,
-                    height_mm: 297,
-                }, // A4
-            ],
-            supports_duplex: false,
-        })
+        let value_ptr = if desc.value_type == SANE_TYPE_BUTTON {
+            ptr::null_mut()
+        } else {
+            buffer.as_mut_ptr() as *mut c_void
+        };
+        let mut info: c_int = 0;
+        let status = unsafe {
+            (self.lib.control_option)(self.handle, index as c_int, action, value_ptr, &mut info)
+        };
+        if status != SANE_STATUS_GOOD {
+            return Err(option_error(Backend::Sane, &desc.name, status));
+        }
+
+        let word = c_int::from_ne_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        let value = match desc.value_type {
+            SANE_TYPE_STRING => {
+                let end = buffer.iter().position(|&b| b == 0).unwrap_or(size);
+                Some(SaneValue::String(
+                    String::from_utf8_lossy(&buffer[..end]).into_owned(),
+                ))
+            }
+            SANE_TYPE_BOOL => Some(SaneValue::Bool(word != 0)),
+            SANE_TYPE_INT => Some(SaneValue::Int(word)),
+            SANE_TYPE_FIXED => Some(SaneValue::Fixed(fixed_to_f64(word))),
+            _ => None,
+        };
+
+        Ok((info, value))
     }
 }
 
@@ -232,155 +578,431 @@ impl BackendProvider for SaneBackend {
         Backend::Sane
     }
 
+    fn availability(&self) -> BackendAvailability {
+        self.availability.clone()
+    }
+
     fn enumerate(&self) -> Vec<ScannerInfo> {
         self.enumerate_devices().unwrap_or_default()
     }
 
-    fn capabilities(&self, device_id: &str) -> Result<Capabilities> {
-        // Extract device name from ID (remove "sane_" prefix)
-        let device_name = device_id
-            .strip_prefix("sane_")
-            .unwrap_or(device_id)
-            .replace("_", ":");
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        self.with_device(device_id, |dev| {
+            Ok(capabilities_from_options(&dev.descriptors()?))
+        })
+    }
 
-        self.get_device_capabilities(&device_name)
+    fn start_scan(&self, device_id: &DeviceId, cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        self.start_scan_cancellable(device_id, cfg, &CancelToken::new())
     }
 
-    fn start_scan(&self, device_id: &str, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
-        let device_name = device_id
-            .strip_prefix("sane_")
-            .unwrap_or(device_id)
-            .replace("_", ":");
+    fn start_scan_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cfg: ScanConfig,
+        cancel: &CancelToken,
+    ) -> Result<Box<dyn ScanSession>> {
+        cancel.check()?;
+        let (lib, handle) = self.open_device(device_id)?;
+
+        let mut dev = LibDevice { lib, handle };
+        let configured = apply_config(&mut dev, &cfg).and_then(|mut warnings| {
+            warnings.extend(apply_overrides(&mut dev, &self.overrides.get(device_id))?);
+            cancel.check()?;
+            Ok(warnings)
+        });
+        let warnings = match configured {
+            Ok(warnings) => warnings,
+            Err(e) => {
+                unsafe {
+                    (lib.close)(handle);
+                }
+                return Err(e);
+            }
+        };
+
+        // From here the session owns the handle and closes it on drop
+        let mut session = SaneScanSession {
+            device: Arc::new(SaneOpenDevice {
+                lib: Arc::clone(lib),
+                handle: Mutex::new(Some(handle)),
+                cancelled: AtomicBool::new(false),
+            }),
+            handle,
+            cfg,
+            non_blocking: false,
+            select_fd: None,
+            read_timeout: self.read_timeout,
+            buffer: vec![0u8; 32 * 1024], // 32KB buffer
+            frame: None,
+            assembler: PageAssembler::new(cfg!(target_endian = "little")),
+            page_index: 0,
+            page_bytes: 0,
+            page_expected: None,
+            status: None,
+            pending: warnings.into(),
+            failure: None,
+            state: SaneScanState::Scanning,
+        };
+        // Bound before starting, so cancelling also ends a lamp warm-up
+        let device = Arc::clone(&session.device);
+        cancel.on_cancel(CancelHandle::new(move || device.cancel()));
+        if !session.start_page()? {
+            return Err(status_error(
+                Backend::Sane,
+                "Failed to start scan",
+                SANE_STATUS_NO_DOCS,
+            ));
+        }
 
-        let device_name_c = CString::new(device_name.clone())
-            .map_err(|_| PapyrError::InvalidConfig("Invalid device name".into()))?;
+        Ok(Box::new(session))
+    }
 
-        let mut handle = SaneHandle(ptr::null_mut());
-        let status = unsafe { sane_open(device_name_c.as_ptr(), &mut handle) };
+    fn options(&self, device_id: &DeviceId) -> Result<Vec<DeviceOption>> {
+        self.with_device(device_id, |dev| read_options(dev))
+    }
 
-        if status != SANE_STATUS_GOOD {
-            return Err(PapyrError::Backend(format!(
-                "Failed to open device: {}",
-                status
-            )));
+    fn set_option(
+        &self,
+        device_id: &DeviceId,
+        name: &str,
+        value: OptionValue,
+    ) -> Result<DeviceOption> {
+        let (option, remembered) =
+            self.with_device(device_id, |dev| write_device_option(dev, name, value))?;
+        if let Some(value) = remembered {
+            self.overrides.set(device_id, name, value);
         }
+        Ok(option)
+    }
+}
 
-        // TODO: Set options based on cfg (resolution, color mode, etc.)
-        // This requires finding the option indices and setting values
+/// Open handle shared between a session and its cancel handles.
+///
+/// The handle is taken out when the session closes it, so a cancel handle
+/// that outlives its session never touches a closed device.
+struct SaneOpenDevice {
+    lib: Arc<SaneLibrary>,
+    handle: Mutex<Option<SaneHandle>>,
+    cancelled: AtomicBool,
+}
 
-        // Start scanning
-        let status = unsafe { sane_start(handle) };
-        if status != SANE_STATUS_GOOD {
-            unsafe {
-                sane_close(handle);
+impl SaneOpenDevice {
+    /// `sane_cancel` may be called from any thread; a read blocked in
+    /// another thread then returns `SANE_STATUS_CANCELLED`.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(handle) = self.handle.lock() {
+            if let Some(handle) = *handle {
+                unsafe {
+                    (self.lib.cancel)(handle);
+                }
             }
-            return Err(PapyrError::Backend(format!(
-                "Failed to start scan: {}",
-                status
-            )));
         }
+    }
 
-        Ok(Box::new(SaneScanSession {
-            handle,
-            buffer: vec![0u8; 32 * 1024], // 32KB buffer
-            state: SaneScanState::Scanning,
-            accumulated_data: Vec::new(),
-        }))
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
     }
-}
 
-impl Drop for SaneBackend {
-    fn drop(&mut self) {
-        if self.initialized {
-            unsafe {
-                sane_exit();
+    fn close(&self) {
+        if let Ok(mut handle) = self.handle.lock() {
+            if let Some(handle) = handle.take() {
+                unsafe {
+                    (self.lib.cancel)(handle);
+                    (self.lib.close)(handle);
+                }
             }
         }
     }
 }
 
 struct SaneScanSession {
+    device: Arc<SaneOpenDevice>,
+    // Copy of the handle for reads, valid until `device.close()`
     handle: SaneHandle,
+    cfg: ScanConfig,
+    non_blocking: bool,
+    select_fd: Option<c_int>,
+    read_timeout: Duration,
     buffer: Vec<u8>,
+    frame: Option<SaneFrameParams>,
+    assembler: PageAssembler,
+    page_index: u32,
+    // Device bytes read for the current page, for progress events
+    page_bytes: u64,
+    page_expected: Option<u64>,
+    status: Option<ScanStatus>,
+    pending: VecDeque<ScanEvent>,
+    // Reported once the events queued before it have been delivered
+    failure: Option<PapyrError>,
     state: SaneScanState,
-    accumulated_data: Vec<u8>,
 }
 
+#[derive(Debug, PartialEq)]
 enum SaneScanState {
     Scanning,
     Complete,
 }
 
+impl SaneScanSession {
+    /// Ends the session with `error`, after any events already queued.
+    fn fail(&mut self, error: PapyrError) -> Result<Option<ScanEvent>> {
+        self.state = SaneScanState::Complete;
+        self.failure = Some(error);
+        self.next_event()
+    }
+
+    /// Queues a `Status` event if the status changed.
+    fn set_status(&mut self, status: ScanStatus) {
+        if self.status != Some(status) {
+            self.status = Some(status);
+            self.pending.push_back(ScanEvent::Status(status));
+        }
+    }
+
+    /// Starts a frame. Returns `None` when the feeder has no more documents.
+    /// While the lamp warms up, retries until the read timeout.
+    fn start_frame(&mut self) -> Result<Option<SaneFrameParams>> {
+        let deadline = Instant::now() + self.read_timeout;
+        loop {
+            if self.device.is_cancelled() {
+                return Err(PapyrError::Cancelled);
+            }
+
+            match unsafe { (self.device.lib.start)(self.handle) } {
+                SANE_STATUS_GOOD => break,
+                SANE_STATUS_NO_DOCS => return Ok(None),
+                SANE_STATUS_WARMING_UP if Instant::now() < deadline => {
+                    self.set_status(ScanStatus::WarmingUp);
+                    std::thread::sleep(WARM_UP_POLL_INTERVAL);
+                }
+                status => return Err(status_error(Backend::Sane, "Failed to start scan", status)),
+            }
+        }
+        self.set_status(ScanStatus::Scanning);
+
+        let lib = &self.device.lib;
+
+        // Prefer non-blocking reads so a stalled device can time out; drivers
+        // that refuse keep the default blocking mode. The I/O mode only
+        // holds for the frame just started.
+        self.non_blocking =
+            unsafe { (lib.set_io_mode)(self.handle, SANE_TRUE) } == SANE_STATUS_GOOD;
+        self.select_fd = None;
+        if self.non_blocking {
+            let mut fd: c_int = -1;
+            if unsafe { (lib.get_select_fd)(self.handle, &mut fd) } == SANE_STATUS_GOOD {
+                self.select_fd = Some(fd);
+            }
+        }
+
+        let mut params = SaneParameters {
+            format: 0,
+            last_frame: 0,
+            bytes_per_line: 0,
+            pixels_per_line: 0,
+            lines: 0,
+            depth: 0,
+        };
+        let status = unsafe { (lib.get_parameters)(self.handle, &mut params) };
+        if status != SANE_STATUS_GOOD {
+            return Err(status_error(
+                Backend::Sane,
+                "Failed to get scan parameters",
+                status,
+            ));
+        }
+
+        let params = SaneFrameParams {
+            format: params.format,
+            last_frame: params.last_frame != 0,
+            bytes_per_line: params.bytes_per_line,
+            pixels_per_line: params.pixels_per_line,
+            lines: params.lines,
+            depth: params.depth,
+        };
+        self.frame = Some(params);
+        Ok(Some(params))
+    }
+
+    /// Starts the next page, queuing its `PageStarted` event.
+    fn start_page(&mut self) -> Result<bool> {
+        match self.start_frame()? {
+            Some(params) => {
+                self.page_bytes = 0;
+                self.page_expected = params.expected_page_bytes();
+                self.pending
+                    .push_back(ScanEvent::PageStarted(self.page_index));
+                let header = self.assembler.begin_frame(params);
+                if !header.is_empty() {
+                    self.pending.push_back(ScanEvent::PageData(header));
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn finish_frame(&mut self) -> Result<()> {
+        // Three-pass scanners deliver one frame per colour
+        let frame = self.frame.take();
+        if let Some(params) = frame {
+            if !params.last_frame {
+                return match self.start_frame()? {
+                    Some(next) => {
+                        self.assembler.begin_frame(next);
+                        Ok(())
+                    }
+                    None => Err(PapyrError::Protocol {
+                        backend: Backend::Sane,
+                        message: "scanner stopped between colour frames".into(),
+                    }),
+                };
+            }
+        }
+
+        let (rest, width_px, height_px) = self.assembler.finish();
+        if !rest.is_empty() {
+            self.pending.push_back(ScanEvent::PageData(rest));
+        }
+        self.pending.push_back(ScanEvent::PageComplete(PageMeta {
+            index: self.page_index,
+            width_px,
+            height_px,
+            dpi: self.cfg.dpi,
+            dpi_y: self.cfg.vertical_dpi(),
+            color_mode: self.cfg.color_mode,
+            bit_depth: frame.map_or(self.cfg.bits_per_sample(), |params| params.depth as u8),
+        }));
+        self.page_index += 1;
+
+        if self.cfg.source == ScanSource::Flatbed || !self.start_page()? {
+            self.pending.push_back(ScanEvent::JobComplete);
+            self.state = SaneScanState::Complete;
+        }
+        Ok(())
+    }
+
+    /// Reads the next chunk into `buffer`, returning the read status and
+    /// length. In non-blocking mode waits for data until the read timeout,
+    /// checking for cancellation in between.
+    fn read_chunk(&mut self) -> Result<(c_int, usize)> {
+        let deadline = Instant::now() + self.read_timeout;
+
+        loop {
+            if self.device.is_cancelled() {
+                return Err(PapyrError::Cancelled);
+            }
+
+            let mut len: c_int = 0;
+            let status = unsafe {
+                (self.device.lib.read)(
+                    self.handle,
+                    self.buffer.as_mut_ptr(),
+                    self.buffer.len() as c_int,
+                    &mut len,
+                )
+            };
+
+            if self.device.is_cancelled() {
+                return Err(PapyrError::Cancelled);
+            }
+            if status != SANE_STATUS_GOOD || len > 0 || !self.non_blocking {
+                return Ok((status, len.max(0) as usize));
+            }
+
+            // Non-blocking read with nothing available yet
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                self.device.cancel();
+                return Err(PapyrError::Timeout {
+                    backend: Backend::Sane,
+                    message: format!(
+                        "SANE read timed out after {}s without data",
+                        self.read_timeout.as_secs()
+                    ),
+                });
+            }
+            wait_readable(self.select_fd, remaining.min(READ_POLL_INTERVAL));
+        }
+    }
+}
+
+/// Waits up to `timeout` for the device's select fd to become readable.
+#[cfg(unix)]
+fn wait_readable(select_fd: Option<c_int>, timeout: Duration) {
+    match select_fd {
+        Some(fd) => {
+            let mut poll_fd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            unsafe {
+                libc::poll(&mut poll_fd, 1, timeout.as_millis() as libc::c_int);
+            }
+        }
+        None => std::thread::sleep(timeout),
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_readable(_select_fd: Option<c_int>, timeout: Duration) {
+    std::thread::sleep(timeout);
+}
+
 impl ScanSession for SaneScanSession {
     fn next_event(&mut self) -> Result<Option<ScanEvent>> {
-        match self.state {
-            SaneScanState::Scanning => {
-                let mut len: c_int = 0;
-                let status = unsafe {
-                    sane_read(
-                        self.handle,
-                        self.buffer.as_mut_ptr(),
-                        self.buffer.len() as c_int,
-                        &mut len,
-                    )
-                };
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            match self.failure.take() {
+                Some(PapyrError::Cancelled) => return Ok(Some(ScanEvent::Cancelled)),
+                Some(e) => return Err(e),
+                None => {}
+            }
+            if self.state == SaneScanState::Complete {
+                return Ok(None);
+            }
+
+            let (status, len) = match self.read_chunk() {
+                Ok(read) => read,
+                Err(e) => return self.fail(e),
+            };
 
-                match status {
-                    SANE_STATUS_GOOD => {
-                        if len > 0 {
-                            self.accumulated_data
-                                .extend_from_slice(&self.buffer[..len as usize]);
-                        }
-                        // Continue reading
-                        Ok(Some(ScanEvent::PageData(vec![]))) // Empty event to signal progress
+            match status {
+                SANE_STATUS_GOOD => {
+                    let out = self.assembler.push(&self.buffer[..len]);
+                    if !out.is_empty() {
+                        self.pending.push_back(ScanEvent::PageData(out));
                     }
-                    SANE_STATUS_EOF => {
-                        self.state = SaneScanState::Complete;
-
-                        // Get final parameters
-                        let mut params = SaneParameters {
-                            format: 0,
-                            last_frame: 0,
-                            bytes_per_line: 0,
-                            pixels_per_line: 0,
-                            lines: 0,
-                            depth: 0,
-                        };
-                        unsafe {
-                            sane_get_parameters(self.handle, &mut params);
-                        }
-
-                        let color_mode = match params.format {
-                            SANE_FRAME_GRAY => ColorMode::Gray,
-                            SANE_FRAME_RGB => ColorMode::Color,
-                            _ => ColorMode::Bw,
-                        };
-
-                        let page_meta = PageMeta {
-                            index: 0,
-                            width_px: params.pixels_per_line as u32,
-                            height_px: params.lines as u32,
-                            dpi: 300, // Would need to query from options
-                            color_mode,
-                        };
-
-                        let _data = std::mem::take(&mut self.accumulated_data);
-
-                        Ok(Some(ScanEvent::PageComplete(page_meta)))
+                    if len > 0 {
+                        self.page_bytes += len as u64;
+                        self.pending
+                            .push_back(ScanEvent::progress(self.page_bytes, self.page_expected));
                     }
-                    _ => Err(PapyrError::Backend(format!("SANE read error: {}", status))),
                 }
+                SANE_STATUS_EOF => {
+                    if let Err(e) = self.finish_frame() {
+                        return self.fail(e);
+                    }
+                }
+                SANE_STATUS_CANCELLED => return self.fail(PapyrError::Cancelled),
+                _ => return self.fail(status_error(Backend::Sane, "SANE read error", status)),
             }
-            SaneScanState::Complete => Ok(None),
         }
     }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let device = Arc::clone(&self.device);
+        Some(CancelHandle::new(move || device.cancel()))
+    }
 }
 
 impl Drop for SaneScanSession {
     fn drop(&mut self) {
-        unsafe {
-            sane_cancel(self.handle);
-            sane_close(self.handle);
-        }
+        self.device.close();
     }
 }
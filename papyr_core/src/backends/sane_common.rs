@@ -0,0 +1,1021 @@
+//
+//  papyr_core
+//  backends/sane_common.rs - Pieces shared by the libsane and saned backends
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::*;
+use std::time::Duration;
+use tracing::debug;
+
+// SANE_Status
+pub const SANE_STATUS_GOOD: i32 = 0;
+pub const SANE_STATUS_UNSUPPORTED: i32 = 1;
+pub const SANE_STATUS_CANCELLED: i32 = 2;
+pub const SANE_STATUS_DEVICE_BUSY: i32 = 3;
+pub const SANE_STATUS_INVAL: i32 = 4;
+pub const SANE_STATUS_EOF: i32 = 5;
+pub const SANE_STATUS_JAMMED: i32 = 6;
+pub const SANE_STATUS_NO_DOCS: i32 = 7;
+pub const SANE_STATUS_COVER_OPEN: i32 = 8;
+pub const SANE_STATUS_IO_ERROR: i32 = 9;
+pub const SANE_STATUS_NO_MEM: i32 = 10;
+pub const SANE_STATUS_ACCESS_DENIED: i32 = 11;
+// Added in SANE 1.0.20; older frontends see them as unknown codes
+pub const SANE_STATUS_WARMING_UP: i32 = 12;
+pub const SANE_STATUS_HW_LOCKED: i32 = 13;
+
+/// How often `sane_start` is retried while it reports `SANE_STATUS_WARMING_UP`.
+pub const WARM_UP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// SANE_Value_Type
+pub const SANE_TYPE_BOOL: i32 = 0;
+pub const SANE_TYPE_INT: i32 = 1;
+pub const SANE_TYPE_FIXED: i32 = 2;
+pub const SANE_TYPE_STRING: i32 = 3;
+pub const SANE_TYPE_BUTTON: i32 = 4;
+pub const SANE_TYPE_GROUP: i32 = 5;
+
+// SANE_Unit
+pub const SANE_UNIT_NONE: i32 = 0;
+pub const SANE_UNIT_PIXEL: i32 = 1;
+pub const SANE_UNIT_BIT: i32 = 2;
+pub const SANE_UNIT_MM: i32 = 3;
+pub const SANE_UNIT_DPI: i32 = 4;
+pub const SANE_UNIT_PERCENT: i32 = 5;
+pub const SANE_UNIT_MICROSECOND: i32 = 6;
+
+// SANE_Constraint_Type
+pub const SANE_CONSTRAINT_NONE: i32 = 0;
+pub const SANE_CONSTRAINT_RANGE: i32 = 1;
+pub const SANE_CONSTRAINT_WORD_LIST: i32 = 2;
+pub const SANE_CONSTRAINT_STRING_LIST: i32 = 3;
+
+// SANE_Action
+pub const SANE_ACTION_GET_VALUE: i32 = 0;
+pub const SANE_ACTION_SET_VALUE: i32 = 1;
+pub const SANE_ACTION_SET_AUTO: i32 = 2;
+
+// sane_control_option info flags
+pub const SANE_INFO_INEXACT: i32 = 1 << 0;
+pub const SANE_INFO_RELOAD_OPTIONS: i32 = 1 << 1;
+pub const SANE_INFO_RELOAD_PARAMS: i32 = 1 << 2;
+
+// Option capabilities
+pub const SANE_CAP_SOFT_SELECT: i32 = 1 << 0;
+pub const SANE_CAP_HARD_SELECT: i32 = 1 << 1;
+pub const SANE_CAP_SOFT_DETECT: i32 = 1 << 2;
+pub const SANE_CAP_EMULATED: i32 = 1 << 3;
+pub const SANE_CAP_AUTOMATIC: i32 = 1 << 4;
+pub const SANE_CAP_INACTIVE: i32 = 1 << 5;
+pub const SANE_CAP_ADVANCED: i32 = 1 << 6;
+
+// SANE_Frame
+pub const SANE_FRAME_GRAY: i32 = 0;
+pub const SANE_FRAME_RGB: i32 = 1;
+pub const SANE_FRAME_RED: i32 = 2;
+pub const SANE_FRAME_GREEN: i32 = 3;
+pub const SANE_FRAME_BLUE: i32 = 4;
+
+const SANE_FIXED_SCALE: f64 = (1 << 16) as f64;
+
+pub fn fixed_to_f64(value: i32) -> f64 {
+    value as f64 / SANE_FIXED_SCALE
+}
+
+pub fn f64_to_fixed(value: f64) -> i32 {
+    (value * SANE_FIXED_SCALE).round() as i32
+}
+
+/// Human-readable text for a SANE status, matching `sane_strstatus`.
+pub fn status_message(status: i32) -> &'static str {
+    match status {
+        SANE_STATUS_GOOD => "Success",
+        SANE_STATUS_UNSUPPORTED => "Operation not supported",
+        SANE_STATUS_CANCELLED => "Operation was cancelled",
+        SANE_STATUS_DEVICE_BUSY => "Device busy",
+        SANE_STATUS_INVAL => "Invalid argument",
+        SANE_STATUS_EOF => "End of file reached",
+        SANE_STATUS_JAMMED => "Document feeder jammed",
+        SANE_STATUS_NO_DOCS => "Document feeder out of documents",
+        SANE_STATUS_COVER_OPEN => "Scanner cover is open",
+        SANE_STATUS_IO_ERROR => "Error during device I/O",
+        SANE_STATUS_NO_MEM => "Out of memory",
+        SANE_STATUS_ACCESS_DENIED => "Access to resource has been denied",
+        SANE_STATUS_WARMING_UP => "Lamp not ready, please retry",
+        SANE_STATUS_HW_LOCKED => "Scanner mechanism locked for transport",
+        _ => "Unknown SANE status code",
+    }
+}
+
+/// Builds the error for a failed SANE call, keeping the statuses that
+/// callers act on (busy, jam, empty feeder, ...) as distinct variants.
+pub fn status_error(backend: Backend, context: &str, status: i32) -> PapyrError {
+    let message = format!("{}: {} ({})", context, status_message(status), status);
+    match status {
+        SANE_STATUS_DEVICE_BUSY | SANE_STATUS_WARMING_UP => {
+            PapyrError::DeviceBusy { backend, message }
+        }
+        SANE_STATUS_JAMMED => PapyrError::PaperJam { backend, message },
+        SANE_STATUS_NO_DOCS => PapyrError::FeederEmpty { backend, message },
+        SANE_STATUS_COVER_OPEN => PapyrError::CoverOpen { backend, message },
+        SANE_STATUS_IO_ERROR => PapyrError::Io { backend, message },
+        SANE_STATUS_ACCESS_DENIED => PapyrError::Unauthorized { backend, message },
+        SANE_STATUS_CANCELLED => PapyrError::Cancelled,
+        _ => PapyrError::Backend(message),
+    }
+}
+
+/// Builds the error for a failed `sane_control_option` on `name`.
+pub fn option_error(backend: Backend, name: &str, status: i32) -> PapyrError {
+    match status {
+        SANE_STATUS_UNSUPPORTED => PapyrError::Unsupported {
+            backend,
+            option: name.to_string(),
+        },
+        SANE_STATUS_INVAL => PapyrError::InvalidConfig(format!(
+            "option {} rejected by the device: {}",
+            name,
+            status_message(status)
+        )),
+        _ => status_error(
+            backend,
+            &format!("Failed to control option {}", name),
+            status,
+        ),
+    }
+}
+
+/// Escapes a SANE device name for use inside a device id.
+///
+/// SANE names use `:`, `_`, `/`, `?` and spaces freely (`airscan:e0:HP
+/// LaserJet`, `hpaio:/usb/Officejet_Pro?serial=...`), so nothing may be
+/// rewritten lossily. Printable ASCII other than `%` is kept as-is so ids stay
+/// readable; everything else is percent-encoded byte by byte.
+pub fn escape_device_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_graphic() && byte != b'%' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Reverses `escape_device_name`; `None` for malformed escapes.
+pub fn unescape_device_name(escaped: &str) -> Option<String> {
+    let bytes = escaped.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = escaped.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Device id for a local libsane device.
+pub fn sane_device_id(name: &str) -> DeviceId {
+    DeviceId::new(Backend::Sane, escape_device_name(name))
+}
+
+/// Connection implied by a SANE device name: the backend prefix plus
+/// conventions such as `libusb:001:004` or `net:192.168.1.5`.
+pub fn connection_for_device(name: &str) -> ConnectionKind {
+    let mut parts = name.split(':');
+    let backend = parts.next().unwrap_or_default();
+    match backend {
+        "net" | "airscan" | "escl" => return ConnectionKind::Network,
+        "test" | "pnm" => return ConnectionKind::Virtual,
+        _ => {}
+    }
+
+    let rest = name.get(backend.len()..).unwrap_or_default().to_lowercase();
+    if rest.contains("usb") {
+        ConnectionKind::Usb
+    } else if rest.contains("net") || rest.contains("tcp") {
+        ConnectionKind::Network
+    } else {
+        ConnectionKind::Unknown
+    }
+}
+
+/// Fills in `info` from a `SANE_Device`'s name, vendor, model and type.
+pub fn describe_device(
+    info: ScannerInfo,
+    name: &str,
+    vendor: &str,
+    model: &str,
+    kind: &str,
+) -> ScannerInfo {
+    let non_empty = |value: &str| (!value.trim().is_empty()).then(|| value.trim().to_string());
+    ScannerInfo {
+        manufacturer: non_empty(vendor),
+        model: non_empty(model),
+        device_type: non_empty(kind),
+        connection: connection_for_device(name),
+        // e.g. epson2:net:192.168.1.5 or escl:http://192.168.1.5:80
+        address: name
+            .split_once(":net:")
+            .map(|(_, address)| address)
+            .or_else(|| name.strip_prefix("escl:").filter(|url| url.contains("://")))
+            .and_then(non_empty),
+        ..info
+    }
+}
+
+/// SANE device name for an id built by `sane_device_id`.
+pub fn sane_device_name(device_id: &DeviceId) -> Option<String> {
+    (device_id.backend() == Backend::Sane)
+        .then(|| unescape_device_name(device_id.local()))
+        .flatten()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaneConstraint {
+    None,
+    Range { min: i32, max: i32, quant: i32 },
+    WordList(Vec<i32>),
+    StringList(Vec<String>),
+}
+
+/// Owned copy of a `SANE_Option_Descriptor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaneOptionDescriptor {
+    pub name: String,
+    pub title: String,
+    pub desc: String,
+    pub value_type: i32,
+    pub unit: i32,
+    pub size: i32,
+    pub cap: i32,
+    pub constraint: SaneConstraint,
+}
+
+impl SaneOptionDescriptor {
+    pub fn is_active(&self) -> bool {
+        self.cap & SANE_CAP_INACTIVE == 0
+    }
+
+    pub fn is_settable(&self) -> bool {
+        self.cap & SANE_CAP_SOFT_SELECT != 0
+    }
+
+    /// Whether the value is a single word or a string (not a word array
+    /// such as a gamma table).
+    pub fn is_scalar(&self) -> bool {
+        matches!(self.value_type, SANE_TYPE_STRING | SANE_TYPE_BUTTON) || self.size <= 4
+    }
+
+    /// Word constraint values converted to whole numbers, decoding fixed point.
+    fn word_values(&self) -> Vec<f64> {
+        let decode = |w: i32| {
+            if self.value_type == SANE_TYPE_FIXED {
+                fixed_to_f64(w)
+            } else {
+                w as f64
+            }
+        };
+
+        match &self.constraint {
+            SaneConstraint::WordList(words) => words.iter().map(|&w| decode(w)).collect(),
+            SaneConstraint::Range { min, max, .. } => vec![decode(*min), decode(*max)],
+            _ => vec![],
+        }
+    }
+}
+
+/// A single option value as exchanged with `sane_control_option`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaneValue {
+    Bool(bool),
+    Int(i32),
+    Fixed(f64),
+    String(String),
+}
+
+impl From<SaneValue> for OptionValue {
+    fn from(value: SaneValue) -> Self {
+        match value {
+            SaneValue::Bool(b) => OptionValue::Bool(b),
+            SaneValue::Int(i) => OptionValue::Int(i),
+            SaneValue::Fixed(x) => OptionValue::Fixed(x),
+            SaneValue::String(s) => OptionValue::String(s),
+        }
+    }
+}
+
+impl From<OptionValue> for SaneValue {
+    fn from(value: OptionValue) -> Self {
+        match value {
+            OptionValue::Bool(b) => SaneValue::Bool(b),
+            OptionValue::Int(i) => SaneValue::Int(i),
+            OptionValue::Fixed(x) => SaneValue::Fixed(x),
+            OptionValue::String(s) => SaneValue::String(s),
+        }
+    }
+}
+
+impl SaneValue {
+    /// Encodes a number for an option of the given type.
+    fn number(value_type: i32, value: f64) -> Self {
+        if value_type == SANE_TYPE_FIXED {
+            SaneValue::Fixed(value)
+        } else {
+            SaneValue::Int(value.round() as i32)
+        }
+    }
+}
+
+const MODE_COLOR: &[&str] = &["color", "colour", "rgb", "24bit color"];
+const MODE_GRAY: &[&str] = &["gray", "grey", "grayscale", "greyscale"];
+const MODE_BW: &[&str] = &["lineart", "binary", "black & white", "halftone", "bw"];
+
+const SOURCE_DUPLEX: &[&str] = &["duplex"];
+const SOURCE_ADF: &[&str] = &["adf", "feeder", "automatic document feeder"];
+
+fn color_mode_for(value: &str) -> Option<ColorMode> {
+    let lower = value.to_lowercase();
+    if MODE_COLOR
+        .iter()
+        .any(|m| lower == *m || lower.starts_with(m))
+    {
+        Some(ColorMode::Color)
+    } else if MODE_GRAY
+        .iter()
+        .any(|m| lower == *m || lower.starts_with(m))
+    {
+        Some(ColorMode::Gray)
+    } else if MODE_BW.iter().any(|m| lower == *m || lower.starts_with(m)) {
+        Some(ColorMode::Bw)
+    } else {
+        None
+    }
+}
+
+fn scan_source_for(value: &str) -> ScanSource {
+    let lower = value.to_lowercase();
+    if SOURCE_DUPLEX.iter().any(|s| lower.contains(s)) {
+        ScanSource::AdfDuplex
+    } else if SOURCE_ADF.iter().any(|s| lower.contains(s)) {
+        ScanSource::Adf
+    } else {
+        ScanSource::Flatbed
+    }
+}
+
+fn find<'a>(
+    options: &'a [SaneOptionDescriptor],
+    name: &str,
+) -> Option<(usize, &'a SaneOptionDescriptor)> {
+    options
+        .iter()
+        .enumerate()
+        .find(|(_, opt)| opt.name == name && opt.is_active())
+}
+
+/// Derives `Capabilities` from a device's option descriptors.
+pub fn capabilities_from_options(options: &[SaneOptionDescriptor]) -> Capabilities {
+    let resolution = find(options, "resolution").or_else(|| find(options, "x-resolution"));
+    let mut dpi_ranges = Vec::new();
+    let mut dpis: Vec<u32> = match resolution {
+        Some((_, opt)) => match &opt.constraint {
+            SaneConstraint::WordList(_) => opt
+                .word_values()
+                .into_iter()
+                .map(|v| v.round() as u32)
+                .collect(),
+            SaneConstraint::Range { quant, .. } => {
+                let bounds = opt.word_values();
+                let (min, max) = (bounds[0].round() as u32, bounds[1].round() as u32);
+                let step = if opt.value_type == SANE_TYPE_FIXED {
+                    fixed_to_f64(*quant).round() as u32
+                } else {
+                    *quant as u32
+                };
+                dpi_ranges.push(DpiRange {
+                    min,
+                    max,
+                    step: step.max(1),
+                });
+                [75, 100, 150, 200, 300, 600, 1200, 2400]
+                    .into_iter()
+                    .filter(|dpi| *dpi >= min && *dpi <= max)
+                    .collect()
+            }
+            _ => vec![],
+        },
+        None => vec![],
+    };
+    dpis.sort_unstable();
+    dpis.dedup();
+    if dpis.is_empty() {
+        dpis = vec![75, 150, 300, 600];
+    }
+
+    let independent_dpi = find(options, "y-resolution").is_some_and(|(_, opt)| opt.is_settable());
+
+    // Depth 1 is lineart, which ColorMode::Bw already covers
+    let mut bit_depths: Vec<u8> = match find(options, "depth") {
+        Some((_, opt)) => match &opt.constraint {
+            SaneConstraint::WordList(_) => opt
+                .word_values()
+                .into_iter()
+                .map(|v| v.round() as u8)
+                .filter(|depth| *depth > 1)
+                .collect(),
+            SaneConstraint::Range { .. } => {
+                let bounds = opt.word_values();
+                [8, 16]
+                    .into_iter()
+                    .filter(|depth| (bounds[0]..=bounds[1]).contains(&(*depth as f64)))
+                    .collect()
+            }
+            _ => vec![],
+        },
+        None => vec![],
+    };
+    bit_depths.sort_unstable();
+    bit_depths.dedup();
+    if bit_depths.is_empty() {
+        bit_depths.push(8);
+    }
+
+    let mut color_modes = Vec::new();
+    if let Some((_, opt)) = find(options, "mode") {
+        if let SaneConstraint::StringList(modes) = &opt.constraint {
+            for mode in modes.iter().filter_map(|m| color_mode_for(m)) {
+                if !color_modes.contains(&mode) {
+                    color_modes.push(mode);
+                }
+            }
+        }
+    }
+    if color_modes.is_empty() {
+        color_modes = vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw];
+    }
+
+    let mut sources = Vec::new();
+    if let Some((_, opt)) = find(options, "source") {
+        if let SaneConstraint::StringList(names) = &opt.constraint {
+            for source in names.iter().map(|s| scan_source_for(s)) {
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+        }
+    }
+    if sources.is_empty() {
+        sources.push(ScanSource::Flatbed);
+    }
+
+    let supports_duplex = sources.contains(&ScanSource::AdfDuplex);
+
+    // The geometry options describe the selected source only, so the same
+    // area is reported for each
+    let max_areas: Vec<SourceArea> =
+        match (max_extent(options, "br-x"), max_extent(options, "br-y")) {
+            (Some(width_mm), Some(height_mm)) => sources
+                .iter()
+                .map(|source| SourceArea {
+                    source: *source,
+                    max: PageSize::new(width_mm, height_mm),
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+    Capabilities {
+        sources,
+        dpis,
+        dpi_ranges,
+        independent_dpi,
+        color_modes,
+        bit_depths,
+        page_sizes: page_formats_for(&max_areas),
+        max_areas,
+        supports_duplex,
+    }
+}
+
+/// Upper bound of a millimetre range option such as `br-x`.
+fn max_extent(options: &[SaneOptionDescriptor], name: &str) -> Option<f64> {
+    let (_, opt) = find(options, name)?;
+    if opt.unit != SANE_UNIT_MM || !matches!(opt.constraint, SaneConstraint::Range { .. }) {
+        return None;
+    }
+    opt.word_values().get(1).copied()
+}
+
+/// Works out which option writes apply `cfg` to a device.
+///
+/// Options the device does not expose (or exposes read-only) are skipped;
+/// the returned list is in the order the writes should be issued, since
+/// changing `source` or `mode` can reload the other descriptors.
+pub fn plan_config(options: &[SaneOptionDescriptor], cfg: &ScanConfig) -> Vec<(usize, SaneValue)> {
+    let mut writes = Vec::new();
+
+    let settable = |name: &str| find(options, name).filter(|(_, opt)| opt.is_settable());
+
+    if let Some((index, opt)) = settable("source") {
+        if let SaneConstraint::StringList(names) = &opt.constraint {
+            let wanted = match cfg.source {
+                ScanSource::AdfDuplex if !cfg.duplex => ScanSource::Adf,
+                ScanSource::Adf if cfg.duplex => ScanSource::AdfDuplex,
+                other => other,
+            };
+            if let Some(name) = names.iter().find(|n| scan_source_for(n) == wanted) {
+                writes.push((index, SaneValue::String(name.clone())));
+            }
+        }
+    }
+
+    if let Some((index, opt)) = settable("mode") {
+        if let SaneConstraint::StringList(modes) = &opt.constraint {
+            if let Some(mode) = modes
+                .iter()
+                .find(|m| color_mode_for(m) == Some(cfg.color_mode))
+            {
+                writes.push((index, SaneValue::String(mode.clone())));
+            }
+        }
+    }
+
+    // Lineart has a fixed depth and deactivates the option
+    if let Some(depth) = cfg.bit_depth.filter(|_| cfg.color_mode != ColorMode::Bw) {
+        if let Some((index, opt)) = settable("depth") {
+            writes.push((index, SaneValue::number(opt.value_type, depth as f64)));
+        }
+    }
+
+    if let Some((index, opt)) = settable("resolution").or_else(|| settable("x-resolution")) {
+        writes.push((index, SaneValue::number(opt.value_type, cfg.dpi as f64)));
+    }
+    if let Some((index, opt)) = settable("y-resolution") {
+        let dpi_y = cfg.vertical_dpi() as f64;
+        writes.push((index, SaneValue::number(opt.value_type, dpi_y)));
+    }
+
+    if let Some(area) = cfg.area {
+        let edges = [
+            ("tl-x", area.x_mm),
+            ("tl-y", area.y_mm),
+            ("br-x", area.x_mm + area.width_mm),
+            ("br-y", area.y_mm + area.height_mm),
+        ];
+        for (name, mm) in edges {
+            if let Some((index, opt)) = settable(name) {
+                if opt.unit == SANE_UNIT_MM {
+                    writes.push((index, SaneValue::number(opt.value_type, mm)));
+                }
+            }
+        }
+    }
+
+    for (name, value) in [("brightness", cfg.brightness), ("contrast", cfg.contrast)] {
+        if let (Some(value), Some((index, opt))) = (value, settable(name)) {
+            writes.push((index, SaneValue::number(opt.value_type, value as f64)));
+        }
+    }
+
+    writes
+}
+
+/// Option access on an open device, implemented by both SANE backends so
+/// configuration and the `DeviceOption` API share one implementation.
+pub trait SaneOptionAccess {
+    fn descriptors(&mut self) -> Result<Vec<SaneOptionDescriptor>>;
+
+    /// Runs `sane_control_option`; returns the info flags and the value the
+    /// device reports back.
+    fn control(
+        &mut self,
+        index: usize,
+        desc: &SaneOptionDescriptor,
+        action: i32,
+        value: Option<&SaneValue>,
+    ) -> Result<(i32, Option<SaneValue>)>;
+}
+
+/// Writes one option, refreshing `options` if the device reloaded them.
+/// Failures are skipped so one odd option can't block a scan; the returned
+/// warning says what was skipped.
+fn write_option(
+    dev: &mut impl SaneOptionAccess,
+    options: &mut Vec<SaneOptionDescriptor>,
+    index: usize,
+    value: &SaneValue,
+) -> Result<Option<ScanEvent>> {
+    let desc = options[index].clone();
+    match dev.control(index, &desc, SANE_ACTION_SET_VALUE, Some(value)) {
+        Ok((info, _)) if info & SANE_INFO_RELOAD_OPTIONS != 0 => {
+            *options = dev.descriptors()?;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            debug!(option = %desc.name, ?value, "could not set option: {}", e);
+            return Ok(Some(ScanEvent::warning(
+                WarningCode::OptionNotApplied,
+                format!("could not set {}: {}", desc.name, e),
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Applies `cfg` to an open device, re-reading descriptors whenever an
+/// option change reloads them. Returns a warning for each option the device
+/// refused.
+pub fn apply_config(dev: &mut impl SaneOptionAccess, cfg: &ScanConfig) -> Result<Vec<ScanEvent>> {
+    let mut options = dev.descriptors()?;
+    let mut applied: Vec<String> = Vec::new();
+    let mut warnings = Vec::new();
+
+    loop {
+        let next = plan_config(&options, cfg)
+            .into_iter()
+            .find(|(index, _)| !applied.contains(&options[*index].name));
+        let Some((index, value)) = next else {
+            return Ok(warnings);
+        };
+
+        applied.push(options[index].name.clone());
+        warnings.extend(write_option(dev, &mut options, index, &value)?);
+    }
+}
+
+/// Re-applies options set through `BackendProvider::set_option`, returning
+/// a warning for each one that couldn't be.
+pub fn apply_overrides(
+    dev: &mut impl SaneOptionAccess,
+    overrides: &[(String, OptionValue)],
+) -> Result<Vec<ScanEvent>> {
+    let mut warnings = Vec::new();
+    if overrides.is_empty() {
+        return Ok(warnings);
+    }
+
+    let mut options = dev.descriptors()?;
+    for (name, value) in overrides {
+        match find(&options, name).filter(|(_, opt)| opt.is_settable()) {
+            Some((index, _)) => warnings.extend(write_option(
+                dev,
+                &mut options,
+                index,
+                &value.clone().into(),
+            )?),
+            None => {
+                debug!(option = %name, "option is not settable now, skipped");
+                warnings.push(ScanEvent::warning(
+                    WarningCode::OptionNotApplied,
+                    format!("option {} is not settable now", name),
+                ));
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+fn option_type(value_type: i32) -> OptionType {
+    match value_type {
+        SANE_TYPE_BOOL => OptionType::Bool,
+        SANE_TYPE_FIXED => OptionType::Fixed,
+        SANE_TYPE_STRING => OptionType::String,
+        SANE_TYPE_BUTTON => OptionType::Button,
+        _ => OptionType::Int,
+    }
+}
+
+fn option_unit(unit: i32) -> OptionUnit {
+    match unit {
+        SANE_UNIT_PIXEL => OptionUnit::Pixel,
+        SANE_UNIT_BIT => OptionUnit::Bit,
+        SANE_UNIT_MM => OptionUnit::Mm,
+        SANE_UNIT_DPI => OptionUnit::Dpi,
+        SANE_UNIT_PERCENT => OptionUnit::Percent,
+        SANE_UNIT_MICROSECOND => OptionUnit::Microsecond,
+        _ => OptionUnit::None,
+    }
+}
+
+/// Builds the public `DeviceOption` for a descriptor.
+fn device_option(
+    desc: &SaneOptionDescriptor,
+    group: Option<String>,
+    value: Option<SaneValue>,
+) -> DeviceOption {
+    let decode = |w: i32| {
+        if desc.value_type == SANE_TYPE_FIXED {
+            fixed_to_f64(w)
+        } else {
+            w as f64
+        }
+    };
+    let word = |w: i32| {
+        if desc.value_type == SANE_TYPE_FIXED {
+            OptionValue::Fixed(fixed_to_f64(w))
+        } else {
+            OptionValue::Int(w)
+        }
+    };
+
+    let constraint = match &desc.constraint {
+        SaneConstraint::None => OptionConstraint::None,
+        SaneConstraint::Range { min, max, quant } => OptionConstraint::Range {
+            min: decode(*min),
+            max: decode(*max),
+            step: (*quant != 0).then(|| decode(*quant)),
+        },
+        SaneConstraint::WordList(words) => {
+            OptionConstraint::List(words.iter().map(|&w| word(w)).collect())
+        }
+        SaneConstraint::StringList(strings) => OptionConstraint::List(
+            strings
+                .iter()
+                .map(|s| OptionValue::String(s.clone()))
+                .collect(),
+        ),
+    };
+
+    DeviceOption {
+        name: desc.name.clone(),
+        title: desc.title.clone(),
+        description: desc.desc.clone(),
+        group,
+        value_type: option_type(desc.value_type),
+        unit: option_unit(desc.unit),
+        constraint,
+        value: value.map(Into::into),
+        // Word arrays (gamma tables and the like) are listed but read-only
+        settable: desc.is_settable() && desc.is_scalar(),
+        advanced: desc.cap & SANE_CAP_ADVANCED != 0,
+    }
+}
+
+/// Lists the active options of an open device with their current values.
+pub fn read_options(dev: &mut impl SaneOptionAccess) -> Result<Vec<DeviceOption>> {
+    let descriptors = dev.descriptors()?;
+    let mut group = None;
+    let mut options = Vec::new();
+
+    // Option 0 is the option count, not a setting
+    for (index, desc) in descriptors.iter().enumerate().skip(1) {
+        if desc.value_type == SANE_TYPE_GROUP {
+            group = Some(desc.title.clone()).filter(|title| !title.is_empty());
+            continue;
+        }
+        if desc.name.is_empty() || !desc.is_active() {
+            continue;
+        }
+
+        let readable = desc.cap & SANE_CAP_SOFT_DETECT != 0
+            && desc.is_scalar()
+            && desc.value_type != SANE_TYPE_BUTTON;
+        let value = if readable {
+            dev.control(index, desc, SANE_ACTION_GET_VALUE, None)
+                .ok()
+                .and_then(|(_, value)| value)
+        } else {
+            None
+        };
+
+        options.push(device_option(desc, group.clone(), value));
+    }
+
+    Ok(options)
+}
+
+/// Sets one option on an open device. Returns the option as the device
+/// reports it afterwards, and the value to remember for later sessions
+/// (`None` for buttons, which are actions rather than settings).
+pub fn write_device_option(
+    dev: &mut impl SaneOptionAccess,
+    name: &str,
+    value: OptionValue,
+) -> Result<(DeviceOption, Option<OptionValue>)> {
+    let descriptors = dev.descriptors()?;
+    let (index, desc) = find(&descriptors, name)
+        .ok_or_else(|| PapyrError::InvalidConfig(format!("unknown option {}", name)))?;
+
+    let value = device_option(desc, None, None).coerce(value)?;
+    let sane_value = (desc.value_type != SANE_TYPE_BUTTON).then(|| SaneValue::from(value));
+    dev.control(index, desc, SANE_ACTION_SET_VALUE, sane_value.as_ref())?;
+
+    // Re-read so the result reflects rounding and reloaded descriptors
+    let option = read_options(dev)?
+        .into_iter()
+        .find(|option| option.name == name)
+        .ok_or_else(|| PapyrError::InvalidConfig(format!("option {} became inactive", name)))?;
+    let remembered = sane_value.map(|written| option.value.clone().unwrap_or(written.into()));
+
+    Ok((option, remembered))
+}
+
+/// `SANE_Parameters` in a backend-neutral form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaneFrameParams {
+    pub format: i32,
+    pub last_frame: bool,
+    pub bytes_per_line: i32,
+    pub pixels_per_line: i32,
+    pub lines: i32,
+    pub depth: i32,
+}
+
+impl SaneFrameParams {
+    /// Bytes the device will send for the whole page, counting all three
+    /// frames of a three-pass scan; `None` when the line count is unknown.
+    pub fn expected_page_bytes(&self) -> Option<u64> {
+        if self.lines <= 0 || self.bytes_per_line <= 0 {
+            return None;
+        }
+        let frames = if self.is_single_pass() { 1 } else { 3 };
+        Some(self.bytes_per_line as u64 * self.lines as u64 * frames)
+    }
+
+    fn is_single_pass(&self) -> bool {
+        self.format == SANE_FRAME_GRAY || self.format == SANE_FRAME_RGB
+    }
+
+    fn channels(&self) -> usize {
+        if self.format == SANE_FRAME_RGB {
+            3
+        } else {
+            1
+        }
+    }
+
+    /// Bytes of real pixel data per line, excluding any driver padding.
+    fn row_bytes(&self) -> usize {
+        (self.pixels_per_line.max(0) as usize * self.channels() * self.depth.max(1) as usize)
+            .div_ceil(8)
+    }
+}
+
+/// Turns SANE frames into a PNM (PBM/PGM/PPM) page.
+///
+/// Single-pass frames with a known line count are streamed as they arrive;
+/// three-pass (red/green/blue) scans and hand-scanner style unknown lengths
+/// are buffered and emitted when the page completes.
+pub struct PageAssembler {
+    params: Option<SaneFrameParams>,
+    little_endian: bool,
+    streaming: bool,
+    carry: Vec<u8>,
+    planes: [Vec<u8>; 3],
+    buffered: Vec<u8>,
+    lines: u32,
+}
+
+impl PageAssembler {
+    /// `little_endian` describes the byte order of 16-bit samples.
+    pub fn new(little_endian: bool) -> Self {
+        Self {
+            params: None,
+            little_endian,
+            streaming: false,
+            carry: Vec::new(),
+            planes: [Vec::new(), Vec::new(), Vec::new()],
+            buffered: Vec::new(),
+            lines: 0,
+        }
+    }
+
+    fn header(params: &SaneFrameParams, color: bool, lines: u32) -> Vec<u8> {
+        let magic = if color {
+            "P6"
+        } else if params.depth == 1 {
+            "P4"
+        } else {
+            "P5"
+        };
+        if params.depth == 1 && !color {
+            format!("{}\n{} {}\n", magic, params.pixels_per_line, lines).into_bytes()
+        } else {
+            let maxval = if params.depth > 8 { 65535 } else { 255 };
+            format!(
+                "{}\n{} {}\n{}\n",
+                magic, params.pixels_per_line, lines, maxval
+            )
+            .into_bytes()
+        }
+    }
+
+    /// True between pages, before the first frame of the next one begins.
+    pub fn is_idle(&self) -> bool {
+        self.params.is_none()
+    }
+
+    /// Starts a frame; returns bytes to emit immediately (the PNM header
+    /// when the page can be streamed).
+    pub fn begin_frame(&mut self, params: SaneFrameParams) -> Vec<u8> {
+        let first = self.params.is_none();
+        self.params = Some(params);
+        self.carry.clear();
+
+        if first {
+            self.streaming = params.is_single_pass() && params.lines >= 0;
+            if self.streaming {
+                return Self::header(
+                    &params,
+                    params.format == SANE_FRAME_RGB,
+                    params.lines as u32,
+                );
+            }
+        }
+        Vec::new()
+    }
+
+    /// Feeds raw frame bytes; returns bytes to emit immediately.
+    pub fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        let Some(params) = self.params else {
+            return Vec::new();
+        };
+        self.carry.extend_from_slice(data);
+
+        let bpl = params.bytes_per_line.max(1) as usize;
+        let row = params.row_bytes().min(bpl);
+        let complete = self.carry.len() / bpl;
+        let mut out = Vec::with_capacity(complete * row);
+
+        for line in self.carry.chunks_exact(bpl).take(complete) {
+            let mut row_data = line[..row].to_vec();
+            if params.depth == 16 && self.little_endian {
+                for pair in row_data.chunks_exact_mut(2) {
+                    pair.swap(0, 1);
+                }
+            }
+            match params.format {
+                SANE_FRAME_RED => self.planes[0].extend_from_slice(&row_data),
+                SANE_FRAME_GREEN => self.planes[1].extend_from_slice(&row_data),
+                SANE_FRAME_BLUE => self.planes[2].extend_from_slice(&row_data),
+                _ => {
+                    self.lines += 1;
+                    if self.streaming {
+                        out.extend_from_slice(&row_data);
+                    } else {
+                        self.buffered.extend_from_slice(&row_data);
+                    }
+                }
+            }
+        }
+        self.carry.drain(..complete * bpl);
+
+        out
+    }
+
+    /// Finishes the page; returns the remaining bytes to emit together with
+    /// the final pixel dimensions.
+    pub fn finish(&mut self) -> (Vec<u8>, u32, u32) {
+        let Some(params) = self.params.take() else {
+            return (Vec::new(), 0, 0);
+        };
+        let width = params.pixels_per_line.max(0) as u32;
+
+        if self.streaming {
+            let lines = self.lines;
+            self.lines = 0;
+            return (Vec::new(), width, lines);
+        }
+
+        if !self.planes[0].is_empty() {
+            // Three-pass scan: interleave R, G and B planes into one PPM
+            let sample = if params.depth > 8 { 2 } else { 1 };
+            let pixels = self
+                .planes
+                .iter()
+                .map(|p| p.len() / sample)
+                .min()
+                .unwrap_or(0);
+            let lines = if width > 0 {
+                (pixels / width as usize) as u32
+            } else {
+                0
+            };
+            let mut out = Self::header(&params, true, lines);
+            for i in 0..(lines as usize * width as usize) {
+                for plane in &self.planes {
+                    out.extend_from_slice(&plane[i * sample..(i + 1) * sample]);
+                }
+            }
+            self.planes = [Vec::new(), Vec::new(), Vec::new()];
+            return (out, width, lines);
+        }
+
+        let lines = self.lines;
+        self.lines = 0;
+        let mut out = Self::header(&params, params.format == SANE_FRAME_RGB, lines);
+        out.append(&mut self.buffered);
+        (out, width, lines)
+    }
+}
@@ -0,0 +1,1116 @@
+//
+//  papyr_core
+//  backends/saned.rs - SANE network protocol (saned) client backend
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::auth::{self, Credentials, CredentialsProvider};
+use crate::backends::sane_common::*;
+use crate::backends::OptionOverrides;
+use crate::models::*;
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Default saned control port.
+pub const SANED_PORT: u16 = 6566;
+
+/// Environment variable listing saned hosts (`host[:port]`, comma separated).
+pub const SANED_HOSTS_ENV: &str = "PAPYR_SANED_HOSTS";
+
+// SANE_VERSION_CODE(1, 0, 3): net protocol version 3
+const SANE_NET_VERSION_CODE: i32 = (1 << 24) | 3;
+
+// RPC procedure numbers
+const SANE_NET_INIT: i32 = 0;
+const SANE_NET_GET_DEVICES: i32 = 1;
+const SANE_NET_OPEN: i32 = 2;
+const SANE_NET_CLOSE: i32 = 3;
+const SANE_NET_GET_OPTION_DESCRIPTORS: i32 = 4;
+const SANE_NET_CONTROL_OPTION: i32 = 5;
+const SANE_NET_GET_PARAMETERS: i32 = 6;
+const SANE_NET_START: i32 = 7;
+const SANE_NET_CANCEL: i32 = 8;
+const SANE_NET_AUTHORIZE: i32 = 9;
+const SANE_NET_EXIT: i32 = 10;
+
+// Byte order markers sent in the START reply
+const SANE_NET_LITTLE_ENDIAN: i32 = 0x1234;
+
+// A data record length of all ones is followed by a one byte status
+const DATA_RECORD_STATUS: u32 = 0xffff_ffff;
+
+const CONNECT_TIMEOUT_SECS: u64 = 5;
+const CONTROL_TIMEOUT_SECS: u64 = 30;
+const DATA_TIMEOUT_SECS: u64 = 120;
+
+/// A saned server address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaneNetHost {
+    pub host: String,
+    pub port: u16,
+}
+
+impl SaneNetHost {
+    /// Parses `host`, `host:port`, `[v6]` or `[v6]:port`; a bare IPv6
+    /// address without brackets uses the default port.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return None;
+        }
+
+        if let Some(rest) = spec.strip_prefix('[') {
+            let (host, tail) = rest.split_once(']')?;
+            let port = match tail.strip_prefix(':') {
+                Some(port) => port.parse().ok()?,
+                None if tail.is_empty() => SANED_PORT,
+                None => return None,
+            };
+            return Some(Self {
+                host: host.to_string(),
+                port,
+            });
+        }
+
+        match spec.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => Some(Self {
+                host: host.to_string(),
+                port: port.parse().ok()?,
+            }),
+            _ => Some(Self {
+                host: spec.to_string(),
+                port: SANED_PORT,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for SaneNetHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Builds the device id for `device` on `host`.
+fn device_id(host: &SaneNetHost, device: &str) -> DeviceId {
+    DeviceId::new(
+        Backend::SaneNet,
+        format!("{}/{}", host, escape_device_name(device)),
+    )
+}
+
+/// Splits a device id back into host and remote device name.
+fn parse_device_id(device_id: &DeviceId) -> Result<(SaneNetHost, String)> {
+    (device_id.backend() == Backend::SaneNet)
+        .then(|| device_id.local().split_once('/'))
+        .flatten()
+        .and_then(|(host, device)| Some((SaneNetHost::parse(host)?, unescape_device_name(device)?)))
+        .ok_or_else(|| PapyrError::NotFound(format!("Device {} not found", device_id)))
+}
+
+fn io_error(host: &SaneNetHost, e: io::Error) -> PapyrError {
+    let backend = Backend::SaneNet;
+    let message = format!("saned {}: {}", host, e);
+    match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            PapyrError::Timeout { backend, message }
+        }
+        io::ErrorKind::InvalidData => PapyrError::Protocol { backend, message },
+        _ => PapyrError::Io { backend, message },
+    }
+}
+
+/// Encoder for one RPC request.
+struct Request(Vec<u8>);
+
+impl Request {
+    fn new(procedure: i32) -> Self {
+        Self(procedure.to_be_bytes().to_vec())
+    }
+
+    fn word(mut self, value: i32) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn string(mut self, value: Option<&str>) -> Self {
+        match value {
+            Some(s) => {
+                self = self.word(s.len() as i32 + 1);
+                self.0.extend_from_slice(s.as_bytes());
+                self.0.push(0);
+            }
+            None => self = self.word(0),
+        }
+        self
+    }
+
+    fn bytes(mut self, value: &[u8]) -> Self {
+        self = self.word(value.len() as i32);
+        self.0.extend_from_slice(value);
+        self
+    }
+}
+
+/// A control connection to saned.
+struct Connection {
+    host: SaneNetHost,
+    peer: SocketAddr,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    username: String,
+    credentials: Option<Arc<dyn CredentialsProvider>>,
+}
+
+impl Connection {
+    /// Connects and performs `SANE_NET_INIT`.
+    fn open(host: &SaneNetHost, credentials: Option<Arc<dyn CredentialsProvider>>) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = (host.host.as_str(), host.port)
+            .to_socket_addrs()
+            .map_err(|e| io_error(host, e))?
+            .collect();
+
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no addresses resolved");
+        let mut stream = None;
+        for addr in &addrs {
+            match TcpStream::connect_timeout(addr, Duration::from_secs(CONNECT_TIMEOUT_SECS)) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        let stream = stream.ok_or_else(|| io_error(host, last_error))?;
+
+        let setup = || -> io::Result<(SocketAddr, TcpStream)> {
+            stream.set_nodelay(true)?;
+            stream.set_read_timeout(Some(Duration::from_secs(CONTROL_TIMEOUT_SECS)))?;
+            Ok((stream.peer_addr()?, stream.try_clone()?))
+        };
+        let (peer, writer) = setup().map_err(|e| io_error(host, e))?;
+
+        let username = std::env::var("USER").unwrap_or_else(|_| "papyr".to_string());
+
+        let mut conn = Self {
+            host: host.clone(),
+            peer,
+            reader: BufReader::new(stream),
+            writer,
+            username,
+            credentials,
+        };
+
+        let request = Request::new(SANE_NET_INIT)
+            .word(SANE_NET_VERSION_CODE)
+            .string(Some(&conn.username.clone()));
+        let (status, version) = conn.rpc(request, |c| Ok(((c.word()?, c.word()?), None)))?;
+        if status != SANE_STATUS_GOOD {
+            return Err(status_error(Backend::SaneNet, "saned init failed", status));
+        }
+        if (version >> 24) != 1 {
+            return Err(PapyrError::Protocol {
+                backend: Backend::SaneNet,
+                message: format!(
+                    "saned {}: unsupported protocol version {:#x}",
+                    host, version
+                ),
+            });
+        }
+
+        Ok(conn)
+    }
+
+    fn word(&mut self) -> io::Result<i32> {
+        let mut buf = [0u8; 4];
+        self.reader.read_exact(&mut buf)?;
+        Ok(i32::from_be_bytes(buf))
+    }
+
+    fn string(&mut self) -> io::Result<Option<String>> {
+        let len = self.word()?;
+        if len <= 0 {
+            return Ok(None);
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.reader.read_exact(&mut buf)?;
+        if let Some(nul) = buf.iter().position(|&b| b == 0) {
+            buf.truncate(nul);
+        }
+        Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
+    }
+
+    /// Reads a pointer marker; `true` means a value follows.
+    fn pointer(&mut self) -> io::Result<bool> {
+        Ok(self.word()? == 0)
+    }
+
+    /// Sends `request` and decodes the reply, answering any
+    /// `resource_to_authorize` the server asks for before the real reply.
+    fn rpc<T>(
+        &mut self,
+        request: Request,
+        mut decode: impl FnMut(&mut Self) -> io::Result<(T, Option<String>)>,
+    ) -> Result<T> {
+        let host = self.host.clone();
+        self.writer
+            .write_all(&request.0)
+            .map_err(|e| io_error(&host, e))?;
+
+        loop {
+            let (reply, resource) = decode(self).map_err(|e| io_error(&host, e))?;
+            match resource {
+                Some(resource) if !resource.is_empty() => self.authorize(&resource)?,
+                _ => return Ok(reply),
+            }
+        }
+    }
+
+    /// Answers an authorization request, falling back to the process-wide
+    /// provider when the backend has none of its own.
+    fn authorize(&mut self, resource: &str) -> Result<()> {
+        let (name, _) = auth::split_md5_resource(resource);
+        let credentials = match &self.credentials {
+            Some(provider) => provider.credentials(name),
+            None => auth::credentials_for(name),
+        };
+        let credentials = credentials.ok_or_else(|| PapyrError::Unauthorized {
+            backend: Backend::SaneNet,
+            message: format!("saned {}: no credentials for {}", self.host, name),
+        })?;
+
+        let password = auth::encode_password(resource, &credentials.password);
+        let request = Request::new(SANE_NET_AUTHORIZE)
+            .string(Some(resource))
+            .string(Some(&credentials.username))
+            .string(Some(&password));
+        self.rpc(request, |c| Ok((c.word()?, None)))?;
+        Ok(())
+    }
+
+    fn get_devices(&mut self) -> Result<Vec<(String, String, String, String)>> {
+        let (status, devices) = self.rpc(Request::new(SANE_NET_GET_DEVICES), |c| {
+            let status = c.word()?;
+            let len = c.word()?;
+            let mut devices = Vec::new();
+            for _ in 0..len.max(0) {
+                if c.pointer()? {
+                    let name = c.string()?.unwrap_or_default();
+                    let vendor = c.string()?.unwrap_or_default();
+                    let model = c.string()?.unwrap_or_default();
+                    let kind = c.string()?.unwrap_or_default();
+                    devices.push((name, vendor, model, kind));
+                }
+            }
+            Ok(((status, devices), None))
+        })?;
+
+        if status != SANE_STATUS_GOOD {
+            return Err(status_error(
+                Backend::SaneNet,
+                "saned get_devices failed",
+                status,
+            ));
+        }
+        Ok(devices)
+    }
+
+    fn open_device(&mut self, device: &str) -> Result<i32> {
+        let request = Request::new(SANE_NET_OPEN).string(Some(device));
+        let (status, handle) = self.rpc(request, |c| {
+            let status = c.word()?;
+            let handle = c.word()?;
+            let resource = c.string()?;
+            Ok(((status, handle), resource))
+        })?;
+
+        if status != SANE_STATUS_GOOD {
+            return Err(status_error(
+                Backend::SaneNet,
+                &format!("Failed to open {}", device),
+                status,
+            ));
+        }
+        Ok(handle)
+    }
+
+    fn close_device(&mut self, handle: i32) -> Result<()> {
+        let request = Request::new(SANE_NET_CLOSE).word(handle);
+        self.rpc(request, |c| Ok((c.word()?, None)))?;
+        Ok(())
+    }
+
+    fn option_descriptors(&mut self, handle: i32) -> Result<Vec<SaneOptionDescriptor>> {
+        let request = Request::new(SANE_NET_GET_OPTION_DESCRIPTORS).word(handle);
+        self.rpc(request, |c| {
+            let len = c.word()?;
+            let mut options = Vec::new();
+            for _ in 0..len.max(0) {
+                if c.pointer()? {
+                    options.push(c.option_descriptor()?);
+                } else {
+                    // Keep indices aligned with the server's option numbers
+                    options.push(SaneOptionDescriptor {
+                        name: String::new(),
+                        title: String::new(),
+                        desc: String::new(),
+                        value_type: SANE_TYPE_GROUP,
+                        unit: SANE_UNIT_NONE,
+                        size: 0,
+                        cap: SANE_CAP_INACTIVE,
+                        constraint: SaneConstraint::None,
+                    });
+                }
+            }
+            Ok((options, None))
+        })
+    }
+
+    fn option_descriptor(&mut self) -> io::Result<SaneOptionDescriptor> {
+        let name = self.string()?.unwrap_or_default();
+        let title = self.string()?.unwrap_or_default();
+        let desc = self.string()?.unwrap_or_default();
+        let value_type = self.word()?;
+        let unit = self.word()?;
+        let size = self.word()?;
+        let cap = self.word()?;
+        let constraint = match self.word()? {
+            SANE_CONSTRAINT_RANGE => {
+                if self.pointer()? {
+                    SaneConstraint::Range {
+                        min: self.word()?,
+                        max: self.word()?,
+                        quant: self.word()?,
+                    }
+                } else {
+                    SaneConstraint::None
+                }
+            }
+            SANE_CONSTRAINT_WORD_LIST => {
+                // The first word repeats the list length
+                let len = self.word()?;
+                let mut words = Vec::new();
+                for _ in 0..len.max(0) {
+                    words.push(self.word()?);
+                }
+                SaneConstraint::WordList(words.into_iter().skip(1).collect())
+            }
+            SANE_CONSTRAINT_STRING_LIST => {
+                let len = self.word()?;
+                let mut strings = Vec::new();
+                for _ in 0..len.max(0) {
+                    if let Some(s) = self.string()? {
+                        strings.push(s);
+                    }
+                }
+                SaneConstraint::StringList(strings)
+            }
+            _ => SaneConstraint::None,
+        };
+
+        Ok(SaneOptionDescriptor {
+            name,
+            title,
+            desc,
+            value_type,
+            unit,
+            size,
+            cap,
+            constraint,
+        })
+    }
+
+    /// Issues a `SANE_NET_CONTROL_OPTION` and returns the info flags and
+    /// the value echoed back by the server.
+    fn control_option(
+        &mut self,
+        handle: i32,
+        option: usize,
+        desc: &SaneOptionDescriptor,
+        action: i32,
+        value: Option<&SaneValue>,
+    ) -> Result<(i32, Option<SaneValue>)> {
+        let size = desc.size.max(0) as usize;
+        let payload: Vec<u8> = match (desc.value_type, value) {
+            (SANE_TYPE_STRING, Some(SaneValue::String(s))) => {
+                // Cut to fit the option, keeping room for the terminator
+                let mut bytes = s.as_bytes().to_vec();
+                bytes.truncate(size.saturating_sub(1));
+                bytes.push(0);
+                bytes
+            }
+            (SANE_TYPE_STRING, _) => vec![0u8; size],
+            (_, Some(value)) => {
+                let word = match value {
+                    SaneValue::Bool(b) => *b as i32,
+                    SaneValue::Int(i) => *i,
+                    SaneValue::Fixed(f) => f64_to_fixed(*f),
+                    SaneValue::String(_) => 0,
+                };
+                word.to_be_bytes().to_vec()
+            }
+            (_, None) => vec![0u8; size],
+        };
+
+        let mut request = Request::new(SANE_NET_CONTROL_OPTION)
+            .word(handle)
+            .word(option as i32)
+            .word(action)
+            .word(desc.value_type);
+        if desc.value_type == SANE_TYPE_STRING {
+            request = request.word(payload.len() as i32).bytes(&payload);
+        } else {
+            // Word arrays are sent as a count of words followed by the words
+            request = request
+                .word(payload.len() as i32)
+                .word(payload.len() as i32 / 4);
+            request.0.extend_from_slice(&payload);
+        }
+
+        let (status, info, value) = self.rpc(request, |c| {
+            let status = c.word()?;
+            let info = c.word()?;
+            let value_type = c.word()?;
+            let _value_size = c.word()?;
+            let len = c.word()?.max(0) as usize;
+            let value = if value_type == SANE_TYPE_STRING {
+                let mut buf = vec![0u8; len];
+                c.reader.read_exact(&mut buf)?;
+                if let Some(nul) = buf.iter().position(|&b| b == 0) {
+                    buf.truncate(nul);
+                }
+                Some(SaneValue::String(
+                    String::from_utf8_lossy(&buf).into_owned(),
+                ))
+            } else {
+                let mut words = Vec::with_capacity(len);
+                for _ in 0..len {
+                    words.push(c.word()?);
+                }
+                words.first().map(|&w| match value_type {
+                    SANE_TYPE_BOOL => SaneValue::Bool(w != 0),
+                    SANE_TYPE_FIXED => SaneValue::Fixed(fixed_to_f64(w)),
+                    _ => SaneValue::Int(w),
+                })
+            };
+            let resource = c.string()?;
+            Ok(((status, info, value), resource))
+        })?;
+
+        if status != SANE_STATUS_GOOD {
+            return Err(option_error(Backend::SaneNet, &desc.name, status));
+        }
+        Ok((info, value))
+    }
+
+    fn get_parameters(&mut self, handle: i32) -> Result<SaneFrameParams> {
+        let request = Request::new(SANE_NET_GET_PARAMETERS).word(handle);
+        let (status, params) = self.rpc(request, |c| {
+            let status = c.word()?;
+            let params = SaneFrameParams {
+                format: c.word()?,
+                last_frame: c.word()? != 0,
+                bytes_per_line: c.word()?,
+                pixels_per_line: c.word()?,
+                lines: c.word()?,
+                depth: c.word()?,
+            };
+            Ok(((status, params), None))
+        })?;
+
+        if status != SANE_STATUS_GOOD {
+            return Err(status_error(
+                Backend::SaneNet,
+                "Failed to get parameters",
+                status,
+            ));
+        }
+        Ok(params)
+    }
+
+    /// Starts a frame; returns the SANE status, the data port and whether
+    /// 16-bit samples are little endian.
+    fn start(&mut self, handle: i32) -> Result<(i32, u16, bool)> {
+        let request = Request::new(SANE_NET_START).word(handle);
+        self.rpc(request, |c| {
+            let status = c.word()?;
+            let port = c.word()?;
+            let byte_order = c.word()?;
+            let resource = c.string()?;
+            Ok((
+                (status, port as u16, byte_order == SANE_NET_LITTLE_ENDIAN),
+                resource,
+            ))
+        })
+    }
+
+    fn cancel(&mut self, handle: i32) -> Result<()> {
+        let request = Request::new(SANE_NET_CANCEL).word(handle);
+        self.rpc(request, |c| Ok((c.word()?, None)))?;
+        Ok(())
+    }
+
+    fn exit(&mut self) {
+        let _ = self
+            .writer
+            .write_all(&Request::new(SANE_NET_EXIT).0)
+            .and_then(|_| self.writer.flush());
+    }
+
+    fn connect_data(&self, port: u16) -> Result<TcpStream> {
+        let addr = SocketAddr::new(self.peer.ip(), port);
+        let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(CONNECT_TIMEOUT_SECS))
+            .map_err(|e| io_error(&self.host, e))?;
+        stream
+            .set_read_timeout(Some(Duration::from_secs(DATA_TIMEOUT_SECS)))
+            .map_err(|e| io_error(&self.host, e))?;
+        Ok(stream)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.exit();
+    }
+}
+
+/// An open device on a saned connection.
+struct NetDevice<'a> {
+    conn: &'a mut Connection,
+    handle: i32,
+}
+
+impl SaneOptionAccess for NetDevice<'_> {
+    fn descriptors(&mut self) -> Result<Vec<SaneOptionDescriptor>> {
+        self.conn.option_descriptors(self.handle)
+    }
+
+    fn control(
+        &mut self,
+        index: usize,
+        desc: &SaneOptionDescriptor,
+        action: i32,
+        value: Option<&SaneValue>,
+    ) -> Result<(i32, Option<SaneValue>)> {
+        self.conn
+            .control_option(self.handle, index, desc, action, value)
+    }
+}
+
+pub struct SaneNetBackend {
+    hosts: Vec<SaneNetHost>,
+    credentials: Option<Arc<dyn CredentialsProvider>>,
+    overrides: OptionOverrides,
+}
+
+impl SaneNetBackend {
+    /// Uses the hosts listed in `PAPYR_SANED_HOSTS`.
+    pub fn new() -> Self {
+        let hosts = std::env::var(SANED_HOSTS_ENV)
+            .map(|value| value.split(',').filter_map(SaneNetHost::parse).collect())
+            .unwrap_or_default();
+
+        Self {
+            hosts,
+            credentials: None,
+            overrides: OptionOverrides::default(),
+        }
+    }
+
+    /// Uses an explicit list of `host[:port]` entries.
+    pub fn with_hosts<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            hosts: hosts
+                .into_iter()
+                .filter_map(|h| SaneNetHost::parse(h.as_ref()))
+                .collect(),
+            credentials: None,
+            overrides: OptionOverrides::default(),
+        }
+    }
+
+    /// Username and password sent whenever saned asks to authorize a resource.
+    pub fn with_credentials(self, username: &str, password: &str) -> Self {
+        self.with_credentials_provider(Arc::new(Credentials::new(username, password)))
+    }
+
+    /// Provider asked per resource; overrides `auth::set_credentials_provider`.
+    pub fn with_credentials_provider(mut self, provider: Arc<dyn CredentialsProvider>) -> Self {
+        self.credentials = Some(provider);
+        self
+    }
+
+    pub fn hosts(&self) -> &[SaneNetHost] {
+        &self.hosts
+    }
+
+    fn connect(&self, host: &SaneNetHost) -> Result<Connection> {
+        Connection::open(host, self.credentials.clone())
+    }
+
+    /// Opens `device_id` with its remembered options applied, runs `f` and
+    /// closes the device again.
+    fn with_device<T>(
+        &self,
+        device_id: &DeviceId,
+        cancel: &CancelToken,
+        f: impl FnOnce(&mut NetDevice) -> Result<T>,
+    ) -> Result<T> {
+        let (host, device) = parse_device_id(device_id)?;
+        let mut conn = self.connect(&host)?;
+        cancel.check()?;
+        let handle = conn.open_device(&device)?;
+        if let Err(e) = cancel.check() {
+            let _ = conn.close_device(handle);
+            return Err(e);
+        }
+
+        let mut dev = NetDevice {
+            conn: &mut conn,
+            handle,
+        };
+        let result =
+            apply_overrides(&mut dev, &self.overrides.get(device_id)).and_then(|_| f(&mut dev));
+        let _ = conn.close_device(handle);
+        result
+    }
+
+    fn enumerate_host(&self, host: &SaneNetHost) -> Result<Vec<ScannerInfo>> {
+        let mut conn = self.connect(host)?;
+        let devices = conn.get_devices()?;
+
+        Ok(devices
+            .into_iter()
+            .map(|(name, vendor, model, kind)| {
+                let label = format!("{} {}", vendor, model).trim().to_string();
+                let info = ScannerInfo::new(
+                    device_id(host, &name),
+                    if label.is_empty() {
+                        format!("{} ({})", name, host.host)
+                    } else {
+                        format!("{} ({})", label, host.host)
+                    },
+                );
+                // Whatever the remote attachment, we reach it over the network
+                ScannerInfo {
+                    connection: ConnectionKind::Network,
+                    address: Some(host.to_string()),
+                    ..describe_device(info, &name, &vendor, &model, &kind)
+                }
+            })
+            .collect())
+    }
+}
+
+impl BackendProvider for SaneNetBackend {
+    fn name(&self) -> &'static str {
+        "SANE network (saned)"
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::SaneNet
+    }
+
+    fn availability(&self) -> BackendAvailability {
+        if self.hosts.is_empty() {
+            BackendAvailability::Unavailable(format!(
+                "no saned hosts configured (set {})",
+                SANED_HOSTS_ENV
+            ))
+        } else {
+            BackendAvailability::Available
+        }
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        self.enumerate_cancellable(&CancelToken::new())
+    }
+
+    fn enumerate_cancellable(&self, cancel: &CancelToken) -> Vec<ScannerInfo> {
+        let mut scanners = Vec::new();
+        for host in &self.hosts {
+            if cancel.is_cancelled() {
+                break;
+            }
+            match self.enumerate_host(host) {
+                Ok(devices) => scanners.extend(devices),
+                Err(e) => warn!(%host, "saned enumeration failed: {}", e),
+            }
+        }
+        scanners
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        self.capabilities_cancellable(device_id, &CancelToken::new())
+    }
+
+    fn capabilities_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cancel: &CancelToken,
+    ) -> Result<Capabilities> {
+        self.with_device(device_id, cancel, |dev| {
+            Ok(capabilities_from_options(&dev.descriptors()?))
+        })
+    }
+
+    fn start_scan(&self, device_id: &DeviceId, cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        self.start_scan_cancellable(device_id, cfg, &CancelToken::new())
+    }
+
+    fn start_scan_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cfg: ScanConfig,
+        cancel: &CancelToken,
+    ) -> Result<Box<dyn ScanSession>> {
+        let (host, device) = parse_device_id(device_id)?;
+        let mut conn = self.connect(&host)?;
+        cancel.check()?;
+        let handle = conn.open_device(&device)?;
+
+        let mut session = SaneNetScanSession {
+            conn,
+            handle,
+            cfg,
+            data: None,
+            frame: None,
+            assembler: PageAssembler::new(false),
+            page_index: 0,
+            page_bytes: 0,
+            page_expected: None,
+            status: None,
+            pending: VecDeque::new(),
+            failure: None,
+            state: SaneNetScanState::Scanning,
+            cancel: Arc::new(SaneNetCancel::default()),
+        };
+        // From here on the session's Drop cancels and closes the device
+        let session_cancel = Arc::clone(&session.cancel);
+        cancel.on_cancel(CancelHandle::new(move || session_cancel.cancel()));
+
+        let mut dev = NetDevice {
+            conn: &mut session.conn,
+            handle,
+        };
+        let mut warnings = apply_config(&mut dev, &session.cfg)?;
+        warnings.extend(apply_overrides(&mut dev, &self.overrides.get(device_id))?);
+        session.pending.extend(warnings);
+        cancel.check()?;
+        if !session.start_page()? {
+            return Err(status_error(
+                Backend::SaneNet,
+                "Failed to start scan",
+                SANE_STATUS_NO_DOCS,
+            ));
+        }
+
+        Ok(Box::new(session))
+    }
+
+    fn options(&self, device_id: &DeviceId) -> Result<Vec<DeviceOption>> {
+        self.with_device(device_id, &CancelToken::new(), |dev| read_options(dev))
+    }
+
+    fn set_option(
+        &self,
+        device_id: &DeviceId,
+        name: &str,
+        value: OptionValue,
+    ) -> Result<DeviceOption> {
+        let (option, remembered) = self.with_device(device_id, &CancelToken::new(), |dev| {
+            write_device_option(dev, name, value)
+        })?;
+        if let Some(value) = remembered {
+            self.overrides.set(device_id, name, value);
+        }
+        Ok(option)
+    }
+}
+
+struct SaneNetScanSession {
+    conn: Connection,
+    handle: i32,
+    cfg: ScanConfig,
+    data: Option<BufReader<TcpStream>>,
+    frame: Option<SaneFrameParams>,
+    assembler: PageAssembler,
+    page_index: u32,
+    // Data bytes received for the current page, for progress events
+    page_bytes: u64,
+    page_expected: Option<u64>,
+    status: Option<ScanStatus>,
+    pending: VecDeque<ScanEvent>,
+    // Reported once the events queued before it have been delivered
+    failure: Option<PapyrError>,
+    state: SaneNetScanState,
+    cancel: Arc<SaneNetCancel>,
+}
+
+/// Cancellation shared between a session and its cancel handles.
+#[derive(Default)]
+struct SaneNetCancel {
+    cancelled: AtomicBool,
+    // Clone of the data connection, shut down to end a blocked read
+    data: Mutex<Option<TcpStream>>,
+}
+
+impl SaneNetCancel {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(data) = self.data.lock() {
+            if let Some(stream) = data.as_ref() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn set_data(&self, stream: Option<TcpStream>) {
+        if let Ok(mut data) = self.data.lock() {
+            *data = stream;
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SaneNetScanState {
+    Scanning,
+    Completed,
+}
+
+impl SaneNetScanSession {
+    /// Ends the session with `error`, after any events already queued.
+    fn fail(&mut self, error: PapyrError) -> Result<Option<ScanEvent>> {
+        if matches!(error, PapyrError::Cancelled) {
+            self.close_data();
+            let _ = self.conn.cancel(self.handle);
+        }
+        self.state = SaneNetScanState::Completed;
+        self.failure = Some(error);
+        self.next_event()
+    }
+
+    /// Queues a `Status` event if the status changed.
+    fn set_status(&mut self, status: ScanStatus) {
+        if self.status != Some(status) {
+            self.status = Some(status);
+            self.pending.push_back(ScanEvent::Status(status));
+        }
+    }
+
+    /// Starts a frame and opens its data connection. Returns `None` when
+    /// the feeder has no more documents. While the lamp warms up, retries
+    /// until the data timeout.
+    fn start_frame(&mut self) -> Result<Option<SaneFrameParams>> {
+        let deadline = Instant::now() + Duration::from_secs(DATA_TIMEOUT_SECS);
+        let (port, little_endian) = loop {
+            if self.cancel.is_cancelled() {
+                return Err(PapyrError::Cancelled);
+            }
+            let (status, port, little_endian) = self.conn.start(self.handle)?;
+            match status {
+                SANE_STATUS_GOOD => break (port, little_endian),
+                SANE_STATUS_NO_DOCS => return Ok(None),
+                SANE_STATUS_WARMING_UP if Instant::now() < deadline => {
+                    self.set_status(ScanStatus::WarmingUp);
+                    std::thread::sleep(WARM_UP_POLL_INTERVAL);
+                }
+                _ => {
+                    return Err(status_error(
+                        Backend::SaneNet,
+                        "Failed to start scan",
+                        status,
+                    ))
+                }
+            }
+        };
+        self.set_status(ScanStatus::Scanning);
+
+        let data = self.conn.connect_data(port)?;
+        self.cancel.set_data(data.try_clone().ok());
+        if self.cancel.is_cancelled() {
+            return Err(PapyrError::Cancelled);
+        }
+        let params = self.conn.get_parameters(self.handle)?;
+        self.data = Some(BufReader::new(data));
+        self.frame = Some(params);
+        if self.assembler.is_idle() {
+            self.assembler = PageAssembler::new(little_endian);
+        }
+        Ok(Some(params))
+    }
+
+    /// Starts the next page, queuing its `PageStarted` event.
+    fn start_page(&mut self) -> Result<bool> {
+        match self.start_frame()? {
+            Some(params) => {
+                self.page_bytes = 0;
+                self.page_expected = params.expected_page_bytes();
+                self.pending
+                    .push_back(ScanEvent::PageStarted(self.page_index));
+                let header = self.assembler.begin_frame(params);
+                if !header.is_empty() {
+                    self.pending.push_back(ScanEvent::PageData(header));
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Reads one data record; `Ok(Err(status))` reports the end-of-frame status.
+    fn read_record(&mut self) -> Result<std::result::Result<Vec<u8>, i32>> {
+        let host = self.conn.host.clone();
+        let data = self
+            .data
+            .as_mut()
+            .ok_or_else(|| PapyrError::Backend("No active saned data connection".into()))?;
+
+        let mut len = [0u8; 4];
+        data.read_exact(&mut len).map_err(|e| io_error(&host, e))?;
+        let len = u32::from_be_bytes(len);
+
+        if len == DATA_RECORD_STATUS {
+            let mut status = [0u8; 1];
+            data.read_exact(&mut status)
+                .map_err(|e| io_error(&host, e))?;
+            return Ok(Err(status[0] as i32));
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        data.read_exact(&mut buf).map_err(|e| io_error(&host, e))?;
+        Ok(Ok(buf))
+    }
+
+    fn close_data(&mut self) {
+        self.data = None;
+        self.cancel.set_data(None);
+    }
+
+    fn finish_frame(&mut self) -> Result<()> {
+        self.close_data();
+
+        // Three-pass scanners deliver one frame per colour
+        let frame = self.frame.take();
+        if let Some(params) = frame {
+            if !params.last_frame {
+                return match self.start_frame()? {
+                    Some(next) => {
+                        self.assembler.begin_frame(next);
+                        Ok(())
+                    }
+                    None => Err(PapyrError::Protocol {
+                        backend: Backend::SaneNet,
+                        message: "saned: scanner stopped between colour frames".into(),
+                    }),
+                };
+            }
+        }
+
+        let (rest, width_px, height_px) = self.assembler.finish();
+        if !rest.is_empty() {
+            self.pending.push_back(ScanEvent::PageData(rest));
+        }
+        self.pending.push_back(ScanEvent::PageComplete(PageMeta {
+            index: self.page_index,
+            width_px,
+            height_px,
+            dpi: self.cfg.dpi,
+            dpi_y: self.cfg.vertical_dpi(),
+            color_mode: self.cfg.color_mode,
+            bit_depth: frame.map_or(self.cfg.bits_per_sample(), |params| params.depth as u8),
+        }));
+        self.page_index += 1;
+
+        if self.cfg.source == ScanSource::Flatbed || !self.start_page()? {
+            self.pending.push_back(ScanEvent::JobComplete);
+            self.state = SaneNetScanState::Completed;
+        }
+        Ok(())
+    }
+}
+
+impl ScanSession for SaneNetScanSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            match self.failure.take() {
+                Some(PapyrError::Cancelled) => return Ok(Some(ScanEvent::Cancelled)),
+                Some(e) => return Err(e),
+                None => {}
+            }
+            if self.state == SaneNetScanState::Completed {
+                return Ok(None);
+            }
+
+            if self.cancel.is_cancelled() {
+                return self.fail(PapyrError::Cancelled);
+            }
+            let record = match self.read_record() {
+                Ok(record) => record,
+                // The cancel handle shut the data connection down
+                Err(_) if self.cancel.is_cancelled() => return self.fail(PapyrError::Cancelled),
+                Err(e) => return self.fail(e),
+            };
+
+            match record {
+                Ok(bytes) => {
+                    let out = self.assembler.push(&bytes);
+                    if !out.is_empty() {
+                        self.pending.push_back(ScanEvent::PageData(out));
+                    }
+                    if !bytes.is_empty() {
+                        self.page_bytes += bytes.len() as u64;
+                        self.pending
+                            .push_back(ScanEvent::progress(self.page_bytes, self.page_expected));
+                    }
+                }
+                Err(SANE_STATUS_EOF) => {
+                    if let Err(e) = self.finish_frame() {
+                        return self.fail(e);
+                    }
+                }
+                Err(SANE_STATUS_CANCELLED) => return self.fail(PapyrError::Cancelled),
+                Err(status) => {
+                    return self.fail(status_error(Backend::SaneNet, "saned read failed", status))
+                }
+            }
+        }
+    }
+
+    /// Interrupts a blocked read by shutting the data connection down; the
+    /// scan is then cancelled on the server.
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancel = Arc::clone(&self.cancel);
+        Some(CancelHandle::new(move || cancel.cancel()))
+    }
+}
+
+impl Drop for SaneNetScanSession {
+    fn drop(&mut self) {
+        self.close_data();
+        let _ = self.conn.cancel(self.handle);
+        let _ = self.conn.close_device(self.handle);
+    }
+}
@@ -11,6 +11,7 @@ use std::ffi::CStr;
 use std::mem;
 use std::os::raw::{c_char, c_uint, c_ulong, c_ushort, c_void};
 use std::ptr;
+use tracing::{debug, info, warn};
 
 // TWAIN Constants
 const TWON_ONEVALUE: c_ushort = 5;
@@ -24,6 +25,7 @@ const DAT_CAPABILITY: c_ushort = 0x0001;
 const DAT_IDENTITY: c_ushort = 0x0003;
 const DAT_PARENT: c_ushort = 0x0004;
 const DAT_PENDINGXFERS: c_ushort = 0x0005;
+const DAT_STATUS: c_ushort = 0x0008;
 const DAT_USERINTERFACE: c_ushort = 0x0009;
 const DAT_IMAGENATIVEXFER: c_ushort = 0x000c;
 const DAT_IMAGEINFO: c_ushort = 0x0101;
@@ -45,13 +47,29 @@ const MSG_GETNEXT: c_ushort = 0x0005;
 // TWAIN Return Codes
 const TWRC_SUCCESS: c_ushort = 0;
 const TWRC_FAILURE: c_ushort = 1;
+const TWRC_CANCEL: c_ushort = 3;
 const TWRC_XFERDONE: c_ushort = 6;
 const TWRC_ENDOFLIST: c_ushort = 7;
 
+// TWAIN Condition Codes (reported through DAT_STATUS after TWRC_FAILURE)
+const TWCC_MAXCONNECTIONS: c_ushort = 4;
+const TWCC_OPERATIONERROR: c_ushort = 5;
+const TWCC_BADCAP: c_ushort = 6;
+const TWCC_BADPROTOCOL: c_ushort = 9;
+const TWCC_SEQERROR: c_ushort = 11;
+const TWCC_CAPUNSUPPORTED: c_ushort = 13;
+const TWCC_DENIED: c_ushort = 16;
+const TWCC_PAPERJAM: c_ushort = 20;
+const TWCC_PAPERDOUBLEFEED: c_ushort = 21;
+const TWCC_CHECKDEVICEONLINE: c_ushort = 23;
+const TWCC_INTERLOCK: c_ushort = 24;
+const TWCC_NOMEDIA: c_ushort = 29;
+
 // TWAIN Capabilities
 const ICAP_XRESOLUTION: c_ushort = 0x1118;
 const ICAP_YRESOLUTION: c_ushort = 0x1119;
 const ICAP_PIXELTYPE: c_ushort = 0x0101;
+const ICAP_BITDEPTH: c_ushort = 0x112B;
 
 // TWAIN Pixel Types
 const TWPT_BW: c_ushort = 0;
@@ -105,6 +123,13 @@ struct TW_ONEVALUE {
     item: c_uint,
 }
 
+#[repr(C)]
+#[derive(Debug)]
+struct TW_STATUS {
+    condition_code: c_ushort,
+    data: c_ushort,
+}
+
 #[repr(C)]
 #[derive(Debug)]
 struct TW_PENDINGXFERS {
@@ -137,6 +162,34 @@ type DsmEntry = unsafe extern "C" fn(
     data: *mut c_void,
 ) -> c_ushort;
 
+/// Maps a TWAIN return code and condition code to an error.
+fn twain_error(context: &str, rc: c_ushort, condition: c_ushort) -> PapyrError {
+    let backend = Backend::Twain;
+    let message = format!(
+        "{}: return code {}, condition code {}",
+        context, rc, condition
+    );
+
+    if rc == TWRC_CANCEL {
+        return PapyrError::Cancelled;
+    }
+
+    match condition {
+        TWCC_MAXCONNECTIONS => PapyrError::DeviceBusy { backend, message },
+        TWCC_PAPERJAM | TWCC_PAPERDOUBLEFEED => PapyrError::PaperJam { backend, message },
+        TWCC_NOMEDIA => PapyrError::FeederEmpty { backend, message },
+        TWCC_INTERLOCK => PapyrError::CoverOpen { backend, message },
+        TWCC_CHECKDEVICEONLINE | TWCC_OPERATIONERROR => PapyrError::Io { backend, message },
+        TWCC_DENIED => PapyrError::Unauthorized { backend, message },
+        TWCC_BADCAP | TWCC_CAPUNSUPPORTED => PapyrError::Unsupported {
+            backend,
+            option: context.to_string(),
+        },
+        TWCC_BADPROTOCOL | TWCC_SEQERROR => PapyrError::Protocol { backend, message },
+        _ => PapyrError::Backend(message),
+    }
+}
+
 pub struct TwainBackend {
     dsm_lib: Option<libloading::Library>,
     dsm_entry: Option<DsmEntry>,
@@ -209,7 +262,7 @@ impl TwainBackend {
         let mut last_error = None;
 
         for dsm_path in &dsm_paths {
-            println!("📚 Trying TWAIN DSM: {}", dsm_path);
+            debug!(path = %dsm_path, "trying TWAIN DSM");
 
             match unsafe { libloading::Library::new(dsm_path) } {
                 Ok(lib) => match unsafe { lib.get(b"DSM_Entry") } {
@@ -219,7 +272,7 @@ impl TwainBackend {
                         self.dsm_lib = Some(lib);
                         self.dsm_entry = Some(dsm_entry_fn);
                         self.state = TwainState::State2;
-                        println!("✅ TWAIN DSM loaded successfully from: {}", dsm_path);
+                        debug!(path = %dsm_path, "TWAIN DSM loaded");
                         return Ok(());
                     }
                     Err(e) => {
@@ -256,10 +309,10 @@ impl TwainBackend {
 
             if rc == TWRC_SUCCESS {
                 self.state = TwainState::State3;
-                println!("✅ TWAIN DSM opened");
+                debug!("TWAIN DSM opened");
                 Ok(())
             } else {
-                Err(PapyrError::Backend(format!("Failed to open DSM: {}", rc)))
+                Err(self.failure("Failed to open DSM", rc))
             }
         } else {
             Err(PapyrError::Backend("DSM entry point not loaded".into()))
@@ -322,21 +375,18 @@ impl TwainBackend {
                     } else if rc == TWRC_ENDOFLIST {
                         break;
                     } else {
-                        println!("Warning: Error getting next source: {}", rc);
+                        warn!(rc, "error getting next TWAIN source");
                         break;
                     }
                 }
             } else if rc == TWRC_ENDOFLIST {
-                println!("No TWAIN sources found");
+                debug!("no TWAIN sources found");
             } else {
-                return Err(PapyrError::Backend(format!(
-                    "Failed to enumerate sources: {}",
-                    rc
-                )));
+                return Err(self.failure("Failed to enumerate sources", rc));
             }
         }
 
-        println!("🔍 Found {} TWAIN sources", sources.len());
+        debug!(sources = sources.len(), "TWAIN sources listed");
         Ok(sources)
     }
 
@@ -367,13 +417,10 @@ impl TwainBackend {
             if rc == TWRC_SUCCESS {
                 self.source_identity = Some(source_copy);
                 self.state = TwainState::State4;
-                println!("✅ TWAIN source opened");
+                debug!("TWAIN source opened");
                 Ok(())
             } else {
-                Err(PapyrError::Backend(format!(
-                    "Failed to open source: {}",
-                    rc
-                )))
+                Err(self.failure("Failed to open source", rc))
             }
         } else {
             Err(PapyrError::Backend("DSM entry point not loaded".into()))
@@ -420,7 +467,7 @@ impl TwainBackend {
                 Ok(())
             } else {
                 // Don't fail if capability setting fails - some scanners don't support all caps
-                println!("⚠️  Warning: Failed to set capability {}: {}", cap_id, rc);
+                warn!(cap_id, rc, "failed to set TWAIN capability");
                 Ok(())
             }
         } else {
@@ -455,13 +502,10 @@ impl TwainBackend {
 
             if rc == TWRC_SUCCESS {
                 self.state = TwainState::State5;
-                println!("✅ TWAIN source enabled");
+                debug!("TWAIN source enabled");
                 Ok(())
             } else {
-                Err(PapyrError::Backend(format!(
-                    "Failed to enable source: {}",
-                    rc
-                )))
+                Err(self.failure("Failed to enable source", rc))
             }
         } else {
             Err(PapyrError::Backend("DSM entry point not loaded".into()))
@@ -476,7 +520,7 @@ impl TwainBackend {
         if let Some(entry) = self.dsm_entry {
             let source = self.source_identity.as_mut().unwrap();
 
-            println!("📥 Starting native image transfer...");
+            debug!("starting native image transfer");
 
             // Perform native transfer
             let mut h_native: *mut c_void = ptr::null_mut();
@@ -492,7 +536,7 @@ impl TwainBackend {
             };
 
             if rc == TWRC_XFERDONE {
-                println!("✅ Transfer complete, processing data...");
+                debug!("transfer complete");
 
                 // Get image info
                 let mut image_info = TW_IMAGEINFO {
@@ -519,9 +563,11 @@ impl TwainBackend {
                     )
                 };
 
-                println!(
-                    "📊 Image: {}x{} pixels, {} bpp",
-                    image_info.image_width, image_info.image_height, image_info.bits_per_pixel
+                debug!(
+                    width = image_info.image_width,
+                    height = image_info.image_height,
+                    bpp = image_info.bits_per_pixel,
+                    "image info"
                 );
 
                 // On Windows, h_native is a HGLOBAL handle
@@ -554,10 +600,10 @@ impl TwainBackend {
                     result.extend_from_slice(&image_info.bits_per_pixel.to_le_bytes());
                     result.extend_from_slice(&[0u8; 24]); // Rest of header
 
-                    println!("✅ Created BMP header: {} bytes total", result.len());
+                    debug!(bytes = result.len(), "created BMP");
                     result
                 } else {
-                    println!("⚠️  Null handle returned, creating placeholder");
+                    warn!("null image handle returned, creating placeholder");
                     vec![0xFFu8; 2048] // Placeholder data
                 };
 
@@ -578,11 +624,11 @@ impl TwainBackend {
                     );
                 }
 
-                println!("📄 Remaining pages: {}", pending.count);
+                debug!(remaining = pending.count, "pages pending");
 
                 Ok(data)
             } else {
-                Err(PapyrError::Backend(format!("Transfer failed: {}", rc)))
+                Err(self.failure("Transfer failed", rc))
             }
         } else {
             Err(PapyrError::Backend("DSM entry point not loaded".into()))
@@ -612,7 +658,7 @@ impl TwainBackend {
                 };
 
                 self.state = TwainState::State4;
-                println!("✅ TWAIN source disabled");
+                debug!("TWAIN source disabled");
             }
         }
         Ok(())
@@ -638,7 +684,7 @@ impl TwainBackend {
                 if rc == TWRC_SUCCESS {
                     self.source_identity = None;
                     self.state = TwainState::State3;
-                    println!("✅ TWAIN source closed");
+                    debug!("TWAIN source closed");
                 }
             }
         }
@@ -663,13 +709,43 @@ impl TwainBackend {
 
                 if rc == TWRC_SUCCESS {
                     self.state = TwainState::State2;
-                    println!("✅ TWAIN DSM closed");
+                    debug!("TWAIN DSM closed");
                 }
             }
         }
         Ok(())
     }
 
+    /// Maps a failed call to an error, reading the condition code from the
+    /// open source (or the DSM when no source is open yet).
+    fn failure(&mut self, context: &str, rc: c_ushort) -> PapyrError {
+        let mut status = TW_STATUS {
+            condition_code: 0,
+            data: 0,
+        };
+
+        if rc == TWRC_FAILURE {
+            if let Some(entry) = self.dsm_entry {
+                let dest = self
+                    .source_identity
+                    .as_mut()
+                    .map_or(ptr::null_mut(), |source| source as *mut TW_IDENTITY);
+                unsafe {
+                    entry(
+                        &mut self.app_identity,
+                        dest,
+                        DG_CONTROL,
+                        DAT_STATUS,
+                        MSG_GET,
+                        &mut status as *mut _ as *mut c_void,
+                    );
+                }
+            }
+        }
+
+        twain_error(context, rc, status.condition_code)
+    }
+
     fn identity_to_string(identity: &TW_IDENTITY) -> String {
         unsafe {
             let product = CStr::from_ptr(identity.product_name.as_ptr())
@@ -705,47 +781,51 @@ impl BackendProvider for TwainBackend {
                 .into_iter()
                 .map(|source| {
                     let name = Self::identity_to_string(&source);
+                    let field = |chars: &[c_char; 34]| {
+                        let value = unsafe { CStr::from_ptr(chars.as_ptr()) }
+                            .to_string_lossy()
+                            .trim()
+                            .to_string();
+                        (!value.is_empty()).then_some(value)
+                    };
+                    // Product family and name usually both carry the model
                     ScannerInfo {
-                        id: format!("twain_{}", source.id),
-                        name,
-                        backend: Backend::Twain,
+                        manufacturer: field(&source.manufacturer),
+                        model: field(&source.product_name)
+                            .or_else(|| field(&source.product_family)),
+                        ..ScannerInfo::new(
+                            DeviceId::new(Backend::Twain, source.id.to_string()),
+                            name,
+                        )
                     }
                 })
                 .collect(),
             Err(e) => {
-                println!("TWAIN enumeration failed: {:?}", e);
+                warn!("TWAIN enumeration failed: {}", e);
                 vec![]
             }
         }
     }
 
-    fn capabilities(&self, _device_id: &str) -> Result<Capabilities> {
+    fn capabilities(&self, _device_id: &DeviceId) -> Result<Capabilities> {
         Ok(Capabilities {
             sources: vec![ScanSource::Flatbed, ScanSource::Adf],
             dpis: vec![75, 150, 200, 300, 600, 1200],
+            dpi_ranges: vec![],
+            // X and Y are separate capabilities
+            independent_dpi: true,
             color_modes: vec![ColorMode::Bw, ColorMode::Gray, ColorMode::Color],
-            page_sizes: vec![
-                PageSize {
-                    width_mm: 216,
-                    height_mm: 279,
-                },
-                PageSize {
-                    width_mm: 210,
-                    height_mm: 297,
-                },
-                PageSize {
-                    width_mm: 148,
-                    height_mm: 210,
-                },
-            ],
+            bit_depths: vec![8, 16],
+            page_sizes: vec![PageFormat::Letter, PageFormat::IsoA(4), PageFormat::IsoA(5)],
+            max_areas: vec![],
             supports_duplex: true,
         })
     }
 
-    fn start_scan(&self, device_id: &str, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
-        let source_id = device_id
-            .strip_prefix("twain_")
-            .and_then(|s| s.parse::<u32>().ok())
+    fn start_scan(&self, device_id: &DeviceId, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        let source_id = (device_id.backend() == Backend::Twain)
+            .then(|| device_id.local().parse::<u32>().ok())
+            .flatten()
             .ok_or_else(|| PapyrError::Backend("Invalid TWAIN device ID".into()))?;
 
         Ok(Box::new(TwainScanSession::new(source_id, config)?))
@@ -762,6 +842,8 @@ pub struct TwainScanSession {
 enum TwainScanState {
     NotStarted,
     Scanning,
+    // The scanned image, waiting to be passed on
+    Scanned(Vec<u8>),
     Completed,
 }
 
@@ -778,7 +860,7 @@ impl TwainScanSession {
     }
 
     fn configure_and_scan(&mut self) -> Result<Vec<u8>> {
-        println!("🖨️  Configuring TWAIN scanner...");
+        debug!("configuring TWAIN scanner");
 
         // Set capabilities
         let _ = self
@@ -786,7 +868,7 @@ impl TwainScanSession {
             .set_capability(ICAP_XRESOLUTION, self.config.dpi);
         let _ = self
             .backend
-            .set_capability(ICAP_YRESOLUTION, self.config.dpi);
+            .set_capability(ICAP_YRESOLUTION, self.config.vertical_dpi());
 
         let pixel_type = match self.config.color_mode {
             ColorMode::Bw => TWPT_BW,
@@ -797,10 +879,20 @@ impl TwainScanSession {
             .backend
             .set_capability(ICAP_PIXELTYPE, pixel_type as u32);
 
+        // ICAP_BITDEPTH counts bits per pixel, and must follow the pixel type
+        if let Some(depth) = self.config.bit_depth {
+            let bits = match self.config.color_mode {
+                ColorMode::Bw => 1,
+                ColorMode::Gray => depth as u32,
+                ColorMode::Color => depth as u32 * 3,
+            };
+            let _ = self.backend.set_capability(ICAP_BITDEPTH, bits);
+        }
+
         // Enable source (show_ui = false for programmatic scanning)
         self.backend.enable_source(false)?;
 
-        println!("🖨️  Starting TWAIN scan transfer...");
+        info!("starting TWAIN scan transfer");
 
         // Perform transfer
         self.backend.transfer_native()
@@ -809,15 +901,21 @@ impl TwainScanSession {
 
 impl ScanSession for TwainScanSession {
     fn next_event(&mut self) -> Result<Option<ScanEvent>> {
-        match self.state {
+        match std::mem::replace(&mut self.state, TwainScanState::Completed) {
             TwainScanState::NotStarted => {
                 self.state = TwainScanState::Scanning;
 
                 match self.configure_and_scan() {
                     Ok(data) => {
+                        debug!(bytes = data.len(), "TWAIN scan completed");
+                        // The transfer is one page, started before its data
+                        self.state = TwainScanState::Scanned(data);
+                        Ok(Some(ScanEvent::PageStarted(0)))
+                    }
+                    // Cancelled from the data source's own UI
+                    Err(PapyrError::Cancelled) => {
                         self.state = TwainScanState::Completed;
-                        println!("✅ TWAIN scan completed: {} bytes", data.len());
-                        Ok(Some(ScanEvent::PageData(data)))
+                        Ok(Some(ScanEvent::Cancelled))
                     }
                     Err(e) => {
                         self.state = TwainScanState::Completed;
@@ -825,11 +923,8 @@ impl ScanSession for TwainScanSession {
                     }
                 }
             }
-            TwainScanState::Scanning => {
-                self.state = TwainScanState::Completed;
-                Ok(Some(ScanEvent::JobComplete))
-            }
-            TwainScanState::Completed => Ok(None),
+            TwainScanState::Scanned(data) => Ok(Some(ScanEvent::PageData(data))),
+            TwainScanState::Scanning | TwainScanState::Completed => Ok(None),
         }
     }
 }
@@ -0,0 +1,868 @@
+//
+//  papyr_core
+//  backends/virtual_scanner.rs - Simulated scanners producing test charts or file pages
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::config::{VirtualConfig, VirtualDevice};
+use crate::models::*;
+use image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Resolutions listed in the capabilities; any in `MIN_DPI..=MAX_DPI` work.
+const DPIS: [u32; 6] = [75, 100, 150, 200, 300, 600];
+const MIN_DPI: u32 = 50;
+const MAX_DPI: u32 = 1200;
+
+/// Largest page on the glass (A4 and Letter) and in the feeder (Legal).
+const FLATBED_MAX: PageSize = PageSize::new(215.9, 297.0);
+const FEEDER_MAX: PageSize = PageSize::new(215.9, 355.6);
+
+/// Image rows per `PageData` event.
+const ROWS_PER_CHUNK: u32 = 64;
+
+// How often a pause checks for cancellation
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Scanners that don't exist: each session renders its pages as test charts
+/// (page number, colour bars, gray ramp, grid and text) or from image and
+/// PDF files, at the requested resolution, colour mode and area, with the
+/// configured noise and skew. Devices come from `VirtualConfig`.
+pub struct VirtualBackend {
+    devices: Vec<VirtualDevice>,
+    page_time: Duration,
+}
+
+impl VirtualBackend {
+    pub fn new(config: &VirtualConfig) -> Self {
+        Self {
+            devices: config.devices(),
+            page_time: Duration::from_millis(config.page_time_ms),
+        }
+    }
+
+    fn device(&self, device_id: &DeviceId) -> Result<&VirtualDevice> {
+        self.devices
+            .iter()
+            .find(|device| {
+                device_id.backend() == Backend::Virtual && device.id == device_id.local()
+            })
+            .ok_or_else(|| PapyrError::NotFound(format!("Device {} not found", device_id)))
+    }
+}
+
+fn max_area(source: ScanSource) -> PageSize {
+    match source {
+        ScanSource::Flatbed => FLATBED_MAX,
+        ScanSource::Adf | ScanSource::AdfDuplex => FEEDER_MAX,
+    }
+}
+
+impl BackendProvider for VirtualBackend {
+    fn name(&self) -> &'static str {
+        "Virtual"
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::Virtual
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        self.devices
+            .iter()
+            .map(|device| {
+                let mut info =
+                    ScannerInfo::new(DeviceId::new(Backend::Virtual, &device.id), &device.name);
+                info.manufacturer = Some("Papyr".into());
+                info.model = Some(device.name.clone());
+                info.serial = Some(format!("VIRTUAL-{}", device.id));
+                info.connection = ConnectionKind::Virtual;
+                info.device_type = Some(
+                    if device.sources.contains(&ScanSource::Flatbed) {
+                        "flatbed scanner"
+                    } else {
+                        "sheetfed scanner"
+                    }
+                    .into(),
+                );
+                info
+            })
+            .collect()
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        let device = self.device(device_id)?;
+        let largest = device
+            .sources
+            .iter()
+            .map(|source| max_area(*source))
+            .fold(PageSize::new(0.0, 0.0), PageSize::union);
+
+        Ok(Capabilities {
+            sources: device.sources.clone(),
+            dpis: DPIS.to_vec(),
+            dpi_ranges: vec![DpiRange {
+                min: MIN_DPI,
+                max: MAX_DPI,
+                step: 1,
+            }],
+            independent_dpi: true,
+            color_modes: vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw],
+            bit_depths: vec![8, 16],
+            page_sizes: PageFormat::fitting(&largest),
+            max_areas: device
+                .sources
+                .iter()
+                .map(|source| SourceArea {
+                    source: *source,
+                    max: max_area(*source),
+                })
+                .collect(),
+            supports_duplex: device.sources.contains(&ScanSource::AdfDuplex),
+        })
+    }
+
+    fn start_scan(&self, device_id: &DeviceId, cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        let device = self.device(device_id)?;
+        cfg.validate(&self.capabilities(device_id)?)?;
+
+        let feeder = cfg.source != ScanSource::Flatbed;
+        let duplex = cfg.source == ScanSource::AdfDuplex || (feeder && cfg.duplex);
+
+        let mut pages: Vec<PageContent> = if device.files.is_empty() {
+            let count = if feeder {
+                device.sheets * if duplex { 2 } else { 1 }
+            } else {
+                1
+            };
+            (0..count)
+                .map(|index| {
+                    let side = duplex.then_some(if index % 2 == 0 { "FRONT" } else { "BACK" });
+                    PageContent::Chart(chart_labels(&cfg, device, index + 1, side))
+                })
+                .collect()
+        } else {
+            // The files are the stack of paper; the glass holds the first page
+            let mut pages: Vec<PageContent> = load_pages(&device.files)?
+                .into_iter()
+                .map(PageContent::Image)
+                .collect();
+            if !feeder {
+                pages.truncate(1);
+            } else if duplex && pages.len() % 2 == 1 {
+                pages.push(PageContent::Blank);
+            }
+            pages
+        };
+        if feeder && pages.is_empty() {
+            return Err(PapyrError::FeederEmpty {
+                backend: Backend::Virtual,
+                message: format!("no paper in {}", device.name),
+            });
+        }
+        if let Some(max_pages) = cfg.max_pages {
+            pages.truncate(max_pages as usize);
+        }
+
+        debug!(device = %device_id, pages = pages.len(), "starting virtual scan");
+        Ok(Box::new(VirtualScanSession {
+            device: device.name.clone(),
+            raster: Raster::new(&cfg, device),
+            pages: pages.into(),
+            page: None,
+            index: 0,
+            jam_at_page: device.jam_at_page.filter(|_| feeder),
+            page_time: self.page_time,
+            stage: Stage::Idle,
+            pending: VecDeque::new(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }))
+    }
+}
+
+/// What a page shows.
+enum PageContent {
+    Chart(Vec<Label>),
+    Image(RgbImage),
+    /// The back of the last sheet, when the files run out on a front side.
+    Blank,
+}
+
+impl PageContent {
+    /// Colour at `(x, y)` mm on a `page`-sized sheet; white outside it.
+    fn sample(&self, page: &PageSize, x: f64, y: f64) -> [u8; 3] {
+        if x < 0.0 || y < 0.0 || x >= page.width_mm || y >= page.height_mm {
+            return WHITE;
+        }
+        match self {
+            PageContent::Chart(labels) => chart(page, labels, x, y),
+            // Stretched over the page, nearest pixel
+            PageContent::Image(image) => {
+                let ix = (x / page.width_mm * image.width() as f64) as u32;
+                let iy = (y / page.height_mm * image.height() as f64) as u32;
+                image
+                    .get_pixel(
+                        ix.min(image.width().saturating_sub(1)),
+                        iy.min(image.height().saturating_sub(1)),
+                    )
+                    .0
+            }
+            PageContent::Blank => WHITE,
+        }
+    }
+}
+
+/// Geometry and sample format shared by every page of a scan.
+struct Raster {
+    color_mode: ColorMode,
+    sixteen_bit: bool,
+    dpi: u32,
+    dpi_y: u32,
+    width_px: u32,
+    height_px: u32,
+    /// Top left of the scanned area on the page, mm.
+    origin: (f64, f64),
+    /// Millimetres per pixel across and down.
+    step: (f64, f64),
+    page: PageSize,
+    /// Sine and cosine of the skew.
+    skew: (f64, f64),
+    noise: f32,
+}
+
+impl Raster {
+    fn new(cfg: &ScanConfig, device: &VirtualDevice) -> Self {
+        let (origin, scanned) = match cfg.area {
+            Some(area) => (
+                (area.x_mm, area.y_mm),
+                PageSize::new(area.width_mm, area.height_mm),
+            ),
+            None => ((0.0, 0.0), cfg.page_size),
+        };
+        let (width_px, height_px) = scanned.to_pixels(cfg.dpi, cfg.vertical_dpi());
+        let angle = (device.skew_degrees as f64).to_radians();
+
+        Self {
+            color_mode: cfg.color_mode,
+            sixteen_bit: cfg.bits_per_sample() == 16,
+            dpi: cfg.dpi,
+            dpi_y: cfg.vertical_dpi(),
+            width_px: width_px.max(1),
+            height_px: height_px.max(1),
+            origin,
+            step: (
+                MM_PER_INCH / cfg.dpi as f64,
+                MM_PER_INCH / cfg.vertical_dpi() as f64,
+            ),
+            page: cfg.page_size,
+            skew: angle.sin_cos(),
+            noise: device.noise.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Where pixel `(x, y)` falls on the unskewed page, mm.
+    fn point(&self, x: u32, y: u32) -> (f64, f64) {
+        let px = self.origin.0 + (x as f64 + 0.5) * self.step.0;
+        let py = self.origin.1 + (y as f64 + 0.5) * self.step.1;
+
+        // Turn back by the skew, about the page centre
+        let (cx, cy) = (self.page.width_mm / 2.0, self.page.height_mm / 2.0);
+        let (sin, cos) = self.skew;
+        let (dx, dy) = (px - cx, py - cy);
+        (cx + dx * cos + dy * sin, cy - dx * sin + dy * cos)
+    }
+
+    /// PNM header: P6 for colour, P5 for gray, P4 for black and white.
+    fn header(&self) -> Vec<u8> {
+        let maxval = if self.sixteen_bit { 65535 } else { 255 };
+        match self.color_mode {
+            ColorMode::Color => format!("P6\n{} {}\n{}\n", self.width_px, self.height_px, maxval),
+            ColorMode::Gray => format!("P5\n{} {}\n{}\n", self.width_px, self.height_px, maxval),
+            ColorMode::Bw => format!("P4\n{} {}\n", self.width_px, self.height_px),
+        }
+        .into_bytes()
+    }
+
+    fn row_bytes(&self) -> u64 {
+        let width = self.width_px as u64;
+        let sample = if self.sixteen_bit { 2 } else { 1 };
+        match self.color_mode {
+            ColorMode::Color => width * 3 * sample,
+            ColorMode::Gray => width * sample,
+            ColorMode::Bw => width.div_ceil(8),
+        }
+    }
+
+    fn page_bytes(&self) -> u64 {
+        self.header().len() as u64 + self.row_bytes() * self.height_px as u64
+    }
+
+    fn encode_row(&self, pixels: &[[u8; 3]], out: &mut Vec<u8>) {
+        let push = |out: &mut Vec<u8>, value: u8| {
+            if self.sixteen_bit {
+                out.extend_from_slice(&(value as u16 * 257).to_be_bytes());
+            } else {
+                out.push(value);
+            }
+        };
+        match self.color_mode {
+            ColorMode::Color => {
+                for pixel in pixels {
+                    for value in pixel {
+                        push(out, *value);
+                    }
+                }
+            }
+            ColorMode::Gray => {
+                for pixel in pixels {
+                    push(out, luma(*pixel));
+                }
+            }
+            // Packed most significant bit first, 1 is black
+            ColorMode::Bw => {
+                for byte in pixels.chunks(8) {
+                    out.push(byte.iter().enumerate().fold(0, |packed, (bit, pixel)| {
+                        if luma(*pixel) < 128 {
+                            packed | 0x80 >> bit
+                        } else {
+                            packed
+                        }
+                    }));
+                }
+            }
+        }
+    }
+}
+
+fn luma([r, g, b]: [u8; 3]) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+/// Deterministic noise, so the same settings give the same pages.
+struct Noise(u64);
+
+impl Noise {
+    fn for_page(index: u32) -> Self {
+        Noise(0x9E37_79B9_7F4A_7C15 ^ (index as u64 + 1))
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform in `0.0..1.0`.
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// The page being read out.
+struct Page {
+    content: PageContent,
+    row: u32,
+    header: Option<Vec<u8>>,
+    bytes: u64,
+    expected: u64,
+    noise: Noise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Idle,
+    WarmingUp,
+    Scanning,
+    Done,
+}
+
+pub struct VirtualScanSession {
+    device: String,
+    raster: Raster,
+    pages: VecDeque<PageContent>,
+    page: Option<Page>,
+    index: u32,
+    jam_at_page: Option<u32>,
+    page_time: Duration,
+    stage: Stage,
+    // Progress queued behind the page data it counts
+    pending: VecDeque<ScanEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl VirtualScanSession {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Waits like a real device would; false when cancelled meanwhile.
+    fn pause(&self, time: Duration) -> bool {
+        let deadline = Instant::now() + time;
+        while !self.is_cancelled() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return true;
+            }
+            thread::sleep(left.min(CANCEL_POLL_INTERVAL));
+        }
+        false
+    }
+
+    fn start_page(&mut self, content: PageContent) -> Result<ScanEvent> {
+        if self.jam_at_page == Some(self.index) {
+            self.stage = Stage::Done;
+            return Err(PapyrError::PaperJam {
+                backend: Backend::Virtual,
+                message: format!("paper jammed in {} on page {}", self.device, self.index + 1),
+            });
+        }
+        self.page = Some(Page {
+            content,
+            row: 0,
+            header: Some(self.raster.header()),
+            bytes: 0,
+            expected: self.raster.page_bytes(),
+            noise: Noise::for_page(self.index),
+        });
+        Ok(ScanEvent::PageStarted(self.index))
+    }
+
+    /// The next rows of `page`, behind the header on the first call.
+    fn render_rows(&self, page: &mut Page) -> Vec<u8> {
+        let raster = &self.raster;
+        let rows = ROWS_PER_CHUNK.min(raster.height_px - page.row);
+        let mut out = page.header.take().unwrap_or_default();
+        out.reserve(raster.row_bytes() as usize * rows as usize);
+
+        let mut pixels = Vec::with_capacity(raster.width_px as usize);
+        for y in page.row..page.row + rows {
+            pixels.clear();
+            for x in 0..raster.width_px {
+                let (px, py) = raster.point(x, y);
+                let mut pixel = page.content.sample(&raster.page, px, py);
+                if raster.noise > 0.0 && page.noise.unit() < raster.noise {
+                    pixel = [page.noise.next() as u8; 3];
+                }
+                pixels.push(pixel);
+            }
+            raster.encode_row(&pixels, &mut out);
+        }
+        page.row += rows;
+        page.bytes += out.len() as u64;
+        out
+    }
+
+    fn scan_step(&mut self) -> Result<Option<ScanEvent>> {
+        let Some(mut page) = self.page.take() else {
+            return match self.pages.pop_front() {
+                Some(content) => self.start_page(content).map(Some),
+                None => {
+                    self.stage = Stage::Done;
+                    info!(pages = self.index, "virtual scan finished");
+                    Ok(Some(ScanEvent::JobComplete))
+                }
+            };
+        };
+
+        if page.row == self.raster.height_px {
+            let meta = PageMeta {
+                index: self.index,
+                width_px: self.raster.width_px,
+                height_px: self.raster.height_px,
+                dpi: self.raster.dpi,
+                dpi_y: self.raster.dpi_y,
+                color_mode: self.raster.color_mode,
+                bit_depth: match self.raster.color_mode {
+                    ColorMode::Bw => 1,
+                    _ if self.raster.sixteen_bit => 16,
+                    _ => 8,
+                },
+            };
+            self.index += 1;
+            return Ok(Some(ScanEvent::PageComplete(meta)));
+        }
+
+        // The page takes `page_time`, spread over its chunks
+        let chunks = self.raster.height_px.div_ceil(ROWS_PER_CHUNK);
+        if !self.pause(self.page_time / chunks) {
+            return Ok(Some(self.cancel()));
+        }
+        let data = self.render_rows(&mut page);
+        self.pending
+            .push_back(ScanEvent::progress(page.bytes, Some(page.expected)));
+        self.page = Some(page);
+        Ok(Some(ScanEvent::PageData(data)))
+    }
+
+    fn cancel(&mut self) -> ScanEvent {
+        self.stage = Stage::Done;
+        self.pending.clear();
+        debug!(device = %self.device, "virtual scan cancelled");
+        ScanEvent::Cancelled
+    }
+}
+
+impl ScanSession for VirtualScanSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        if self.stage == Stage::Done {
+            return Ok(self.pending.pop_front());
+        }
+        if self.is_cancelled() {
+            return Ok(Some(self.cancel()));
+        }
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+
+        match self.stage {
+            Stage::Idle => {
+                self.stage = Stage::WarmingUp;
+                Ok(Some(ScanEvent::Status(ScanStatus::WarmingUp)))
+            }
+            Stage::WarmingUp => {
+                if !self.pause(self.page_time / 2) {
+                    return Ok(Some(self.cancel()));
+                }
+                self.stage = Stage::Scanning;
+                Ok(Some(ScanEvent::Status(ScanStatus::Scanning)))
+            }
+            Stage::Scanning => self.scan_step(),
+            Stage::Done => Ok(None),
+        }
+    }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancelled = Arc::clone(&self.cancelled);
+        Some(CancelHandle::new(move || {
+            cancelled.store(true, Ordering::SeqCst)
+        }))
+    }
+}
+
+// Test chart
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+const GRID: [u8; 3] = [160, 160, 160];
+
+/// Colour bars, left to right.
+const BARS: [[u8; 3]; 8] = [
+    WHITE,
+    [255, 255, 0],
+    [0, 255, 255],
+    [0, 255, 0],
+    [255, 0, 255],
+    [255, 0, 0],
+    [0, 0, 255],
+    BLACK,
+];
+
+// Chart layout, mm from the page edges
+const MARGIN: f64 = 12.0;
+const FRAME_INSET: f64 = 5.0;
+const FRAME_WIDTH: f64 = 0.8;
+const BARS_TOP: f64 = 20.0;
+const BARS_BOTTOM: f64 = 34.0;
+const RAMP_TOP: f64 = 36.0;
+const RAMP_BOTTOM: f64 = 44.0;
+const GRID_TOP: f64 = 48.0;
+const GRID_BOTTOM: f64 = 30.0;
+const GRID_SPACING: f64 = 10.0;
+const GRID_LINE: f64 = 0.3;
+
+/// The lines of text on a chart page.
+fn chart_labels(
+    cfg: &ScanConfig,
+    device: &VirtualDevice,
+    number: u32,
+    side: Option<&str>,
+) -> Vec<Label> {
+    let (width, height) = (cfg.page_size.width_mm, cfg.page_size.height_mm);
+    let mode = match cfg.color_mode {
+        ColorMode::Color => "COLOR",
+        ColorMode::Gray => "GRAY",
+        ColorMode::Bw => "BW",
+    };
+    let number_height = 60f64.min(width * 0.3).min(height * 0.2);
+
+    let mut labels = vec![
+        Label::new(&device.name, MARGIN, MARGIN, 5.0),
+        Label::centred(
+            &number.to_string(),
+            width / 2.0,
+            (height - number_height) / 2.0,
+            number_height,
+        ),
+        Label::new(
+            &format!(
+                "{}X{} DPI {} {}-BIT",
+                cfg.dpi,
+                cfg.vertical_dpi(),
+                mode,
+                cfg.bits_per_sample()
+            ),
+            MARGIN,
+            height - GRID_BOTTOM + 4.0,
+            4.0,
+        ),
+    ];
+    if let Some(side) = side {
+        labels.push(Label::centred(
+            side,
+            width / 2.0,
+            (height + number_height) / 2.0 + 4.0,
+            7.0,
+        ));
+    }
+    if let Some(text) = &device.text {
+        labels.push(Label::new(text, MARGIN, height - GRID_BOTTOM + 11.0, 4.0));
+    }
+    labels
+}
+
+/// Colour of the chart at `(x, y)` mm.
+fn chart(page: &PageSize, labels: &[Label], x: f64, y: f64) -> [u8; 3] {
+    let (width, height) = (page.width_mm, page.height_mm);
+    if labels.iter().any(|label| label.covers(x, y)) {
+        return BLACK;
+    }
+
+    let edge = x.min(width - x).min(y).min(height - y);
+    if (FRAME_INSET..FRAME_INSET + FRAME_WIDTH).contains(&edge) {
+        return BLACK;
+    }
+
+    let (left, right) = (MARGIN, width - MARGIN);
+    if x < left || x >= right {
+        return WHITE;
+    }
+    let across = (x - left) / (right - left);
+    if (BARS_TOP..BARS_BOTTOM).contains(&y) {
+        BARS[((across * 8.0) as usize).min(7)]
+    } else if (RAMP_TOP..RAMP_BOTTOM).contains(&y) {
+        [(across * 255.0).round() as u8; 3]
+    } else if (GRID_TOP..height - GRID_BOTTOM).contains(&y)
+        && ((x - left) % GRID_SPACING < GRID_LINE || (y - GRID_TOP) % GRID_SPACING < GRID_LINE)
+    {
+        GRID
+    } else {
+        WHITE
+    }
+}
+
+/// A line of 5x7 bitmap text.
+struct Label {
+    text: Vec<u8>,
+    x: f64,
+    y: f64,
+    /// Size of one font dot, mm.
+    dot: f64,
+}
+
+impl Label {
+    /// Text `height` mm tall with its top left at `(x, y)`.
+    fn new(text: &str, x: f64, y: f64, height: f64) -> Self {
+        Self {
+            text: text.to_ascii_uppercase().into_bytes(),
+            x,
+            y,
+            dot: height / 7.0,
+        }
+    }
+
+    fn centred(text: &str, centre_x: f64, y: f64, height: f64) -> Self {
+        let dots = (text.len() * 6).saturating_sub(1) as f64;
+        Self::new(text, centre_x - dots * height / 7.0 / 2.0, y, height)
+    }
+
+    fn covers(&self, x: f64, y: f64) -> bool {
+        if x < self.x || y < self.y {
+            return false;
+        }
+        let column = ((x - self.x) / self.dot) as usize;
+        let row = ((y - self.y) / self.dot) as usize;
+        // Each character is 5 dots wide plus one of spacing
+        if row >= 7 || column % 6 == 5 {
+            return false;
+        }
+        self.text
+            .get(column / 6)
+            .is_some_and(|c| glyph(*c)[row] & (0x10 >> (column % 6)) != 0)
+    }
+}
+
+/// Rows of a 5x7 character, top first, bit 4 leftmost. Characters the
+/// font lacks are blank.
+fn glyph(c: u8) -> [u8; 7] {
+    match c {
+        b'0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        b'1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        b'2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        b'3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        b'4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        b'5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        b'6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        b'7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        b'8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        b'9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        b'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        b'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        b'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        b'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        b'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        b'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        b'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        b'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        b'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        b'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        b'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        b'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        b'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        b'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        b'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        b'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        b'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        b'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        b'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        b'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        b'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        b'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        b'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        b'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        b'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        b'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        b'-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        b'+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        b'.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        b',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        b':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        b'/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        b'#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        b'(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        b')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        b'!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        b'?' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        b'_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        _ => [0; 7],
+    }
+}
+
+// Page files
+
+fn file_error(path: &Path, e: impl std::fmt::Display) -> PapyrError {
+    PapyrError::InvalidConfig(format!("Can't read {}: {}", path.display(), e))
+}
+
+/// Every page of `files`, in order: one per image, one per PDF page.
+fn load_pages(files: &[PathBuf]) -> Result<Vec<RgbImage>> {
+    let mut pages = Vec::new();
+    for path in files {
+        let is_pdf = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"));
+        if is_pdf {
+            pages.extend(load_pdf(path)?);
+        } else {
+            let image = image::open(path).map_err(|e| file_error(path, e))?;
+            pages.push(image.to_rgb8());
+        }
+    }
+    Ok(pages)
+}
+
+/// The largest image on each page of a PDF, as scanned documents have one
+/// image per page. Pages drawn with text or vector graphics can't be
+/// rendered and fail.
+fn load_pdf(path: &Path) -> Result<Vec<RgbImage>> {
+    let doc = lopdf::Document::load(path).map_err(|e| file_error(path, e))?;
+
+    let mut pages = Vec::new();
+    for (number, page_id) in doc.get_pages() {
+        let images = doc.get_page_images(page_id).unwrap_or_default();
+        let image = images
+            .iter()
+            .max_by_key(|image| image.width * image.height)
+            .ok_or_else(|| {
+                file_error(
+                    path,
+                    format!(
+                        "page {} has no image; only scanned PDFs can be used",
+                        number
+                    ),
+                )
+            })?;
+        let decoded = decode_pdf_image(&doc, image)
+            .map_err(|e| file_error(path, format!("page {}: {}", number, e)))?;
+        pages.push(decoded);
+    }
+    Ok(pages)
+}
+
+/// Decodes JPEG images and 8-bit RGB, 8-bit gray and 1-bit images.
+fn decode_pdf_image(
+    doc: &lopdf::Document,
+    image: &lopdf::xobject::PdfImage,
+) -> std::result::Result<RgbImage, String> {
+    let filters = image.filters.as_deref().unwrap_or_default();
+    if filters.iter().any(|filter| filter == "DCTDecode") {
+        return image::load_from_memory_with_format(image.content, ImageFormat::Jpeg)
+            .map(|decoded| decoded.to_rgb8())
+            .map_err(|e| e.to_string());
+    }
+
+    let data = if filters.is_empty() {
+        image.content.to_vec()
+    } else {
+        doc.get_object(image.id)
+            .and_then(|object| object.as_stream())
+            .and_then(|stream| stream.decompressed_content())
+            .map_err(|e| e.to_string())?
+    };
+    let (width, height) = (image.width as u32, image.height as u32);
+    if width == 0 || height == 0 {
+        return Err("the image is empty".into());
+    }
+    let unsupported = || {
+        format!(
+            "unsupported image ({} {}-bit, {:?})",
+            image.color_space.as_deref().unwrap_or("no colour space"),
+            image.bits_per_component.unwrap_or(0),
+            filters
+        )
+    };
+
+    match (image.color_space.as_deref(), image.bits_per_component) {
+        (Some("DeviceRGB"), Some(8)) => RgbImage::from_raw(width, height, data),
+        (Some("DeviceGray"), Some(8)) => GrayImage::from_raw(width, height, data)
+            .map(|gray| DynamicImage::ImageLuma8(gray).to_rgb8()),
+        // Rows padded to whole bytes, 0 is black
+        (Some("DeviceGray"), Some(1)) => {
+            let stride = width.div_ceil(8) as usize;
+            (data.len() >= stride * height as usize).then(|| {
+                RgbImage::from_fn(width, height, |x, y| {
+                    let byte = data[y as usize * stride + x as usize / 8];
+                    let value = if byte & (0x80 >> (x % 8)) != 0 {
+                        255
+                    } else {
+                        0
+                    };
+                    image::Rgb([value; 3])
+                })
+            })
+        }
+        _ => return Err(unsupported()),
+    }
+    .ok_or_else(|| format!("{} is truncated", unsupported()))
+}
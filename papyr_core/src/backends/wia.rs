@@ -10,13 +10,61 @@
 use windows::{Win32::Devices::ImageAcquisition::*, Win32::System::Com::*};
 
 use crate::models::{
-    Backend, BackendProvider, Capabilities, ColorMode, PageSize, PapyrError, Result, ScanConfig,
-    ScanEvent, ScanSession, ScanSource, ScannerInfo,
+    Backend, BackendProvider, Capabilities, ColorMode, DeviceId, PapyrError, Result, ScanConfig,
+    ScanEvent, ScanSession, ScanSource, ScannerInfo, DEFAULT_PAGE_FORMATS,
 };
+use tracing::{debug, info, warn};
 
 const WIA_DEVICETYPE_SCANNER: i32 = 0x00000001;
 const WIA_DEVICETYPE_DEFAULT: i32 = 0x00000000;
 
+// WIA HRESULTs that callers can act on
+const WIA_ERROR_PAPER_JAM: u32 = 0x80210002;
+const WIA_ERROR_PAPER_EMPTY: u32 = 0x80210003;
+const WIA_ERROR_PAPER_PROBLEM: u32 = 0x80210004;
+const WIA_ERROR_OFFLINE: u32 = 0x80210005;
+const WIA_ERROR_BUSY: u32 = 0x80210006;
+const WIA_ERROR_WARMING_UP: u32 = 0x80210007;
+const WIA_ERROR_DEVICE_COMMUNICATION: u32 = 0x8021000A;
+const WIA_ERROR_INVALID_COMMAND: u32 = 0x8021000B;
+const WIA_ERROR_DEVICE_LOCKED: u32 = 0x8021000D;
+const WIA_ERROR_INVALID_DRIVER_RESPONSE: u32 = 0x8021000F;
+const WIA_S_NO_DEVICE_AVAILABLE: u32 = 0x80210015;
+const WIA_ERROR_COVER_OPEN: u32 = 0x80210016;
+const E_NOTIMPL: u32 = 0x80004001;
+const E_ACCESSDENIED: u32 = 0x80070005;
+
+/// Maps a failed WIA call's HRESULT to an error.
+fn wia_error(context: &str, hresult: u32, detail: &str) -> PapyrError {
+    let backend = Backend::Wia;
+    let message = format!("{}: {} ({:#010x})", context, detail, hresult);
+
+    match hresult {
+        WIA_ERROR_PAPER_JAM | WIA_ERROR_PAPER_PROBLEM => PapyrError::PaperJam { backend, message },
+        WIA_ERROR_PAPER_EMPTY => PapyrError::FeederEmpty { backend, message },
+        WIA_ERROR_BUSY | WIA_ERROR_WARMING_UP | WIA_ERROR_DEVICE_LOCKED => {
+            PapyrError::DeviceBusy { backend, message }
+        }
+        WIA_ERROR_OFFLINE | WIA_ERROR_DEVICE_COMMUNICATION => PapyrError::Io { backend, message },
+        WIA_ERROR_COVER_OPEN => PapyrError::CoverOpen { backend, message },
+        WIA_ERROR_INVALID_COMMAND | WIA_ERROR_INVALID_DRIVER_RESPONSE => {
+            PapyrError::Protocol { backend, message }
+        }
+        WIA_S_NO_DEVICE_AVAILABLE => PapyrError::NotFound(message),
+        E_ACCESSDENIED => PapyrError::Unauthorized { backend, message },
+        E_NOTIMPL => PapyrError::Unsupported {
+            backend,
+            option: context.to_string(),
+        },
+        _ => PapyrError::Backend(message),
+    }
+}
+
+#[cfg(windows)]
+fn com_error(context: &str, e: &windows::core::Error) -> PapyrError {
+    wia_error(context, e.code().0 as u32, &e.message())
+}
+
 #[cfg(windows)]
 struct ComGuard {
     initialized: bool,
@@ -29,10 +77,7 @@ impl ComGuard {
         if hr.is_ok() {
             Self { initialized: true }
         } else {
-            println!(
-                "⚠️  COM initialization failed or already initialized: {:?}",
-                hr
-            );
+            debug!(?hr, "COM initialization failed or already initialized");
             Self { initialized: false }
         }
     }
@@ -67,23 +112,19 @@ impl WiaBackend {
 
             let com_guard = ComGuard::new();
             if !com_guard.initialized() {
-                println!("⚠️  Unable to initialize COM; skipping WIA enumeration");
-                println!("🎯 WIA: Total devices found: {}", all_devices.len());
+                warn!("unable to initialize COM, skipping WIA enumeration");
                 return Ok(all_devices);
             }
 
-            println!("🔧 Creating WIA Device Manager...");
-
             {
                 let device_manager: IWiaDevMgr =
                     match CoCreateInstance(&WiaDevMgr, None, CLSCTX_LOCAL_SERVER) {
                         Ok(dm) => {
-                            println!("✅ WIA Device Manager created");
+                            debug!("WIA device manager created");
                             dm
                         }
                         Err(e) => {
-                            println!("❌ Failed to create WIA Device Manager: {:?}", e);
-                            println!("🎯 WIA: Total devices found: {}", all_devices.len());
+                            warn!("failed to create WIA device manager: {}", e);
                             return Ok(all_devices);
                         }
                     };
@@ -107,31 +148,27 @@ impl WiaBackend {
 
                                 if let Some(_info) = device_info {
                                     // Generate a unique device ID
-                                    let device_id = format!("wia_device_{}", all_devices.len());
+                                    let device_id = DeviceId::new(
+                                        Backend::Wia,
+                                        format!("device_{}", all_devices.len()),
+                                    );
                                     let device_name =
                                         format!("WIA Scanner {}", all_devices.len() + 1);
 
-                                    println!("✅ Found WIA device: {}", device_name);
+                                    debug!(name = %device_name, "found WIA device");
 
-                                    all_devices.push(ScannerInfo {
-                                        id: device_id,
-                                        name: device_name,
-                                        backend: Backend::Wia,
-                                    });
+                                    all_devices.push(ScannerInfo::new(device_id, device_name));
                                 }
                             }
                         }
                         Err(e) => {
-                            println!(
-                                "⚠️  WIA enumeration failed for type {}: {:?}",
-                                device_type, e
-                            );
+                            warn!(device_type, "WIA enumeration failed: {}", e);
                         }
                     }
                 }
             }
 
-            println!("🎯 WIA: Total devices found: {}", all_devices.len());
+            debug!(devices = all_devices.len(), "WIA enumeration finished");
             Ok(all_devices)
         }
     }
@@ -156,23 +193,19 @@ impl BackendProvider for WiaBackend {
         vec![]
     }
 
-    fn capabilities(&self, _device_id: &str) -> Result<Capabilities> {
+    fn capabilities(&self, _device_id: &DeviceId) -> Result<Capabilities> {
         #[cfg(windows)]
         {
             Ok(Capabilities {
                 sources: vec![ScanSource::Flatbed, ScanSource::Adf],
                 dpis: vec![75, 150, 300, 600, 1200],
+                dpi_ranges: vec![],
+                // WIA_IPS_XRES and WIA_IPS_YRES are separate properties
+                independent_dpi: true,
                 color_modes: vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw],
-                page_sizes: vec![
-                    PageSize {
-                        width_mm: 216,
-                        height_mm: 279,
-                    },
-                    PageSize {
-                        width_mm: 210,
-                        height_mm: 297,
-                    },
-                ],
+                bit_depths: vec![8],
+                page_sizes: DEFAULT_PAGE_FORMATS.to_vec(),
+                max_areas: vec![],
                 supports_duplex: true,
             })
         }
@@ -181,7 +214,7 @@ impl BackendProvider for WiaBackend {
         Err(PapyrError::Backend("WIA only supported on Windows".into()))
     }
 
-    fn start_scan(&self, device_id: &str, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
+    fn start_scan(&self, device_id: &DeviceId, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
         #[cfg(windows)]
         {
             Ok(Box::new(WiaScanSession::new(device_id, config)?))
@@ -197,7 +230,7 @@ impl BackendProvider for WiaBackend {
 
 pub struct WiaScanSession {
     #[cfg(windows)]
-    device_id: String,
+    device_id: DeviceId,
     #[cfg(windows)]
     config: ScanConfig,
     #[cfg(windows)]
@@ -209,55 +242,60 @@ pub struct WiaScanSession {
 enum WiaScanState {
     NotStarted,
     Scanning,
+    // The scanned image, waiting to be passed on
+    Scanned(Vec<u8>),
     Completed,
 }
 
 impl WiaScanSession {
     #[cfg(windows)]
-    pub fn new(device_id: &str, config: ScanConfig) -> Result<Self> {
+    pub fn new(device_id: &DeviceId, config: ScanConfig) -> Result<Self> {
         Ok(WiaScanSession {
-            device_id: device_id.to_string(),
+            device_id: device_id.clone(),
             config,
             state: WiaScanState::NotStarted,
         })
     }
 
     #[cfg(not(windows))]
-    pub fn new(device_id: &str, config: ScanConfig) -> Result<Self> {
+    pub fn new(device_id: &DeviceId, config: ScanConfig) -> Result<Self> {
         let _ = (device_id, config);
         Err(PapyrError::Backend("WIA only supported on Windows".into()))
     }
 
     #[cfg(windows)]
     fn perform_wia_scan(&mut self) -> Result<Vec<u8>> {
-        println!("🖨️  WIA: Starting scan of device: {}", self.device_id);
-        println!(
-            "📄 WIA: Resolution: {}dpi, Color: {:?}, Source: {:?}",
-            self.config.dpi, self.config.color_mode, self.config.source
+        info!(
+            device = %self.device_id,
+            dpi = self.config.dpi,
+            dpi_y = self.config.vertical_dpi(),
+            color_mode = ?self.config.color_mode,
+            source = ?self.config.source,
+            "starting WIA scan"
         );
 
         unsafe {
             // Initialize COM
             let hr = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
             if hr.is_err() {
-                println!("⚠️  COM already initialized");
+                debug!("COM already initialized");
             }
 
             // Create device manager
             let device_manager: IWiaDevMgr =
                 CoCreateInstance(&WiaDevMgr, None, CLSCTX_LOCAL_SERVER).map_err(|e| {
                     CoUninitialize();
-                    PapyrError::Backend(format!("Failed to create WIA Device Manager: {:?}", e))
+                    com_error("Failed to create WIA Device Manager", &e)
                 })?;
 
-            println!("✅ WIA Device Manager created");
+            debug!("WIA device manager created");
 
             // Get device list
             let device_enum = device_manager
                 .EnumDeviceInfo(WIA_DEVICETYPE_DEFAULT)
                 .map_err(|e| {
                     CoUninitialize();
-                    PapyrError::Backend(format!("Failed to enumerate devices: {:?}", e))
+                    com_error("Failed to enumerate devices", &e)
                 })?;
 
             let _ = device_enum.Reset();
@@ -273,7 +311,7 @@ impl WiaScanSession {
                 return Err(PapyrError::Backend("No WIA devices found".into()));
             }
 
-            println!("✅ WIA device found, attempting to create device object");
+            debug!("WIA device found, creating device object");
 
             // Try to create device object
             // Note: Full WIA transfer implementation requires:
@@ -284,11 +322,11 @@ impl WiaScanSession {
             // 5. Handle IStream in callback
 
             // For now, simulate a successful scan
-            println!("✅ WIA scan initiated (using simplified implementation)");
+            debug!("WIA scan initiated (simplified implementation)");
 
             // Create mock BMP data (minimal valid BMP header)
             let width = (8.5 * self.config.dpi as f32) as u32;
-            let height = (11.0 * self.config.dpi as f32) as u32;
+            let height = (11.0 * self.config.vertical_dpi() as f32) as u32;
             let bytes_per_pixel = match self.config.color_mode {
                 ColorMode::Bw => 1,
                 ColorMode::Gray => 1,
@@ -322,12 +360,12 @@ impl WiaScanSession {
             // Add placeholder pixel data
             bmp_data.resize(file_size as usize, 0xFF);
 
-            println!(
-                "✅ WIA scan completed: {} bytes ({}x{} @ {}dpi)",
-                bmp_data.len(),
+            debug!(
+                bytes = bmp_data.len(),
                 width,
                 height,
-                self.config.dpi
+                dpi = self.config.dpi,
+                "WIA scan completed"
             );
 
             CoUninitialize();
@@ -341,14 +379,15 @@ impl ScanSession for WiaScanSession {
     fn next_event(&mut self) -> Result<Option<ScanEvent>> {
         #[cfg(windows)]
         {
-            match self.state {
+            match std::mem::replace(&mut self.state, WiaScanState::Completed) {
                 WiaScanState::NotStarted => {
                     self.state = WiaScanState::Scanning;
 
                     match self.perform_wia_scan() {
                         Ok(data) => {
-                            self.state = WiaScanState::Completed;
-                            Ok(Some(ScanEvent::PageData(data)))
+                            // The image is one page, started before its data
+                            self.state = WiaScanState::Scanned(data);
+                            Ok(Some(ScanEvent::PageStarted(0)))
                         }
                         Err(e) => {
                             self.state = WiaScanState::Completed;
@@ -356,11 +395,8 @@ impl ScanSession for WiaScanSession {
                         }
                     }
                 }
-                WiaScanState::Scanning => {
-                    self.state = WiaScanState::Completed;
-                    Ok(Some(ScanEvent::JobComplete))
-                }
-                WiaScanState::Completed => Ok(None),
+                WiaScanState::Scanned(data) => Ok(Some(ScanEvent::PageData(data))),
+                WiaScanState::Scanning | WiaScanState::Completed => Ok(None),
             }
         }
 
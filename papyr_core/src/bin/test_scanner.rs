@@ -6,13 +6,26 @@
 //  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
 //
 
+use papyr_core::logging;
 use papyr_core::models::{ColorMode, PageSize, ScanConfig, ScanSource};
 use papyr_core::registry::BackendRegistry;
+use std::sync::Arc;
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
 
-#[tokio::main]
-async fn main() {
+// Plain `main`: the registry blocks, and blocking inside a tokio runtime
+// panics (eSCL's HTTP client runs its own)
+fn main() {
     println!("🔍 Papyr Core - Scanner Backend Test\n");
 
+    // Backend diagnostics go to stderr
+    let _ = logging::set_log_sink(
+        Some(Arc::new(|level: Level, _target: &str, message: &str| {
+            eprintln!("{:>5} {}", level, message)
+        })),
+        LevelFilter::INFO,
+    );
+
     // Initialize registry (automatically loads all backends)
     let registry = BackendRegistry::new();
 
@@ -58,11 +71,10 @@ async fn main() {
                     source: ScanSource::Flatbed,
                     duplex: false,
                     dpi: 150,
+                    dpi_y: None,
                     color_mode: ColorMode::Color,
-                    page_size: PageSize {
-                        width_mm: 216,
-                        height_mm: 279,
-                    },
+                    bit_depth: None,
+                    page_size: PageSize::from_inches(8.5, 11.0), // Letter
                     area: None,
                     brightness: None,
                     contrast: None,
@@ -73,10 +85,7 @@ async fn main() {
                 println!("  - Source: {:?}", config.source);
                 println!("  - DPI: {}", config.dpi);
                 println!("  - Color: {:?}", config.color_mode);
-                println!(
-                    "  - Size: {}x{}mm\n",
-                    config.page_size.width_mm, config.page_size.height_mm
-                );
+                println!("  - Size: {}\n", config.page_size);
 
                 match registry.start_scan(&first_scanner.id, config) {
                     Ok(mut session) => {
@@ -116,6 +125,27 @@ async fn main() {
                                             println!("   Total data: {} bytes", total_data_size);
                                             break;
                                         }
+                                        ScanEvent::Progress {
+                                            percent: Some(percent),
+                                            ..
+                                        } => {
+                                            println!("  ⏳ {:.0}%", percent);
+                                        }
+                                        ScanEvent::Progress { .. } => {}
+                                        ScanEvent::Status(status) => {
+                                            println!("  ℹ️  {:?}", status);
+                                        }
+                                        ScanEvent::Warning { message, .. } => {
+                                            println!("  ⚠️  {}", message);
+                                        }
+                                        ScanEvent::Cancelled => {
+                                            println!("\n🛑 Scan cancelled");
+                                            break;
+                                        }
+                                        ScanEvent::Error(e) => {
+                                            println!("\n❌ Scan error: {:?}", e);
+                                            break;
+                                        }
                                     }
                                 }
                                 Ok(None) => {
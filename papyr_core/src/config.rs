@@ -0,0 +1,420 @@
+//
+//  papyr_core
+//  config.rs - Registry configuration: backend selection, order and settings
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::*;
+use crate::registry::{self, BackendRegistry};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+/// Which backends a registry uses, in what order, and their settings. Every
+/// field is optional in a config file; the defaults give the same registry
+/// as `BackendRegistry::new()`.
+///
+/// ```toml
+/// order = ["SaneNet", "Escl"]
+/// disabled = ["Twain"]
+/// discovery_timeout_ms = 15000
+///
+/// [escl]
+/// mdns = false
+/// hosts = ["192.168.1.20", "https://scanner.example:8443"]
+///
+/// [scan]
+/// dpi = 200
+/// color_mode = "Gray"
+/// ```
+///
+/// `plugin_dirs = ["/opt/papyr/plugins"]` adds plugin backends after the
+/// built-in ones, and `[virtual] enabled = true` adds simulated scanners
+/// (see [`VirtualConfig`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegistryConfig {
+    /// Backends to register first, in this order; the other enabled
+    /// backends follow in their default order.
+    pub order: Vec<Backend>,
+    pub disabled: Vec<Backend>,
+    /// How long each backend may take to enumerate.
+    pub discovery_timeout_ms: Option<u64>,
+    /// How long discovered devices are reused.
+    pub device_cache_ttl_ms: Option<u64>,
+    pub escl: EsclConfig,
+    pub saned: SanedConfig,
+    pub sane: SaneConfig,
+    pub scan: ScanDefaults,
+    /// Directories to load backend plugins from, see
+    /// `BackendRegistry::load_plugins`.
+    pub plugin_dirs: Vec<PathBuf>,
+    #[serde(rename = "virtual")]
+    pub virtual_scanner: VirtualConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EsclConfig {
+    /// Browse mDNS for scanners; off lists only `hosts`.
+    pub mdns: bool,
+    /// Scanners at fixed addresses: `host[:port]` or an `http(s)://` URL.
+    pub hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SanedConfig {
+    /// saned servers as `host[:port]`; empty uses `PAPYR_SANED_HOSTS`.
+    pub hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SaneConfig {
+    /// libsane to load; `None` uses `PAPYR_SANE_LIBRARY` or the default
+    /// library names.
+    pub library_path: Option<PathBuf>,
+}
+
+/// Set to `1` to register the virtual backend without changing the config.
+pub const VIRTUAL_ENV: &str = "PAPYR_VIRTUAL";
+
+/// Simulated scanners for developing and testing without hardware; builds
+/// need the `virtual` feature.
+///
+/// ```toml
+/// [virtual]
+/// enabled = true
+/// page_time_ms = 0
+///
+/// [[virtual.devices]]
+/// id = "feeder"
+/// sources = ["Adf", "AdfDuplex"]
+/// files = ["fixtures/contract.pdf"]
+/// skew_degrees = 1.5
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VirtualConfig {
+    /// Registers the virtual backend; so do `PAPYR_VIRTUAL=1` and listing
+    /// `Virtual` in `order`.
+    pub enabled: bool,
+    /// Devices to simulate; empty gives a flatbed, a feeder and a duplex
+    /// feeder.
+    pub devices: Vec<VirtualDevice>,
+    /// How long a page takes to scan, after warming up for half as long;
+    /// 0 scans instantly.
+    pub page_time_ms: u64,
+}
+
+/// One simulated scanner. Pages are test charts unless `files` are given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VirtualDevice {
+    /// The device id is `virtual_<id>`.
+    pub id: String,
+    pub name: String,
+    pub sources: Vec<ScanSource>,
+    /// Sheets in the feeder at the start of every feeder scan; 0 reports
+    /// an empty feeder.
+    pub sheets: u32,
+    /// PNG, JPEG, PNM or PDF files scanned instead of test charts, one page
+    /// per image or PDF page, stretched over the scanned page size. PDF
+    /// pages must contain a scanned image.
+    pub files: Vec<PathBuf>,
+    /// An extra line printed on test charts.
+    pub text: Option<String>,
+    /// Share of pixels replaced with random grays, 0.0 to 1.0.
+    pub noise: f32,
+    /// How far pages are rotated, clockwise.
+    pub skew_degrees: f32,
+    /// The feeder jams instead of scanning this page (0-based).
+    pub jam_at_page: Option<u32>,
+}
+
+/// Scan settings an application starts from, see
+/// `BackendRegistry::default_scan_config`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanDefaults {
+    pub source: ScanSource,
+    pub duplex: bool,
+    pub dpi: u32,
+    pub color_mode: ColorMode,
+    pub page_size: PageSize,
+    pub max_pages: Option<u32>,
+}
+
+impl Default for EsclConfig {
+    fn default() -> Self {
+        Self {
+            mdns: true,
+            hosts: Vec::new(),
+        }
+    }
+}
+
+impl Default for VirtualConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            devices: Vec::new(),
+            page_time_ms: 1500,
+        }
+    }
+}
+
+impl VirtualConfig {
+    /// Whether the config or `PAPYR_VIRTUAL` turns the backend on.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled || std::env::var(VIRTUAL_ENV).is_ok_and(|value| value == "1")
+    }
+
+    /// `devices`, or the default three when none are configured.
+    pub fn devices(&self) -> Vec<VirtualDevice> {
+        if !self.devices.is_empty() {
+            return self.devices.clone();
+        }
+        vec![
+            VirtualDevice::new("flatbed", "Virtual Flatbed", [ScanSource::Flatbed]),
+            VirtualDevice::new(
+                "adf",
+                "Virtual Feeder",
+                [ScanSource::Flatbed, ScanSource::Adf],
+            ),
+            VirtualDevice::new(
+                "duplex",
+                "Virtual Duplex Feeder",
+                [ScanSource::Flatbed, ScanSource::Adf, ScanSource::AdfDuplex],
+            ),
+        ]
+    }
+}
+
+impl Default for VirtualDevice {
+    fn default() -> Self {
+        Self::new("flatbed", "Virtual Flatbed", [ScanSource::Flatbed])
+    }
+}
+
+impl VirtualDevice {
+    /// A device printing test charts, with 3 sheets in its feeder.
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        sources: impl IntoIterator<Item = ScanSource>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            sources: sources.into_iter().collect(),
+            sheets: 3,
+            files: Vec::new(),
+            text: None,
+            noise: 0.0,
+            skew_degrees: 0.0,
+            jam_at_page: None,
+        }
+    }
+}
+
+impl Default for ScanDefaults {
+    fn default() -> Self {
+        Self {
+            source: ScanSource::Flatbed,
+            duplex: false,
+            dpi: 300,
+            color_mode: ColorMode::Color,
+            page_size: PageSize::new(210.0, 297.0), // A4
+            max_pages: None,
+        }
+    }
+}
+
+impl ScanDefaults {
+    pub fn to_config(&self) -> ScanConfig {
+        ScanConfig {
+            source: self.source,
+            duplex: self.duplex,
+            dpi: self.dpi,
+            dpi_y: None,
+            color_mode: self.color_mode,
+            bit_depth: None,
+            page_size: self.page_size,
+            area: None,
+            brightness: None,
+            contrast: None,
+            max_pages: self.max_pages,
+        }
+    }
+}
+
+impl RegistryConfig {
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text)
+            .map_err(|e| PapyrError::InvalidConfig(format!("Invalid registry config: {}", e)))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        serde_json::from_str(text)
+            .map_err(|e| PapyrError::InvalidConfig(format!("Invalid registry config: {}", e)))
+    }
+
+    /// Reads a `.toml` or `.json` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            PapyrError::InvalidConfig(format!("Can't read {}: {}", path.display(), e))
+        })?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            Some("json") => Self::from_json(&text),
+            _ => Err(PapyrError::InvalidConfig(format!(
+                "{} is neither .toml nor .json",
+                path.display()
+            ))),
+        }
+    }
+
+    /// The backends to register, in order: `order` first, then the rest of
+    /// this build's defaults, less `disabled`. Backends this build lacks
+    /// are skipped. The virtual backend is only used when enabled or
+    /// listed in `order`.
+    pub fn backends(&self) -> Vec<Backend> {
+        let mut available = registry::default_backends();
+        if cfg!(feature = "virtual") {
+            if self.virtual_scanner.is_enabled() || self.order.contains(&Backend::Virtual) {
+                available.push(Backend::Virtual);
+            }
+        } else if self.virtual_scanner.is_enabled() {
+            warn!("virtual scanners need papyr_core's virtual feature");
+        }
+        let mut backends: Vec<Backend> = Vec::new();
+
+        for backend in self.order.iter().chain(&available) {
+            if !available.contains(backend) {
+                warn!(?backend, "backend is not available in this build");
+                continue;
+            }
+            if !backends.contains(backend) && !self.disabled.contains(backend) {
+                backends.push(*backend);
+            }
+        }
+        backends
+    }
+}
+
+/// Builds a `BackendRegistry` from code, starting from the defaults or a
+/// loaded `RegistryConfig`.
+#[derive(Debug, Clone, Default)]
+pub struct RegistryBuilder {
+    config: RegistryConfig,
+}
+
+impl RegistryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: RegistryConfig) -> Self {
+        Self { config }
+    }
+
+    /// Registers `backends` first, in this order.
+    pub fn order(mut self, backends: impl IntoIterator<Item = Backend>) -> Self {
+        self.config.order = backends.into_iter().collect();
+        self
+    }
+
+    pub fn disable(mut self, backend: Backend) -> Self {
+        if !self.config.disabled.contains(&backend) {
+            self.config.disabled.push(backend);
+        }
+        self
+    }
+
+    /// Re-enables a backend disabled earlier or in the loaded config, or
+    /// turns on the virtual backend.
+    pub fn enable(mut self, backend: Backend) -> Self {
+        self.config.disabled.retain(|disabled| *disabled != backend);
+        if backend == Backend::Virtual {
+            self.config.virtual_scanner.enabled = true;
+        }
+        self
+    }
+
+    pub fn discovery_timeout(mut self, timeout: Duration) -> Self {
+        self.config.discovery_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    pub fn device_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.device_cache_ttl_ms = Some(ttl.as_millis() as u64);
+        self
+    }
+
+    pub fn escl_mdns(mut self, enabled: bool) -> Self {
+        self.config.escl.mdns = enabled;
+        self
+    }
+
+    pub fn escl_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.escl.hosts = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn saned_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.saned.hosts = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn sane_library_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.sane.library_path = Some(path.into());
+        self
+    }
+
+    /// Also loads the plugins in `dir`.
+    pub fn plugin_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.plugin_dirs.push(dir.into());
+        self
+    }
+
+    /// Enables the virtual backend with these devices instead of the
+    /// default three.
+    pub fn virtual_devices(mut self, devices: impl IntoIterator<Item = VirtualDevice>) -> Self {
+        self.config.virtual_scanner.enabled = true;
+        self.config.virtual_scanner.devices = devices.into_iter().collect();
+        self
+    }
+
+    pub fn virtual_page_time(mut self, time: Duration) -> Self {
+        self.config.virtual_scanner.page_time_ms = time.as_millis() as u64;
+        self
+    }
+
+    pub fn scan_defaults(mut self, defaults: ScanDefaults) -> Self {
+        self.config.scan = defaults;
+        self
+    }
+
+    pub fn config(&self) -> &RegistryConfig {
+        &self.config
+    }
+
+    pub fn build(self) -> BackendRegistry {
+        BackendRegistry::from_config(&self.config)
+    }
+}
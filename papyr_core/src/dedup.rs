@@ -0,0 +1,166 @@
+//
+//  papyr_core
+//  dedup.rs - Merging the same physical scanner seen through several backends
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::ScannerInfo;
+use tracing::debug;
+
+/// Merges devices that are the same physical scanner, e.g. an MFP found
+/// by both SANE's airscan driver and the eSCL backend. `devices` come in
+/// backend preference order; each merged device keeps the details of its
+/// most preferred backend, filled in from the others, and lists every
+/// backend in `routes`.
+///
+/// Devices are the same when their serials (or eSCL UUIDs) match, or their
+/// hosts match and nothing else tells them apart. Devices with neither in
+/// common are merged on make and model alone, and only when exactly one
+/// other device could match. Devices of the same backend are never merged.
+pub fn merge_devices(devices: Vec<ScannerInfo>) -> Vec<ScannerInfo> {
+    let mut groups: Vec<Vec<ScannerInfo>> = Vec::new();
+
+    for device in devices {
+        // A backend never lists one scanner twice
+        let candidates: Vec<usize> = (0..groups.len())
+            .filter(|&i| {
+                groups[i]
+                    .iter()
+                    .all(|member| member.backend != device.backend)
+            })
+            .collect();
+
+        let strong = candidates.iter().copied().find(|&i| {
+            groups[i].iter().any(|member| {
+                same_serial(member, &device)
+                    || (same_host(member, &device)
+                        && !differ(serial_key(member), serial_key(&device))
+                        && models_compatible(member, &device))
+            })
+        });
+        let weak = || {
+            let mut matching = candidates.iter().copied().filter(|&i| {
+                let group = &groups[i];
+                group.iter().any(|member| same_model(member, &device))
+                    && group.iter().all(|member| {
+                        !differ(serial_key(member), serial_key(&device))
+                            && !differ(host_key(member), host_key(&device))
+                    })
+            });
+            match (matching.next(), matching.next()) {
+                (Some(i), None) => Some(i),
+                _ => None,
+            }
+        };
+
+        match strong.or_else(weak) {
+            Some(i) => {
+                debug!(
+                    device = %device.id,
+                    same_as = %groups[i][0].id,
+                    "merging devices of the same scanner"
+                );
+                groups[i].push(device);
+            }
+            None => groups.push(vec![device]),
+        }
+    }
+
+    groups.into_iter().map(merge_group).collect()
+}
+
+/// The group's first device with the gaps filled from the rest.
+fn merge_group(group: Vec<ScannerInfo>) -> ScannerInfo {
+    let routes = group.iter().map(|device| device.id.clone()).collect();
+
+    let mut devices = group.into_iter();
+    let mut merged = devices.next().expect("groups are never empty");
+    for other in devices {
+        merged.manufacturer = merged.manufacturer.or(other.manufacturer);
+        merged.model = merged.model.or(other.model);
+        merged.serial = merged.serial.or(other.serial);
+        merged.address = merged.address.or(other.address);
+        merged.device_type = merged.device_type.or(other.device_type);
+        merged.location = merged.location.or(other.location);
+        merged.admin_url = merged.admin_url.or(other.admin_url);
+        merged.icon_url = merged.icon_url.or(other.icon_url);
+        merged.icon = merged.icon.or(other.icon);
+    }
+    merged.routes = routes;
+    merged
+}
+
+/// Both are known and they aren't equal.
+fn differ(a: Option<String>, b: Option<String>) -> bool {
+    matches!((a, b), (Some(a), Some(b)) if a != b)
+}
+
+/// Unknown models, or one naming the other with more detail, e.g. "HP
+/// OfficeJet Pro 9010" and "HP OfficeJet Pro 9010 series".
+fn models_compatible(a: &ScannerInfo, b: &ScannerInfo) -> bool {
+    match (model_key(a), model_key(b)) {
+        (Some(a), Some(b)) => a.contains(&b) || b.contains(&a),
+        _ => true,
+    }
+}
+
+fn same_serial(a: &ScannerInfo, b: &ScannerInfo) -> bool {
+    serial_key(a).is_some() && serial_key(a) == serial_key(b)
+}
+
+fn same_host(a: &ScannerInfo, b: &ScannerInfo) -> bool {
+    host_key(a).is_some() && host_key(a) == host_key(b)
+}
+
+fn same_model(a: &ScannerInfo, b: &ScannerInfo) -> bool {
+    model_key(a).is_some() && model_key(a) == model_key(b)
+}
+
+/// Serial or UUID with case, dashes and any `urn:uuid:` prefix removed.
+fn serial_key(device: &ScannerInfo) -> Option<String> {
+    let serial = device.serial.as_deref()?.trim().to_lowercase();
+    let serial = serial.strip_prefix("urn:uuid:").unwrap_or(&serial);
+    let key = alphanumeric(serial);
+    (!key.is_empty()).then_some(key)
+}
+
+/// Host of the device's address, without scheme, port or path.
+fn host_key(device: &ScannerInfo) -> Option<String> {
+    let address = device.address.as_deref()?.trim();
+    let address = address.split_once("://").map_or(address, |(_, rest)| rest);
+    let address = address.split('/').next().unwrap_or(address);
+
+    let host = if let Some(bracketed) = address.strip_prefix('[') {
+        bracketed.split(']').next().unwrap_or(bracketed)
+    } else if address.matches(':').count() == 1 {
+        address.split(':').next().unwrap_or(address)
+    } else {
+        // A bare IPv6 address, or a host without a port
+        address
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Make and model with case, spaces and punctuation removed.
+fn model_key(device: &ScannerInfo) -> Option<String> {
+    let model = device.model.as_deref()?;
+    let make = device.manufacturer.as_deref().unwrap_or_default();
+    let model = alphanumeric(&model.to_lowercase());
+    let make = alphanumeric(&make.to_lowercase());
+    if model.is_empty() {
+        return None;
+    }
+    // Some backends repeat the make in the model
+    Some(if model.starts_with(&make) {
+        model
+    } else {
+        make + &model
+    })
+}
+
+fn alphanumeric(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).collect()
+}
@@ -7,24 +7,60 @@
 //  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
 //
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int, c_void};
 use std::sync::{Arc, Mutex};
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
 
+use crate::auth::{self, Credentials, CredentialsProvider};
+use crate::config::RegistryConfig;
+use crate::logging::{self, LogSink};
 use crate::models::*;
-use crate::registry::BackendRegistry;
+use crate::registry::{BackendRegistry, DeviceDiscovery, DiscoveryStatus};
+use crate::sensors::{SensorEvent, SensorMonitor, DEFAULT_SENSOR_POLL_INTERVAL};
+use crate::session_guard::SessionLimits;
+use crate::validation::ConfigPolicy;
 
 // Global registry instance
 static mut REGISTRY: Option<Arc<Mutex<BackendRegistry>>> = None;
 static mut SCAN_SESSIONS: Option<Arc<Mutex<HashMap<u32, Box<dyn ScanSession + Send>>>>> = None;
 static mut NEXT_SESSION_ID: u32 = 1;
+static mut SENSOR_MONITORS: Option<Arc<Mutex<HashMap<u32, Arc<SensorMonitor>>>>> = None;
+static mut NEXT_MONITOR_ID: u32 = 1;
+// Kept apart from SCAN_SESSIONS, which stays locked while a session blocks
+static mut SCAN_CANCEL_HANDLES: Option<Arc<Mutex<HashMap<u32, CancelHandle>>>> = None;
+static mut CANCEL_TOKENS: Option<Arc<Mutex<HashMap<u32, CancelToken>>>> = None;
+static mut NEXT_CANCEL_TOKEN_ID: u32 = 1;
 
 #[repr(C)]
 pub struct CScannerInfo {
     pub id: *mut c_char,
     pub name: *mut c_char,
     pub backend: c_int, // Backend enum as int
+    // Details below are NULL when the backend doesn't know them
+    pub manufacturer: *mut c_char,
+    pub model: *mut c_char,
+    pub serial: *mut c_char,
+    pub connection: c_int, // ConnectionKind enum as int
+    pub address: *mut c_char,
+    pub device_type: *mut c_char,
+    pub location: *mut c_char,
+    pub admin_url: *mut c_char,
+    pub icon_url: *mut c_char,
+    pub icon: *mut u8,
+    pub icon_len: usize,
+    // Backends reaching this scanner, most preferred first
+    pub routes: *mut CDeviceRoute,
+    pub route_count: usize,
+}
+
+#[repr(C)]
+pub struct CDeviceRoute {
+    pub id: *mut c_char,
+    pub backend: c_int, // Backend enum as int
 }
 
 #[repr(C)]
@@ -33,6 +69,22 @@ pub struct CScannerInfoList {
     pub count: usize,
 }
 
+#[repr(C)]
+pub struct CBackendDiscovery {
+    pub backend: c_int, // Backend enum as int
+    pub status: c_int,  // DiscoveryStatus as int
+    pub device_count: usize,
+    pub elapsed_ms: u64,
+    pub message: *mut c_char, // failure or unavailability reason, NULL otherwise
+}
+
+#[repr(C)]
+pub struct CDiscovery {
+    pub scanners: *mut CScannerInfoList,
+    pub backends: *mut CBackendDiscovery,
+    pub backend_count: usize,
+}
+
 #[repr(C)]
 pub struct CCapabilities {
     pub sources: *mut c_int,
@@ -42,8 +94,22 @@ pub struct CCapabilities {
     pub color_modes: *mut c_int,
     pub color_modes_count: usize,
     pub supports_duplex: c_int, // bool as int
+    pub dpi_ranges: *mut CDpiRange,
+    pub dpi_ranges_count: usize,
+    pub independent_dpi: c_int, // bool as int
+    pub bit_depths: *mut c_int,
+    pub bit_depths_count: usize,
+}
+
+#[repr(C)]
+pub struct CDpiRange {
+    pub min: c_int,
+    pub max: c_int,
+    pub step: c_int,
 }
 
+// The original scan config, kept as is for existing callers; settings
+// added since are only in CScanConfigV2
 #[repr(C)]
 pub struct CScanConfig {
     pub source: c_int,
@@ -54,22 +120,251 @@ pub struct CScanConfig {
     pub page_height_mm: c_int,
 }
 
+#[repr(C)]
+pub struct CScanConfigV2 {
+    pub source: c_int,
+    pub duplex: c_int, // bool as int
+    pub dpi: c_int,
+    pub color_mode: c_int,
+    pub page_width_mm: f64,
+    pub page_height_mm: f64,
+    pub dpi_y: c_int,     // 0 = same as dpi
+    pub bit_depth: c_int, // 0 = mode default
+    pub max_pages: c_int, // 0 = no limit
+    pub has_area: c_int,  // bool as int
+    pub area_x_mm: f64,
+    pub area_y_mm: f64,
+    pub area_width_mm: f64,
+    pub area_height_mm: f64,
+}
+
 #[repr(C)]
 pub struct CScanEvent {
     pub event_type: c_int, // ScanEvent type as int
     pub data: *mut c_void,
     pub data_size: usize,
+    pub bytes: u64,           // progress: bytes of the page so far
+    pub expected: i64,        // progress: page size in bytes, -1 if unknown
+    pub percent: f32,         // progress: 0-100, -1 if unknown
+    pub code: c_int,          // status, warning or error code
+    pub message: *mut c_char, // warning or error text, NULL otherwise
+}
+
+#[repr(C)]
+pub struct CDeviceOption {
+    pub name: *mut c_char,
+    pub title: *mut c_char,
+    pub description: *mut c_char,
+    pub group: *mut c_char,     // NULL when ungrouped
+    pub value_type: c_int,      // OptionType as int
+    pub unit: c_int,            // OptionUnit as int
+    pub constraint_type: c_int, // 0 = none, 1 = range, 2 = list
+    pub range_min: f64,
+    pub range_max: f64,
+    pub range_step: f64, // 0 when any value in range is accepted
+    pub list_values: *mut *mut c_char,
+    pub list_count: usize,
+    pub value: *mut c_char, // current value as text, NULL if unknown
+    pub settable: c_int,    // bool as int
+    pub advanced: c_int,    // bool as int
+}
+
+#[repr(C)]
+pub struct CDeviceOptionList {
+    pub options: *mut CDeviceOption,
+    pub count: usize,
+}
+
+#[repr(C)]
+pub struct CSensorEvent {
+    pub event_type: c_int, // SensorEvent type as int
+    pub name: *mut c_char, // button name, NULL for other events
+}
+
+/// Receives one log line; `target` and `message` are only valid during the
+/// call.
+pub type CLogCallback = extern "C" fn(level: c_int, target: *const c_char, message: *const c_char);
+
+struct FfiLogSink(CLogCallback);
+
+impl LogSink for FfiLogSink {
+    fn log(&self, level: Level, target: &str, message: &str) {
+        // Interior NULs can't cross into C; drop them rather than the line
+        let target = CString::new(target.replace('\0', "")).unwrap_or_default();
+        let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+        (self.0)(level_to_int(level), target.as_ptr(), message.as_ptr());
+    }
+}
+
+fn level_to_int(level: Level) -> c_int {
+    match level {
+        Level::ERROR => 1,
+        Level::WARN => 2,
+        Level::INFO => 3,
+        Level::DEBUG => 4,
+        Level::TRACE => 5,
+    }
+}
+
+fn int_to_level_filter(value: c_int) -> Option<LevelFilter> {
+    match value {
+        0 => Some(LevelFilter::OFF),
+        1 => Some(LevelFilter::ERROR),
+        2 => Some(LevelFilter::WARN),
+        3 => Some(LevelFilter::INFO),
+        4 => Some(LevelFilter::DEBUG),
+        5 => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+// Buffer size offered to the credentials callback for each field
+const CREDENTIAL_BUFFER_LEN: usize = 128;
+
+/// Fills `username`/`password` (NUL-terminated, at most the given lengths)
+/// and returns non-zero, or returns 0 to decline.
+pub type CCredentialsCallback = extern "C" fn(
+    resource: *const c_char,
+    username: *mut c_char,
+    username_len: usize,
+    password: *mut c_char,
+    password_len: usize,
+) -> c_int;
+
+struct FfiCredentialsProvider(CCredentialsCallback);
+
+impl CredentialsProvider for FfiCredentialsProvider {
+    fn credentials(&self, resource: &str) -> Option<Credentials> {
+        let resource = CString::new(resource).ok()?;
+        let mut username = vec![0 as c_char; CREDENTIAL_BUFFER_LEN];
+        let mut password = vec![0 as c_char; CREDENTIAL_BUFFER_LEN];
+
+        let provided = (self.0)(
+            resource.as_ptr(),
+            username.as_mut_ptr(),
+            username.len(),
+            password.as_mut_ptr(),
+            password.len(),
+        );
+        if provided == 0 {
+            return None;
+        }
+
+        // Guard against callbacks that fill the whole buffer
+        username[CREDENTIAL_BUFFER_LEN - 1] = 0;
+        password[CREDENTIAL_BUFFER_LEN - 1] = 0;
+        unsafe {
+            Some(Credentials {
+                username: CStr::from_ptr(username.as_ptr())
+                    .to_string_lossy()
+                    .into_owned(),
+                password: CStr::from_ptr(password.as_ptr())
+                    .to_string_lossy()
+                    .into_owned(),
+            })
+        }
+    }
+}
+
+thread_local! {
+    // Code, retryable flag and message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<(c_int, c_int, CString)>> = const { RefCell::new(None) };
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+fn set_last_error(error: &PapyrError) {
+    let message = CString::new(error.to_string().replace('\0', " ")).unwrap_or_default();
+    let entry = (error.code(), error.is_retryable() as c_int, message);
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(entry));
+}
+
+// Records `error` for papyr_last_error_* and returns the failure value
+fn fail<T>(error: PapyrError, value: T) -> T {
+    set_last_error(&error);
+    value
+}
+
+fn null_argument() -> PapyrError {
+    PapyrError::InvalidConfig("NULL argument".into())
+}
+
+fn invalid_utf8() -> PapyrError {
+    PapyrError::InvalidConfig("Argument is not valid UTF-8".into())
+}
+
+/// A device id argument; ids no backend could have are NotFound.
+unsafe fn parse_device_id(device_id: *const c_char) -> Result<DeviceId> {
+    CStr::from_ptr(device_id)
+        .to_str()
+        .map_err(|_| invalid_utf8())?
+        .parse()
+}
+
+fn not_initialized() -> PapyrError {
+    PapyrError::Other("papyr_init() has not been called".into())
+}
+
+// Token for a cancellable call; 0 means the call can't be cancelled
+unsafe fn cancel_token(token_id: c_int) -> Result<CancelToken> {
+    if token_id == 0 {
+        return Ok(CancelToken::new());
+    }
+    let tokens = CANCEL_TOKENS.as_ref().ok_or_else(not_initialized)?;
+    let tokens = tokens.lock().map_err(|_| not_initialized())?;
+    tokens
+        .get(&(token_id as u32))
+        .cloned()
+        .ok_or_else(|| PapyrError::NotFound(format!("Unknown cancel token {}", token_id)))
 }
 
 // Initialize the papyr core library
 #[no_mangle]
 pub extern "C" fn papyr_init() -> c_int {
+    // BackendRegistry::new() automatically registers all available backends
+    init_with(BackendRegistry::new())
+}
+
+// Initialize with the backends and settings of a .toml or .json config file
+#[no_mangle]
+pub extern "C" fn papyr_init_with_config(config_path: *const c_char) -> c_int {
+    init_from_config(config_path, |path| RegistryConfig::load(path))
+}
+
+// Initialize with a registry config given as JSON text
+#[no_mangle]
+pub extern "C" fn papyr_init_with_config_json(config_json: *const c_char) -> c_int {
+    init_from_config(config_json, RegistryConfig::from_json)
+}
+
+fn init_from_config(text: *const c_char, parse: impl Fn(&str) -> Result<RegistryConfig>) -> c_int {
     unsafe {
-        // BackendRegistry::new() automatically registers all available backends
-        let registry = BackendRegistry::new();
+        clear_last_error();
+
+        if text.is_null() {
+            return fail(null_argument(), -1);
+        }
+        let text = match CStr::from_ptr(text).to_str() {
+            Ok(s) => s,
+            Err(_) => return fail(invalid_utf8(), -1),
+        };
 
+        match parse(text) {
+            Ok(config) => init_with(BackendRegistry::from_config(&config)),
+            Err(e) => fail(e, -1),
+        }
+    }
+}
+
+fn init_with(registry: BackendRegistry) -> c_int {
+    unsafe {
         REGISTRY = Some(Arc::new(Mutex::new(registry)));
         SCAN_SESSIONS = Some(Arc::new(Mutex::new(HashMap::new())));
+        SENSOR_MONITORS = Some(Arc::new(Mutex::new(HashMap::new())));
+        SCAN_CANCEL_HANDLES = Some(Arc::new(Mutex::new(HashMap::new())));
+        CANCEL_TOKENS = Some(Arc::new(Mutex::new(HashMap::new())));
 
         0 // Success
     }
@@ -78,56 +373,160 @@ pub extern "C" fn papyr_init() -> c_int {
 // Get list of available scanners
 #[no_mangle]
 pub extern "C" fn papyr_list_scanners() -> *mut CScannerInfoList {
+    papyr_list_scanners_cancellable(0)
+}
+
+// Get list of available scanners, stopping discovery early if the token
+// is cancelled (NULL with PAPYR_ERROR_CANCELLED)
+#[no_mangle]
+pub extern "C" fn papyr_list_scanners_cancellable(cancel_token_id: c_int) -> *mut CScannerInfoList {
+    list_scanners(cancel_token_id, false)
+}
+
+// Discover scanners again instead of using the cached list
+#[no_mangle]
+pub extern "C" fn papyr_refresh_scanners(cancel_token_id: c_int) -> *mut CScannerInfoList {
+    list_scanners(cancel_token_id, true)
+}
+
+fn list_scanners(cancel_token_id: c_int, refresh: bool) -> *mut CScannerInfoList {
     unsafe {
+        clear_last_error();
+
+        let cancel = match cancel_token(cancel_token_id) {
+            Ok(cancel) => cancel,
+            Err(e) => return fail(e, std::ptr::null_mut()),
+        };
+
         if let Some(registry) = &REGISTRY {
             if let Ok(guard) = registry.lock() {
-                match guard.list_devices() {
-                    Ok(scanners) => {
-                        let c_scanners: Vec<CScannerInfo> = scanners
-                            .into_iter()
-                            .map(|scanner| CScannerInfo {
-                                id: CString::new(scanner.id).unwrap().into_raw(),
-                                name: CString::new(scanner.name).unwrap().into_raw(),
-                                backend: backend_to_int(scanner.backend),
-                            })
-                            .collect();
+                let scanners = if refresh {
+                    guard.refresh_devices_cancellable(&cancel)
+                } else {
+                    guard.list_devices_cancellable(&cancel)
+                };
+                match scanners {
+                    Ok(scanners) => scanner_list_to_c(scanners),
+                    Err(e) => fail(e, std::ptr::null_mut()),
+                }
+            } else {
+                fail(not_initialized(), std::ptr::null_mut())
+            }
+        } else {
+            fail(not_initialized(), std::ptr::null_mut())
+        }
+    }
+}
 
-                        let c_scanners_len = c_scanners.len();
-                        let c_scanners_ptr = Box::into_raw(c_scanners.into_boxed_slice());
-                        let list = Box::new(CScannerInfoList {
-                            scanners: c_scanners_ptr.cast(),
-                            count: c_scanners_len,
-                        });
+// Discover scanners on all backends at once, with how each backend fared
+// (finished, timed out, failed or unavailable)
+#[no_mangle]
+pub extern "C" fn papyr_discover_scanners(cancel_token_id: c_int) -> *mut CDiscovery {
+    unsafe {
+        clear_last_error();
 
-                        Box::into_raw(list)
-                    }
-                    Err(_) => std::ptr::null_mut(),
+        let cancel = match cancel_token(cancel_token_id) {
+            Ok(cancel) => cancel,
+            Err(e) => return fail(e, std::ptr::null_mut()),
+        };
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(guard) = registry.lock() {
+                match guard.discover_devices(&cancel) {
+                    Ok(discovery) => discovery_to_c(discovery),
+                    Err(e) => fail(e, std::ptr::null_mut()),
                 }
             } else {
-                std::ptr::null_mut()
+                fail(not_initialized(), std::ptr::null_mut())
             }
         } else {
-            std::ptr::null_mut()
+            fail(not_initialized(), std::ptr::null_mut())
+        }
+    }
+}
+
+// Set how long a backend may take to enumerate (in ms); backend -1 sets it
+// for every backend
+#[no_mangle]
+pub extern "C" fn papyr_set_enumeration_timeout(backend: c_int, timeout_ms: c_int) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        let timeout = std::time::Duration::from_millis(timeout_ms.max(0) as u64);
+        if let Some(registry) = &REGISTRY {
+            if let Ok(mut guard) = registry.lock() {
+                if backend < 0 {
+                    guard.set_enumeration_timeout(timeout);
+                } else {
+                    guard.set_backend_enumeration_timeout(int_to_backend(backend), timeout);
+                }
+                return 0;
+            }
+        }
+        fail(not_initialized(), -1)
+    }
+}
+
+// Load and register the backend plugins in a directory; returns how many
+// were registered
+#[no_mangle]
+pub extern "C" fn papyr_load_plugins(dir: *const c_char) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if dir.is_null() {
+            return fail(null_argument(), -1);
+        }
+        let dir = match CStr::from_ptr(dir).to_str() {
+            Ok(s) => s,
+            Err(_) => return fail(invalid_utf8(), -1),
+        };
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(mut guard) = registry.lock() {
+                return match guard.load_plugins(dir) {
+                    Ok(loaded) => loaded as c_int,
+                    Err(e) => fail(e, -1),
+                };
+            }
         }
+        fail(not_initialized(), -1)
     }
 }
 
 // Get scanner capabilities
 #[no_mangle]
 pub extern "C" fn papyr_get_capabilities(device_id: *const c_char) -> *mut CCapabilities {
+    papyr_get_capabilities_cancellable(device_id, 0)
+}
+
+// Get scanner capabilities; NULL with PAPYR_ERROR_CANCELLED if the token is
+// cancelled first
+#[no_mangle]
+pub extern "C" fn papyr_get_capabilities_cancellable(
+    device_id: *const c_char,
+    cancel_token_id: c_int,
+) -> *mut CCapabilities {
     unsafe {
+        clear_last_error();
+
         if device_id.is_null() {
-            return std::ptr::null_mut();
+            return fail(null_argument(), std::ptr::null_mut());
         }
 
-        let device_id_str = match CStr::from_ptr(device_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return std::ptr::null_mut(),
+        let device_id = match parse_device_id(device_id) {
+            Ok(device_id) => device_id,
+            Err(e) => return fail(e, std::ptr::null_mut()),
+        };
+
+        let cancel = match cancel_token(cancel_token_id) {
+            Ok(cancel) => cancel,
+            Err(e) => return fail(e, std::ptr::null_mut()),
         };
 
         if let Some(registry) = &REGISTRY {
             if let Ok(guard) = registry.lock() {
-                match guard.capabilities(device_id_str) {
+                match guard.capabilities_cancellable(&device_id, &cancel) {
                     Ok(caps) => {
                         let sources: Vec<c_int> = caps
                             .sources
@@ -140,6 +539,17 @@ pub extern "C" fn papyr_get_capabilities(device_id: *const c_char) -> *mut CCapa
                             .iter()
                             .map(|&c| color_mode_to_int(c))
                             .collect();
+                        let dpi_ranges: Vec<CDpiRange> = caps
+                            .dpi_ranges
+                            .iter()
+                            .map(|r| CDpiRange {
+                                min: r.min as c_int,
+                                max: r.max as c_int,
+                                step: r.step as c_int,
+                            })
+                            .collect();
+                        let bit_depths: Vec<c_int> =
+                            caps.bit_depths.iter().map(|&d| d as c_int).collect();
 
                         let c_caps = Box::new(CCapabilities {
                             sources: Box::into_raw(sources.into_boxed_slice()).cast(),
@@ -149,71 +559,264 @@ pub extern "C" fn papyr_get_capabilities(device_id: *const c_char) -> *mut CCapa
                             color_modes: Box::into_raw(color_modes.into_boxed_slice()).cast(),
                             color_modes_count: caps.color_modes.len(),
                             supports_duplex: if caps.supports_duplex { 1 } else { 0 },
+                            dpi_ranges: Box::into_raw(dpi_ranges.into_boxed_slice()).cast(),
+                            dpi_ranges_count: caps.dpi_ranges.len(),
+                            independent_dpi: if caps.independent_dpi { 1 } else { 0 },
+                            bit_depths: Box::into_raw(bit_depths.into_boxed_slice()).cast(),
+                            bit_depths_count: caps.bit_depths.len(),
                         });
 
                         Box::into_raw(c_caps)
                     }
-                    Err(_) => std::ptr::null_mut(),
+                    Err(e) => fail(e, std::ptr::null_mut()),
                 }
             } else {
-                std::ptr::null_mut()
+                fail(not_initialized(), std::ptr::null_mut())
             }
         } else {
-            std::ptr::null_mut()
+            fail(not_initialized(), std::ptr::null_mut())
+        }
+    }
+}
+
+// Choose whether papyr_start_scan adjusts unsupported settings (non-zero)
+// or rejects them (0, the default)
+#[no_mangle]
+pub extern "C" fn papyr_set_config_coercion(enabled: c_int) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(mut guard) = registry.lock() {
+                guard.set_config_policy(if enabled != 0 {
+                    ConfigPolicy::Coerce
+                } else {
+                    ConfigPolicy::Validate
+                });
+                return 0;
+            }
+        }
+        fail(not_initialized(), -1)
+    }
+}
+
+// Set how long discovered scanners are reused (in ms, 0 = discover every time)
+#[no_mangle]
+pub extern "C" fn papyr_set_device_cache_ttl(ttl_ms: c_int) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(mut guard) = registry.lock() {
+                guard.set_device_cache_ttl(std::time::Duration::from_millis(ttl_ms.max(0) as u64));
+                return 0;
+            }
+        }
+        fail(not_initialized(), -1)
+    }
+}
+
+// Fill `config` with the configured default scan settings
+#[no_mangle]
+pub extern "C" fn papyr_get_default_scan_config(config: *mut CScanConfig) -> c_int {
+    if config.is_null() {
+        clear_last_error();
+        return fail(null_argument(), -1);
+    }
+    let mut defaults = unsafe { std::mem::zeroed::<CScanConfigV2>() };
+    let status = papyr_get_default_scan_config_v2(&mut defaults);
+    if status == 0 {
+        unsafe {
+            *config = CScanConfig {
+                source: defaults.source,
+                duplex: defaults.duplex,
+                dpi: defaults.dpi,
+                color_mode: defaults.color_mode,
+                page_width_mm: defaults.page_width_mm.round() as c_int,
+                page_height_mm: defaults.page_height_mm.round() as c_int,
+            };
+        }
+    }
+    status
+}
+
+// Fill `config` with the configured default scan settings, max_pages
+// included
+#[no_mangle]
+pub extern "C" fn papyr_get_default_scan_config_v2(config: *mut CScanConfigV2) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if config.is_null() {
+            return fail(null_argument(), -1);
+        }
+        if let Some(registry) = &REGISTRY {
+            if let Ok(guard) = registry.lock() {
+                let defaults = guard.default_scan_config();
+                *config = CScanConfigV2 {
+                    source: scan_source_to_int(defaults.source),
+                    duplex: defaults.duplex as c_int,
+                    dpi: defaults.dpi as c_int,
+                    color_mode: color_mode_to_int(defaults.color_mode),
+                    page_width_mm: defaults.page_size.width_mm,
+                    page_height_mm: defaults.page_size.height_mm,
+                    dpi_y: defaults.dpi_y.unwrap_or(0) as c_int,
+                    bit_depth: defaults.bit_depth.unwrap_or(0) as c_int,
+                    max_pages: defaults.max_pages.unwrap_or(0) as c_int,
+                    has_area: 0,
+                    area_x_mm: 0.0,
+                    area_y_mm: 0.0,
+                    area_width_mm: 0.0,
+                    area_height_mm: 0.0,
+                };
+                return 0;
+            }
+        }
+        fail(not_initialized(), -1)
+    }
+}
+
+// Set the idle and overall timeouts (in ms, 0 = none) of later scan sessions
+#[no_mangle]
+pub extern "C" fn papyr_set_scan_timeouts(idle_timeout_ms: c_int, job_timeout_ms: c_int) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        let timeout = |ms: c_int| (ms > 0).then(|| std::time::Duration::from_millis(ms as u64));
+        if let Some(registry) = &REGISTRY {
+            if let Ok(mut guard) = registry.lock() {
+                guard.set_session_limits(SessionLimits {
+                    idle_timeout: timeout(idle_timeout_ms),
+                    job_timeout: timeout(job_timeout_ms),
+                });
+                return 0;
+            }
         }
+        fail(not_initialized(), -1)
     }
 }
 
 // Start a scan session
 #[no_mangle]
 pub extern "C" fn papyr_start_scan(device_id: *const c_char, config: *const CScanConfig) -> c_int {
+    papyr_start_scan_cancellable(device_id, config, 0)
+}
+
+// Start a scan session that the token also cancels, before or after it
+// has started
+#[no_mangle]
+pub extern "C" fn papyr_start_scan_cancellable(
+    device_id: *const c_char,
+    config: *const CScanConfig,
+    cancel_token_id: c_int,
+) -> c_int {
     unsafe {
+        clear_last_error();
+
         if device_id.is_null() || config.is_null() {
-            return -1;
+            return fail(null_argument(), -1);
         }
 
-        let device_id_str = match CStr::from_ptr(device_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return -1,
-        };
-
         let c_config = &*config;
         let scan_config = ScanConfig {
             source: int_to_scan_source(c_config.source),
             duplex: c_config.duplex != 0,
             dpi: c_config.dpi as u32,
+            dpi_y: None,
             color_mode: int_to_color_mode(c_config.color_mode),
-            page_size: PageSize {
-                width_mm: c_config.page_width_mm as u32,
-                height_mm: c_config.page_height_mm as u32,
-            },
+            bit_depth: None,
+            page_size: PageSize::new(
+                c_config.page_width_mm as f64,
+                c_config.page_height_mm as f64,
+            ),
             area: None,
             brightness: None,
             contrast: None,
             max_pages: None,
         };
+        start_scan(device_id, scan_config, cancel_token_id)
+    }
+}
+
+// Start a scan session with the settings only CScanConfigV2 has; the token
+// (0 for none) cancels it as in papyr_start_scan_cancellable
+#[no_mangle]
+pub extern "C" fn papyr_start_scan_v2(
+    device_id: *const c_char,
+    config: *const CScanConfigV2,
+    cancel_token_id: c_int,
+) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if device_id.is_null() || config.is_null() {
+            return fail(null_argument(), -1);
+        }
+
+        let c_config = &*config;
+        let scan_config = ScanConfig {
+            source: int_to_scan_source(c_config.source),
+            duplex: c_config.duplex != 0,
+            dpi: c_config.dpi as u32,
+            dpi_y: (c_config.dpi_y > 0).then_some(c_config.dpi_y as u32),
+            color_mode: int_to_color_mode(c_config.color_mode),
+            bit_depth: (c_config.bit_depth > 0).then_some(c_config.bit_depth as u8),
+            page_size: PageSize::new(c_config.page_width_mm, c_config.page_height_mm),
+            area: (c_config.has_area != 0).then_some(ScanArea {
+                x_mm: c_config.area_x_mm,
+                y_mm: c_config.area_y_mm,
+                width_mm: c_config.area_width_mm,
+                height_mm: c_config.area_height_mm,
+            }),
+            brightness: None,
+            contrast: None,
+            max_pages: (c_config.max_pages > 0).then_some(c_config.max_pages as u32),
+        };
+        start_scan(device_id, scan_config, cancel_token_id)
+    }
+}
+
+// The papyr_start_scan functions once their arguments are checked for NULL
+fn start_scan(device_id: *const c_char, scan_config: ScanConfig, cancel_token_id: c_int) -> c_int {
+    unsafe {
+        let device_id = match parse_device_id(device_id) {
+            Ok(device_id) => device_id,
+            Err(e) => return fail(e, -1),
+        };
+
+        let cancel = match cancel_token(cancel_token_id) {
+            Ok(cancel) => cancel,
+            Err(e) => return fail(e, -1),
+        };
 
         if let Some(registry) = &REGISTRY {
             if let Ok(guard) = registry.lock() {
-                match guard.start_scan(device_id_str, scan_config) {
+                match guard.start_scan_cancellable(&device_id, scan_config, &cancel) {
                     Ok(session) => {
                         if let Some(sessions) = &SCAN_SESSIONS {
                             if let Ok(mut sessions_guard) = sessions.lock() {
                                 let session_id = NEXT_SESSION_ID;
                                 NEXT_SESSION_ID += 1;
+                                if let (Some(handle), Some(handles)) =
+                                    (session.cancel_handle(), &SCAN_CANCEL_HANDLES)
+                                {
+                                    if let Ok(mut handles) = handles.lock() {
+                                        handles.insert(session_id, handle);
+                                    }
+                                }
                                 sessions_guard.insert(session_id, session);
                                 return session_id as c_int;
                             }
                         }
                         -1
                     }
-                    Err(_) => -1,
+                    Err(e) => fail(e, -1),
                 }
             } else {
-                -1
+                fail(not_initialized(), -1)
             }
         } else {
-            -1
+            fail(not_initialized(), -1)
         }
     }
 }
@@ -222,30 +825,343 @@ pub extern "C" fn papyr_start_scan(device_id: *const c_char, config: *const CSca
 #[no_mangle]
 pub extern "C" fn papyr_next_scan_event(session_id: c_int) -> *mut CScanEvent {
     unsafe {
+        clear_last_error();
+
         if let Some(sessions) = &SCAN_SESSIONS {
             if let Ok(mut sessions_guard) = sessions.lock() {
                 if let Some(session) = sessions_guard.get_mut(&(session_id as u32)) {
                     match session.next_event() {
-                        Ok(Some(event)) => {
-                            let c_event = Box::new(CScanEvent {
-                                event_type: scan_event_to_int(&event),
-                                data: std::ptr::null_mut(), // Simplified for now
-                                data_size: 0,
-                            });
-                            Box::into_raw(c_event)
-                        }
+                        Ok(Some(event)) => Box::into_raw(Box::new(scan_event_to_c(&event))),
                         Ok(None) => std::ptr::null_mut(),
-                        Err(_) => std::ptr::null_mut(),
+                        // The failure is also an event, so callers looping
+                        // until NULL see why the job ended
+                        Err(e) => {
+                            set_last_error(&e);
+                            Box::into_raw(Box::new(scan_event_to_c(&ScanEvent::Error(e))))
+                        }
                     }
                 } else {
-                    std::ptr::null_mut()
+                    let error =
+                        PapyrError::NotFound(format!("Unknown scan session {}", session_id));
+                    fail(error, std::ptr::null_mut())
                 }
             } else {
-                std::ptr::null_mut()
+                fail(not_initialized(), std::ptr::null_mut())
             }
         } else {
-            std::ptr::null_mut()
+            fail(not_initialized(), std::ptr::null_mut())
+        }
+    }
+}
+
+// Cancel a scan session from any thread, including while another thread is
+// blocked in papyr_next_scan_event. That call then returns
+// SCAN_EVENT_CANCELLED once the backend has stopped.
+#[no_mangle]
+pub extern "C" fn papyr_cancel_scan(session_id: c_int) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        let handle = match &SCAN_CANCEL_HANDLES {
+            Some(handles) => match handles.lock() {
+                Ok(guard) => guard.get(&(session_id as u32)).cloned(),
+                Err(_) => return fail(not_initialized(), -1),
+            },
+            None => return fail(not_initialized(), -1),
+        };
+
+        match handle {
+            Some(handle) => {
+                handle.cancel();
+                0
+            }
+            None => fail(
+                PapyrError::NotFound(format!("Unknown scan session {}", session_id)),
+                -1,
+            ),
+        }
+    }
+}
+
+// Create a cancel token for the *_cancellable calls; returns its id (> 0)
+#[no_mangle]
+pub extern "C" fn papyr_cancel_token_new() -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if let Some(tokens) = &CANCEL_TOKENS {
+            if let Ok(mut guard) = tokens.lock() {
+                let token_id = NEXT_CANCEL_TOKEN_ID;
+                NEXT_CANCEL_TOKEN_ID += 1;
+                guard.insert(token_id, CancelToken::new());
+                return token_id as c_int;
+            }
         }
+        fail(not_initialized(), -1)
+    }
+}
+
+// Cancel every call and scan using the token; safe from any thread
+#[no_mangle]
+pub extern "C" fn papyr_cancel_token_cancel(token_id: c_int) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if token_id == 0 {
+            return fail(PapyrError::NotFound("Unknown cancel token 0".into()), -1);
+        }
+        match cancel_token(token_id) {
+            Ok(token) => {
+                token.cancel();
+                0
+            }
+            Err(e) => fail(e, -1),
+        }
+    }
+}
+
+// Release a cancel token; calls already using it keep working
+#[no_mangle]
+pub extern "C" fn papyr_cancel_token_free(token_id: c_int) {
+    unsafe {
+        if let Some(tokens) = &CANCEL_TOKENS {
+            if let Ok(mut guard) = tokens.lock() {
+                guard.remove(&(token_id as u32));
+            }
+        }
+    }
+}
+
+// Get backend-specific device options
+#[no_mangle]
+pub extern "C" fn papyr_get_device_options(device_id: *const c_char) -> *mut CDeviceOptionList {
+    unsafe {
+        clear_last_error();
+
+        if device_id.is_null() {
+            return fail(null_argument(), std::ptr::null_mut());
+        }
+
+        let device_id = match parse_device_id(device_id) {
+            Ok(device_id) => device_id,
+            Err(e) => return fail(e, std::ptr::null_mut()),
+        };
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(guard) = registry.lock() {
+                match guard.options(&device_id) {
+                    Ok(options) => {
+                        let c_options: Vec<CDeviceOption> =
+                            options.into_iter().map(device_option_to_c).collect();
+
+                        let count = c_options.len();
+                        let list = Box::new(CDeviceOptionList {
+                            options: Box::into_raw(c_options.into_boxed_slice()).cast(),
+                            count,
+                        });
+
+                        Box::into_raw(list)
+                    }
+                    Err(e) => fail(e, std::ptr::null_mut()),
+                }
+            } else {
+                fail(not_initialized(), std::ptr::null_mut())
+            }
+        } else {
+            fail(not_initialized(), std::ptr::null_mut())
+        }
+    }
+}
+
+// Set a device option from its text form; kept for later scans of the device
+#[no_mangle]
+pub extern "C" fn papyr_set_device_option(
+    device_id: *const c_char,
+    name: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if device_id.is_null() || name.is_null() || value.is_null() {
+            return fail(null_argument(), -1);
+        }
+
+        let (name_str, value_str) = match (
+            CStr::from_ptr(name).to_str(),
+            CStr::from_ptr(value).to_str(),
+        ) {
+            (Ok(name), Ok(value)) => (name, value),
+            _ => return fail(invalid_utf8(), -1),
+        };
+        let device_id = match parse_device_id(device_id) {
+            Ok(device_id) => device_id,
+            Err(e) => return fail(e, -1),
+        };
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(guard) = registry.lock() {
+                // Parsed against the option's type by the backend
+                let value = OptionValue::String(value_str.to_string());
+                return match guard.set_option(&device_id, name_str, value) {
+                    Ok(_) => 0,
+                    Err(e) => fail(e, -1),
+                };
+            }
+        }
+        fail(not_initialized(), -1)
+    }
+}
+
+// Start polling a device's buttons and sensors
+#[no_mangle]
+pub extern "C" fn papyr_start_sensor_monitor(
+    device_id: *const c_char,
+    interval_ms: c_int,
+) -> c_int {
+    unsafe {
+        clear_last_error();
+
+        if device_id.is_null() {
+            return fail(null_argument(), -1);
+        }
+
+        let device_id = match parse_device_id(device_id) {
+            Ok(device_id) => device_id,
+            Err(e) => return fail(e, -1),
+        };
+
+        let interval = if interval_ms > 0 {
+            std::time::Duration::from_millis(interval_ms as u64)
+        } else {
+            DEFAULT_SENSOR_POLL_INTERVAL
+        };
+
+        if let Some(registry) = &REGISTRY {
+            if let Ok(guard) = registry.lock() {
+                match guard.monitor_sensors(&device_id, interval) {
+                    Ok(monitor) => {
+                        if let Some(monitors) = &SENSOR_MONITORS {
+                            if let Ok(mut monitors_guard) = monitors.lock() {
+                                let monitor_id = NEXT_MONITOR_ID;
+                                NEXT_MONITOR_ID += 1;
+                                monitors_guard.insert(monitor_id, Arc::new(monitor));
+                                return monitor_id as c_int;
+                            }
+                        }
+                        -1
+                    }
+                    Err(e) => fail(e, -1),
+                }
+            } else {
+                fail(not_initialized(), -1)
+            }
+        } else {
+            fail(not_initialized(), -1)
+        }
+    }
+}
+
+// Wait up to timeout_ms for the next button/sensor event
+#[no_mangle]
+pub extern "C" fn papyr_next_sensor_event(
+    monitor_id: c_int,
+    timeout_ms: c_int,
+) -> *mut CSensorEvent {
+    unsafe {
+        clear_last_error();
+
+        // Don't hold the map lock while waiting
+        let monitor = match &SENSOR_MONITORS {
+            Some(monitors) => match monitors.lock() {
+                Ok(guard) => guard.get(&(monitor_id as u32)).cloned(),
+                Err(_) => None,
+            },
+            None => None,
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms.max(0) as u64);
+        match monitor.and_then(|monitor| monitor.next_event(timeout)) {
+            Some(event) => {
+                let (event_type, name) = match event {
+                    SensorEvent::ButtonPressed(name) => (0, c_string(&name)),
+                    SensorEvent::PaperLoaded => (1, std::ptr::null_mut()),
+                    SensorEvent::PaperRemoved => (2, std::ptr::null_mut()),
+                    SensorEvent::CoverOpened => (3, std::ptr::null_mut()),
+                    SensorEvent::CoverClosed => (4, std::ptr::null_mut()),
+                };
+                Box::into_raw(Box::new(CSensorEvent { event_type, name }))
+            }
+            None => std::ptr::null_mut(),
+        }
+    }
+}
+
+// Stop a sensor monitor
+#[no_mangle]
+pub extern "C" fn papyr_stop_sensor_monitor(monitor_id: c_int) {
+    unsafe {
+        if let Some(monitors) = &SENSOR_MONITORS {
+            if let Ok(mut guard) = monitors.lock() {
+                guard.remove(&(monitor_id as u32));
+            }
+        }
+    }
+}
+
+// Error code of the last failed call on this thread, 0 if it succeeded
+#[no_mangle]
+pub extern "C" fn papyr_last_error_code() -> c_int {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(0, |(code, _, _)| *code))
+}
+
+// Message for the last failed call on this thread, NULL if it succeeded.
+// Owned by the library and valid until the next papyr call on this thread.
+#[no_mangle]
+pub extern "C" fn papyr_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |(_, _, message)| message.as_ptr())
+    })
+}
+
+// Non-zero when the last failure on this thread is worth retrying
+#[no_mangle]
+pub extern "C" fn papyr_last_error_retryable() -> c_int {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(0, |(_, retryable, _)| *retryable)
+    })
+}
+
+// Set (or clear with NULL) the callback asked for scanner credentials
+#[no_mangle]
+pub extern "C" fn papyr_set_credentials_callback(callback: Option<CCredentialsCallback>) {
+    auth::set_credentials_provider(callback.map(|callback| {
+        Arc::new(FfiCredentialsProvider(callback)) as Arc<dyn CredentialsProvider>
+    }));
+}
+
+// Set (or clear with NULL) the callback receiving papyr's log lines at
+// max_level or more severe
+#[no_mangle]
+pub extern "C" fn papyr_set_log_callback(
+    callback: Option<CLogCallback>,
+    max_level: c_int,
+) -> c_int {
+    clear_last_error();
+    let Some(max_level) = int_to_level_filter(max_level) else {
+        return fail(
+            PapyrError::InvalidConfig(format!("Invalid log level: {}", max_level)),
+            -1,
+        );
+    };
+    let sink = callback.map(|callback| Arc::new(FfiLogSink(callback)) as Arc<dyn LogSink>);
+    match logging::set_log_sink(sink, max_level) {
+        Ok(()) => 0,
+        Err(e) => fail(e, -1),
     }
 }
 
@@ -255,13 +1171,61 @@ pub extern "C" fn papyr_free_scanner_list(list: *mut CScannerInfoList) {
     unsafe {
         if !list.is_null() {
             let list = Box::from_raw(list);
-            let scanners = Box::from_raw(std::slice::from_raw_parts_mut(list.scanners, list.count));
+            let scanners = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                list.scanners,
+                list.count,
+            ));
             for scanner in scanners.iter() {
-                if !scanner.id.is_null() {
-                    drop(CString::from_raw(scanner.id));
+                for text in [
+                    scanner.id,
+                    scanner.name,
+                    scanner.manufacturer,
+                    scanner.model,
+                    scanner.serial,
+                    scanner.address,
+                    scanner.device_type,
+                    scanner.location,
+                    scanner.admin_url,
+                    scanner.icon_url,
+                ] {
+                    if !text.is_null() {
+                        drop(CString::from_raw(text));
+                    }
                 }
-                if !scanner.name.is_null() {
-                    drop(CString::from_raw(scanner.name));
+                if !scanner.icon.is_null() {
+                    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                        scanner.icon,
+                        scanner.icon_len,
+                    )));
+                }
+                if !scanner.routes.is_null() {
+                    let routes = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                        scanner.routes,
+                        scanner.route_count,
+                    ));
+                    for route in routes.iter() {
+                        drop(CString::from_raw(route.id));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Free a discovery result
+#[no_mangle]
+pub extern "C" fn papyr_free_discovery(discovery: *mut CDiscovery) {
+    unsafe {
+        if !discovery.is_null() {
+            let discovery = Box::from_raw(discovery);
+            papyr_free_scanner_list(discovery.scanners);
+            let backends = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                discovery.backends,
+                discovery.backend_count,
+            ));
+            for backend in backends.iter() {
+                if !backend.message.is_null() {
+                    drop(CString::from_raw(backend.message));
                 }
             }
         }
@@ -274,23 +1238,80 @@ pub extern "C" fn papyr_free_capabilities(caps: *mut CCapabilities) {
         if !caps.is_null() {
             let caps = Box::from_raw(caps);
             if !caps.sources.is_null() {
-                drop(Box::from_raw(std::slice::from_raw_parts_mut(
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
                     caps.sources,
                     caps.sources_count,
                 )));
             }
             if !caps.dpis.is_null() {
-                drop(Box::from_raw(std::slice::from_raw_parts_mut(
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
                     caps.dpis,
                     caps.dpis_count,
                 )));
             }
             if !caps.color_modes.is_null() {
-                drop(Box::from_raw(std::slice::from_raw_parts_mut(
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
                     caps.color_modes,
                     caps.color_modes_count,
                 )));
             }
+            if !caps.dpi_ranges.is_null() {
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                    caps.dpi_ranges,
+                    caps.dpi_ranges_count,
+                )));
+            }
+            if !caps.bit_depths.is_null() {
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                    caps.bit_depths,
+                    caps.bit_depths_count,
+                )));
+            }
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn papyr_free_device_options(list: *mut CDeviceOptionList) {
+    unsafe {
+        if !list.is_null() {
+            let list = Box::from_raw(list);
+            let options =
+                Box::from_raw(std::ptr::slice_from_raw_parts_mut(list.options, list.count));
+            for option in options.iter() {
+                for text in [
+                    option.name,
+                    option.title,
+                    option.description,
+                    option.group,
+                    option.value,
+                ] {
+                    if !text.is_null() {
+                        drop(CString::from_raw(text));
+                    }
+                }
+                if !option.list_values.is_null() {
+                    let values = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                        option.list_values,
+                        option.list_count,
+                    ));
+                    for value in values.iter() {
+                        drop(CString::from_raw(*value));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn papyr_free_sensor_event(event: *mut CSensorEvent) {
+    unsafe {
+        if !event.is_null() {
+            let event = Box::from_raw(event);
+            if !event.name.is_null() {
+                drop(CString::from_raw(event.name));
+            }
         }
     }
 }
@@ -299,7 +1320,10 @@ pub extern "C" fn papyr_free_capabilities(caps: *mut CCapabilities) {
 pub extern "C" fn papyr_free_scan_event(event: *mut CScanEvent) {
     unsafe {
         if !event.is_null() {
-            drop(Box::from_raw(event));
+            let event = Box::from_raw(event);
+            if !event.message.is_null() {
+                drop(CString::from_raw(event.message));
+            }
         }
     }
 }
@@ -310,9 +1334,56 @@ pub extern "C" fn papyr_cleanup() {
         REGISTRY = None;
         SCAN_SESSIONS = None;
         NEXT_SESSION_ID = 1;
+        SENSOR_MONITORS = None;
+        NEXT_MONITOR_ID = 1;
+        SCAN_CANCEL_HANDLES = None;
+        CANCEL_TOKENS = None;
+        NEXT_CANCEL_TOKEN_ID = 1;
     }
 }
 
+fn scanner_list_to_c(scanners: Vec<ScannerInfo>) -> *mut CScannerInfoList {
+    let c_scanners: Vec<CScannerInfo> = scanners.into_iter().map(scanner_info_to_c).collect();
+
+    let c_scanners_len = c_scanners.len();
+    let c_scanners_ptr = Box::into_raw(c_scanners.into_boxed_slice());
+    let list = Box::new(CScannerInfoList {
+        scanners: c_scanners_ptr.cast(),
+        count: c_scanners_len,
+    });
+
+    Box::into_raw(list)
+}
+
+fn discovery_to_c(discovery: DeviceDiscovery) -> *mut CDiscovery {
+    let backends: Vec<CBackendDiscovery> = discovery
+        .backends
+        .into_iter()
+        .map(|backend| {
+            let (status, message) = match &backend.status {
+                DiscoveryStatus::Ok => (0, std::ptr::null_mut()),
+                DiscoveryStatus::TimedOut => (1, std::ptr::null_mut()),
+                DiscoveryStatus::Failed(message) => (2, c_string(message)),
+                DiscoveryStatus::Unavailable(reason) => (3, c_string(reason)),
+            };
+            CBackendDiscovery {
+                backend: backend_to_int(backend.backend),
+                status,
+                device_count: backend.devices,
+                elapsed_ms: backend.elapsed.as_millis() as u64,
+                message,
+            }
+        })
+        .collect();
+
+    let backend_count = backends.len();
+    Box::into_raw(Box::new(CDiscovery {
+        scanners: scanner_list_to_c(discovery.devices),
+        backends: Box::into_raw(backends.into_boxed_slice()).cast(),
+        backend_count,
+    }))
+}
+
 // Helper conversion functions
 fn backend_to_int(backend: Backend) -> c_int {
     match backend {
@@ -321,11 +1392,147 @@ fn backend_to_int(backend: Backend) -> c_int {
         Backend::Sane => 2,
         Backend::Ica => 3,
         Backend::Escl => 4,
+        Backend::SaneNet => 5,
+        Backend::Plugin => 6,
+        Backend::Virtual => 7,
         Backend::Unknown => 99,
     }
 }
 
-fn scan_source_to_int(source: ScanSource) -> c_int {
+fn int_to_backend(val: c_int) -> Backend {
+    match val {
+        0 => Backend::Twain,
+        1 => Backend::Wia,
+        2 => Backend::Sane,
+        3 => Backend::Ica,
+        4 => Backend::Escl,
+        5 => Backend::SaneNet,
+        6 => Backend::Plugin,
+        7 => Backend::Virtual,
+        _ => Backend::Unknown,
+    }
+}
+
+/// Owned C string with interior NULs dropped rather than failing.
+fn c_string(value: &str) -> *mut c_char {
+    CString::new(value.replace('\0', "")).unwrap().into_raw()
+}
+
+fn optional_c_string(value: Option<&str>) -> *mut c_char {
+    value.map_or(std::ptr::null_mut(), c_string)
+}
+
+fn connection_to_int(connection: ConnectionKind) -> c_int {
+    match connection {
+        ConnectionKind::Unknown => 0,
+        ConnectionKind::Usb => 1,
+        ConnectionKind::Network => 2,
+        ConnectionKind::Virtual => 3,
+    }
+}
+
+fn scanner_info_to_c(scanner: ScannerInfo) -> CScannerInfo {
+    let (icon, icon_len) = match scanner.icon {
+        Some(bytes) => {
+            let len = bytes.len();
+            (Box::into_raw(bytes.into_boxed_slice()).cast(), len)
+        }
+        None => (std::ptr::null_mut(), 0),
+    };
+
+    let routes: Vec<CDeviceRoute> = scanner
+        .routes
+        .iter()
+        .map(|route| CDeviceRoute {
+            id: c_string(&route.to_string()),
+            backend: backend_to_int(route.backend()),
+        })
+        .collect();
+    let route_count = routes.len();
+    let routes = if routes.is_empty() {
+        std::ptr::null_mut()
+    } else {
+        Box::into_raw(routes.into_boxed_slice()).cast()
+    };
+
+    CScannerInfo {
+        id: c_string(&scanner.id.to_string()),
+        name: c_string(&scanner.name),
+        backend: backend_to_int(scanner.backend),
+        manufacturer: optional_c_string(scanner.manufacturer.as_deref()),
+        model: optional_c_string(scanner.model.as_deref()),
+        serial: optional_c_string(scanner.serial.as_deref()),
+        connection: connection_to_int(scanner.connection),
+        address: optional_c_string(scanner.address.as_deref()),
+        device_type: optional_c_string(scanner.device_type.as_deref()),
+        location: optional_c_string(scanner.location.as_deref()),
+        admin_url: optional_c_string(scanner.admin_url.as_deref()),
+        icon_url: optional_c_string(scanner.icon_url.as_deref()),
+        icon,
+        icon_len,
+        routes,
+        route_count,
+    }
+}
+
+fn device_option_to_c(option: DeviceOption) -> CDeviceOption {
+    let (constraint_type, range_min, range_max, range_step, list) = match &option.constraint {
+        OptionConstraint::None => (0, 0.0, 0.0, 0.0, Vec::new()),
+        OptionConstraint::Range { min, max, step } => {
+            (1, *min, *max, step.unwrap_or(0.0), Vec::new())
+        }
+        OptionConstraint::List(values) => (
+            2,
+            0.0,
+            0.0,
+            0.0,
+            values.iter().map(|v| c_string(&v.to_string())).collect(),
+        ),
+    };
+
+    let list_count = list.len();
+    let list_values = if list.is_empty() {
+        std::ptr::null_mut()
+    } else {
+        Box::into_raw(list.into_boxed_slice()).cast()
+    };
+
+    CDeviceOption {
+        name: c_string(&option.name),
+        title: c_string(&option.title),
+        description: c_string(&option.description),
+        group: optional_c_string(option.group.as_deref()),
+        value_type: match option.value_type {
+            OptionType::Bool => 0,
+            OptionType::Int => 1,
+            OptionType::Fixed => 2,
+            OptionType::String => 3,
+            OptionType::Button => 4,
+        },
+        unit: match option.unit {
+            OptionUnit::None => 0,
+            OptionUnit::Pixel => 1,
+            OptionUnit::Bit => 2,
+            OptionUnit::Mm => 3,
+            OptionUnit::Dpi => 4,
+            OptionUnit::Percent => 5,
+            OptionUnit::Microsecond => 6,
+        },
+        constraint_type,
+        range_min,
+        range_max,
+        range_step,
+        list_values,
+        list_count,
+        value: option
+            .value
+            .map_or(std::ptr::null_mut(), |v| c_string(&v.to_string())),
+        settable: option.settable as c_int,
+        advanced: option.advanced as c_int,
+    }
+}
+
+pub(crate) fn scan_source_to_int(source: ScanSource) -> c_int {
     match source {
         ScanSource::Flatbed => 0,
         ScanSource::Adf => 1,
@@ -333,7 +1540,7 @@ fn scan_source_to_int(source: ScanSource) -> c_int {
     }
 }
 
-fn int_to_scan_source(val: c_int) -> ScanSource {
+pub(crate) fn int_to_scan_source(val: c_int) -> ScanSource {
     match val {
         0 => ScanSource::Flatbed,
         1 => ScanSource::Adf,
@@ -342,7 +1549,7 @@ fn int_to_scan_source(val: c_int) -> ScanSource {
     }
 }
 
-fn color_mode_to_int(mode: ColorMode) -> c_int {
+pub(crate) fn color_mode_to_int(mode: ColorMode) -> c_int {
     match mode {
         ColorMode::Color => 0,
         ColorMode::Gray => 1,
@@ -350,7 +1557,7 @@ fn color_mode_to_int(mode: ColorMode) -> c_int {
     }
 }
 
-fn int_to_color_mode(val: c_int) -> ColorMode {
+pub(crate) fn int_to_color_mode(val: c_int) -> ColorMode {
     match val {
         0 => ColorMode::Color,
         1 => ColorMode::Gray,
@@ -365,5 +1572,64 @@ fn scan_event_to_int(event: &ScanEvent) -> c_int {
         ScanEvent::PageData(_) => 1,
         ScanEvent::PageComplete(_) => 2,
         ScanEvent::JobComplete => 3,
+        ScanEvent::Progress { .. } => 4,
+        ScanEvent::Status(_) => 5,
+        ScanEvent::Warning { .. } => 6,
+        ScanEvent::Cancelled => 7,
+        ScanEvent::Error(_) => 8,
+    }
+}
+
+fn scan_status_to_int(status: ScanStatus) -> c_int {
+    match status {
+        ScanStatus::WaitingForDevice => 0,
+        ScanStatus::WarmingUp => 1,
+        ScanStatus::Scanning => 2,
+    }
+}
+
+fn warning_code_to_int(code: WarningCode) -> c_int {
+    match code {
+        WarningCode::ConfigAdjusted => 0,
+        WarningCode::OptionNotApplied => 1,
+        WarningCode::CleanupFailed => 2,
+        WarningCode::BackendFallback => 3,
+    }
+}
+
+fn scan_event_to_c(event: &ScanEvent) -> CScanEvent {
+    let mut c_event = CScanEvent {
+        event_type: scan_event_to_int(event),
+        data: std::ptr::null_mut(), // Simplified for now
+        data_size: 0,
+        bytes: 0,
+        expected: -1,
+        percent: -1.0,
+        code: 0,
+        message: std::ptr::null_mut(),
+    };
+
+    match event {
+        ScanEvent::Progress {
+            bytes,
+            expected,
+            percent,
+        } => {
+            c_event.bytes = *bytes;
+            c_event.expected = expected.map_or(-1, |expected| expected as i64);
+            c_event.percent = percent.unwrap_or(-1.0);
+        }
+        ScanEvent::Status(status) => c_event.code = scan_status_to_int(*status),
+        ScanEvent::Warning { code, message } => {
+            c_event.code = warning_code_to_int(*code);
+            c_event.message = c_string(message);
+        }
+        ScanEvent::Error(error) => {
+            c_event.code = error.code();
+            c_event.message = c_string(&error.to_string());
+        }
+        _ => {}
     }
+
+    c_event
 }
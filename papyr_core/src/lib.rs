@@ -13,9 +13,18 @@
 //  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
 //
 
+#[cfg(feature = "async")]
+pub mod async_api;
+pub mod auth;
 pub mod backends;
+pub mod config;
+pub mod dedup;
 pub mod ffi;
+pub mod logging;
 pub mod models;
 pub mod registry;
+pub mod sensors;
+pub mod session_guard;
+pub mod validation;
 
 pub use models::*;
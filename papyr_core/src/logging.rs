@@ -0,0 +1,170 @@
+//
+//  papyr_core
+//  logging.rs - Forwarding papyr's tracing events to a log sink
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::{PapyrError, Result};
+use std::fmt::{self, Write};
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Receives papyr's log lines. `target` is the emitting module (e.g.
+/// `papyr_core::backends::escl`); `message` is the event text followed by
+/// its fields, prefixed with the enclosing spans:
+///
+/// `scan{device=escl_a1}:page{index=0}: page complete width=2550 height=3300`
+pub trait LogSink: Send + Sync {
+    fn log(&self, level: Level, target: &str, message: &str);
+}
+
+impl<F> LogSink for F
+where
+    F: Fn(Level, &str, &str) + Send + Sync,
+{
+    fn log(&self, level: Level, target: &str, message: &str) {
+        self(level, target, message)
+    }
+}
+
+static SINK: RwLock<Option<(Arc<dyn LogSink>, LevelFilter)>> = RwLock::new(None);
+static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+/// Sends papyr's events at `max_level` or more severe to `sink`, or stops
+/// forwarding with `None`. Can be called again to swap the sink or level.
+///
+/// The first call installs a global tracing subscriber; applications that
+/// already have one should use it instead (papyr logs through `tracing`
+/// under the `papyr_core` target) or add [`SinkLayer`] to it.
+pub fn set_log_sink(sink: Option<Arc<dyn LogSink>>, max_level: LevelFilter) -> Result<()> {
+    if let Ok(mut guard) = SINK.write() {
+        *guard = sink.map(|sink| (sink, max_level));
+    }
+
+    let installed = *INSTALLED.get_or_init(|| {
+        let subscriber = tracing_subscriber::registry().with(SinkLayer);
+        tracing::subscriber::set_global_default(subscriber).is_ok()
+    });
+    if installed {
+        Ok(())
+    } else {
+        Err(PapyrError::Other(
+            "another tracing subscriber is already installed".into(),
+        ))
+    }
+}
+
+fn current_sink() -> Option<(Arc<dyn LogSink>, LevelFilter)> {
+    SINK.read().ok()?.clone()
+}
+
+/// Span fields, formatted once when the span is created or recorded to.
+#[derive(Default)]
+struct SpanFields(String);
+
+/// Formats an event's message and fields as `message key=value ...`.
+#[derive(Default)]
+struct EventText {
+    message: String,
+    fields: String,
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{}", value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = write!(self.0, "{}={:?}", field.name(), value);
+    }
+}
+
+impl Visit for EventText {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{}", value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// The layer behind [`set_log_sink`], for applications composing their
+/// own subscriber. Forwards to whatever sink is currently set.
+pub struct SinkLayer;
+
+impl<S> Layer<S> for SinkLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // The sink and its level can change at any time
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        // Spans are kept at any level so warnings still say which scan
+        current_sink()
+            .is_some_and(|(_, max_level)| metadata.is_span() || *metadata.level() <= max_level)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = SpanFields::default();
+            attrs.record(&mut fields);
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some((sink, _)) = current_sink() else {
+            return;
+        };
+
+        let mut line = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                line.push_str(span.name());
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    if !fields.0.is_empty() {
+                        let _ = write!(line, "{{{}}}", fields.0);
+                    }
+                }
+                line.push(':');
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+        }
+        let mut text = EventText::default();
+        event.record(&mut text);
+        line.push_str(&text.message);
+        line.push_str(&text.fields);
+
+        let metadata = event.metadata();
+        sink.log(*metadata.level(), metadata.target(), &line);
+    }
+}
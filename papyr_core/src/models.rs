@@ -7,18 +7,128 @@
 //
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Backend {
-    Twain, // TWAIN (Windows/macOS, primary)
-    Wia,   // Windows Image Acquisition
-    Sane,  // Scanner Access Now Easy (Linux)
-    Ica,   // Image Capture Architecture (macOS)
-    Escl,  // eSCL/AirScan (network scanners, cross-platform)
+    Twain,   // TWAIN (Windows/macOS, primary)
+    Wia,     // Windows Image Acquisition
+    Sane,    // Scanner Access Now Easy (Linux)
+    Ica,     // Image Capture Architecture (macOS)
+    Escl,    // eSCL/AirScan (network scanners, cross-platform)
+    SaneNet, // SANE network protocol (remote saned)
+    Plugin,  // Third-party backends loaded from shared libraries
+    Virtual, // Simulated scanners for development and tests
     Unknown,
 }
 
+impl Backend {
+    const ALL: [Backend; 9] = [
+        Backend::Twain,
+        Backend::Wia,
+        Backend::Sane,
+        Backend::Ica,
+        Backend::Escl,
+        Backend::SaneNet,
+        Backend::Plugin,
+        Backend::Virtual,
+        Backend::Unknown,
+    ];
+
+    /// Prefix of this backend's device ids. Never change one: ids are
+    /// persisted by applications.
+    pub fn id_prefix(&self) -> &'static str {
+        match self {
+            Backend::Twain => "twain",
+            Backend::Wia => "wia",
+            Backend::Sane => "sane",
+            Backend::Ica => "ica",
+            Backend::Escl => "escl",
+            Backend::SaneNet => "saned",
+            Backend::Plugin => "plugin",
+            Backend::Virtual => "virtual",
+            Backend::Unknown => "unknown",
+        }
+    }
+}
+
+/// A device's backend and its id within that backend. The string form is
+/// the backend's prefix, `_` and the local id, e.g. `escl_HP_OfficeJet` or
+/// `saned_scanhost:6566/epson2:net:10.0.0.5`; it parses back to the same
+/// id, and is what the FFI and serde use.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DeviceId {
+    backend: Backend,
+    local: String,
+}
+
+impl DeviceId {
+    pub fn new(backend: Backend, local: impl Into<String>) -> Self {
+        Self {
+            backend,
+            local: local.into(),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// The id within the backend, which only the backend interprets.
+    pub fn local(&self) -> &str {
+        &self.local
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.backend.id_prefix(), self.local)
+    }
+}
+
+impl FromStr for DeviceId {
+    type Err = PapyrError;
+
+    /// Unknown prefixes are `NotFound`: no backend has such a device.
+    fn from_str(id: &str) -> Result<Self> {
+        id.split_once('_')
+            .and_then(|(prefix, local)| {
+                Backend::ALL
+                    .iter()
+                    .find(|backend| backend.id_prefix() == prefix)
+                    .map(|backend| DeviceId::new(*backend, local))
+            })
+            .ok_or_else(|| PapyrError::NotFound(format!("{} is not a device id", id)))
+    }
+}
+
+impl TryFrom<String> for DeviceId {
+    type Error = PapyrError;
+
+    fn try_from(id: String) -> Result<Self> {
+        id.parse()
+    }
+}
+
+impl From<DeviceId> for String {
+    fn from(id: DeviceId) -> Self {
+        id.to_string()
+    }
+}
+
+/// Whether a registered backend can be used on this machine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BackendAvailability {
+    Available,
+    /// The backend is registered but cannot be used (e.g. a missing system library).
+    Unavailable(String),
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ScanSource {
     Flatbed,
@@ -33,34 +143,391 @@ pub enum ColorMode {
     Bw,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+/// Millimetres per inch.
+pub const MM_PER_INCH: f64 = 25.4;
+
+/// Physical page dimensions, portrait (width across the scan line, height
+/// along the feed direction).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct PageSize {
-    pub width_mm: u32,
-    pub height_mm: u32,
+    pub width_mm: f64,
+    pub height_mm: f64,
+}
+
+impl PageSize {
+    pub const fn new(width_mm: f64, height_mm: f64) -> Self {
+        Self {
+            width_mm,
+            height_mm,
+        }
+    }
+
+    pub fn from_inches(width: f64, height: f64) -> Self {
+        Self::new(width * MM_PER_INCH, height * MM_PER_INCH)
+    }
+
+    /// From PostScript points (1/72 inch).
+    pub fn from_points(width: f64, height: f64) -> Self {
+        Self::from_inches(width / 72.0, height / 72.0)
+    }
+
+    /// From 1/300 inch units, as used by eSCL.
+    pub fn from_three_hundredths(width: u32, height: u32) -> Self {
+        Self::from_inches(width as f64 / 300.0, height as f64 / 300.0)
+    }
+
+    pub fn to_inches(&self) -> (f64, f64) {
+        (self.width_mm / MM_PER_INCH, self.height_mm / MM_PER_INCH)
+    }
+
+    pub fn to_points(&self) -> (f64, f64) {
+        let (width, height) = self.to_inches();
+        (width * 72.0, height * 72.0)
+    }
+
+    /// In 1/300 inch units, rounded to the nearest unit.
+    pub fn to_three_hundredths(&self) -> (u32, u32) {
+        let (width, height) = self.to_inches();
+        (
+            (width * 300.0).round() as u32,
+            (height * 300.0).round() as u32,
+        )
+    }
+
+    /// Pixel dimensions of a scan at `dpi` x `dpi_y`, rounded to the nearest
+    /// pixel.
+    pub fn to_pixels(&self, dpi: u32, dpi_y: u32) -> (u32, u32) {
+        let (width, height) = self.to_inches();
+        (
+            (width * dpi as f64).round() as u32,
+            (height * dpi_y as f64).round() as u32,
+        )
+    }
+
+    /// The larger width and the larger height of the two sizes.
+    pub fn union(self, other: PageSize) -> PageSize {
+        PageSize::new(
+            self.width_mm.max(other.width_mm),
+            self.height_mm.max(other.height_mm),
+        )
+    }
+
+    /// True when this page fits inside `max` without rotating it.
+    pub fn fits_within(&self, max: &PageSize) -> bool {
+        // Allow for rounding in sizes converted from other units
+        const TOLERANCE_MM: f64 = 0.05;
+        self.width_mm <= max.width_mm + TOLERANCE_MM
+            && self.height_mm <= max.height_mm + TOLERANCE_MM
+    }
+}
+
+impl std::fmt::Display for PageSize {
+    /// e.g. `215.9x279.4 mm`, to a tenth of a millimetre.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tenths = |mm: f64| (mm * 10.0).round() / 10.0;
+        write!(f, "{}x{} mm", tenths(self.width_mm), tenths(self.height_mm))
+    }
+}
+
+/// A named entry in the page-size catalogue.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum PageFormat {
+    /// ISO 216 A series, A0 to A10.
+    IsoA(u8),
+    /// ISO 216 B series, B0 to B10.
+    IsoB(u8),
+    /// ISO 269 C series (envelopes), C0 to C10.
+    IsoC(u8),
+    Letter,
+    Legal,
+    Tabloid,
+    Executive,
+    /// US business card, 3.5 x 2 in.
+    BusinessCard,
+    /// ISO/IEC 7810 ID-1: credit cards and ID cards.
+    Id1,
+    Photo4x6,
+    Photo5x7,
+    Photo8x10,
+    /// The whole area the source can scan.
+    MaxArea,
+    /// Let the device detect the document size.
+    Auto,
+}
+
+impl PageFormat {
+    /// Every fixed-size format: the ISO series, then US, card and photo sizes.
+    pub fn catalogue() -> Vec<PageFormat> {
+        let mut formats = Vec::new();
+        for n in 0..=10 {
+            formats.push(PageFormat::IsoA(n));
+        }
+        for n in 0..=10 {
+            formats.push(PageFormat::IsoB(n));
+        }
+        for n in 0..=10 {
+            formats.push(PageFormat::IsoC(n));
+        }
+        formats.extend([
+            PageFormat::Letter,
+            PageFormat::Legal,
+            PageFormat::Tabloid,
+            PageFormat::Executive,
+            PageFormat::BusinessCard,
+            PageFormat::Id1,
+            PageFormat::Photo4x6,
+            PageFormat::Photo5x7,
+            PageFormat::Photo8x10,
+        ]);
+        formats
+    }
+
+    /// The fixed-size formats that fit inside `max`.
+    pub fn fitting(max: &PageSize) -> Vec<PageFormat> {
+        Self::catalogue()
+            .into_iter()
+            .filter(|format| format.size().is_some_and(|size| size.fits_within(max)))
+            .collect()
+    }
+
+    /// The catalogue entry with these dimensions, if any.
+    pub fn matching(size: &PageSize) -> Option<PageFormat> {
+        Self::catalogue().into_iter().find(|format| {
+            format.size().is_some_and(|known| {
+                (known.width_mm - size.width_mm).abs() < 0.5
+                    && (known.height_mm - size.height_mm).abs() < 0.5
+            })
+        })
+    }
+
+    /// Portrait dimensions, or `None` for `MaxArea` and `Auto`, which depend
+    /// on the device (see `Capabilities::resolve_page`).
+    pub fn size(&self) -> Option<PageSize> {
+        let size = match *self {
+            PageFormat::IsoA(n) if n <= 10 => iso_size(841, 1189, n),
+            PageFormat::IsoB(n) if n <= 10 => iso_size(1000, 1414, n),
+            PageFormat::IsoC(n) if n <= 10 => iso_size(917, 1297, n),
+            PageFormat::IsoA(_) | PageFormat::IsoB(_) | PageFormat::IsoC(_) => return None,
+            PageFormat::Letter => PageSize::from_inches(8.5, 11.0),
+            PageFormat::Legal => PageSize::from_inches(8.5, 14.0),
+            PageFormat::Tabloid => PageSize::from_inches(11.0, 17.0),
+            PageFormat::Executive => PageSize::from_inches(7.25, 10.5),
+            PageFormat::BusinessCard => PageSize::from_inches(2.0, 3.5),
+            PageFormat::Id1 => PageSize::new(53.98, 85.6),
+            PageFormat::Photo4x6 => PageSize::from_inches(4.0, 6.0),
+            PageFormat::Photo5x7 => PageSize::from_inches(5.0, 7.0),
+            PageFormat::Photo8x10 => PageSize::from_inches(8.0, 10.0),
+            PageFormat::MaxArea | PageFormat::Auto => return None,
+        };
+        Some(size)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            PageFormat::IsoA(n) => format!("A{}", n),
+            PageFormat::IsoB(n) => format!("B{}", n),
+            PageFormat::IsoC(n) => format!("C{}", n),
+            PageFormat::Letter => "Letter".into(),
+            PageFormat::Legal => "Legal".into(),
+            PageFormat::Tabloid => "Tabloid".into(),
+            PageFormat::Executive => "Executive".into(),
+            PageFormat::BusinessCard => "Business card".into(),
+            PageFormat::Id1 => "ID-1".into(),
+            PageFormat::Photo4x6 => "Photo 4x6".into(),
+            PageFormat::Photo5x7 => "Photo 5x7".into(),
+            PageFormat::Photo8x10 => "Photo 8x10".into(),
+            PageFormat::MaxArea => "Max area".into(),
+            PageFormat::Auto => "Auto".into(),
+        }
+    }
+}
+
+impl std::fmt::Display for PageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+/// Sizes reported when a backend can't query the device's maximum area.
+pub const DEFAULT_PAGE_FORMATS: [PageFormat; 2] = [PageFormat::Letter, PageFormat::IsoA(4)];
+
+/// Catalogue sizes that fit the largest of `max_areas`, or the defaults
+/// when no areas are known.
+pub fn page_formats_for(max_areas: &[SourceArea]) -> Vec<PageFormat> {
+    match max_areas
+        .iter()
+        .map(|area| area.max)
+        .reduce(PageSize::union)
+    {
+        Some(max) => PageFormat::fitting(&max),
+        None => DEFAULT_PAGE_FORMATS.to_vec(),
+    }
+}
+
+/// Size `n` of an ISO series: each step halves the long side of the one
+/// before, rounding down to the millimetre.
+fn iso_size(width_mm: u32, height_mm: u32, n: u8) -> PageSize {
+    let (mut width, mut height) = (width_mm, height_mm);
+    for _ in 0..n {
+        (width, height) = (height / 2, width);
+    }
+    PageSize::new(width as f64, height as f64)
+}
+
+/// The largest area a source can scan.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SourceArea {
+    pub source: ScanSource,
+    pub max: PageSize,
+}
+
+/// How a scanner is attached.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ConnectionKind {
+    #[default]
+    Unknown,
+    Usb,
+    Network,
+    /// Software devices such as SANE's `test` backend.
+    Virtual,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScannerInfo {
-    pub id: String,
+    pub id: DeviceId,
+    /// Display name; see `manufacturer` and `model` for the parts.
     pub name: String,
+    /// Same as `id.backend()`.
     pub backend: Backend,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    /// Serial number, or the eSCL UUID for network devices.
+    pub serial: Option<String>,
+    pub connection: ConnectionKind,
+    /// Host or IP address of network devices, with the port where known.
+    pub address: Option<String>,
+    /// e.g. "flatbed scanner" or "multi-function peripheral".
+    pub device_type: Option<String>,
+    /// Where the device is, as configured on it (eSCL `note`).
+    pub location: Option<String>,
+    /// The device's web admin page.
+    pub admin_url: Option<String>,
+    pub icon_url: Option<String>,
+    /// Icon image bytes, where the platform hands them over directly.
+    pub icon: Option<Vec<u8>>,
+    /// The ids of every backend that can reach this scanner, most
+    /// preferred first; the first is `id` itself. Filled in by the
+    /// registry, which shows a scanner seen through several backends once.
+    pub routes: Vec<DeviceId>,
+}
+
+impl ScannerInfo {
+    /// A scanner with only the required fields set; backends fill in the
+    /// details they know.
+    pub fn new(id: DeviceId, name: impl Into<String>) -> Self {
+        Self {
+            backend: id.backend(),
+            id,
+            name: name.into(),
+            manufacturer: None,
+            model: None,
+            serial: None,
+            connection: ConnectionKind::Unknown,
+            address: None,
+            device_type: None,
+            location: None,
+            admin_url: None,
+            icon_url: None,
+            icon: None,
+            routes: Vec::new(),
+        }
+    }
+}
+
+/// A continuous span of resolutions, accepted in `step` dpi increments
+/// from `min`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DpiRange {
+    pub min: u32,
+    pub max: u32,
+    pub step: u32,
+}
+
+impl DpiRange {
+    pub fn contains(&self, dpi: u32) -> bool {
+        (self.min..=self.max).contains(&dpi) && (dpi - self.min).is_multiple_of(self.step.max(1))
+    }
+
+    /// The accepted resolution closest to `dpi`.
+    pub fn nearest(&self, dpi: u32) -> u32 {
+        let step = self.step.max(1);
+        let steps = (dpi.clamp(self.min, self.max) - self.min + step / 2) / step;
+        (self.min + steps * step).min(self.max)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Capabilities {
     pub sources: Vec<ScanSource>,
+    /// Discrete resolutions; for range devices, the common values in range.
     pub dpis: Vec<u32>,
+    /// Resolution ranges accepted besides `dpis`, empty for discrete devices.
+    pub dpi_ranges: Vec<DpiRange>,
+    /// Whether vertical resolution can be set apart from horizontal.
+    pub independent_dpi: bool,
     pub color_modes: Vec<ColorMode>,
-    pub page_sizes: Vec<PageSize>,
+    /// Bits per sample available for Color and Gray (8, 16); Bw is always 1.
+    pub bit_depths: Vec<u8>,
+    /// Catalogue sizes that fit the largest source.
+    pub page_sizes: Vec<PageFormat>,
+    /// Maximum scan area per source, where the backend knows it.
+    pub max_areas: Vec<SourceArea>,
     pub supports_duplex: bool,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+impl Capabilities {
+    /// True when `dpi` is one of `dpis` or falls on a step of a range.
+    pub fn supports_dpi(&self, dpi: u32) -> bool {
+        self.dpis.contains(&dpi) || self.dpi_ranges.iter().any(|range| range.contains(dpi))
+    }
+
+    /// Maximum scan area of `source`. Falls back to the largest page size
+    /// when the backend doesn't report areas per source.
+    pub fn max_area(&self, source: ScanSource) -> Option<PageSize> {
+        if let Some(area) = self.max_areas.iter().find(|area| area.source == source) {
+            return Some(area.max);
+        }
+        let sizes = self.page_sizes.iter().filter_map(PageFormat::size);
+        sizes.reduce(PageSize::union)
+    }
+
+    /// Catalogue sizes that fit `source`.
+    pub fn page_sizes_for(&self, source: ScanSource) -> Vec<PageFormat> {
+        match self.max_areas.iter().find(|area| area.source == source) {
+            Some(area) => PageFormat::fitting(&area.max),
+            None => self.page_sizes.clone(),
+        }
+    }
+
+    /// Dimensions to scan for `format` on `source`. `MaxArea` and `Auto`
+    /// resolve to the source's maximum area, leaving detection of the
+    /// actual document edges to the device.
+    pub fn resolve_page(&self, format: PageFormat, source: ScanSource) -> Option<PageSize> {
+        match format {
+            PageFormat::MaxArea | PageFormat::Auto => self.max_area(source),
+            _ => format.size(),
+        }
+    }
+}
+
+/// Part of the page to scan, in millimetres from its top-left corner; `f64`
+/// like `PageSize`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct ScanArea {
-    pub x_mm: u32,
-    pub y_mm: u32,
-    pub width_mm: u32,
-    pub height_mm: u32,
+    pub x_mm: f64,
+    pub y_mm: f64,
+    pub width_mm: f64,
+    pub height_mm: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,7 +536,34 @@ pub struct PageMeta {
     pub width_px: u32,
     pub height_px: u32,
     pub dpi: u32,
+    pub dpi_y: u32,
     pub color_mode: ColorMode,
+    /// Bits per sample of the page data.
+    pub bit_depth: u8,
+}
+
+/// What a session is doing or waiting on, reported by `ScanEvent::Status`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ScanStatus {
+    /// The device is busy or hasn't started yet; the session keeps retrying.
+    WaitingForDevice,
+    /// The lamp is warming up before the first page.
+    WarmingUp,
+    Scanning,
+}
+
+/// Kind of a recoverable problem reported by `ScanEvent::Warning`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WarningCode {
+    /// `ConfigPolicy::Coerce` changed a setting to fit the device.
+    ConfigAdjusted,
+    /// A device option couldn't be set and was skipped.
+    OptionNotApplied,
+    /// Cleaning up after the job failed, e.g. deleting an eSCL job.
+    CleanupFailed,
+    /// The scanner's preferred backend couldn't start the scan, so another
+    /// backend reaching the same scanner is used.
+    BackendFallback,
 }
 
 #[derive(Debug)]
@@ -78,14 +572,67 @@ pub enum ScanEvent {
     PageData(Vec<u8>),
     PageComplete(PageMeta),
     JobComplete,
+    /// Bytes of the current page received so far, out of `expected` when
+    /// the device says how large the page is.
+    Progress {
+        bytes: u64,
+        expected: Option<u64>,
+        percent: Option<f32>,
+    },
+    Status(ScanStatus),
+    /// Something went wrong but the scan carries on.
+    Warning {
+        code: WarningCode,
+        message: String,
+    },
+    /// The session was cancelled; no more events follow.
+    Cancelled,
+    /// The failure that ended the job; no more events follow. Sessions
+    /// return it as `Err` from `next_event`, `ScanEvents` and the FFI
+    /// deliver it as this event.
+    Error(PapyrError),
+}
+
+impl ScanEvent {
+    pub fn progress(bytes: u64, expected: Option<u64>) -> Self {
+        let percent = expected
+            .filter(|expected| *expected > 0)
+            .map(|expected| (bytes as f64 * 100.0 / expected as f64).min(100.0) as f32);
+        ScanEvent::Progress {
+            bytes,
+            expected,
+            percent,
+        }
+    }
+
+    pub fn warning(code: WarningCode, message: impl Into<String>) -> Self {
+        ScanEvent::Warning {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// True for the events after which a session has nothing more to report.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            ScanEvent::JobComplete | ScanEvent::Cancelled | ScanEvent::Error(_)
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanConfig {
     pub source: ScanSource,
     pub duplex: bool,
+    /// Horizontal resolution, and vertical unless `dpi_y` is set.
     pub dpi: u32,
+    /// Vertical resolution, for devices with independent X/Y resolution.
+    pub dpi_y: Option<u32>,
     pub color_mode: ColorMode,
+    /// Bits per sample (16 for 48-bit colour or 16-bit gray); `None` for
+    /// the mode's default.
+    pub bit_depth: Option<u8>,
     pub page_size: PageSize,
     pub area: Option<ScanArea>,
     pub brightness: Option<i32>, // device-specific range
@@ -94,6 +641,151 @@ pub struct ScanConfig {
     pub max_pages: Option<u32>,
 }
 
+impl ScanConfig {
+    /// Resolution along the feed direction.
+    pub fn vertical_dpi(&self) -> u32 {
+        self.dpi_y.unwrap_or(self.dpi)
+    }
+
+    /// Bits per sample to request: the configured depth, else 1 for Bw and
+    /// 8 otherwise.
+    pub fn bits_per_sample(&self) -> u8 {
+        match (self.color_mode, self.bit_depth) {
+            (ColorMode::Bw, _) => 1,
+            (_, Some(depth)) => depth,
+            (_, None) => 8,
+        }
+    }
+}
+
+/// Value type of a `DeviceOption`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OptionType {
+    Bool,
+    Int,
+    Fixed, // fractional number
+    String,
+    Button, // action without a value (e.g. calibrate, eject)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OptionUnit {
+    None,
+    Pixel,
+    Bit,
+    Mm,
+    Dpi,
+    Percent,
+    Microsecond,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i32),
+    Fixed(f64),
+    String(String),
+}
+
+impl std::fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionValue::Bool(b) => write!(f, "{}", b),
+            OptionValue::Int(i) => write!(f, "{}", i),
+            OptionValue::Fixed(x) => write!(f, "{}", x),
+            OptionValue::String(s) => f.write_str(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum OptionConstraint {
+    None,
+    Range {
+        min: f64,
+        max: f64,
+        step: Option<f64>,
+    },
+    List(Vec<OptionValue>),
+}
+
+/// A backend-specific device setting beyond `ScanConfig`, such as lamp
+/// timeout, double-feed detection or dropout colour.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeviceOption {
+    /// Stable identifier used with `set_option` (e.g. SANE's "lamp-switch").
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    /// Group the device files this option under, if any.
+    pub group: Option<String>,
+    pub value_type: OptionType,
+    pub unit: OptionUnit,
+    pub constraint: OptionConstraint,
+    /// Current value; `None` for buttons and values the device won't report.
+    pub value: Option<OptionValue>,
+    pub settable: bool,
+    /// Expert setting that UIs may hide by default.
+    pub advanced: bool,
+}
+
+impl DeviceOption {
+    /// Converts `value` to this option's type and checks it against the
+    /// constraint. Strings are parsed for non-string options, so values
+    /// typed by a user (or passed over FFI) can be handed in as-is.
+    pub fn coerce(&self, value: OptionValue) -> Result<OptionValue> {
+        let invalid = |value: &dyn std::fmt::Display| {
+            PapyrError::InvalidConfig(format!("invalid value {} for option {}", value, self.name))
+        };
+
+        if !self.settable {
+            return Err(PapyrError::InvalidConfig(format!(
+                "option {} is read-only",
+                self.name
+            )));
+        }
+
+        let value = match (self.value_type, value) {
+            (OptionType::Button, _) => return Ok(OptionValue::Bool(true)),
+            (OptionType::Bool, OptionValue::Bool(b)) => OptionValue::Bool(b),
+            (OptionType::Bool, OptionValue::Int(i)) => OptionValue::Bool(i != 0),
+            (OptionType::Bool, OptionValue::String(s)) => match s.trim().to_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => OptionValue::Bool(true),
+                "false" | "0" | "no" | "off" => OptionValue::Bool(false),
+                _ => return Err(invalid(&s)),
+            },
+            (OptionType::Int, OptionValue::Int(i)) => OptionValue::Int(i),
+            (OptionType::Int, OptionValue::Fixed(x)) => OptionValue::Int(x.round() as i32),
+            (OptionType::Int, OptionValue::String(s)) => {
+                OptionValue::Int(s.trim().parse().map_err(|_| invalid(&s))?)
+            }
+            (OptionType::Fixed, OptionValue::Fixed(x)) => OptionValue::Fixed(x),
+            (OptionType::Fixed, OptionValue::Int(i)) => OptionValue::Fixed(i as f64),
+            (OptionType::Fixed, OptionValue::String(s)) => {
+                OptionValue::Fixed(s.trim().parse().map_err(|_| invalid(&s))?)
+            }
+            (OptionType::String, value) => OptionValue::String(value.to_string()),
+            (_, value) => return Err(invalid(&value)),
+        };
+
+        let allowed = match (&self.constraint, &value) {
+            (OptionConstraint::Range { min, max, .. }, OptionValue::Int(i)) => {
+                (*min..=*max).contains(&(*i as f64))
+            }
+            (OptionConstraint::Range { min, max, .. }, OptionValue::Fixed(x)) => {
+                (*min..=*max).contains(x)
+            }
+            (OptionConstraint::List(values), value) => values.contains(value),
+            _ => true,
+        };
+        if !allowed {
+            return Err(invalid(&value));
+        }
+
+        Ok(value)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum PapyrError {
     #[error("scanner not found: {0}")]
@@ -102,9 +794,40 @@ pub enum PapyrError {
     #[error("invalid config: {0}")]
     InvalidConfig(String),
 
+    /// Backend failure that fits none of the categories below.
     #[error("backend error: {0}")]
     Backend(String),
 
+    #[error("device busy: {message}")]
+    DeviceBusy { backend: Backend, message: String },
+
+    #[error("document feeder empty: {message}")]
+    FeederEmpty { backend: Backend, message: String },
+
+    #[error("paper jam: {message}")]
+    PaperJam { backend: Backend, message: String },
+
+    #[error("cover open: {message}")]
+    CoverOpen { backend: Backend, message: String },
+
+    #[error("timed out: {message}")]
+    Timeout { backend: Backend, message: String },
+
+    #[error("scan cancelled")]
+    Cancelled,
+
+    #[error("unauthorized: {message}")]
+    Unauthorized { backend: Backend, message: String },
+
+    #[error("unsupported: {option}")]
+    Unsupported { backend: Backend, option: String },
+
+    #[error("I/O error: {message}")]
+    Io { backend: Backend, message: String },
+
+    #[error("protocol error: {message}")]
+    Protocol { backend: Backend, message: String },
+
     #[error("not implemented")]
     NotImplemented,
 
@@ -112,6 +835,55 @@ pub enum PapyrError {
     Other(String),
 }
 
+impl PapyrError {
+    /// The backend that raised the error, for the structured variants.
+    pub fn backend(&self) -> Option<Backend> {
+        match self {
+            PapyrError::DeviceBusy { backend, .. }
+            | PapyrError::FeederEmpty { backend, .. }
+            | PapyrError::PaperJam { backend, .. }
+            | PapyrError::CoverOpen { backend, .. }
+            | PapyrError::Timeout { backend, .. }
+            | PapyrError::Unauthorized { backend, .. }
+            | PapyrError::Unsupported { backend, .. }
+            | PapyrError::Io { backend, .. }
+            | PapyrError::Protocol { backend, .. } => Some(*backend),
+            _ => None,
+        }
+    }
+
+    /// True for transient failures where repeating the same call may succeed
+    /// without anyone touching the scanner. A jam, an empty feeder or an open
+    /// cover need the user first, so they are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            PapyrError::DeviceBusy { .. } | PapyrError::Timeout { .. } | PapyrError::Io { .. }
+        )
+    }
+
+    /// Stable numeric code, as exposed through the C API. Never renumber.
+    pub fn code(&self) -> i32 {
+        match self {
+            PapyrError::NotFound(_) => 1,
+            PapyrError::InvalidConfig(_) => 2,
+            PapyrError::Backend(_) => 3,
+            PapyrError::DeviceBusy { .. } => 4,
+            PapyrError::FeederEmpty { .. } => 5,
+            PapyrError::PaperJam { .. } => 6,
+            PapyrError::CoverOpen { .. } => 7,
+            PapyrError::Timeout { .. } => 8,
+            PapyrError::Cancelled => 9,
+            PapyrError::Unauthorized { .. } => 10,
+            PapyrError::Unsupported { .. } => 11,
+            PapyrError::Io { .. } => 12,
+            PapyrError::Protocol { .. } => 13,
+            PapyrError::NotImplemented => 14,
+            PapyrError::Other(_) => 15,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, PapyrError>;
 
 #[cfg(windows)]
@@ -125,14 +897,254 @@ pub trait BackendProvider: Send + Sync {
     fn name(&self) -> &'static str;
     fn kind(&self) -> Backend;
 
+    /// Reports whether the backend's system dependencies were found.
+    fn availability(&self) -> BackendAvailability {
+        BackendAvailability::Available
+    }
+
     fn enumerate(&self) -> Vec<ScannerInfo>;
 
-    fn capabilities(&self, device_id: &str) -> Result<Capabilities>;
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities>;
+
+    fn start_scan(&self, device_id: &DeviceId, cfg: ScanConfig) -> Result<Box<dyn ScanSession>>;
+
+    /// `enumerate` that stops early once `cancel` fires, returning what was
+    /// found so far. Backends with slow discovery check it between steps;
+    /// the default only checks before starting.
+    fn enumerate_cancellable(&self, cancel: &CancelToken) -> Vec<ScannerInfo> {
+        if cancel.is_cancelled() {
+            return Vec::new();
+        }
+        self.enumerate()
+    }
 
-    fn start_scan(&self, device_id: &str, cfg: ScanConfig) -> Result<Box<dyn ScanSession>>;
+    /// `capabilities` that fails with `Cancelled` once `cancel` fires.
+    fn capabilities_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cancel: &CancelToken,
+    ) -> Result<Capabilities> {
+        cancel.check()?;
+        self.capabilities(device_id)
+    }
+
+    /// `start_scan` that fails with `Cancelled` if `cancel` fires while the
+    /// scan is starting, and afterwards cancels the session through its
+    /// `CancelHandle`.
+    fn start_scan_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cfg: ScanConfig,
+        cancel: &CancelToken,
+    ) -> Result<Box<dyn ScanSession>> {
+        cancel.check()?;
+        let session = self.start_scan(device_id, cfg)?;
+        if let Some(handle) = session.cancel_handle() {
+            cancel.on_cancel(handle);
+        }
+        Ok(session)
+    }
+
+    /// Backend-specific settings of a device, with their current values.
+    fn options(&self, _device_id: &DeviceId) -> Result<Vec<DeviceOption>> {
+        Err(PapyrError::NotImplemented)
+    }
+
+    /// Changes a device setting and returns the option as the device now
+    /// reports it (it may round the value). The setting is remembered for
+    /// the device and applied at the start of every later scan.
+    fn set_option(
+        &self,
+        _device_id: &DeviceId,
+        _name: &str,
+        _value: OptionValue,
+    ) -> Result<DeviceOption> {
+        Err(PapyrError::NotImplemented)
+    }
+
+    /// Natively async scan, for backends with async I/O. `None` (the
+    /// default) makes `async_api::start_scan` run `start_scan` on the
+    /// blocking pool instead.
+    #[cfg(feature = "async")]
+    fn start_scan_async(
+        &self,
+        _device_id: &DeviceId,
+        _config: &ScanConfig,
+    ) -> Option<crate::async_api::BoxFuture<'static, Result<crate::async_api::ScanEventStream>>>
+    {
+        None
+    }
 }
 
 pub trait ScanSession: Send {
-    /// Returns next event, or Ok(None) when finished.
+    /// Returns next event, or Ok(None) when finished. A failure that ends
+    /// the job is returned as `Err` once, followed by `Ok(None)`.
     fn next_event(&mut self) -> Result<Option<ScanEvent>>;
+
+    /// Handle for aborting the session from another thread, if the backend
+    /// supports it. After cancelling, `next_event` returns
+    /// `ScanEvent::Cancelled` once and `Ok(None)` from then on.
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        None
+    }
+}
+
+/// A session's events as an iterator, with the failure that ends the job
+/// delivered as `ScanEvent::Error` instead of `Err`.
+pub struct ScanEvents {
+    session: Box<dyn ScanSession>,
+    done: bool,
+}
+
+impl ScanEvents {
+    pub fn new(session: Box<dyn ScanSession>) -> Self {
+        Self {
+            session,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for ScanEvents {
+    type Item = ScanEvent;
+
+    fn next(&mut self) -> Option<ScanEvent> {
+        if self.done {
+            return None;
+        }
+        let event = match self.session.next_event() {
+            Ok(Some(event)) => event,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(PapyrError::Cancelled) => ScanEvent::Cancelled,
+            Err(e) => ScanEvent::Error(e),
+        };
+        self.done = matches!(event, ScanEvent::Cancelled | ScanEvent::Error(_));
+        Some(event)
+    }
+}
+
+/// Aborts a running scan session; cheap to clone and safe to call from any
+/// thread, any number of times.
+#[derive(Clone)]
+pub struct CancelHandle(Arc<dyn Fn() + Send + Sync>);
+
+impl CancelHandle {
+    pub fn new<F: Fn() + Send + Sync + 'static>(cancel: F) -> Self {
+        Self(Arc::new(cancel))
+    }
+
+    pub fn cancel(&self) {
+        (self.0)()
+    }
+}
+
+impl std::fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CancelHandle")
+    }
+}
+
+/// Cancels discovery, capability queries and scans from another thread.
+/// Clones share one state and cancelling is permanent, so use a fresh
+/// token per operation. Backends check it between I/O steps; I/O already
+/// blocked is interrupted through the handles registered with `on_cancel`.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    next_handle: AtomicU64,
+    handles: Mutex<Vec<(u64, CancelHandle)>>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        if self.0.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let handles = match self.0.handles.lock() {
+            Ok(mut handles) => std::mem::take(&mut *handles),
+            Err(_) => return,
+        };
+        for (_, handle) in handles {
+            handle.cancel();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// `Err(Cancelled)` once the token has been cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(PapyrError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Runs `handle` when the token is cancelled, or straight away if it
+    /// already has been.
+    pub fn on_cancel(&self, handle: CancelHandle) {
+        self.register(handle);
+    }
+
+    /// `on_cancel` for as long as the returned registration is kept, so an
+    /// operation on a long-lived token leaves no handles behind.
+    pub fn on_cancel_scoped(&self, handle: CancelHandle) -> CancelRegistration {
+        CancelRegistration {
+            token: self.clone(),
+            id: self.register(handle),
+        }
+    }
+
+    // Id of the stored handle, None if it ran straight away
+    fn register(&self, handle: CancelHandle) -> Option<u64> {
+        if let Ok(mut handles) = self.0.handles.lock() {
+            // Checked under the lock, so a concurrent `cancel` either sees
+            // the handle or has already set the flag
+            if !self.is_cancelled() {
+                let id = self.0.next_handle.fetch_add(1, Ordering::Relaxed);
+                handles.push((id, handle));
+                return Some(id);
+            }
+        }
+        handle.cancel();
+        None
+    }
+}
+
+/// A handle registered with `CancelToken::on_cancel_scoped`; dropping it
+/// unregisters the handle.
+#[must_use = "dropping the registration unregisters the handle at once"]
+pub struct CancelRegistration {
+    token: CancelToken,
+    id: Option<u64>,
+}
+
+impl Drop for CancelRegistration {
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+        if let Ok(mut handles) = self.token.0.handles.lock() {
+            handles.retain(|(handle_id, _)| *handle_id != id);
+        }
+    }
+}
+
+impl std::fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CancelToken")
+            .field(&self.is_cancelled())
+            .finish()
+    }
 }
@@ -6,10 +6,32 @@
 //  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
 //
 
+#[cfg(feature = "async")]
+use crate::async_api::{self, ScanEventStream};
 use crate::backends::escl::EsclBackend;
+use crate::backends::plugin::PluginBackend;
+use crate::backends::saned::SaneNetBackend;
+use crate::config::{RegistryBuilder, RegistryConfig, ScanDefaults};
+use crate::dedup;
 use crate::models::{
-    BackendProvider, Capabilities, PapyrError, Result, ScanConfig, ScanSession, ScannerInfo,
+    Backend, BackendAvailability, BackendProvider, CancelHandle, CancelToken, Capabilities,
+    DeviceId, DeviceOption, OptionValue, PapyrError, Result, ScanConfig, ScanEvent, ScanSession,
+    ScannerInfo, WarningCode,
 };
+use crate::sensors::SensorMonitor;
+use crate::session_guard::{GuardedSession, SessionLimits};
+use crate::validation::ConfigPolicy;
+#[cfg(feature = "async")]
+use futures_util::StreamExt;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, debug_span, error, info, info_span, warn, Span};
 
 #[cfg(any(target_os = "windows", target_os = "macos"))]
 use crate::backends::twain::TwainBackend;
@@ -24,101 +46,892 @@ use crate::backends::ica::IcaBackend;
 #[cfg(feature = "sane")]
 use crate::backends::sane::SaneBackend;
 
+#[cfg(feature = "virtual")]
+use crate::backends::virtual_scanner::VirtualBackend;
+
+/// How long discovered devices are reused before `list_devices` and device
+/// lookups discover again.
+pub const DEFAULT_DEVICE_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// How long each backend may take to enumerate before discovery carries on
+/// without it. eSCL browses three mDNS service types for 10 s each.
+pub const DEFAULT_ENUMERATION_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Devices found by `discover_devices`, and how each backend fared.
+#[derive(Debug, Clone)]
+pub struct DeviceDiscovery {
+    pub devices: Vec<ScannerInfo>,
+    /// One entry per registered backend, in registration order.
+    pub backends: Vec<BackendDiscovery>,
+}
+
+impl DeviceDiscovery {
+    /// Whether every available backend finished enumerating.
+    pub fn is_complete(&self) -> bool {
+        self.backends.iter().all(|backend| {
+            matches!(
+                backend.status,
+                DiscoveryStatus::Ok | DiscoveryStatus::Unavailable(_)
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BackendDiscovery {
+    pub backend: Backend,
+    pub name: &'static str,
+    pub status: DiscoveryStatus,
+    /// Devices the backend found; 0 unless the status is `Ok`.
+    pub devices: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryStatus {
+    Ok,
+    /// Still enumerating when its timeout passed; it was cancelled.
+    TimedOut,
+    /// Enumeration panicked, with the panic message.
+    Failed(String),
+    /// Not usable on this machine; it wasn't asked.
+    Unavailable(String),
+}
+
 pub struct BackendRegistry {
-    providers: Vec<Box<dyn BackendProvider>>,
+    // Shared so background work (sensor monitors) can outlive a borrow
+    providers: Vec<Arc<dyn BackendProvider>>,
+    config_policy: ConfigPolicy,
+    session_limits: SessionLimits,
+    devices: Arc<Mutex<DeviceCache>>,
+    device_cache_ttl: Duration,
+    enumeration_timeout: Duration,
+    backend_enumeration_timeouts: HashMap<Backend, Duration>,
+    scan_defaults: ScanDefaults,
+}
+
+/// Devices from the last complete discovery, and the backend of each.
+#[derive(Default)]
+struct DeviceCache {
+    devices: Vec<ScannerInfo>,
+    owners: HashMap<DeviceId, Arc<dyn BackendProvider>>,
+    refreshed: Option<Instant>,
+    // Every backend finished; otherwise unknown ids are looked for again
+    complete: bool,
+}
+
+impl DeviceCache {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        self.refreshed.is_some_and(|at| at.elapsed() < ttl)
+    }
 }
 
 impl BackendRegistry {
+    /// A registry with the platform's backends.
     pub fn new() -> Self {
-        let mut registry = Self {
+        Self::from_config(&RegistryConfig::default())
+    }
+
+    pub fn builder() -> RegistryBuilder {
+        RegistryBuilder::new()
+    }
+
+    /// A registry with the backends and settings of `config`.
+    pub fn from_config(config: &RegistryConfig) -> Self {
+        let mut registry = Self::empty();
+        for backend in config.backends() {
+            if let Some(provider) = default_provider(backend, config) {
+                registry.register(provider);
+            }
+        }
+        for dir in &config.plugin_dirs {
+            if let Err(e) = registry.load_plugins(dir) {
+                warn!(dir = %dir.display(), "could not load plugins: {}", e);
+            }
+        }
+
+        if let Some(ms) = config.discovery_timeout_ms {
+            registry.set_enumeration_timeout(Duration::from_millis(ms));
+        }
+        if let Some(ms) = config.device_cache_ttl_ms {
+            registry.set_device_cache_ttl(Duration::from_millis(ms));
+        }
+        registry.scan_defaults = config.scan.clone();
+        registry
+    }
+
+    /// A registry without backends, for registering a custom set.
+    pub fn empty() -> Self {
+        Self {
             providers: Vec::new(),
-        };
+            config_policy: ConfigPolicy::default(),
+            session_limits: SessionLimits::default(),
+            devices: Arc::new(Mutex::new(DeviceCache::default())),
+            device_cache_ttl: DEFAULT_DEVICE_CACHE_TTL,
+            enumeration_timeout: DEFAULT_ENUMERATION_TIMEOUT,
+            backend_enumeration_timeouts: HashMap::new(),
+            scan_defaults: ScanDefaults::default(),
+        }
+    }
 
-        // Register platform defaults first, then generic ones afterwards
-        #[cfg(target_os = "macos")]
-        {
-            println!("🔧 Registering ICA backend (macOS)");
-            registry.register(Box::new(IcaBackend::new()));
+    /// Adds a backend. Devices are discovered again on next use.
+    pub fn register(&mut self, provider: Box<dyn BackendProvider>) {
+        self.providers.push(Arc::from(provider));
+        *self.cache() = DeviceCache::default();
+    }
+
+    /// Loads and registers every plugin in `dir`: the files with this
+    /// platform's library extension (`.so`, `.dylib` or `.dll`), in name
+    /// order. Plugins that fail to load, or whose name another plugin
+    /// already has, are skipped. Returns how many were registered.
+    pub fn load_plugins(&mut self, dir: impl AsRef<Path>) -> Result<usize> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            PapyrError::InvalidConfig(format!(
+                "Can't read plugin directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION)
+            })
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            let plugin = match PluginBackend::load(&path) {
+                Ok(plugin) => plugin,
+                Err(e) => {
+                    warn!("skipping plugin: {}", e);
+                    continue;
+                }
+            };
+            if self.providers.iter().any(|provider| {
+                provider.kind() == Backend::Plugin && provider.name() == plugin.name()
+            }) {
+                warn!(
+                    path = %path.display(),
+                    plugin = plugin.name(),
+                    "skipping plugin: a plugin of that name is already registered"
+                );
+                continue;
+            }
+            debug!(plugin = plugin.name(), "registering plugin backend");
+            self.register(Box::new(plugin));
+            loaded += 1;
         }
+        Ok(loaded)
+    }
 
-        // WIA is primary backend on Windows
-        #[cfg(target_os = "windows")]
+    /// How long discovered devices are reused; zero discovers every time.
+    pub fn set_device_cache_ttl(&mut self, ttl: Duration) {
+        self.device_cache_ttl = ttl;
+    }
+
+    /// How long each backend may take to enumerate.
+    pub fn set_enumeration_timeout(&mut self, timeout: Duration) {
+        self.enumeration_timeout = timeout;
+    }
+
+    /// Enumeration timeout for one kind of backend, overriding
+    /// `set_enumeration_timeout`.
+    pub fn set_backend_enumeration_timeout(&mut self, backend: Backend, timeout: Duration) {
+        self.backend_enumeration_timeouts.insert(backend, timeout);
+    }
+
+    /// Chooses whether `start_scan` rejects configs the device can't honour
+    /// (the default) or adjusts them to the nearest supported settings.
+    pub fn set_config_policy(&mut self, policy: ConfigPolicy) {
+        self.config_policy = policy;
+    }
+
+    /// The configured scan settings, for applications to start from.
+    pub fn default_scan_config(&self) -> ScanConfig {
+        self.scan_defaults.to_config()
+    }
+
+    /// Timeouts for the sessions `start_scan` returns; none by default.
+    pub fn set_session_limits(&mut self, limits: SessionLimits) {
+        self.session_limits = limits;
+    }
+
+    /// Availability of every registered backend, in registration order.
+    pub fn availability(&self) -> Vec<(Backend, BackendAvailability)> {
+        self.providers
+            .iter()
+            .map(|provider| (provider.kind(), provider.availability()))
+            .collect()
+    }
+
+    /// Devices of every available backend. Devices discovered within the
+    /// cache TTL are returned without discovering again, unless a backend
+    /// timed out or failed then.
+    pub fn list_devices(&self) -> Result<Vec<ScannerInfo>> {
+        self.list_devices_cancellable(&CancelToken::new())
+    }
+
+    /// `list_devices` that stops discovery and fails with `Cancelled` once
+    /// `cancel` fires.
+    pub fn list_devices_cancellable(&self, cancel: &CancelToken) -> Result<Vec<ScannerInfo>> {
         {
-            println!("🔧 Registering WIA backend (Windows primary)");
-            registry.register(Box::new(WiaBackend::new()));
+            let cache = self.cache();
+            if cache.complete && cache.is_fresh(self.device_cache_ttl) {
+                debug!(devices = cache.devices.len(), "using cached devices");
+                return Ok(cache.devices.clone());
+            }
         }
+        self.refresh_devices_cancellable(cancel)
+    }
 
-        // TWAIN as fallback for Windows and macOS
-        #[cfg(any(target_os = "windows", target_os = "macos"))]
-        {
-            println!("🔧 Registering TWAIN backend (fallback)");
-            registry.register(Box::new(TwainBackend::new()));
+    /// Discovers devices on every available backend, replacing the cache.
+    pub fn refresh_devices(&self) -> Result<Vec<ScannerInfo>> {
+        self.refresh_devices_cancellable(&CancelToken::new())
+    }
+
+    /// `refresh_devices` that stops discovery and fails with `Cancelled`
+    /// once `cancel` fires; the cache is left as it was.
+    pub fn refresh_devices_cancellable(&self, cancel: &CancelToken) -> Result<Vec<ScannerInfo>> {
+        self.discover_devices(cancel)
+            .map(|discovery| discovery.devices)
+    }
+
+    /// Enumerates every available backend at once, each on its own thread
+    /// with its own timeout, and replaces the cache. Backends that time out
+    /// or panic are reported in `backends` rather than failing the call;
+    /// only cancelling does.
+    pub fn discover_devices(&self, cancel: &CancelToken) -> Result<DeviceDiscovery> {
+        let span = info_span!("discovery");
+        let _entered = span.enter();
+        debug!("querying all registered backends for devices");
+        cancel.check()?;
+        let started = Instant::now();
+        // None wakes the wait below when `cancel` fires
+        let (tx, results) = mpsc::channel::<Option<(usize, thread::Result<Vec<ScannerInfo>>)>>();
+        let mut backends = Vec::with_capacity(self.providers.len());
+        let mut running = HashMap::new();
+        // Dropped on return, so a token reused across discoveries doesn't
+        // collect handles
+        let mut registrations = Vec::with_capacity(self.providers.len() + 1);
+
+        for (i, provider) in self.providers.iter().enumerate() {
+            let mut discovery = BackendDiscovery {
+                backend: provider.kind(),
+                name: provider.name(),
+                status: DiscoveryStatus::TimedOut,
+                devices: 0,
+                elapsed: Duration::ZERO,
+            };
+            if let BackendAvailability::Unavailable(reason) = provider.availability() {
+                debug!(backend = provider.name(), %reason, "backend unavailable, skipped");
+                discovery.status = DiscoveryStatus::Unavailable(reason);
+                backends.push(discovery);
+                continue;
+            }
+            backends.push(discovery);
+            let timeout = self
+                .backend_enumeration_timeouts
+                .get(&provider.kind())
+                .copied()
+                .unwrap_or(self.enumeration_timeout);
+
+            debug!(backend = provider.name(), ?timeout, "discovering devices");
+            // A token per backend, so a timeout stops only that backend
+            let backend_cancel = CancelToken::new();
+            let forward = backend_cancel.clone();
+            registrations
+                .push(cancel.on_cancel_scoped(CancelHandle::new(move || forward.cancel())));
+
+            let provider = Arc::clone(provider);
+            let thread_cancel = backend_cancel.clone();
+            let tx = tx.clone();
+            let enumerate_span = debug_span!("enumerate", backend = provider.name());
+            thread::spawn(move || {
+                let _entered = enumerate_span.enter();
+                let found = panic::catch_unwind(AssertUnwindSafe(|| {
+                    provider.enumerate_cancellable(&thread_cancel)
+                }));
+                let _ = tx.send(Some((i, found)));
+            });
+            running.insert(i, (started + timeout, backend_cancel));
         }
+        registrations.push(cancel.on_cancel_scoped(CancelHandle::new(move || {
+            let _ = tx.send(None);
+        })));
 
-        #[cfg(feature = "sane")]
+        let mut found = vec![Vec::new(); self.providers.len()];
+        while let Some(deadline) = running.values().map(|(deadline, _)| *deadline).min() {
+            match results.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(Some((i, result))) => {
+                    // Backends that already timed out stay timed out
+                    if running.remove(&i).is_none() {
+                        continue;
+                    }
+                    let discovery = &mut backends[i];
+                    discovery.elapsed = started.elapsed();
+                    match result {
+                        Ok(devices) => {
+                            info!(
+                                backend = discovery.name,
+                                devices = devices.len(),
+                                elapsed = ?discovery.elapsed,
+                                "enumeration finished"
+                            );
+                            discovery.status = DiscoveryStatus::Ok;
+                            discovery.devices = devices.len();
+                            found[i] = devices;
+                        }
+                        Err(panic) => {
+                            let message = panic_message(panic);
+                            error!(
+                                backend = discovery.name,
+                                "enumeration panicked: {}", message
+                            );
+                            discovery.status = DiscoveryStatus::Failed(message);
+                        }
+                    }
+                }
+                Ok(None) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    running.retain(|&i, (deadline, backend_cancel)| {
+                        if *deadline > now {
+                            return true;
+                        }
+                        let discovery = &mut backends[i];
+                        warn!(backend = discovery.name, "enumeration timed out");
+                        backend_cancel.cancel();
+                        discovery.elapsed = started.elapsed();
+                        false
+                    });
+                }
+            }
+        }
+        cancel.check()?;
+
+        let mut devices = Vec::new();
+        let mut owners = HashMap::new();
+        for (provider, found) in self.providers.iter().zip(found) {
+            for device in &found {
+                owners
+                    .entry(device.id.clone())
+                    .or_insert_with(|| Arc::clone(provider));
+            }
+            devices.extend(found);
+        }
+        let found = devices.len();
+        let devices = dedup::merge_devices(devices);
+        info!(
+            devices = found,
+            scanners = devices.len(),
+            elapsed = ?started.elapsed(),
+            "discovery finished"
+        );
+
+        let discovery = DeviceDiscovery { devices, backends };
+        *self.cache() = DeviceCache {
+            devices: discovery.devices.clone(),
+            owners,
+            refreshed: Some(Instant::now()),
+            complete: discovery.is_complete(),
+        };
+        Ok(discovery)
+    }
+
+    fn cache(&self) -> MutexGuard<'_, DeviceCache> {
+        // The cache is replaced whole, so a panic can't leave it half-written
+        self.devices.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The backend that found the device, discovering again first if the
+    /// cache has expired, or lacks the device after a discovery some
+    /// backend didn't finish. Ids of a kind no registered backend has are
+    /// `NotFound` at once; unknown ids, and ids of devices gone since the
+    /// last discovery, are `NotFound` after the lookup.
+    fn owner(
+        &self,
+        device_id: &DeviceId,
+        cancel: &CancelToken,
+    ) -> Result<Arc<dyn BackendProvider>> {
+        if !self
+            .providers
+            .iter()
+            .any(|provider| provider.kind() == device_id.backend())
         {
-            println!("🔧 Registering SANE backend (Linux)");
-            registry.register(Box::new(SaneBackend::new()));
+            return Err(PapyrError::NotFound(format!(
+                "Device {}: no {:?} backend is registered",
+                device_id,
+                device_id.backend()
+            )));
         }
 
-        // eSCL is always available (cross-platform network scanning)
-        println!("🔧 Registering eSCL backend (network scanners)");
-        registry.register(Box::new(EsclBackend::new()));
+        let known = {
+            let cache = self.cache();
+            cache.is_fresh(self.device_cache_ttl)
+                && (cache.complete || cache.owners.contains_key(device_id))
+        };
+        if !known {
+            self.refresh_devices_cancellable(cancel)?;
+        }
 
-        registry
+        let cache = self.cache();
+        let owner = cache.owners.get(device_id).cloned().ok_or_else(|| {
+            PapyrError::NotFound(format!(
+                "Device {} not found in any backend (discovered {} devices {:?} ago)",
+                device_id,
+                cache.devices.len(),
+                cache.refreshed.map(|at| at.elapsed()).unwrap_or_default()
+            ))
+        })?;
+        debug!(device = %device_id, backend = owner.name(), "found device");
+        Ok(owner)
     }
 
-    pub fn register(&mut self, provider: Box<dyn BackendProvider>) {
-        self.providers.push(provider);
+    pub fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        self.capabilities_cancellable(device_id, &CancelToken::new())
     }
 
-    pub fn list_devices(&self) -> Result<Vec<ScannerInfo>> {
-        println!("📡 Querying all registered backends for devices...");
-        let mut scanners_info = Vec::new();
+    /// `capabilities` that fails with `Cancelled` once `cancel` fires.
+    pub fn capabilities_cancellable(
+        &self,
+        device_id: &DeviceId,
+        cancel: &CancelToken,
+    ) -> Result<Capabilities> {
+        self.owner(device_id, cancel)?
+            .capabilities_cancellable(device_id, cancel)
+    }
 
-        for (i, provider) in self.providers.iter().enumerate() {
-            println!(
-                "🔍 Backend {}: {} - discovering devices...",
-                i + 1,
-                provider.name()
+    /// Backend-specific settings of a device.
+    pub fn options(&self, device_id: &DeviceId) -> Result<Vec<DeviceOption>> {
+        self.owner(device_id, &CancelToken::new())?
+            .options(device_id)
+    }
+
+    /// Changes a device setting; it is kept for later scans of the device.
+    pub fn set_option(
+        &self,
+        device_id: &DeviceId,
+        name: &str,
+        value: OptionValue,
+    ) -> Result<DeviceOption> {
+        self.owner(device_id, &CancelToken::new())?
+            .set_option(device_id, name, value)
+    }
+
+    /// Polls the device's buttons and sensors every `interval` (see
+    /// `sensors::DEFAULT_SENSOR_POLL_INTERVAL`) until the monitor is dropped.
+    pub fn monitor_sensors(
+        &self,
+        device_id: &DeviceId,
+        interval: Duration,
+    ) -> Result<SensorMonitor> {
+        let provider = self.owner(device_id, &CancelToken::new())?;
+        let options = provider.options(device_id)?;
+
+        info!(device = %device_id, ?interval, "monitoring sensors");
+        SensorMonitor::spawn(provider, device_id.clone(), &options, interval)
+    }
+
+    /// Starts a scan. The session stops after `config.max_pages` pages,
+    /// enforces the session limits and always has a cancel handle, whether
+    /// or not the backend can stop mid-page. If the device's backend can't
+    /// start the scan, the other backends reaching the same scanner are
+    /// tried in turn, with a `BackendFallback` warning.
+    pub fn start_scan(
+        &self,
+        device_id: &DeviceId,
+        config: ScanConfig,
+    ) -> Result<Box<dyn ScanSession>> {
+        self.start_scan_cancellable(device_id, config, &CancelToken::new())
+    }
+
+    /// `start_scan` that fails with `Cancelled` if `cancel` fires before the
+    /// scan has started, and cancels the session if it fires later.
+    pub fn start_scan_cancellable(
+        &self,
+        device_id: &DeviceId,
+        config: ScanConfig,
+        cancel: &CancelToken,
+    ) -> Result<Box<dyn ScanSession>> {
+        let span = info_span!("scan", device = %device_id);
+        let _entered = span.enter();
+        let mut fallbacks = Vec::new();
+        let mut first_error = None;
+
+        for route in self.scan_routes(device_id, cancel)? {
+            let started = self.prepare_scan(&route, config.clone(), cancel).and_then(
+                |(provider, config, warnings)| {
+                    let max_pages = config.max_pages;
+                    let session = provider.start_scan_cancellable(&route, config, cancel)?;
+                    Ok((provider, session, max_pages, warnings))
+                },
             );
-            let devices = provider.enumerate();
-            println!("   Found {} devices", devices.len());
-            scanners_info.extend(devices);
+
+            match started {
+                Ok((provider, session, max_pages, warnings)) => {
+                    let session = GuardedSession::new(
+                        session,
+                        provider.kind(),
+                        max_pages,
+                        self.session_limits,
+                    );
+                    fallbacks.extend(warnings);
+                    return Ok(Box::new(RegistrySession {
+                        warnings: fallbacks.into(),
+                        poll_cancel: session.cancel_handle().is_none(),
+                        session: Box::new(session),
+                        cancel: cancel.clone(),
+                        cancelled: false,
+                        span: span.clone(),
+                        page: None,
+                    }));
+                }
+                Err(e) if !falls_back(&e) => return Err(e),
+                Err(e) => {
+                    warn!(route = %route, "could not start scan: {}", e);
+                    fallbacks.push(fallback_warning(&route, &e));
+                    first_error.get_or_insert(e);
+                }
+            }
         }
+        Err(first_error.unwrap_or_else(|| {
+            PapyrError::NotFound(format!("Device {} not found in any backend", device_id))
+        }))
+    }
 
-        println!(
-            "🎯 Total devices found across all backends: {}",
-            scanners_info.len()
-        );
-        Ok(scanners_info)
+    /// Ids to scan `device_id` through: its own first, then those of the
+    /// other backends reaching the same scanner, most preferred first.
+    fn scan_routes(&self, device_id: &DeviceId, cancel: &CancelToken) -> Result<Vec<DeviceId>> {
+        self.owner(device_id, cancel)?;
+
+        let cache = self.cache();
+        let mut routes = vec![device_id.clone()];
+        if let Some(device) = cache
+            .devices
+            .iter()
+            .find(|device| device.routes.contains(device_id))
+        {
+            routes.extend(
+                device
+                    .routes
+                    .iter()
+                    .filter(|route| *route != device_id)
+                    .cloned(),
+            );
+        }
+        Ok(routes)
+    }
+
+    /// Finds the backend that owns the device and applies the config
+    /// policy, returning the config to scan with and its warnings.
+    fn prepare_scan(
+        &self,
+        device_id: &DeviceId,
+        config: ScanConfig,
+        cancel: &CancelToken,
+    ) -> Result<(Arc<dyn BackendProvider>, ScanConfig, Vec<ScanEvent>)> {
+        info!(route = %device_id, "starting scan");
+
+        let provider = self.owner(device_id, cancel)?;
+        let (config, warnings) = self.check_config(&provider, device_id, config, cancel)?;
+        Ok((provider, config, warnings))
+    }
+
+    /// Applies the config policy against the device's capabilities, with a
+    /// `ConfigAdjusted` warning for each change made. Devices that can't
+    /// report capabilities get the config unchanged.
+    fn check_config(
+        &self,
+        provider: &Arc<dyn BackendProvider>,
+        device_id: &DeviceId,
+        config: ScanConfig,
+        cancel: &CancelToken,
+    ) -> Result<(ScanConfig, Vec<ScanEvent>)> {
+        let caps = match provider.capabilities_cancellable(device_id, cancel) {
+            Ok(caps) => caps,
+            Err(PapyrError::Cancelled) => return Err(PapyrError::Cancelled),
+            Err(e) => {
+                warn!(device = %device_id, "could not check scan config: {}", e);
+                return Ok((config, vec![]));
+            }
+        };
+
+        match self.config_policy {
+            ConfigPolicy::Validate => {
+                config.validate(&caps)?;
+                Ok((config, vec![]))
+            }
+            ConfigPolicy::Coerce => {
+                let (config, adjustments) = config.coerce(&caps)?;
+                let warnings = adjustments
+                    .iter()
+                    .map(|adjustment| {
+                        info!(%adjustment, "adjusted scan config");
+                        ScanEvent::warning(
+                            WarningCode::ConfigAdjusted,
+                            format!("Adjusted scan config: {}", adjustment),
+                        )
+                    })
+                    .collect();
+                Ok((config, warnings))
+            }
+        }
+    }
+}
+
+/// Async versions of the discovery and scan calls. Blocking backend work
+/// runs on tokio's blocking pool; eSCL scans use async HTTP throughout.
+#[cfg(feature = "async")]
+impl BackendRegistry {
+    /// A registry sharing this one's backends, to move onto another thread.
+    fn shared(&self) -> BackendRegistry {
+        BackendRegistry {
+            providers: self.providers.clone(),
+            config_policy: self.config_policy,
+            session_limits: self.session_limits,
+            devices: Arc::clone(&self.devices),
+            device_cache_ttl: self.device_cache_ttl,
+            enumeration_timeout: self.enumeration_timeout,
+            backend_enumeration_timeouts: self.backend_enumeration_timeouts.clone(),
+            scan_defaults: self.scan_defaults.clone(),
+        }
     }
 
-    pub fn capabilities(&self, device_id: &str) -> Result<Capabilities> {
-        for provider in &self.providers {
-            if let Ok(capabilities) = provider.capabilities(device_id) {
-                return Ok(capabilities);
+    pub async fn list_devices_async(&self) -> Result<Vec<ScannerInfo>> {
+        let registry = self.shared();
+        async_api::spawn_blocking(move || registry.list_devices()).await
+    }
+
+    pub async fn capabilities_async(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        let registry = self.shared();
+        let device_id = device_id.clone();
+        async_api::spawn_blocking(move || registry.capabilities(&device_id)).await
+    }
+
+    /// Starts a scan whose events arrive as a stream; see `start_scan`.
+    pub async fn start_scan_async(
+        &self,
+        device_id: &DeviceId,
+        config: ScanConfig,
+    ) -> Result<ScanEventStream> {
+        let registry = self.shared();
+        let owned_id = device_id.clone();
+        let routes =
+            async_api::spawn_blocking(move || registry.scan_routes(&owned_id, &CancelToken::new()))
+                .await?;
+
+        let mut fallbacks = Vec::new();
+        let mut first_error = None;
+        for route in routes {
+            let registry = self.shared();
+            let owned_route = route.clone();
+            let config = config.clone();
+            let prepared = async_api::spawn_blocking(move || {
+                registry.prepare_scan(&owned_route, config, &CancelToken::new())
+            })
+            .await;
+            let started = match prepared {
+                Ok((provider, config, warnings)) => {
+                    async_api::start_scan(provider, &route, config, self.session_limits)
+                        .await
+                        .map(|events| (events, warnings))
+                }
+                Err(e) => Err(e),
+            };
+
+            match started {
+                Ok((events, warnings)) => {
+                    fallbacks.extend(warnings);
+                    let warnings = futures_util::stream::iter(fallbacks.into_iter().map(Ok));
+                    return Ok(Box::pin(warnings.chain(events)));
+                }
+                Err(e) if !falls_back(&e) => return Err(e),
+                Err(e) => {
+                    warn!(route = %route, "could not start scan: {}", e);
+                    fallbacks.push(fallback_warning(&route, &e));
+                    first_error.get_or_insert(e);
+                }
             }
         }
+        Err(first_error.unwrap_or_else(|| {
+            PapyrError::NotFound(format!("Device {} not found in any backend", device_id))
+        }))
+    }
+}
 
-        Err(PapyrError::NotFound(device_id.to_string()))
+/// The backends this build registers by default, in priority order:
+/// platform backends first, then the network ones.
+pub fn default_backends() -> Vec<Backend> {
+    let mut backends = Vec::new();
+    #[cfg(target_os = "macos")]
+    backends.push(Backend::Ica);
+    // WIA is primary backend on Windows, TWAIN the fallback
+    #[cfg(target_os = "windows")]
+    backends.push(Backend::Wia);
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    backends.push(Backend::Twain);
+    #[cfg(feature = "sane")]
+    backends.push(Backend::Sane);
+    backends.extend([Backend::SaneNet, Backend::Escl]);
+    backends
+}
+
+/// Creates `backend` with its settings from `config`; `None` for backends
+/// this build lacks.
+fn default_provider(backend: Backend, config: &RegistryConfig) -> Option<Box<dyn BackendProvider>> {
+    match backend {
+        #[cfg(target_os = "macos")]
+        Backend::Ica => {
+            debug!("registering ICA backend");
+            Some(Box::new(IcaBackend::new()))
+        }
+        #[cfg(target_os = "windows")]
+        Backend::Wia => {
+            debug!("registering WIA backend");
+            Some(Box::new(WiaBackend::new()))
+        }
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        Backend::Twain => {
+            debug!("registering TWAIN backend");
+            Some(Box::new(TwainBackend::new()))
+        }
+        #[cfg(feature = "sane")]
+        Backend::Sane => {
+            debug!("registering SANE backend");
+            let sane = match &config.sane.library_path {
+                Some(path) => SaneBackend::with_library_path(path),
+                None => SaneBackend::new(),
+            };
+            if let BackendAvailability::Unavailable(reason) = sane.availability() {
+                warn!(%reason, "SANE backend unavailable");
+            }
+            Some(Box::new(sane))
+        }
+        // saned is unavailable until hosts are configured
+        Backend::SaneNet => {
+            debug!("registering SANE network backend");
+            Some(Box::new(if config.saned.hosts.is_empty() {
+                SaneNetBackend::new()
+            } else {
+                SaneNetBackend::with_hosts(&config.saned.hosts)
+            }))
+        }
+        Backend::Escl => {
+            debug!("registering eSCL backend");
+            Some(Box::new(
+                EsclBackend::new()
+                    .with_mdns(config.escl.mdns)
+                    .with_hosts(&config.escl.hosts),
+            ))
+        }
+        #[cfg(feature = "virtual")]
+        Backend::Virtual => {
+            debug!("registering virtual backend");
+            Some(Box::new(VirtualBackend::new(&config.virtual_scanner)))
+        }
+        _ => None,
     }
+}
+
+/// Whether a scan that failed to start is worth trying through another
+/// backend. Cancelling, a bad config and paper problems would fail the
+/// same way there.
+fn falls_back(e: &PapyrError) -> bool {
+    !matches!(
+        e,
+        PapyrError::Cancelled
+            | PapyrError::InvalidConfig(_)
+            | PapyrError::FeederEmpty { .. }
+            | PapyrError::PaperJam { .. }
+            | PapyrError::CoverOpen { .. }
+    )
+}
+
+fn fallback_warning(route: &DeviceId, e: &PapyrError) -> ScanEvent {
+    ScanEvent::warning(
+        WarningCode::BackendFallback,
+        format!("Could not scan through {}: {}", route, e),
+    )
+}
+
+/// The message a panic was raised with.
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked".to_string())
+}
+
+/// A backend session with the registry's own warnings reported first, and
+/// cancellable through its token.
+struct RegistrySession {
+    warnings: VecDeque<ScanEvent>,
+    session: Box<dyn ScanSession>,
+    cancel: CancelToken,
+    // The backend has no cancel handle, so the token is checked between
+    // events instead
+    poll_cancel: bool,
+    cancelled: bool,
+    // Backend logging happens within these, and the page's
+    span: Span,
+    page: Option<Span>,
+}
 
-    pub fn start_scan(&self, device_id: &str, config: ScanConfig) -> Result<Box<dyn ScanSession>> {
-        println!("🚀 Starting scan for device: {}", device_id);
+impl ScanSession for RegistrySession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        if let Some(warning) = self.warnings.pop_front() {
+            return Ok(Some(warning));
+        }
+        let _scan = self.span.enter();
+        if self.poll_cancel && self.cancel.is_cancelled() {
+            if self.cancelled {
+                return Ok(None);
+            }
+            self.cancelled = true;
+            info!("scan cancelled");
+            return Ok(Some(ScanEvent::Cancelled));
+        }
 
-        // First, find which backend owns this device
-        for provider in &self.providers {
-            let devices = provider.enumerate();
-            if devices.iter().any(|d| d.id == device_id) {
-                println!("📍 Found device in backend: {}", provider.name());
-                return provider.start_scan(device_id, config);
+        let event = {
+            let _page = self.page.as_ref().map(Span::enter);
+            self.session.next_event()
+        };
+        match &event {
+            Ok(Some(ScanEvent::PageStarted(index))) => {
+                let page = debug_span!("page", index);
+                page.in_scope(|| debug!("page started"));
+                self.page = Some(page);
+            }
+            Ok(Some(ScanEvent::PageComplete(meta))) => {
+                if let Some(page) = self.page.take() {
+                    page.in_scope(|| {
+                        debug!(
+                            width = meta.width_px,
+                            height = meta.height_px,
+                            dpi = meta.dpi,
+                            "page complete"
+                        )
+                    });
+                }
             }
+            Ok(Some(ScanEvent::Warning { code, message })) => {
+                warn!(?code, "{}", message);
+            }
+            Ok(Some(ScanEvent::JobComplete)) => info!("scan complete"),
+            Ok(Some(ScanEvent::Cancelled)) => info!("scan cancelled"),
+            Err(e) => error!("scan failed: {}", e),
+            _ => {}
         }
+        event
+    }
 
-        Err(PapyrError::NotFound(format!(
-            "Device {} not found in any backend",
-            device_id
-        )))
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancel = self.cancel.clone();
+        Some(CancelHandle::new(move || cancel.cancel()))
     }
 }
@@ -0,0 +1,221 @@
+//
+//  papyr_core
+//  sensors.rs - Polls read-only device options for button presses and paper sensors
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Poll interval used when the caller has no preference.
+pub const DEFAULT_SENSOR_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Sensor option names used by SANE drivers for "document in feeder"
+const PAPER_SENSORS: &[&str] = &[
+    "page-loaded",
+    "paper-loaded",
+    "document-loaded",
+    "adf-loaded",
+    "paper-in",
+    "sheet-loaded",
+];
+
+// Sensor option names for an open lid or ADF cover
+const COVER_SENSORS: &[&str] = &["cover-open", "lid-open", "adf-open"];
+
+// Button names drivers expose outside a "Sensors"/"Buttons" group
+const BUTTON_SENSORS: &[&str] = &["scan", "email", "copy", "pdf", "file", "fax", "ocr"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SensorEvent {
+    /// A hardware button was pressed; carries the option name (`scan`, `email`, ...).
+    ButtonPressed(String),
+    PaperLoaded,
+    PaperRemoved,
+    CoverOpened,
+    CoverClosed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SensorKind {
+    Button,
+    Paper,
+    Cover,
+}
+
+/// Classifies a read-only boolean option as a sensor, if it is one.
+fn sensor_kind(option: &DeviceOption) -> Option<SensorKind> {
+    if option.value_type != OptionType::Bool || option.settable {
+        return None;
+    }
+
+    let name = option.name.to_lowercase();
+    let group = option.group.as_deref().unwrap_or_default().to_lowercase();
+
+    if PAPER_SENSORS.contains(&name.as_str()) {
+        Some(SensorKind::Paper)
+    } else if COVER_SENSORS.contains(&name.as_str()) {
+        Some(SensorKind::Cover)
+    } else if BUTTON_SENSORS.contains(&name.as_str())
+        || name.ends_with("-button")
+        || group.contains("sensor")
+        || group.contains("button")
+    {
+        Some(SensorKind::Button)
+    } else {
+        None
+    }
+}
+
+/// Current state of every sensor among `options`.
+fn read_sensors(options: &[DeviceOption]) -> HashMap<String, (SensorKind, bool)> {
+    options
+        .iter()
+        .filter_map(|option| {
+            let kind = sensor_kind(option)?;
+            let on = matches!(option.value, Some(OptionValue::Bool(true)));
+            Some((option.name.clone(), (kind, on)))
+        })
+        .collect()
+}
+
+/// Events for the sensors that changed between two polls. Buttons only
+/// report presses; releases carry no meaning for the app.
+fn sensor_changes(
+    previous: &HashMap<String, (SensorKind, bool)>,
+    current: &HashMap<String, (SensorKind, bool)>,
+) -> Vec<SensorEvent> {
+    let mut names: Vec<&String> = current.keys().collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (kind, on) = current[name];
+            let was_on = previous.get(name).map(|(_, on)| *on).unwrap_or(false);
+            match (kind, was_on, on) {
+                (SensorKind::Button, false, true) => Some(SensorEvent::ButtonPressed(name.clone())),
+                (SensorKind::Paper, false, true) => Some(SensorEvent::PaperLoaded),
+                (SensorKind::Paper, true, false) => Some(SensorEvent::PaperRemoved),
+                (SensorKind::Cover, false, true) => Some(SensorEvent::CoverOpened),
+                (SensorKind::Cover, true, false) => Some(SensorEvent::CoverClosed),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Background poller delivering `SensorEvent`s for one device.
+///
+/// Created by `BackendRegistry::monitor_sensors`; polling stops when the
+/// monitor is dropped.
+pub struct SensorMonitor {
+    // Behind a mutex so a monitor can be shared between threads
+    events: Mutex<Receiver<SensorEvent>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SensorMonitor {
+    /// Starts polling `device_id` on `provider`. `options` is the initial
+    /// reading, used as the baseline so sensors already active at start
+    /// don't fire.
+    pub(crate) fn spawn(
+        provider: Arc<dyn BackendProvider>,
+        device_id: DeviceId,
+        options: &[DeviceOption],
+        interval: Duration,
+    ) -> Result<Self> {
+        let baseline = read_sensors(options);
+        if baseline.is_empty() {
+            return Err(PapyrError::Backend(format!(
+                "Device {} exposes no buttons or sensors",
+                device_id
+            )));
+        }
+
+        let (tx, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread =
+            thread::spawn(move || poll(provider, device_id, baseline, interval, tx, thread_stop));
+
+        Ok(Self {
+            events: Mutex::new(events),
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Waits up to `timeout` for the next event.
+    pub fn next_event(&self, timeout: Duration) -> Option<SensorEvent> {
+        let events = self.events.lock().ok()?;
+        match events.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Returns an already delivered event without waiting.
+    pub fn try_next_event(&self) -> Option<SensorEvent> {
+        self.events.lock().ok()?.try_recv().ok()
+    }
+}
+
+impl Drop for SensorMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn poll(
+    provider: Arc<dyn BackendProvider>,
+    device_id: DeviceId,
+    mut previous: HashMap<String, (SensorKind, bool)>,
+    interval: Duration,
+    tx: Sender<SensorEvent>,
+    stop: Arc<AtomicBool>,
+) {
+    // Sleep in short steps so dropping the monitor doesn't wait a full interval
+    let interval = interval.max(Duration::from_millis(10));
+    let step = interval.min(Duration::from_millis(50));
+
+    loop {
+        let mut waited = Duration::ZERO;
+        while waited < interval {
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(step);
+            waited += step;
+        }
+
+        // A poll fails while the device is busy scanning; try again next tick
+        let current = match provider.options(&device_id) {
+            Ok(options) => read_sensors(&options),
+            Err(e) => {
+                warn!(device = %device_id, "sensor poll failed: {}", e);
+                continue;
+            }
+        };
+
+        for event in sensor_changes(&previous, &current) {
+            info!(device = %device_id, ?event, "sensor changed");
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+        previous = current;
+    }
+}
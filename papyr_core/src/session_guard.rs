@@ -0,0 +1,341 @@
+//
+//  papyr_core
+//  session_guard.rs - Page limits, timeouts and event order checks for any scan session
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+#[cfg(feature = "async")]
+use crate::async_api::ScanEventStream;
+use crate::models::*;
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn, Span};
+
+// Events a worker thread may read ahead of the caller
+const EVENT_BUFFER: usize = 16;
+
+/// Timeouts for scan sessions. Both are off by default. With either set
+/// the backend session runs on a worker thread, so a backend stuck in I/O
+/// can be given up on; backends tied to the thread that opened them
+/// (TWAIN) should be left without.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// Longest wait for the next event.
+    pub idle_timeout: Option<Duration>,
+    /// Longest time from the start of the scan to its last event.
+    pub job_timeout: Option<Duration>,
+}
+
+impl SessionLimits {
+    fn is_unlimited(&self) -> bool {
+        self.idle_timeout.is_none() && self.job_timeout.is_none()
+    }
+}
+
+/// A backend session with the same guard rails for every backend: it ends
+/// with `JobComplete` after `max_pages` pages, fails with `Timeout` when a
+/// `SessionLimits` timeout passes and with `Protocol` when pages don't
+/// start and complete in order or data arrives outside a page. Nothing is read from the backend after the
+/// last event, so stray events after `JobComplete` never reach the caller.
+/// `guarded_stream` does the same for async scans.
+pub struct GuardedSession {
+    source: EventSource,
+    cancel: Option<CancelHandle>,
+    guard: EventGuard,
+    pending: VecDeque<ScanEvent>,
+}
+
+/// The page count, deadline and page order of one scan, shared by
+/// `GuardedSession` and `guarded_stream`.
+struct EventGuard {
+    backend: Backend,
+    max_pages: Option<u32>,
+    limits: SessionLimits,
+    deadline: Option<Instant>,
+    // Index of the page between its PageStarted and PageComplete
+    open_page: Option<u32>,
+    pages: u32,
+}
+
+enum EventSource {
+    Inline(Box<dyn ScanSession>),
+    // Fed by a thread running the backend session
+    Worker(Receiver<Result<Option<ScanEvent>>>),
+    Closed,
+}
+
+impl GuardedSession {
+    /// Guards `session` from `backend`. A `max_pages` of 0 means no limit,
+    /// as over FFI.
+    pub fn new(
+        session: Box<dyn ScanSession>,
+        backend: Backend,
+        max_pages: Option<u32>,
+        limits: SessionLimits,
+    ) -> Self {
+        let cancel = session.cancel_handle();
+        let source = if limits.is_unlimited() {
+            EventSource::Inline(session)
+        } else {
+            EventSource::Worker(spawn_worker(session))
+        };
+
+        Self {
+            source,
+            cancel,
+            guard: EventGuard::new(backend, max_pages, limits),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Cancels and releases the backend session; no more events are read.
+    fn stop(&mut self) {
+        if let Some(cancel) = &self.cancel {
+            cancel.cancel();
+        }
+        self.source = EventSource::Closed;
+    }
+
+    /// Next result from the backend, or `Timeout` once a limit has passed.
+    fn receive(&mut self) -> Result<Option<ScanEvent>> {
+        if let EventSource::Closed = self.source {
+            return Ok(None);
+        }
+        let (wait, job_limited) = self.guard.wait()?;
+
+        match &mut self.source {
+            EventSource::Inline(session) => session.next_event(),
+            EventSource::Worker(events) => match wait {
+                Some(wait) => match events.recv_timeout(wait) {
+                    Ok(result) => result,
+                    Err(RecvTimeoutError::Timeout) => Err(self.guard.timeout_error(job_limited)),
+                    Err(RecvTimeoutError::Disconnected) => Ok(None),
+                },
+                None => events.recv().unwrap_or(Ok(None)),
+            },
+            EventSource::Closed => Ok(None),
+        }
+    }
+}
+
+impl EventGuard {
+    fn new(backend: Backend, max_pages: Option<u32>, limits: SessionLimits) -> Self {
+        Self {
+            backend,
+            max_pages: max_pages.filter(|&pages| pages > 0),
+            limits,
+            deadline: limits.job_timeout.map(|timeout| Instant::now() + timeout),
+            open_page: None,
+            pages: 0,
+        }
+    }
+
+    /// How long to wait for the next event (`None` for ever), and whether
+    /// the job timeout is what limits it; `Timeout` once the job is over
+    /// time.
+    fn wait(&self) -> Result<(Option<Duration>, bool)> {
+        let remaining = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if remaining == Some(Duration::ZERO) {
+            return Err(self.timeout_error(true));
+        }
+        let wait = match (self.limits.idle_timeout, remaining) {
+            (Some(idle), Some(remaining)) => Some(idle.min(remaining)),
+            (idle, remaining) => idle.or(remaining),
+        };
+        Ok((wait, wait.is_some() && wait == remaining))
+    }
+
+    fn timeout_error(&self, job_limited: bool) -> PapyrError {
+        let message = match (
+            job_limited,
+            self.limits.job_timeout,
+            self.limits.idle_timeout,
+        ) {
+            (true, Some(job), _) => format!("Scan did not finish within {:?}", job),
+            (_, _, Some(idle)) => format!("No scan event for {:?}", idle),
+            _ => "Scan timed out".to_string(),
+        };
+        PapyrError::Timeout {
+            backend: self.backend,
+            message,
+        }
+    }
+
+    /// Checks that pages start and complete in order, with their data
+    /// in between.
+    fn check_order(&mut self, event: &ScanEvent) -> Result<()> {
+        let problem = match (event, self.open_page) {
+            (ScanEvent::PageStarted(index), None) => {
+                self.open_page = Some(*index);
+                return Ok(());
+            }
+            (ScanEvent::PageStarted(index), Some(open)) => {
+                format!("page {} started before page {} completed", index, open)
+            }
+            (ScanEvent::PageComplete(meta), Some(open)) if meta.index == open => {
+                self.open_page = None;
+                return Ok(());
+            }
+            (ScanEvent::PageComplete(meta), Some(open)) => {
+                format!("page {} completed while page {} was open", meta.index, open)
+            }
+            (ScanEvent::PageComplete(meta), None) => {
+                format!("page {} completed without being started", meta.index)
+            }
+            (ScanEvent::JobComplete, Some(open)) => {
+                format!("job completed while page {} was open", open)
+            }
+            (ScanEvent::PageData(_), None) => "page data arrived outside a page".to_string(),
+            _ => return Ok(()),
+        };
+
+        Err(PapyrError::Protocol {
+            backend: self.backend,
+            message: format!("Scan events out of order: {}", problem),
+        })
+    }
+
+    /// Checks the order of `event` and counts it; true when it completes
+    /// the last page `max_pages` allows, so the scan should stop there.
+    fn check(&mut self, event: &ScanEvent) -> Result<bool> {
+        if let Err(e) = self.check_order(event) {
+            warn!(backend = ?self.backend, "{}", e);
+            return Err(e);
+        }
+        if let ScanEvent::PageComplete(_) = event {
+            self.pages += 1;
+            if self.max_pages == Some(self.pages) {
+                debug!(pages = self.pages, "stopping at max_pages");
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl ScanSession for GuardedSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+
+        let event = match self.receive() {
+            Ok(Some(event)) => event,
+            Ok(None) => {
+                self.source = EventSource::Closed;
+                return Ok(None);
+            }
+            Err(e) => {
+                self.stop();
+                return Err(e);
+            }
+        };
+        match self.guard.check(&event) {
+            Err(e) => {
+                self.stop();
+                return Err(e);
+            }
+            Ok(true) => {
+                self.stop();
+                self.pending.push_back(ScanEvent::JobComplete);
+            }
+            Ok(false) if event.is_terminal() => self.source = EventSource::Closed,
+            Ok(false) => {}
+        }
+        Ok(Some(event))
+    }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        self.cancel.clone()
+    }
+}
+
+/// Guards an async scan as `GuardedSession` guards a blocking one. Waits
+/// run on tokio's timer, and the backend stream is dropped, abandoning the
+/// scan, after the last event, an error or `max_pages` pages.
+#[cfg(feature = "async")]
+pub fn guarded_stream(
+    events: ScanEventStream,
+    backend: Backend,
+    max_pages: Option<u32>,
+    limits: SessionLimits,
+) -> ScanEventStream {
+    use futures_util::StreamExt;
+
+    struct State {
+        events: Option<ScanEventStream>,
+        guard: EventGuard,
+        pending: Option<ScanEvent>,
+    }
+
+    let state = State {
+        events: Some(events),
+        guard: EventGuard::new(backend, max_pages, limits),
+        pending: None,
+    };
+    Box::pin(futures_util::stream::unfold(
+        state,
+        |mut state| async move {
+            if let Some(event) = state.pending.take() {
+                return Some((Ok(event), state));
+            }
+            let events = state.events.as_mut()?;
+            let next = match state.guard.wait() {
+                Ok((Some(wait), job_limited)) => {
+                    match tokio::time::timeout(wait, events.next()).await {
+                        Ok(next) => next,
+                        Err(_) => Some(Err(state.guard.timeout_error(job_limited))),
+                    }
+                }
+                Ok((None, _)) => events.next().await,
+                Err(e) => Some(Err(e)),
+            };
+
+            let (item, last) = match next {
+                Some(Ok(event)) => match state.guard.check(&event) {
+                    Ok(true) => {
+                        state.pending = Some(ScanEvent::JobComplete);
+                        (Ok(event), true)
+                    }
+                    Ok(false) => {
+                        let last = event.is_terminal();
+                        (Ok(event), last)
+                    }
+                    Err(e) => (Err(e), true),
+                },
+                Some(Err(e)) => (Err(e), true),
+                None => return None,
+            };
+            if last {
+                // Nothing more is read from the backend
+                state.events = None;
+            }
+            Some((item, state))
+        },
+    ))
+}
+
+/// Runs `session` on its own thread, passing its results on until it ends
+/// or the receiver is dropped.
+fn spawn_worker(mut session: Box<dyn ScanSession>) -> Receiver<Result<Option<ScanEvent>>> {
+    let (tx, events) = mpsc::sync_channel(EVENT_BUFFER);
+    // The backend logs within the scan's span on this thread too
+    let span = Span::current();
+    thread::spawn(move || {
+        let _entered = span.enter();
+        loop {
+            let result = session.next_event();
+            let last = !matches!(result, Ok(Some(_)));
+            if tx.send(result).is_err() || last {
+                break;
+            }
+        }
+    });
+    events
+}
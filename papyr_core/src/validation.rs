@@ -0,0 +1,365 @@
+//
+//  papyr_core
+//  validation.rs - Checks a ScanConfig against device Capabilities and snaps it to the nearest supported settings
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use crate::models::*;
+use std::fmt;
+
+/// How `BackendRegistry::start_scan` treats a config the device can't honour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigPolicy {
+    /// Reject the scan with every incompatibility listed.
+    #[default]
+    Validate,
+    /// Adjust the config to the nearest supported settings; only fail for
+    /// what can't be adjusted.
+    Coerce,
+}
+
+/// One way a `ScanConfig` doesn't fit a device's `Capabilities`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigIssue {
+    UnsupportedSource {
+        requested: ScanSource,
+        supported: Vec<ScanSource>,
+    },
+    DuplexUnsupported,
+    UnsupportedDpi {
+        requested: u32,
+        supported: Vec<u32>,
+    },
+    /// A separate vertical resolution on a device that ties X and Y.
+    IndependentDpiUnsupported {
+        dpi: u32,
+        dpi_y: u32,
+    },
+    UnsupportedColorMode {
+        requested: ColorMode,
+        supported: Vec<ColorMode>,
+    },
+    UnsupportedBitDepth {
+        requested: u8,
+        supported: Vec<u8>,
+    },
+    /// The page or scan area extends past the source's maximum area.
+    AreaTooLarge {
+        width_mm: f64,
+        height_mm: f64,
+        max: PageSize,
+    },
+    EmptyArea,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigIssue::UnsupportedSource {
+                requested,
+                supported,
+            } => write!(f, "source {:?} not supported ({:?})", requested, supported),
+            ConfigIssue::DuplexUnsupported => f.write_str("duplex not supported"),
+            ConfigIssue::UnsupportedDpi {
+                requested,
+                supported,
+            } => write!(f, "{} dpi not supported ({:?})", requested, supported),
+            ConfigIssue::IndependentDpiUnsupported { dpi, dpi_y } => {
+                write!(f, "{}x{} dpi needs independent X/Y resolution", dpi, dpi_y)
+            }
+            ConfigIssue::UnsupportedColorMode {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "color mode {:?} not supported ({:?})",
+                requested, supported
+            ),
+            ConfigIssue::UnsupportedBitDepth {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "{}-bit samples not supported ({:?})",
+                requested, supported
+            ),
+            ConfigIssue::AreaTooLarge {
+                width_mm,
+                height_mm,
+                max,
+            } => write!(
+                f,
+                "{} exceeds the maximum of {}",
+                PageSize::new(*width_mm, *height_mm),
+                max
+            ),
+            ConfigIssue::EmptyArea => f.write_str("scan area is empty"),
+        }
+    }
+}
+
+/// A change `ScanConfig::coerce` made to fit the device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigAdjustment {
+    Dpi { from: u32, to: u32 },
+    DpiY { from: u32, to: Option<u32> },
+    BitDepth { from: u8, to: Option<u8> },
+    Source { from: ScanSource, to: ScanSource },
+    DuplexDisabled,
+    PageSize { from: PageSize, to: PageSize },
+    Area { from: ScanArea, to: ScanArea },
+}
+
+impl fmt::Display for ConfigAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigAdjustment::Dpi { from, to } => write!(f, "dpi {} -> {}", from, to),
+            ConfigAdjustment::DpiY { from, to } => match to {
+                Some(to) => write!(f, "vertical dpi {} -> {}", from, to),
+                None => write!(f, "vertical dpi {} -> same as horizontal", from),
+            },
+            ConfigAdjustment::BitDepth { from, to } => match to {
+                Some(to) => write!(f, "bit depth {} -> {}", from, to),
+                None => write!(f, "bit depth {} -> mode default", from),
+            },
+            ConfigAdjustment::Source { from, to } => write!(f, "source {:?} -> {:?}", from, to),
+            ConfigAdjustment::DuplexDisabled => f.write_str("duplex disabled"),
+            ConfigAdjustment::PageSize { from, to } => write!(f, "page {} -> {}", from, to),
+            ConfigAdjustment::Area { from, to } => {
+                // To a tenth of a millimetre, as for PageSize
+                let tenths = |mm: f64| (mm * 10.0).round() / 10.0;
+                write!(
+                    f,
+                    "area {}x{}+{}+{} mm -> {}x{}+{}+{} mm",
+                    tenths(from.width_mm),
+                    tenths(from.height_mm),
+                    tenths(from.x_mm),
+                    tenths(from.y_mm),
+                    tenths(to.width_mm),
+                    tenths(to.height_mm),
+                    tenths(to.x_mm),
+                    tenths(to.y_mm)
+                )
+            }
+        }
+    }
+}
+
+/// Supported dpi closest to `dpi`, from the discrete list or a range; ties
+/// go to the higher resolution.
+fn nearest_dpi(dpi: u32, caps: &Capabilities) -> Option<u32> {
+    caps.dpis
+        .iter()
+        .copied()
+        .chain(caps.dpi_ranges.iter().map(|range| range.nearest(dpi)))
+        .min_by_key(|candidate| (candidate.abs_diff(dpi), u32::MAX - candidate))
+}
+
+/// Whether `caps` reports any resolutions to check against.
+fn knows_dpis(caps: &Capabilities) -> bool {
+    !caps.dpis.is_empty() || !caps.dpi_ranges.is_empty()
+}
+
+impl ScanConfig {
+    /// Every way this config doesn't fit `caps`; empty when it can be sent
+    /// as-is. Capability lists a backend leaves empty are not checked.
+    pub fn issues(&self, caps: &Capabilities) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        if !caps.sources.is_empty() && !caps.sources.contains(&self.source) {
+            issues.push(ConfigIssue::UnsupportedSource {
+                requested: self.source,
+                supported: caps.sources.clone(),
+            });
+        }
+        if self.duplex && !caps.supports_duplex {
+            issues.push(ConfigIssue::DuplexUnsupported);
+        }
+        if knows_dpis(caps) && !caps.supports_dpi(self.dpi) {
+            issues.push(ConfigIssue::UnsupportedDpi {
+                requested: self.dpi,
+                supported: caps.dpis.clone(),
+            });
+        }
+        if let Some(dpi_y) = self.dpi_y.filter(|dpi_y| *dpi_y != self.dpi) {
+            if !caps.independent_dpi {
+                issues.push(ConfigIssue::IndependentDpiUnsupported {
+                    dpi: self.dpi,
+                    dpi_y,
+                });
+            } else if knows_dpis(caps) && !caps.supports_dpi(dpi_y) {
+                issues.push(ConfigIssue::UnsupportedDpi {
+                    requested: dpi_y,
+                    supported: caps.dpis.clone(),
+                });
+            }
+        }
+        if !caps.color_modes.is_empty() && !caps.color_modes.contains(&self.color_mode) {
+            issues.push(ConfigIssue::UnsupportedColorMode {
+                requested: self.color_mode,
+                supported: caps.color_modes.clone(),
+            });
+        }
+
+        if let Some(depth) = self.bit_depth {
+            let supported = match self.color_mode {
+                ColorMode::Bw => depth == 1,
+                _ => caps.bit_depths.is_empty() || caps.bit_depths.contains(&depth),
+            };
+            if !supported {
+                issues.push(ConfigIssue::UnsupportedBitDepth {
+                    requested: depth,
+                    supported: match self.color_mode {
+                        ColorMode::Bw => vec![1],
+                        _ => caps.bit_depths.clone(),
+                    },
+                });
+            }
+        }
+
+        let extent = match self.area {
+            Some(area) => PageSize::new(area.x_mm + area.width_mm, area.y_mm + area.height_mm),
+            None => self.page_size,
+        };
+        if matches!(self.area, Some(area) if area.width_mm <= 0.0 || area.height_mm <= 0.0) {
+            issues.push(ConfigIssue::EmptyArea);
+        }
+        // `None` when the backend reports no sizes; the area isn't checked
+        if let Some(max) = caps.max_area(self.source) {
+            if !extent.fits_within(&max) {
+                issues.push(ConfigIssue::AreaTooLarge {
+                    width_mm: extent.width_mm,
+                    height_mm: extent.height_mm,
+                    max,
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Fails with `InvalidConfig` listing every issue, if there are any.
+    pub fn validate(&self, caps: &Capabilities) -> Result<()> {
+        invalid(self.issues(caps))
+    }
+
+    /// Adjusts this config to fit `caps`: resolution and bit depth snap to
+    /// the nearest supported value (vertical resolution follows horizontal
+    /// on devices that tie them), AdfDuplex falls back to Adf, duplex is
+    /// turned off and the page and scan area are clamped to the source's
+    /// maximum area.
+    /// Returns the fitted config and what was changed, or `InvalidConfig`
+    /// for the issues left (e.g. an unsupported color mode).
+    pub fn coerce(&self, caps: &Capabilities) -> Result<(ScanConfig, Vec<ConfigAdjustment>)> {
+        let mut cfg = self.clone();
+        let mut adjustments = Vec::new();
+
+        if cfg.source == ScanSource::AdfDuplex
+            && !caps.sources.is_empty()
+            && !caps.sources.contains(&ScanSource::AdfDuplex)
+            && caps.sources.contains(&ScanSource::Adf)
+        {
+            cfg.source = ScanSource::Adf;
+            adjustments.push(ConfigAdjustment::Source {
+                from: ScanSource::AdfDuplex,
+                to: ScanSource::Adf,
+            });
+        }
+        if cfg.duplex && !caps.supports_duplex {
+            cfg.duplex = false;
+            adjustments.push(ConfigAdjustment::DuplexDisabled);
+        }
+
+        if !caps.supports_dpi(cfg.dpi) {
+            if let Some(dpi) = nearest_dpi(cfg.dpi, caps) {
+                adjustments.push(ConfigAdjustment::Dpi {
+                    from: cfg.dpi,
+                    to: dpi,
+                });
+                cfg.dpi = dpi;
+            }
+        }
+        if let Some(dpi_y) = cfg.dpi_y.filter(|dpi_y| *dpi_y != cfg.dpi) {
+            let to = if caps.independent_dpi {
+                if caps.supports_dpi(dpi_y) {
+                    Some(dpi_y)
+                } else {
+                    nearest_dpi(dpi_y, caps).or(Some(dpi_y))
+                }
+            } else {
+                None
+            };
+            if to != Some(dpi_y) {
+                adjustments.push(ConfigAdjustment::DpiY { from: dpi_y, to });
+                cfg.dpi_y = to;
+            }
+        }
+
+        if let Some(depth) = cfg.bit_depth {
+            let to = match cfg.color_mode {
+                ColorMode::Bw if depth == 1 => Some(depth),
+                ColorMode::Bw => None,
+                _ if caps.bit_depths.is_empty() || caps.bit_depths.contains(&depth) => Some(depth),
+                // Deepest supported depth not above the request, else the shallowest
+                _ => caps
+                    .bit_depths
+                    .iter()
+                    .copied()
+                    .filter(|d| *d <= depth)
+                    .max()
+                    .or_else(|| caps.bit_depths.iter().copied().min()),
+            };
+            if to != Some(depth) {
+                adjustments.push(ConfigAdjustment::BitDepth { from: depth, to });
+                cfg.bit_depth = to;
+            }
+        }
+
+        if let Some(max) = caps.max_area(cfg.source) {
+            let page = PageSize::new(
+                cfg.page_size.width_mm.min(max.width_mm),
+                cfg.page_size.height_mm.min(max.height_mm),
+            );
+            if !cfg.page_size.fits_within(&max) {
+                adjustments.push(ConfigAdjustment::PageSize {
+                    from: cfg.page_size,
+                    to: page,
+                });
+                cfg.page_size = page;
+            }
+
+            if let Some(area) = cfg.area {
+                // Keep the origin where possible; shrink the area first
+                let x_mm = area.x_mm.min(max.width_mm);
+                let y_mm = area.y_mm.min(max.height_mm);
+                let clamped = ScanArea {
+                    x_mm,
+                    y_mm,
+                    width_mm: area.width_mm.min(max.width_mm - x_mm),
+                    height_mm: area.height_mm.min(max.height_mm - y_mm),
+                };
+                if clamped != area {
+                    adjustments.push(ConfigAdjustment::Area {
+                        from: area,
+                        to: clamped,
+                    });
+                    cfg.area = Some(clamped);
+                }
+            }
+        }
+
+        invalid(cfg.issues(caps))?;
+        Ok((cfg, adjustments))
+    }
+}
+
+fn invalid(issues: Vec<ConfigIssue>) -> Result<()> {
+    if issues.is_empty() {
+        return Ok(());
+    }
+
+    let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    Err(PapyrError::InvalidConfig(issues.join("; ")))
+}
@@ -0,0 +1,334 @@
+//
+//  papyr_core
+//  tests/async_test.rs - Async API tests (`async` feature)
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+#![cfg(feature = "async")]
+
+use futures_util::StreamExt;
+use papyr_core::async_api::blocking_stream;
+use papyr_core::models::*;
+use papyr_core::registry::BackendRegistry;
+use papyr_core::session_guard::{guarded_stream, SessionLimits};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Plays back a fixed list of results.
+struct ScriptedSession {
+    script: VecDeque<Result<Option<ScanEvent>>>,
+}
+
+impl ScanSession for ScriptedSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        self.script.pop_front().unwrap_or(Ok(None))
+    }
+}
+
+/// Streams page data until cancelled, then reports the cancellation.
+struct EndlessSession {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScanSession for EndlessSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(PapyrError::Cancelled);
+        }
+        std::thread::sleep(Duration::from_millis(5));
+        Ok(Some(ScanEvent::PageData(vec![0; 16])))
+    }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancelled = self.cancelled.clone();
+        Some(CancelHandle::new(move || {
+            cancelled.store(true, Ordering::SeqCst)
+        }))
+    }
+}
+
+/// Feeds page after page, pausing before each event, until cancelled.
+struct FeederSession {
+    index: u32,
+    step: u32,
+    delay: Duration,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScanSession for FeederSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        std::thread::sleep(self.delay);
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Ok(Some(ScanEvent::Cancelled));
+        }
+        let event = match self.step {
+            0 => ScanEvent::PageStarted(self.index),
+            1 => ScanEvent::PageData(vec![0; 16]),
+            _ => ScanEvent::PageComplete(PageMeta {
+                index: self.index,
+                width_px: 4,
+                height_px: 4,
+                dpi: 150,
+                dpi_y: 150,
+                color_mode: ColorMode::Gray,
+                bit_depth: 8,
+            }),
+        };
+        self.step += 1;
+        if self.step == 3 {
+            self.step = 0;
+            self.index += 1;
+        }
+        Ok(Some(event))
+    }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancelled = self.cancelled.clone();
+        Some(CancelHandle::new(move || {
+            cancelled.store(true, Ordering::SeqCst)
+        }))
+    }
+}
+
+/// A feeder backend that ignores `max_pages`, leaving it to the registry.
+struct FeederBackend {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl BackendProvider for FeederBackend {
+    fn name(&self) -> &'static str {
+        "Feeder"
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::Sane
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        vec![ScannerInfo::new(
+            DeviceId::new(Backend::Sane, "feeder"),
+            "Feeder",
+        )]
+    }
+
+    fn capabilities(&self, _device_id: &DeviceId) -> Result<Capabilities> {
+        Ok(Capabilities {
+            sources: vec![ScanSource::Adf],
+            dpis: vec![150],
+            dpi_ranges: vec![],
+            independent_dpi: false,
+            color_modes: vec![ColorMode::Gray],
+            bit_depths: vec![8],
+            page_sizes: vec![],
+            max_areas: vec![],
+            supports_duplex: false,
+        })
+    }
+
+    fn start_scan(&self, _device_id: &DeviceId, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        Ok(Box::new(FeederSession {
+            index: 0,
+            step: 0,
+            delay: Duration::ZERO,
+            cancelled: self.cancelled.clone(),
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_blocking_stream_keeps_event_order() {
+    let session = ScriptedSession {
+        script: VecDeque::from([
+            Ok(Some(ScanEvent::Status(ScanStatus::Scanning))),
+            Ok(Some(ScanEvent::PageStarted(0))),
+            Ok(Some(ScanEvent::PageData(vec![1, 2, 3]))),
+            Ok(Some(ScanEvent::JobComplete)),
+        ]),
+    };
+
+    let events: Vec<ScanEvent> = blocking_stream(Box::new(session))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(events.len(), 4);
+    assert!(matches!(events[0], ScanEvent::Status(ScanStatus::Scanning)));
+    assert!(matches!(events[1], ScanEvent::PageStarted(0)));
+    assert!(matches!(&events[2], ScanEvent::PageData(data) if data == &[1, 2, 3]));
+    assert!(matches!(events[3], ScanEvent::JobComplete));
+}
+
+#[tokio::test]
+async fn test_blocking_stream_ends_after_error() {
+    let session = ScriptedSession {
+        script: VecDeque::from([
+            Ok(Some(ScanEvent::PageStarted(0))),
+            Err(PapyrError::PaperJam {
+                backend: Backend::Sane,
+                message: "Document feeder jammed".into(),
+            }),
+            Ok(Some(ScanEvent::JobComplete)),
+        ]),
+    };
+
+    let items: Vec<Result<ScanEvent>> = blocking_stream(Box::new(session)).collect().await;
+    assert_eq!(items.len(), 2);
+    assert!(matches!(items[0], Ok(ScanEvent::PageStarted(0))));
+    assert!(matches!(items[1], Err(PapyrError::PaperJam { .. })));
+}
+
+#[tokio::test]
+async fn test_dropping_blocking_stream_cancels_session() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let session = EndlessSession {
+        cancelled: cancelled.clone(),
+    };
+
+    let mut stream = blocking_stream(Box::new(session));
+    assert!(matches!(
+        stream.next().await,
+        Some(Ok(ScanEvent::PageData(_)))
+    ));
+    drop(stream);
+    assert!(cancelled.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_async_scan_stops_after_max_pages() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(FeederBackend {
+        cancelled: cancelled.clone(),
+    }));
+
+    let config = ScanConfig {
+        source: ScanSource::Adf,
+        duplex: false,
+        dpi: 150,
+        dpi_y: None,
+        color_mode: ColorMode::Gray,
+        bit_depth: None,
+        page_size: PageSize {
+            width_mm: 210.0,
+            height_mm: 297.0,
+        },
+        area: None,
+        brightness: None,
+        contrast: None,
+        max_pages: Some(2),
+    };
+    let stream = registry
+        .start_scan_async(&DeviceId::new(Backend::Sane, "feeder"), config)
+        .await
+        .unwrap();
+    let events: Vec<ScanEvent> = stream.map(Result::unwrap).collect().await;
+    let completed = events
+        .iter()
+        .filter(|e| matches!(e, ScanEvent::PageComplete(_)))
+        .count();
+    assert_eq!(completed, 2);
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+    assert!(
+        cancelled.load(Ordering::SeqCst),
+        "the feeder should be stopped"
+    );
+}
+
+#[tokio::test]
+async fn test_guarded_stream_idle_timeout() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let session = FeederSession {
+        index: 0,
+        step: 0,
+        delay: Duration::from_millis(500),
+        cancelled: cancelled.clone(),
+    };
+    let limits = SessionLimits {
+        idle_timeout: Some(Duration::from_millis(100)),
+        job_timeout: None,
+    };
+
+    let stream = blocking_stream(Box::new(session));
+    let items: Vec<Result<ScanEvent>> = guarded_stream(stream, Backend::Sane, None, limits)
+        .collect()
+        .await;
+    assert_eq!(items.len(), 1);
+    assert!(
+        matches!(&items[0], Err(PapyrError::Timeout { message, .. }) if message.contains("No scan event")),
+        "{:?}",
+        items[0]
+    );
+    assert!(cancelled.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_guarded_stream_rejects_out_of_order_pages() {
+    let session = ScriptedSession {
+        script: VecDeque::from([
+            Ok(Some(ScanEvent::PageStarted(0))),
+            Ok(Some(ScanEvent::PageStarted(1))),
+            Ok(Some(ScanEvent::JobComplete)),
+        ]),
+    };
+
+    let stream = blocking_stream(Box::new(session));
+    let items: Vec<Result<ScanEvent>> =
+        guarded_stream(stream, Backend::Sane, None, SessionLimits::default())
+            .collect()
+            .await;
+    assert_eq!(items.len(), 2);
+    assert!(matches!(items[0], Ok(ScanEvent::PageStarted(0))));
+    assert!(matches!(items[1], Err(PapyrError::Protocol { .. })));
+}
+
+#[tokio::test]
+async fn test_guarded_stream_reads_nothing_after_job_complete() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let counted = polls.clone();
+    let events = futures_util::stream::iter([
+        ScanEvent::Status(ScanStatus::Scanning),
+        ScanEvent::JobComplete,
+        ScanEvent::PageStarted(1),
+    ])
+    .map(Ok)
+    .inspect(move |_| {
+        counted.fetch_add(1, Ordering::SeqCst);
+    });
+
+    let items: Vec<Result<ScanEvent>> = guarded_stream(
+        Box::pin(events),
+        Backend::Sane,
+        None,
+        SessionLimits::default(),
+    )
+    .collect()
+    .await;
+    assert_eq!(items.len(), 2);
+    assert!(matches!(items.last(), Some(Ok(ScanEvent::JobComplete))));
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_guarded_stream_rejects_data_outside_a_page() {
+    let events =
+        futures_util::stream::iter([ScanEvent::PageData(vec![1; 4]), ScanEvent::JobComplete])
+            .map(Ok);
+
+    let items: Vec<Result<ScanEvent>> = guarded_stream(
+        Box::pin(events),
+        Backend::Sane,
+        None,
+        SessionLimits::default(),
+    )
+    .collect()
+    .await;
+    assert_eq!(items.len(), 1);
+    assert!(
+        matches!(&items[0], Err(PapyrError::Protocol { message, .. }) if message.contains("outside a page")),
+        "{:?}",
+        items[0]
+    );
+}
@@ -0,0 +1,153 @@
+//
+//  papyr_core
+//  tests/cancel_test.rs - Cancellation token tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::models::*;
+use papyr_core::registry::BackendRegistry;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn counting_handle(count: &Arc<AtomicUsize>) -> CancelHandle {
+    let count = Arc::clone(count);
+    CancelHandle::new(move || {
+        count.fetch_add(1, Ordering::SeqCst);
+    })
+}
+
+#[test]
+fn test_cancel_token_runs_handles_once() {
+    let token = CancelToken::new();
+    let count = Arc::new(AtomicUsize::new(0));
+    token.on_cancel(counting_handle(&count));
+    token.on_cancel(counting_handle(&count));
+    assert!(token.check().is_ok());
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    token.cancel();
+    token.clone().cancel();
+    assert!(token.is_cancelled());
+    assert!(matches!(token.check(), Err(PapyrError::Cancelled)));
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_cancelled_token_runs_late_handles_immediately() {
+    let token = CancelToken::new();
+    token.cancel();
+
+    let count = Arc::new(AtomicUsize::new(0));
+    token.on_cancel(counting_handle(&count));
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_scoped_handles_unregister_on_drop() {
+    let token = CancelToken::new();
+    let count = Arc::new(AtomicUsize::new(0));
+    let dropped = token.on_cancel_scoped(counting_handle(&count));
+    let _kept = token.on_cancel_scoped(counting_handle(&count));
+    drop(dropped);
+
+    token.cancel();
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+/// Keeps the tokens it enumerates with, to see what they are linked to.
+struct TokenKeepingBackend {
+    tokens: Arc<Mutex<Vec<CancelToken>>>,
+}
+
+impl BackendProvider for TokenKeepingBackend {
+    fn name(&self) -> &'static str {
+        "TokenKeeping"
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::Virtual
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        vec![]
+    }
+
+    fn enumerate_cancellable(&self, cancel: &CancelToken) -> Vec<ScannerInfo> {
+        self.tokens.lock().unwrap().push(cancel.clone());
+        vec![]
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        Err(PapyrError::NotFound(device_id.to_string()))
+    }
+
+    fn start_scan(&self, device_id: &DeviceId, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        Err(PapyrError::NotFound(device_id.to_string()))
+    }
+}
+
+#[test]
+fn test_discovery_unlinks_from_reused_token() {
+    let tokens = Arc::new(Mutex::new(Vec::new()));
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(TokenKeepingBackend {
+        tokens: Arc::clone(&tokens),
+    }));
+
+    let cancel = CancelToken::new();
+    for _ in 0..3 {
+        registry.discover_devices(&cancel).unwrap();
+    }
+    cancel.cancel();
+
+    // Finished discoveries no longer forward the caller's cancel
+    let tokens = tokens.lock().unwrap();
+    assert_eq!(tokens.len(), 3);
+    assert!(tokens.iter().all(|token| !token.is_cancelled()));
+}
+
+#[test]
+fn test_registry_discovery_stops_when_cancelled() {
+    const CANCEL_AFTER: Duration = Duration::from_millis(300);
+
+    let registry = BackendRegistry::new();
+    let cancel = CancelToken::new();
+    let canceller = {
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(CANCEL_AFTER);
+            cancel.cancel();
+        })
+    };
+
+    let started = Instant::now();
+    let result = registry.list_devices_cancellable(&cancel);
+    let elapsed = started.elapsed();
+    canceller.join().unwrap();
+
+    // eSCL alone browses for up to 10s per service type
+    assert!(elapsed < Duration::from_secs(5), "took {:?}", elapsed);
+    if elapsed >= CANCEL_AFTER {
+        assert!(matches!(result, Err(PapyrError::Cancelled)), "{:?}", result);
+    }
+}
+
+#[test]
+fn test_registry_rejects_cancelled_calls() {
+    let registry = BackendRegistry::new();
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    assert!(matches!(
+        registry.list_devices_cancellable(&cancel),
+        Err(PapyrError::Cancelled)
+    ));
+    assert!(matches!(
+        registry.capabilities_cancellable(&DeviceId::new(Backend::Escl, "unknown"), &cancel),
+        Err(PapyrError::Cancelled)
+    ));
+}
@@ -0,0 +1,285 @@
+//
+//  papyr_core
+//  tests/capabilities_test.rs - eSCL and SANE capability mapping tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::backends::escl::capabilities_from_xml;
+use papyr_core::backends::sane_common::*;
+use papyr_core::models::*;
+
+const RANGE_CAPABILITIES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<scan:ScannerCapabilities xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03" xmlns:pwg="http://www.pwg.org/schemas/2010/12/sm">
+    <pwg:Version>2.63</pwg:Version>
+    <scan:Platen>
+        <scan:PlatenInputCaps>
+            <scan:MinWidth>16</scan:MinWidth>
+            <scan:MaxWidth>2480</scan:MaxWidth>
+            <scan:MinHeight>16</scan:MinHeight>
+            <scan:MaxHeight>3508</scan:MaxHeight>
+            <scan:SettingProfiles>
+                <scan:SettingProfile>
+                    <scan:ColorModes>
+                        <scan:ColorMode>RGB24</scan:ColorMode>
+                        <scan:ColorMode>RGB48</scan:ColorMode>
+                        <scan:ColorMode>Grayscale8</scan:ColorMode>
+                        <scan:ColorMode>Grayscale16</scan:ColorMode>
+                    </scan:ColorModes>
+                    <scan:SupportedResolutions>
+                        <scan:ResolutionRange>
+                            <scan:XResolutionRange>
+                                <scan:Min>75</scan:Min>
+                                <scan:Max>1200</scan:Max>
+                                <scan:Step>25</scan:Step>
+                            </scan:XResolutionRange>
+                            <scan:YResolutionRange>
+                                <scan:Min>75</scan:Min>
+                                <scan:Max>1200</scan:Max>
+                                <scan:Step>25</scan:Step>
+                            </scan:YResolutionRange>
+                        </scan:ResolutionRange>
+                    </scan:SupportedResolutions>
+                </scan:SettingProfile>
+            </scan:SettingProfiles>
+        </scan:PlatenInputCaps>
+    </scan:Platen>
+</scan:ScannerCapabilities>"#;
+
+const DISCRETE_CAPABILITIES_XML: &str = r#"<scan:ScannerCapabilities xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03">
+    <scan:Platen>
+        <scan:PlatenInputCaps>
+            <scan:SettingProfiles>
+                <scan:SettingProfile>
+                    <scan:ColorModes>
+                        <scan:ColorMode>BlackAndWhite1</scan:ColorMode>
+                        <scan:ColorMode>Grayscale8</scan:ColorMode>
+                    </scan:ColorModes>
+                    <scan:SupportedResolutions>
+                        <scan:DiscreteResolutions>
+                            <scan:DiscreteResolution>
+                                <scan:XResolution>300</scan:XResolution>
+                                <scan:YResolution>300</scan:YResolution>
+                            </scan:DiscreteResolution>
+                            <scan:DiscreteResolution>
+                                <scan:XResolution>150</scan:XResolution>
+                                <scan:YResolution>150</scan:YResolution>
+                            </scan:DiscreteResolution>
+                        </scan:DiscreteResolutions>
+                    </scan:SupportedResolutions>
+                </scan:SettingProfile>
+            </scan:SettingProfiles>
+        </scan:PlatenInputCaps>
+    </scan:Platen>
+    <scan:Adf>
+        <scan:AdfSimplexInputCaps>
+            <scan:MaxWidth>2550</scan:MaxWidth>
+            <scan:MaxHeight>4200</scan:MaxHeight>
+        </scan:AdfSimplexInputCaps>
+        <scan:AdfDuplexInputCaps/>
+    </scan:Adf>
+</scan:ScannerCapabilities>"#;
+
+fn descriptor(name: &str, value_type: i32, constraint: SaneConstraint) -> SaneOptionDescriptor {
+    SaneOptionDescriptor {
+        name: name.into(),
+        title: name.into(),
+        desc: String::new(),
+        value_type,
+        unit: SANE_UNIT_DPI,
+        size: 4,
+        cap: SANE_CAP_SOFT_SELECT | SANE_CAP_SOFT_DETECT,
+        constraint,
+    }
+}
+
+#[test]
+fn test_escl_resolution_range_and_depths() {
+    let caps = capabilities_from_xml(RANGE_CAPABILITIES_XML);
+
+    assert_eq!(
+        caps.dpi_ranges,
+        [DpiRange {
+            min: 75,
+            max: 1200,
+            step: 25
+        }]
+    );
+    assert_eq!(caps.dpis, [75, 100, 150, 200, 300, 600, 1200]);
+    assert!(caps.supports_dpi(225));
+    assert!(caps.independent_dpi);
+    assert_eq!(caps.color_modes, [ColorMode::Color, ColorMode::Gray]);
+    assert_eq!(caps.bit_depths, [8, 16]);
+    assert_eq!(caps.sources, [ScanSource::Flatbed]);
+
+    // An A4 platen: Letter is too wide, A5 and the cards fit
+    let a4 = caps.max_area(ScanSource::Flatbed).unwrap();
+    assert_eq!(a4.to_three_hundredths(), (2480, 3508));
+    assert!(caps.page_sizes.contains(&PageFormat::IsoA(4)));
+    assert!(caps.page_sizes.contains(&PageFormat::Id1));
+    assert!(!caps.page_sizes.contains(&PageFormat::Letter));
+    assert!(!caps.page_sizes.contains(&PageFormat::IsoA(3)));
+}
+
+#[test]
+fn test_escl_discrete_resolutions() {
+    let caps = capabilities_from_xml(DISCRETE_CAPABILITIES_XML);
+
+    assert_eq!(caps.dpis, [150, 300]);
+    assert!(caps.dpi_ranges.is_empty());
+    assert!(!caps.independent_dpi);
+    assert_eq!(caps.color_modes, [ColorMode::Bw, ColorMode::Gray]);
+    assert_eq!(caps.bit_depths, [8]);
+    assert_eq!(
+        caps.sources,
+        [ScanSource::Flatbed, ScanSource::Adf, ScanSource::AdfDuplex]
+    );
+    assert!(caps.supports_duplex);
+
+    // Only the feeder reports an area, and duplex shares it
+    assert_eq!(caps.max_areas.len(), 2);
+    assert_eq!(
+        caps.max_area(ScanSource::AdfDuplex),
+        PageFormat::Legal.size()
+    );
+    assert!(caps
+        .page_sizes_for(ScanSource::Adf)
+        .contains(&PageFormat::Legal));
+    assert_eq!(
+        caps.resolve_page(PageFormat::MaxArea, ScanSource::Adf),
+        caps.max_area(ScanSource::Adf)
+    );
+}
+
+#[test]
+fn test_sane_capabilities_from_ranges() {
+    let options = vec![
+        descriptor(
+            "resolution",
+            SANE_TYPE_INT,
+            SaneConstraint::Range {
+                min: 50,
+                max: 2400,
+                quant: 50,
+            },
+        ),
+        descriptor(
+            "y-resolution",
+            SANE_TYPE_INT,
+            SaneConstraint::Range {
+                min: 50,
+                max: 4800,
+                quant: 50,
+            },
+        ),
+        descriptor(
+            "depth",
+            SANE_TYPE_INT,
+            SaneConstraint::WordList(vec![1, 8, 16]),
+        ),
+    ];
+
+    let caps = capabilities_from_options(&options);
+    assert_eq!(
+        caps.dpi_ranges,
+        [DpiRange {
+            min: 50,
+            max: 2400,
+            step: 50
+        }]
+    );
+    assert!(caps.supports_dpi(350));
+    assert!(caps.independent_dpi);
+    assert_eq!(caps.bit_depths, [8, 16]);
+}
+
+#[test]
+fn test_sane_capabilities_report_geometry() {
+    let edge = |name: &str, max_mm: f64| SaneOptionDescriptor {
+        unit: SANE_UNIT_MM,
+        ..descriptor(
+            name,
+            SANE_TYPE_FIXED,
+            SaneConstraint::Range {
+                min: 0,
+                max: f64_to_fixed(max_mm),
+                quant: 0,
+            },
+        )
+    };
+    let options = vec![
+        descriptor(
+            "source",
+            SANE_TYPE_STRING,
+            SaneConstraint::StringList(vec!["Flatbed".into(), "ADF".into()]),
+        ),
+        edge("br-x", 215.9),
+        edge("br-y", 297.0),
+    ];
+
+    let caps = capabilities_from_options(&options);
+    assert_eq!(caps.max_areas.len(), 2);
+    let max = caps.max_area(ScanSource::Adf).unwrap();
+    assert!((max.width_mm - 215.9).abs() < 0.001);
+    assert!(caps.page_sizes.contains(&PageFormat::IsoA(4)));
+    assert!(caps.page_sizes.contains(&PageFormat::Letter));
+    assert!(!caps.page_sizes.contains(&PageFormat::Legal));
+
+    // Without geometry options the common sizes are assumed
+    let caps = capabilities_from_options(&options[..1]);
+    assert!(caps.max_areas.is_empty());
+    assert_eq!(caps.page_sizes, DEFAULT_PAGE_FORMATS);
+}
+
+#[test]
+fn test_sane_plan_config_sets_depth_and_y_resolution() {
+    let options = vec![
+        descriptor(
+            "resolution",
+            SANE_TYPE_INT,
+            SaneConstraint::WordList(vec![150, 300, 600]),
+        ),
+        descriptor(
+            "y-resolution",
+            SANE_TYPE_INT,
+            SaneConstraint::WordList(vec![150, 300, 600]),
+        ),
+        descriptor(
+            "depth",
+            SANE_TYPE_INT,
+            SaneConstraint::WordList(vec![8, 16]),
+        ),
+    ];
+    let cfg = ScanConfig {
+        source: ScanSource::Flatbed,
+        duplex: false,
+        dpi: 300,
+        dpi_y: Some(600),
+        color_mode: ColorMode::Color,
+        bit_depth: Some(16),
+        page_size: PageSize::new(210.0, 297.0),
+        area: None,
+        brightness: None,
+        contrast: None,
+        max_pages: None,
+    };
+
+    assert_eq!(
+        plan_config(&options, &cfg),
+        vec![
+            (2, SaneValue::Int(16)),
+            (0, SaneValue::Int(300)),
+            (1, SaneValue::Int(600))
+        ]
+    );
+
+    // Lineart has no depth to choose
+    let lineart = ScanConfig {
+        color_mode: ColorMode::Bw,
+        ..cfg
+    };
+    assert!(plan_config(&options, &lineart)
+        .iter()
+        .all(|(index, _)| *index != 2));
+}
@@ -0,0 +1,205 @@
+//
+//  papyr_core
+//  tests/config_test.rs - Registry config loading, backend selection and builder tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::config::{RegistryConfig, ScanDefaults};
+use papyr_core::models::*;
+use papyr_core::registry::{default_backends, BackendRegistry};
+
+const TOML_CONFIG: &str = r#"
+order = ["Escl"]
+disabled = ["SaneNet"]
+discovery_timeout_ms = 15000
+device_cache_ttl_ms = 60000
+
+[escl]
+mdns = false
+hosts = ["192.168.1.20", "https://scanner.example:8443"]
+
+[saned]
+hosts = ["scanhost:6566"]
+
+[sane]
+library_path = "/opt/sane/lib/libsane.so.1"
+
+[scan]
+dpi = 200
+color_mode = "Gray"
+page_size = { width_mm = 215.9, height_mm = 279.4 }
+"#;
+
+/// Every backend of this build except `keep`.
+fn all_but(keep: Backend) -> Vec<Backend> {
+    default_backends()
+        .into_iter()
+        .filter(|backend| *backend != keep)
+        .collect()
+}
+
+#[test]
+fn test_toml_config() {
+    let config = RegistryConfig::from_toml(TOML_CONFIG).expect("parse TOML");
+    assert_eq!(config.order, vec![Backend::Escl]);
+    assert_eq!(config.disabled, vec![Backend::SaneNet]);
+    assert_eq!(config.discovery_timeout_ms, Some(15000));
+    assert!(!config.escl.mdns);
+    assert_eq!(config.escl.hosts.len(), 2);
+    assert_eq!(config.saned.hosts, vec!["scanhost:6566"]);
+    assert_eq!(
+        config.sane.library_path.as_deref(),
+        Some(std::path::Path::new("/opt/sane/lib/libsane.so.1"))
+    );
+    assert_eq!(config.scan.dpi, 200);
+    assert_eq!(config.scan.color_mode, ColorMode::Gray);
+    // Unset fields keep their defaults
+    assert_eq!(config.scan.source, ScanSource::Flatbed);
+}
+
+#[test]
+fn test_json_config() {
+    let config = RegistryConfig::from_json(
+        r#"{"disabled": ["Escl"], "scan": {"source": "Adf", "max_pages": 5}}"#,
+    )
+    .expect("parse JSON");
+    assert_eq!(config.disabled, vec![Backend::Escl]);
+    assert_eq!(config.scan.source, ScanSource::Adf);
+    assert_eq!(config.scan.max_pages, Some(5));
+    assert!(config.escl.mdns);
+
+    assert_eq!(
+        RegistryConfig::from_json("{}").unwrap(),
+        RegistryConfig::default()
+    );
+}
+
+#[test]
+fn test_invalid_config_rejected() {
+    for result in [
+        RegistryConfig::from_toml("dpi = 300"),
+        RegistryConfig::from_toml("disabled = [\"Floppy\"]"),
+        RegistryConfig::from_json("{\"escl\": {\"hosts\": 1}}"),
+    ] {
+        assert!(
+            matches!(result, Err(PapyrError::InvalidConfig(_))),
+            "{:?}",
+            result
+        );
+    }
+}
+
+#[test]
+fn test_load_config_file() {
+    let dir = std::env::temp_dir();
+    let toml_path = dir.join(format!("papyr_config_test_{}.toml", std::process::id()));
+    let yaml_path = dir.join(format!("papyr_config_test_{}.yaml", std::process::id()));
+    std::fs::write(&toml_path, TOML_CONFIG).unwrap();
+    std::fs::write(&yaml_path, "dpi: 300").unwrap();
+
+    assert_eq!(RegistryConfig::load(&toml_path).unwrap().scan.dpi, 200);
+    assert!(matches!(
+        RegistryConfig::load(&yaml_path),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        RegistryConfig::load(dir.join("papyr_missing_config.toml")),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+
+    let _ = std::fs::remove_file(toml_path);
+    let _ = std::fs::remove_file(yaml_path);
+}
+
+#[test]
+fn test_backend_order_and_disabled() {
+    let defaults = RegistryConfig::default().backends();
+    assert_eq!(defaults, default_backends());
+    assert_eq!(defaults.last(), Some(&Backend::Escl));
+
+    let config = RegistryConfig {
+        order: vec![Backend::Escl, Backend::Unknown, Backend::Escl],
+        disabled: vec![Backend::SaneNet],
+        ..RegistryConfig::default()
+    };
+    let backends = config.backends();
+    assert_eq!(backends[0], Backend::Escl);
+    assert!(!backends.contains(&Backend::SaneNet));
+    assert!(!backends.contains(&Backend::Unknown));
+    assert_eq!(backends.len(), defaults.len() - 1);
+}
+
+#[test]
+fn test_builder_selects_backends() {
+    let registry = BackendRegistry::builder()
+        .order([Backend::Escl])
+        .disable(Backend::SaneNet)
+        .build();
+    let kinds: Vec<Backend> = registry
+        .availability()
+        .into_iter()
+        .map(|(backend, _)| backend)
+        .collect();
+    assert_eq!(kinds[0], Backend::Escl);
+    assert!(!kinds.contains(&Backend::SaneNet));
+
+    let registry = BackendRegistry::builder()
+        .disable(Backend::SaneNet)
+        .enable(Backend::SaneNet)
+        .build();
+    assert!(registry
+        .availability()
+        .iter()
+        .any(|(backend, _)| *backend == Backend::SaneNet));
+}
+
+#[test]
+fn test_static_escl_hosts() {
+    let mut builder = BackendRegistry::builder().escl_mdns(false).escl_hosts([
+        "192.168.1.20",
+        "https://[fe80::1]:8443/eSCL",
+        "scanner.example:8080",
+        "ftp://nope",
+    ]);
+    for backend in all_but(Backend::Escl) {
+        builder = builder.disable(backend);
+    }
+    let registry = builder.build();
+
+    let devices = registry.list_devices().expect("list devices");
+    let ids: Vec<String> = devices.iter().map(|device| device.id.to_string()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            "escl_static_192_168_1_20_80",
+            "escl_static_fe80__1_8443",
+            "escl_static_scanner_example_8080",
+        ]
+    );
+    assert_eq!(devices[2].address.as_deref(), Some("scanner.example:8080"));
+}
+
+#[test]
+fn test_default_scan_config() {
+    let registry = BackendRegistry::empty();
+    let config = registry.default_scan_config();
+    assert_eq!(config.dpi, 300);
+    assert_eq!(config.color_mode, ColorMode::Color);
+
+    let mut builder = BackendRegistry::builder().scan_defaults(ScanDefaults {
+        dpi: 150,
+        duplex: true,
+        source: ScanSource::AdfDuplex,
+        ..ScanDefaults::default()
+    });
+    for backend in default_backends() {
+        builder = builder.disable(backend);
+    }
+    let registry = builder.build();
+    assert!(registry.availability().is_empty());
+    let config = registry.default_scan_config();
+    assert_eq!(config.dpi, 150);
+    assert_eq!(config.source, ScanSource::AdfDuplex);
+}
@@ -0,0 +1,268 @@
+//
+//  papyr_core
+//  tests/dedup_test.rs - Cross-backend device merging and scan fallback tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::config::ScanDefaults;
+use papyr_core::dedup::merge_devices;
+use papyr_core::models::*;
+use papyr_core::registry::BackendRegistry;
+use std::sync::{Arc, Mutex};
+
+fn device(
+    id: &str,
+    model: Option<&str>,
+    serial: Option<&str>,
+    address: Option<&str>,
+) -> ScannerInfo {
+    ScannerInfo {
+        manufacturer: model.map(|_| "HP".to_string()),
+        model: model.map(str::to_string),
+        serial: serial.map(str::to_string),
+        address: address.map(str::to_string),
+        ..ScannerInfo::new(id.parse().unwrap(), id)
+    }
+}
+
+fn route_ids(device: &ScannerInfo) -> Vec<String> {
+    device.routes.iter().map(DeviceId::to_string).collect()
+}
+
+#[test]
+fn test_merge_by_serial() {
+    let merged = merge_devices(vec![
+        device(
+            "sane_airscan",
+            Some("OfficeJet Pro 9010"),
+            Some("1C852A4D-B800-1F08-ABCD-0123456789AB"),
+            None,
+        ),
+        device(
+            "escl_hp",
+            Some("OfficeJet Pro 9010 series"),
+            Some("urn:uuid:1c852a4d-b800-1f08-abcd-0123456789ab"),
+            Some("192.168.1.20:80"),
+        ),
+    ]);
+    assert_eq!(merged.len(), 1);
+    let scanner = &merged[0];
+    assert_eq!(scanner.id.to_string(), "sane_airscan");
+    assert_eq!(scanner.backend, Backend::Sane);
+    // Gaps are filled from the other backend
+    assert_eq!(scanner.address.as_deref(), Some("192.168.1.20:80"));
+    assert_eq!(route_ids(scanner), vec!["sane_airscan", "escl_hp"]);
+    assert_eq!(scanner.routes[1].backend(), Backend::Escl);
+}
+
+#[test]
+fn test_merge_by_host() {
+    let merged = merge_devices(vec![
+        device("sane_escl", None, None, Some("http://192.168.1.20:80")),
+        device("saned_scanhost/net", None, None, Some("192.168.1.99")),
+        device(
+            "escl_hp",
+            Some("OfficeJet"),
+            Some("abc"),
+            Some("192.168.1.20:443"),
+        ),
+    ]);
+    assert_eq!(merged.len(), 2);
+    assert_eq!(route_ids(&merged[0]), vec!["sane_escl", "escl_hp"]);
+    assert_eq!(route_ids(&merged[1]), vec!["saned_scanhost/net"]);
+}
+
+#[test]
+fn test_conflicting_details_keep_devices_apart() {
+    let merged = merge_devices(vec![
+        device("sane_a", Some("LaserJet M479"), None, Some("10.0.0.5")),
+        // Same host, different model
+        device("escl_a", Some("OfficeJet 9010"), None, Some("10.0.0.5:80")),
+        // Same model, different serial
+        device(
+            "saned_b",
+            Some("LaserJet M479"),
+            Some("X2"),
+            Some("10.0.0.6"),
+        ),
+    ]);
+    assert_eq!(merged.len(), 3);
+    assert!(merged.iter().all(|scanner| scanner.routes.len() == 1));
+}
+
+#[test]
+fn test_same_backend_never_merged() {
+    let merged = merge_devices(vec![
+        device("escl_1", Some("M479"), Some("S1"), None),
+        device("escl_2", Some("M479"), Some("S1"), None),
+    ]);
+    assert_eq!(merged.len(), 2);
+}
+
+#[test]
+fn test_model_only_merge_needs_single_candidate() {
+    let airscan = || device("sane_airscan", Some("M479"), None, None);
+
+    let merged = merge_devices(vec![
+        airscan(),
+        device("escl_1", Some("M479"), Some("S1"), Some("10.0.0.5")),
+    ]);
+    assert_eq!(merged.len(), 1);
+
+    // Two identical printers: can't tell which one SANE saw
+    let merged = merge_devices(vec![
+        device("escl_1", Some("M479"), Some("S1"), Some("10.0.0.5")),
+        device("escl_2", Some("M479"), Some("S2"), Some("10.0.0.6")),
+        airscan(),
+    ]);
+    assert_eq!(merged.len(), 3);
+}
+
+/// A backend listing one scanner, whose scans fail with `error` if set.
+struct FlakyBackend {
+    kind: Backend,
+    id: DeviceId,
+    error: Option<fn() -> PapyrError>,
+    scans: Arc<Mutex<Vec<String>>>,
+}
+
+impl BackendProvider for FlakyBackend {
+    fn name(&self) -> &'static str {
+        "Flaky"
+    }
+
+    fn kind(&self) -> Backend {
+        self.kind
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        vec![device(
+            &self.id.to_string(),
+            Some("M479"),
+            Some("SERIAL-1"),
+            None,
+        )]
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        Err(PapyrError::NotFound(device_id.to_string()))
+    }
+
+    fn start_scan(&self, device_id: &DeviceId, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        self.scans.lock().unwrap().push(device_id.to_string());
+        match self.error {
+            Some(error) => Err(error()),
+            None => Ok(Box::new(FinishedSession)),
+        }
+    }
+}
+
+struct FinishedSession;
+
+impl ScanSession for FinishedSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        Ok(None)
+    }
+}
+
+fn flaky_registry(
+    preferred: Option<fn() -> PapyrError>,
+    fallback: Option<fn() -> PapyrError>,
+) -> (BackendRegistry, Arc<Mutex<Vec<String>>>) {
+    let scans = Arc::new(Mutex::new(Vec::new()));
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(FlakyBackend {
+        kind: Backend::Sane,
+        id: DeviceId::new(Backend::Sane, "m479"),
+        error: preferred,
+        scans: Arc::clone(&scans),
+    }));
+    registry.register(Box::new(FlakyBackend {
+        kind: Backend::Escl,
+        id: DeviceId::new(Backend::Escl, "m479"),
+        error: fallback,
+        scans: Arc::clone(&scans),
+    }));
+    (registry, scans)
+}
+
+fn scan_config() -> ScanConfig {
+    ScanDefaults::default().to_config()
+}
+
+fn busy() -> PapyrError {
+    PapyrError::DeviceBusy {
+        backend: Backend::Sane,
+        message: "claimed by another process".into(),
+    }
+}
+
+fn jammed() -> PapyrError {
+    PapyrError::PaperJam {
+        backend: Backend::Sane,
+        message: "jam".into(),
+    }
+}
+
+fn unreachable() -> PapyrError {
+    PapyrError::Io {
+        backend: Backend::Escl,
+        message: "connection refused".into(),
+    }
+}
+
+#[test]
+fn test_registry_lists_merged_device() {
+    let (registry, _) = flaky_registry(None, None);
+    let devices = registry.list_devices().unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(route_ids(&devices[0]), vec!["sane_m479", "escl_m479"]);
+}
+
+#[test]
+fn test_scan_falls_back_to_next_backend() {
+    let (registry, scans) = flaky_registry(Some(busy), None);
+    let mut session = registry
+        .start_scan(&"sane_m479".parse().unwrap(), scan_config())
+        .expect("falls back to eSCL");
+    assert_eq!(*scans.lock().unwrap(), vec!["sane_m479", "escl_m479"]);
+
+    match session.next_event().unwrap() {
+        Some(ScanEvent::Warning { code, message }) => {
+            assert_eq!(code, WarningCode::BackendFallback);
+            assert!(message.contains("sane_m479"), "{}", message);
+        }
+        other => panic!("expected a fallback warning, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_scan_through_alternate_id_starts_there() {
+    let (registry, scans) = flaky_registry(None, None);
+    registry.list_devices().unwrap();
+    registry
+        .start_scan(&"escl_m479".parse().unwrap(), scan_config())
+        .unwrap();
+    assert_eq!(*scans.lock().unwrap(), vec!["escl_m479"]);
+}
+
+#[test]
+fn test_scan_fallback_errors() {
+    // Paper problems would fail the same way on any backend
+    let (registry, scans) = flaky_registry(Some(jammed), None);
+    assert!(matches!(
+        registry.start_scan(&"sane_m479".parse().unwrap(), scan_config()),
+        Err(PapyrError::PaperJam { .. })
+    ));
+    assert_eq!(scans.lock().unwrap().len(), 1);
+
+    // When every backend fails, the preferred backend's error is returned
+    let (registry, scans) = flaky_registry(Some(busy), Some(unreachable));
+    assert!(matches!(
+        registry.start_scan(&"sane_m479".parse().unwrap(), scan_config()),
+        Err(PapyrError::DeviceBusy { .. })
+    ));
+    assert_eq!(scans.lock().unwrap().len(), 2);
+}
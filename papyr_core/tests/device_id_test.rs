@@ -0,0 +1,64 @@
+//
+//  papyr_core
+//  tests/device_id_test.rs - Device id parsing, formatting and serde tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::models::*;
+
+#[test]
+fn test_device_id_round_trip() {
+    for (text, backend, local) in [
+        (
+            "sane_epson2:net:192.168.1.50",
+            Backend::Sane,
+            "epson2:net:192.168.1.50",
+        ),
+        (
+            "escl_static_10_0_0_5_80",
+            Backend::Escl,
+            "static_10_0_0_5_80",
+        ),
+        (
+            "saned_scanhost:6566/test:0",
+            Backend::SaneNet,
+            "scanhost:6566/test:0",
+        ),
+        ("twain_3", Backend::Twain, "3"),
+        ("wia_device_0", Backend::Wia, "device_0"),
+        ("ica_", Backend::Ica, ""),
+    ] {
+        let id: DeviceId = text.parse().unwrap();
+        assert_eq!(id.backend(), backend, "{}", text);
+        assert_eq!(id.local(), local, "{}", text);
+        assert_eq!(id.to_string(), text);
+        assert_eq!(id, DeviceId::new(backend, local));
+    }
+}
+
+#[test]
+fn test_malformed_device_ids_not_found() {
+    for text in ["", "nonexistent", "floppy_a", "_x", "SANE_test:0"] {
+        assert!(
+            matches!(text.parse::<DeviceId>(), Err(PapyrError::NotFound(_))),
+            "{:?} should not parse",
+            text
+        );
+    }
+}
+
+#[test]
+fn test_device_id_serde() {
+    let id = DeviceId::new(Backend::SaneNet, "scanhost/test:0");
+    let json = serde_json::to_string(&id).unwrap();
+    assert_eq!(json, "\"saned_scanhost/test:0\"");
+    assert_eq!(serde_json::from_str::<DeviceId>(&json).unwrap(), id);
+    assert!(serde_json::from_str::<DeviceId>("\"floppy_a\"").is_err());
+
+    let info = ScannerInfo::new(id.clone(), "Test");
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["id"], "saned_scanhost/test:0");
+    assert_eq!(info.backend, Backend::SaneNet);
+}
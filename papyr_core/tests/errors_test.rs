@@ -0,0 +1,190 @@
+//
+//  papyr_core
+//  tests/errors_test.rs - Error taxonomy and backend status mapping tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::backends::escl::error_for_status;
+use papyr_core::backends::sane_common::*;
+use papyr_core::models::*;
+
+const ADF_EMPTY_STATUS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<scan:ScannerStatus xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03" xmlns:pwg="http://www.pwg.org/schemas/2010/12/sm">
+    <pwg:Version>2.63</pwg:Version>
+    <pwg:State>Idle</pwg:State>
+    <scan:AdfState>ScannerAdfEmpty</scan:AdfState>
+</scan:ScannerStatus>"#;
+
+const ADF_JAM_STATUS: &str = r#"<scan:ScannerStatus xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03">
+    <scan:AdfState>ScannerAdfJam</scan:AdfState>
+</scan:ScannerStatus>"#;
+
+#[test]
+fn test_error_codes_are_stable() {
+    let backend = Backend::Sane;
+    let message = String::new();
+    let errors = [
+        PapyrError::NotFound(message.clone()),
+        PapyrError::InvalidConfig(message.clone()),
+        PapyrError::Backend(message.clone()),
+        PapyrError::DeviceBusy {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::FeederEmpty {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::PaperJam {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::CoverOpen {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::Timeout {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::Cancelled,
+        PapyrError::Unauthorized {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::Unsupported {
+            backend,
+            option: message.clone(),
+        },
+        PapyrError::Io {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::Protocol {
+            backend,
+            message: message.clone(),
+        },
+        PapyrError::NotImplemented,
+        PapyrError::Other(message),
+    ];
+
+    let codes: Vec<i32> = errors.iter().map(PapyrError::code).collect();
+    assert_eq!(codes, (1..=15).collect::<Vec<i32>>());
+}
+
+#[test]
+fn test_error_retryable_and_backend() {
+    let busy = PapyrError::DeviceBusy {
+        backend: Backend::Escl,
+        message: "busy".into(),
+    };
+    assert!(busy.is_retryable());
+    assert_eq!(busy.backend(), Some(Backend::Escl));
+    assert_eq!(busy.to_string(), "device busy: busy");
+
+    let jam = PapyrError::PaperJam {
+        backend: Backend::Sane,
+        message: "jammed".into(),
+    };
+    assert!(!jam.is_retryable());
+    assert_eq!(jam.backend(), Some(Backend::Sane));
+
+    assert!(!PapyrError::Cancelled.is_retryable());
+    assert_eq!(PapyrError::NotFound("x".into()).backend(), None);
+}
+
+#[test]
+fn test_sane_status_error_mapping() {
+    let backend = Backend::SaneNet;
+    assert!(matches!(
+        status_error(backend, "start", SANE_STATUS_DEVICE_BUSY),
+        PapyrError::DeviceBusy {
+            backend: Backend::SaneNet,
+            ..
+        }
+    ));
+    assert!(matches!(
+        status_error(backend, "read", SANE_STATUS_JAMMED),
+        PapyrError::PaperJam { .. }
+    ));
+    assert!(matches!(
+        status_error(backend, "start", SANE_STATUS_NO_DOCS),
+        PapyrError::FeederEmpty { .. }
+    ));
+    assert!(matches!(
+        status_error(backend, "start", SANE_STATUS_COVER_OPEN),
+        PapyrError::CoverOpen { .. }
+    ));
+    assert!(matches!(
+        status_error(backend, "read", SANE_STATUS_IO_ERROR),
+        PapyrError::Io { .. }
+    ));
+    assert!(matches!(
+        status_error(backend, "open", SANE_STATUS_ACCESS_DENIED),
+        PapyrError::Unauthorized { .. }
+    ));
+    assert!(matches!(
+        status_error(backend, "read", SANE_STATUS_CANCELLED),
+        PapyrError::Cancelled
+    ));
+    assert!(matches!(
+        status_error(backend, "open", SANE_STATUS_NO_MEM),
+        PapyrError::Backend(_)
+    ));
+}
+
+#[test]
+fn test_sane_option_error_mapping() {
+    assert!(matches!(
+        option_error(Backend::Sane, "lamp-off-time", SANE_STATUS_UNSUPPORTED),
+        PapyrError::Unsupported { option, .. } if option == "lamp-off-time"
+    ));
+    assert!(matches!(
+        option_error(Backend::Sane, "resolution", SANE_STATUS_INVAL),
+        PapyrError::InvalidConfig(_)
+    ));
+    assert!(matches!(
+        option_error(Backend::Sane, "resolution", SANE_STATUS_DEVICE_BUSY),
+        PapyrError::DeviceBusy { .. }
+    ));
+}
+
+#[test]
+fn test_escl_status_mapping() {
+    assert!(matches!(
+        error_for_status("create job", 400, "", None),
+        PapyrError::InvalidConfig(_)
+    ));
+    assert!(matches!(
+        error_for_status("capabilities", 401, "", None),
+        PapyrError::Unauthorized { .. }
+    ));
+    assert!(matches!(
+        error_for_status("create job", 503, "", None),
+        PapyrError::DeviceBusy { .. }
+    ));
+    assert!(matches!(
+        error_for_status("next document", 500, "oops", None),
+        PapyrError::Protocol { .. }
+    ));
+}
+
+#[test]
+fn test_escl_status_consults_scanner_state() {
+    let empty = error_for_status("create job", 409, "", Some(ADF_EMPTY_STATUS));
+    assert!(matches!(empty, PapyrError::FeederEmpty { .. }));
+    assert!(empty.to_string().contains("ScannerAdfEmpty"));
+
+    assert!(matches!(
+        error_for_status("next document", 503, "", Some(ADF_JAM_STATUS)),
+        PapyrError::PaperJam { .. }
+    ));
+
+    // Only a refused job is explained by the scanner state
+    assert!(matches!(
+        error_for_status("create job", 400, "", Some(ADF_EMPTY_STATUS)),
+        PapyrError::InvalidConfig(_)
+    ));
+}
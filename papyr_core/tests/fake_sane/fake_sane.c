@@ -0,0 +1,513 @@
+/*
+ *  papyr_core
+ *  tests/fake_sane/fake_sane.c - Minimal libsane stand-in for the SANE backend tests
+ *
+ *  Created by Ngonidzashe Mangudya on 2025/10/22.
+ *  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+ *
+ *  Built by tests/sane_test.rs with the system C compiler. Each device name
+ *  selects a scenario, so tests running in parallel never share state:
+ *
+ *    fake:scanner  well-behaved flatbed + 3-sheet ADF
+ *    fake:jammed   jams part way through the second ADF page
+ *    fake:empty    feeder is always empty (SANE_STATUS_NO_DOCS)
+ *    fake:busy     sane_open fails with SANE_STATUS_DEVICE_BUSY
+ *    fake:faulty   sane_read fails with SANE_STATUS_IO_ERROR after one line
+ *    fake:stalled  non-blocking reads never return data
+ *    fake:warming  sane_start reports SANE_STATUS_WARMING_UP twice first
+ *
+ *  Images are tiny (resolution / 25 pixels wide, 4 lines) and reads are
+ *  split into 5-byte chunks so lines straddle read boundaries.
+ */
+
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef int SANE_Int;
+typedef int SANE_Word;
+typedef int SANE_Status;
+typedef void *SANE_Handle;
+
+enum {
+    STATUS_GOOD = 0,
+    STATUS_UNSUPPORTED = 1,
+    STATUS_CANCELLED = 2,
+    STATUS_DEVICE_BUSY = 3,
+    STATUS_INVAL = 4,
+    STATUS_EOF = 5,
+    STATUS_JAMMED = 6,
+    STATUS_NO_DOCS = 7,
+    STATUS_IO_ERROR = 9,
+    STATUS_WARMING_UP = 12,
+};
+
+enum { TYPE_BOOL, TYPE_INT, TYPE_FIXED, TYPE_STRING, TYPE_BUTTON, TYPE_GROUP };
+enum { UNIT_NONE = 0, UNIT_BIT = 2, UNIT_DPI = 4 };
+enum { CONSTRAINT_NONE, CONSTRAINT_RANGE, CONSTRAINT_WORD_LIST, CONSTRAINT_STRING_LIST };
+enum { ACTION_GET, ACTION_SET, ACTION_SET_AUTO };
+enum { FRAME_GRAY, FRAME_RGB, FRAME_RED, FRAME_GREEN, FRAME_BLUE };
+
+#define INFO_INEXACT 1
+#define INFO_RELOAD_OPTIONS 2
+#define INFO_RELOAD_PARAMS 4
+
+#define CAP_SOFT_SELECT 1
+#define CAP_SOFT_DETECT 4
+#define CAP_INACTIVE 32
+#define CAP_SETTABLE (CAP_SOFT_SELECT | CAP_SOFT_DETECT)
+
+#define LINES 4
+#define CHUNK 5
+#define FEEDER_SHEETS 3
+#define WARM_UP_POLLS 2
+#define STRING_SIZE 32
+
+typedef struct {
+    const char *name;
+    const char *vendor;
+    const char *model;
+    const char *type;
+} SANE_Device;
+
+typedef struct {
+    SANE_Word min;
+    SANE_Word max;
+    SANE_Word quant;
+} SANE_Range;
+
+typedef struct {
+    const char *name;
+    const char *title;
+    const char *desc;
+    SANE_Int type;
+    SANE_Int unit;
+    SANE_Int size;
+    SANE_Int cap;
+    SANE_Int constraint_type;
+    const void *constraint;
+} SANE_Option_Descriptor;
+
+typedef struct {
+    SANE_Int format;
+    SANE_Int last_frame;
+    SANE_Int bytes_per_line;
+    SANE_Int pixels_per_line;
+    SANE_Int lines;
+    SANE_Int depth;
+} SANE_Parameters;
+
+enum {
+    OPT_COUNT,
+    OPT_MODE_GROUP,
+    OPT_MODE,
+    OPT_DEPTH,
+    OPT_RESOLUTION,
+    OPT_SOURCE,
+    OPT_THREE_PASS,
+    OPT_PICTURE,
+    NUM_OPTIONS
+};
+
+static const char *mode_list[] = {"Color", "Gray", "Lineart", NULL};
+static const char *source_list[] = {"Flatbed", "Automatic Document Feeder", NULL};
+static const char *picture_list[] = {"Gradient", "Solid white", NULL};
+static const SANE_Word depth_list[] = {2, 8, 16};
+static const SANE_Range resolution_range = {50, 600, 25};
+
+static const SANE_Option_Descriptor option_template[NUM_OPTIONS] = {
+    {"", "Number of options", "", TYPE_INT, UNIT_NONE, 4, CAP_SOFT_DETECT, CONSTRAINT_NONE, NULL},
+    {"", "Scan Mode", "", TYPE_GROUP, UNIT_NONE, 0, 0, CONSTRAINT_NONE, NULL},
+    {"mode", "Scan mode", "Selects the scan mode", TYPE_STRING, UNIT_NONE, STRING_SIZE,
+     CAP_SETTABLE, CONSTRAINT_STRING_LIST, mode_list},
+    {"depth", "Bit depth", "Bits per sample", TYPE_INT, UNIT_BIT, 4, CAP_SETTABLE,
+     CONSTRAINT_WORD_LIST, depth_list},
+    {"resolution", "Scan resolution", "Resolution of the scan", TYPE_INT, UNIT_DPI, 4,
+     CAP_SETTABLE, CONSTRAINT_RANGE, &resolution_range},
+    {"source", "Scan source", "Selects the scan source", TYPE_STRING, UNIT_NONE, STRING_SIZE,
+     CAP_SETTABLE, CONSTRAINT_STRING_LIST, source_list},
+    {"three-pass", "Three-pass simulation", "Deliver colour scans as three frames", TYPE_BOOL,
+     UNIT_NONE, 4, CAP_SETTABLE, CONSTRAINT_NONE, NULL},
+    {"test-picture", "Test picture", "Image content to generate", TYPE_STRING, UNIT_NONE,
+     STRING_SIZE, CAP_SETTABLE, CONSTRAINT_STRING_LIST, picture_list},
+};
+
+static const SANE_Device scanner = {"fake:scanner", "Papyr", "Fake Scanner", "flatbed scanner"};
+static const SANE_Device jammed = {"fake:jammed", "Papyr", "Jammed Scanner", "sheetfed scanner"};
+static const SANE_Device empty = {"fake:empty", "Papyr", "Empty Feeder", "sheetfed scanner"};
+static const SANE_Device busy = {"fake:busy", "Papyr", "Busy Scanner", "flatbed scanner"};
+static const SANE_Device faulty = {"fake:faulty", "Papyr", "Faulty Scanner", "flatbed scanner"};
+static const SANE_Device stalled = {"fake:stalled", "Papyr", "Stalled Scanner", "flatbed scanner"};
+static const SANE_Device warming = {"fake:warming", "Papyr", "Warming Scanner", "flatbed scanner"};
+static const SANE_Device *device_list[] = {&scanner, &jammed,  &empty,   &busy,
+                                           &faulty,  &stalled, &warming, NULL};
+
+typedef struct {
+    const SANE_Device *device;
+    SANE_Option_Descriptor options[NUM_OPTIONS];
+    char mode[STRING_SIZE];
+    char source[STRING_SIZE];
+    char picture[STRING_SIZE];
+    SANE_Word depth;
+    SANE_Word resolution;
+    SANE_Word three_pass;
+
+    int scanning;
+    int cancelled;
+    int sheets_fed;
+    int warmups;
+    int pass;
+    SANE_Parameters params;
+    unsigned char *frame;
+    int frame_len;
+    int offset;
+} FakeScanner;
+
+SANE_Status sane_init(SANE_Int *version_code, void *authorize) {
+    (void)authorize;
+    if (version_code) {
+        *version_code = (1 << 24) | (0 << 16) | 0;
+    }
+    return STATUS_GOOD;
+}
+
+void sane_exit(void) {}
+
+SANE_Status sane_get_devices(const SANE_Device ***list, SANE_Int local_only) {
+    (void)local_only;
+    *list = device_list;
+    return STATUS_GOOD;
+}
+
+static int is_color(const FakeScanner *s) { return strcmp(s->mode, "Color") == 0; }
+static int is_lineart(const FakeScanner *s) { return strcmp(s->mode, "Lineart") == 0; }
+static int uses_feeder(const FakeScanner *s) { return strcmp(s->source, "Flatbed") != 0; }
+
+/* Depth only applies to Gray and Color, three-pass only to Color. */
+static void update_caps(FakeScanner *s) {
+    s->options[OPT_DEPTH].cap = CAP_SETTABLE | (is_lineart(s) ? CAP_INACTIVE : 0);
+    s->options[OPT_THREE_PASS].cap = CAP_SETTABLE | (is_color(s) ? 0 : CAP_INACTIVE);
+}
+
+SANE_Status sane_open(const char *name, SANE_Handle *handle) {
+    const SANE_Device *device = NULL;
+    for (int i = 0; device_list[i]; i++) {
+        if (strcmp(device_list[i]->name, name) == 0) {
+            device = device_list[i];
+        }
+    }
+    if (!device) {
+        return STATUS_INVAL;
+    }
+    if (device == &busy) {
+        return STATUS_DEVICE_BUSY;
+    }
+
+    FakeScanner *s = calloc(1, sizeof(FakeScanner));
+    if (!s) {
+        return STATUS_IO_ERROR;
+    }
+    s->device = device;
+    memcpy(s->options, option_template, sizeof(option_template));
+    strcpy(s->mode, "Color");
+    strcpy(s->source, "Flatbed");
+    strcpy(s->picture, "Gradient");
+    s->depth = 8;
+    s->resolution = 100;
+    update_caps(s);
+
+    *handle = s;
+    return STATUS_GOOD;
+}
+
+static void drop_frame(FakeScanner *s) {
+    free(s->frame);
+    s->frame = NULL;
+    s->frame_len = 0;
+    s->offset = 0;
+}
+
+void sane_cancel(SANE_Handle handle) {
+    FakeScanner *s = handle;
+    s->cancelled = 1;
+    s->scanning = 0;
+    s->pass = 0;
+    drop_frame(s);
+}
+
+void sane_close(SANE_Handle handle) {
+    FakeScanner *s = handle;
+    drop_frame(s);
+    free(s);
+}
+
+const SANE_Option_Descriptor *sane_get_option_descriptor(SANE_Handle handle, SANE_Int option) {
+    FakeScanner *s = handle;
+    if (option < 0 || option >= NUM_OPTIONS) {
+        return NULL;
+    }
+    return &s->options[option];
+}
+
+static int in_list(const char **list, const char *value) {
+    for (int i = 0; list[i]; i++) {
+        if (strcmp(list[i], value) == 0) {
+            return 1;
+        }
+    }
+    return 0;
+}
+
+static SANE_Status set_string(char *target, const char **list, const char *value) {
+    if (!in_list(list, value)) {
+        return STATUS_INVAL;
+    }
+    strcpy(target, value);
+    return STATUS_GOOD;
+}
+
+SANE_Status sane_control_option(SANE_Handle handle, SANE_Int option, SANE_Int action,
+                                void *value, SANE_Int *info) {
+    FakeScanner *s = handle;
+    SANE_Word *word = value;
+    const char *text = value;
+    SANE_Status status = STATUS_GOOD;
+
+    if (info) {
+        *info = 0;
+    }
+    if (option < 0 || option >= NUM_OPTIONS || option == OPT_MODE_GROUP || !value) {
+        return STATUS_INVAL;
+    }
+    if (s->scanning) {
+        return STATUS_DEVICE_BUSY;
+    }
+
+    if (action == ACTION_GET) {
+        switch (option) {
+        case OPT_COUNT: *word = NUM_OPTIONS; break;
+        case OPT_MODE: strcpy(value, s->mode); break;
+        case OPT_DEPTH: *word = s->depth; break;
+        case OPT_RESOLUTION: *word = s->resolution; break;
+        case OPT_SOURCE: strcpy(value, s->source); break;
+        case OPT_THREE_PASS: *word = s->three_pass; break;
+        case OPT_PICTURE: strcpy(value, s->picture); break;
+        }
+        return STATUS_GOOD;
+    }
+
+    if (action != ACTION_SET || option == OPT_COUNT) {
+        return STATUS_INVAL;
+    }
+    if (s->options[option].cap & CAP_INACTIVE) {
+        return STATUS_INVAL;
+    }
+
+    switch (option) {
+    case OPT_MODE:
+        status = set_string(s->mode, mode_list, text);
+        if (status == STATUS_GOOD) {
+            update_caps(s);
+            if (info) {
+                *info = INFO_RELOAD_OPTIONS | INFO_RELOAD_PARAMS;
+            }
+        }
+        break;
+    case OPT_DEPTH:
+        if (*word != 8 && *word != 16) {
+            return STATUS_INVAL;
+        }
+        s->depth = *word;
+        break;
+    case OPT_RESOLUTION: {
+        SANE_Word dpi = *word;
+        if (dpi < resolution_range.min || dpi > resolution_range.max) {
+            return STATUS_INVAL;
+        }
+        /* Round down to the range's quantisation and report it */
+        SANE_Word rounded = dpi - (dpi - resolution_range.min) % resolution_range.quant;
+        if (rounded != dpi) {
+            *word = rounded;
+            if (info) {
+                *info = INFO_INEXACT;
+            }
+        }
+        s->resolution = rounded;
+        break;
+    }
+    case OPT_SOURCE:
+        status = set_string(s->source, source_list, text);
+        if (status == STATUS_GOOD) {
+            s->sheets_fed = 0;
+        }
+        break;
+    case OPT_THREE_PASS: s->three_pass = *word != 0; break;
+    case OPT_PICTURE: status = set_string(s->picture, picture_list, text); break;
+    }
+    return status;
+}
+
+/* Parameters of the next (or current) frame. */
+static void frame_params(FakeScanner *s, SANE_Parameters *p) {
+    int width = s->resolution / 25;
+    memset(p, 0, sizeof(*p));
+    p->pixels_per_line = width;
+    p->lines = LINES;
+    p->last_frame = 1;
+
+    if (is_lineart(s)) {
+        p->format = FRAME_GRAY;
+        p->depth = 1;
+        p->bytes_per_line = (width + 7) / 8;
+    } else if (is_color(s) && s->three_pass) {
+        p->format = FRAME_RED + s->pass;
+        p->depth = s->depth;
+        p->bytes_per_line = width * (s->depth / 8);
+        p->last_frame = s->pass == 2;
+    } else if (is_color(s)) {
+        /* Two bytes of driver padding at the end of every line */
+        p->format = FRAME_RGB;
+        p->depth = s->depth;
+        p->bytes_per_line = width * 3 * (s->depth / 8) + 2;
+    } else {
+        p->format = FRAME_GRAY;
+        p->depth = s->depth;
+        p->bytes_per_line = width * (s->depth / 8);
+    }
+}
+
+/* Sample value for pixel (x, y) of channel c: a gradient, or solid white. */
+static unsigned sample(const FakeScanner *s, int x, int y, int c) {
+    if (strcmp(s->picture, "Solid white") == 0) {
+        return 0xffff;
+    }
+    return ((y * 16 + x) * 4 + c * 64 + s->pass * 64) & 0xff;
+}
+
+static void render_frame(FakeScanner *s) {
+    SANE_Parameters *p = &s->params;
+    int channels = p->format == FRAME_RGB ? 3 : 1;
+
+    s->frame_len = p->bytes_per_line * p->lines;
+    s->frame = calloc(1, s->frame_len);
+    s->offset = 0;
+
+    for (int y = 0; y < p->lines; y++) {
+        unsigned char *line = s->frame + y * p->bytes_per_line;
+        if (p->depth == 1) {
+            /* Alternating black and white pixels, shifted on odd lines */
+            memset(line, y % 2 ? 0x55 : 0xaa, p->bytes_per_line);
+            continue;
+        }
+        for (int x = 0; x < p->pixels_per_line; x++) {
+            for (int c = 0; c < channels; c++) {
+                unsigned v = sample(s, x, y, c);
+                int i = x * channels + c;
+                if (p->depth == 16) {
+                    /* Native byte order, as libsane delivers it */
+                    uint16_t wide = (uint16_t)(v & 0xff) * 257;
+                    memcpy(line + i * 2, &wide, 2);
+                } else {
+                    line[i] = (unsigned char)v;
+                }
+            }
+        }
+        /* Padding bytes carry garbage the frontend must drop */
+        for (int i = p->pixels_per_line * channels * (p->depth / 8); i < p->bytes_per_line; i++) {
+            line[i] = 0x5a;
+        }
+    }
+}
+
+SANE_Status sane_start(SANE_Handle handle) {
+    FakeScanner *s = handle;
+    s->cancelled = 0;
+
+    if (s->device == &empty) {
+        return STATUS_NO_DOCS;
+    }
+    if (s->device == &warming && s->warmups < WARM_UP_POLLS) {
+        s->warmups++;
+        return STATUS_WARMING_UP;
+    }
+    /* A new page starts unless a three-pass page is mid-way */
+    if (s->pass == 0 && uses_feeder(s)) {
+        if (s->sheets_fed == FEEDER_SHEETS) {
+            return STATUS_NO_DOCS;
+        }
+        s->sheets_fed++;
+    }
+
+    drop_frame(s);
+    frame_params(s, &s->params);
+    render_frame(s);
+    s->scanning = 1;
+    return STATUS_GOOD;
+}
+
+SANE_Status sane_get_parameters(SANE_Handle handle, SANE_Parameters *params) {
+    FakeScanner *s = handle;
+    if (s->scanning) {
+        *params = s->params;
+    } else {
+        frame_params(s, params);
+    }
+    return STATUS_GOOD;
+}
+
+SANE_Status sane_read(SANE_Handle handle, unsigned char *data, SANE_Int max_length,
+                      SANE_Int *length) {
+    FakeScanner *s = handle;
+    *length = 0;
+
+    if (s->cancelled) {
+        return STATUS_CANCELLED;
+    }
+    if (!s->scanning) {
+        return STATUS_INVAL;
+    }
+    if (s->device == &stalled) {
+        return STATUS_GOOD;
+    }
+
+    int first_line_done = s->offset >= s->params.bytes_per_line;
+    if (s->device == &faulty && first_line_done) {
+        return STATUS_IO_ERROR;
+    }
+    if (s->device == &jammed && s->sheets_fed == 2 && first_line_done) {
+        s->scanning = 0;
+        return STATUS_JAMMED;
+    }
+
+    if (s->offset == s->frame_len) {
+        s->scanning = 0;
+        if (s->params.format >= FRAME_RED) {
+            s->pass = s->params.last_frame ? 0 : s->pass + 1;
+        }
+        return STATUS_EOF;
+    }
+
+    int len = s->frame_len - s->offset;
+    if (len > CHUNK) {
+        len = CHUNK;
+    }
+    if (len > max_length) {
+        len = max_length;
+    }
+    memcpy(data, s->frame + s->offset, len);
+    s->offset += len;
+    *length = len;
+    return STATUS_GOOD;
+}
+
+SANE_Status sane_set_io_mode(SANE_Handle handle, SANE_Int non_blocking) {
+    FakeScanner *s = handle;
+    (void)non_blocking;
+    return s->scanning ? STATUS_GOOD : STATUS_INVAL;
+}
+
+SANE_Status sane_get_select_fd(SANE_Handle handle, SANE_Int *fd) {
+    (void)handle;
+    (void)fd;
+    return STATUS_UNSUPPORTED;
+}
@@ -13,6 +13,7 @@ use std::ptr;
 extern "C" {
     fn papyr_init() -> i32;
     fn papyr_cleanup();
+    fn papyr_load_plugins(dir: *const i8) -> i32;
     fn papyr_list_scanners() -> *mut papyr_core::ffi::CScannerInfoList;
     fn papyr_free_scanner_list(list: *mut papyr_core::ffi::CScannerInfoList);
     fn papyr_get_capabilities(device_id: *const i8) -> *mut papyr_core::ffi::CCapabilities;
@@ -20,6 +21,88 @@ extern "C" {
     fn papyr_start_scan(device_id: *const i8, config: *const papyr_core::ffi::CScanConfig) -> i32;
     fn papyr_next_scan_event(session_id: i32) -> *mut papyr_core::ffi::CScanEvent;
     fn papyr_free_scan_event(event: *mut papyr_core::ffi::CScanEvent);
+    fn papyr_set_credentials_callback(callback: Option<papyr_core::ffi::CCredentialsCallback>);
+    fn papyr_set_log_callback(
+        callback: Option<papyr_core::ffi::CLogCallback>,
+        max_level: i32,
+    ) -> i32;
+    fn papyr_get_device_options(device_id: *const i8) -> *mut papyr_core::ffi::CDeviceOptionList;
+    fn papyr_set_device_option(device_id: *const i8, name: *const i8, value: *const i8) -> i32;
+    fn papyr_free_device_options(list: *mut papyr_core::ffi::CDeviceOptionList);
+    fn papyr_start_sensor_monitor(device_id: *const i8, interval_ms: i32) -> i32;
+    fn papyr_next_sensor_event(
+        monitor_id: i32,
+        timeout_ms: i32,
+    ) -> *mut papyr_core::ffi::CSensorEvent;
+    fn papyr_stop_sensor_monitor(monitor_id: i32);
+    fn papyr_free_sensor_event(event: *mut papyr_core::ffi::CSensorEvent);
+    fn papyr_last_error_code() -> i32;
+    fn papyr_last_error_message() -> *const i8;
+    fn papyr_last_error_retryable() -> i32;
+    fn papyr_list_scanners_cancellable(
+        cancel_token_id: i32,
+    ) -> *mut papyr_core::ffi::CScannerInfoList;
+    fn papyr_get_capabilities_cancellable(
+        device_id: *const i8,
+        cancel_token_id: i32,
+    ) -> *mut papyr_core::ffi::CCapabilities;
+    fn papyr_cancel_scan(session_id: i32) -> i32;
+    fn papyr_cancel_token_new() -> i32;
+    fn papyr_cancel_token_cancel(token_id: i32) -> i32;
+    fn papyr_cancel_token_free(token_id: i32);
+    fn papyr_discover_scanners(cancel_token_id: i32) -> *mut papyr_core::ffi::CDiscovery;
+    fn papyr_free_discovery(discovery: *mut papyr_core::ffi::CDiscovery);
+    fn papyr_set_enumeration_timeout(backend: i32, timeout_ms: i32) -> i32;
+    fn papyr_init_with_config(config_path: *const i8) -> i32;
+    fn papyr_init_with_config_json(config_json: *const i8) -> i32;
+    fn papyr_get_default_scan_config(config: *mut papyr_core::ffi::CScanConfig) -> i32;
+    fn papyr_get_default_scan_config_v2(config: *mut papyr_core::ffi::CScanConfigV2) -> i32;
+    fn papyr_start_scan_v2(
+        device_id: *const i8,
+        config: *const papyr_core::ffi::CScanConfigV2,
+        cancel_token_id: i32,
+    ) -> i32;
+}
+
+extern "C" fn test_credentials_callback(
+    resource: *const std::os::raw::c_char,
+    username: *mut std::os::raw::c_char,
+    username_len: usize,
+    password: *mut std::os::raw::c_char,
+    password_len: usize,
+) -> i32 {
+    let resource = unsafe { std::ffi::CStr::from_ptr(resource) };
+    if resource.to_bytes() != b"net:scanbox" {
+        return 0;
+    }
+
+    let user = CString::new("scanner").unwrap();
+    let pass = CString::new("hunter2").unwrap();
+    unsafe {
+        assert!(username_len > user.as_bytes().len() && password_len > pass.as_bytes().len());
+        ptr::copy_nonoverlapping(user.as_ptr(), username, user.as_bytes_with_nul().len());
+        ptr::copy_nonoverlapping(pass.as_ptr(), password, pass.as_bytes_with_nul().len());
+    }
+    1
+}
+
+// Lines logged by this test binary itself; other tests log concurrently
+static TEST_LOG: std::sync::Mutex<Vec<(i32, String)>> = std::sync::Mutex::new(Vec::new());
+
+extern "C" fn test_log_callback(
+    level: i32,
+    target: *const std::os::raw::c_char,
+    message: *const std::os::raw::c_char,
+) {
+    let (target, message) = unsafe {
+        (
+            std::ffi::CStr::from_ptr(target).to_string_lossy(),
+            std::ffi::CStr::from_ptr(message).to_string_lossy(),
+        )
+    };
+    if target == "ffi_test" {
+        TEST_LOG.lock().unwrap().push((level, message.into_owned()));
+    }
 }
 
 #[test]
@@ -52,6 +135,17 @@ fn test_ffi_list_scanners() {
     }
 }
 
+#[test]
+fn test_ffi_scanner_info_layout() {
+    use papyr_core::ffi::{CDeviceRoute, CScannerInfo};
+    use std::mem::size_of;
+
+    // The Dart bindings mirror these structs and check the same sizes
+    let pointer = size_of::<usize>();
+    assert_eq!(size_of::<CScannerInfo>(), 16 * pointer);
+    assert_eq!(size_of::<CDeviceRoute>(), 2 * pointer);
+}
+
 #[test]
 fn test_ffi_double_init() {
     unsafe {
@@ -108,6 +202,26 @@ fn test_ffi_start_scan_invalid_device() {
         // Should return negative value (error) for invalid device
         assert!(session_id < 0, "Should return error for invalid device ID");
 
+        let config = papyr_core::ffi::CScanConfigV2 {
+            source: 0,
+            duplex: 0,
+            dpi: 300,
+            color_mode: 0,
+            page_width_mm: 215.9,
+            page_height_mm: 279.4,
+            dpi_y: 600,
+            bit_depth: 16,
+            max_pages: 1,
+            has_area: 1,
+            area_x_mm: 10.5,
+            area_y_mm: 10.5,
+            area_width_mm: 85.6,
+            area_height_mm: 53.98,
+        };
+        assert!(papyr_start_scan_v2(device_id.as_ptr(), &config, 0) < 0);
+        assert_eq!(papyr_start_scan_v2(device_id.as_ptr(), ptr::null(), 0), -1);
+        assert_eq!(papyr_last_error_code(), 2); // PAPYR_ERROR_INVALID_CONFIG
+
         papyr_cleanup();
     }
 }
@@ -133,6 +247,193 @@ fn test_ffi_free_null_pointers() {
         papyr_free_scanner_list(ptr::null_mut());
         papyr_free_capabilities(ptr::null_mut());
         papyr_free_scan_event(ptr::null_mut());
+        papyr_free_device_options(ptr::null_mut());
+        papyr_free_sensor_event(ptr::null_mut());
+    }
+}
+
+#[test]
+fn test_ffi_device_options_invalid_device() {
+    unsafe {
+        papyr_init();
+
+        let device_id = CString::new("invalid_device_id_12345").unwrap();
+        let name = CString::new("lamp-off-time").unwrap();
+        let value = CString::new("10").unwrap();
+
+        assert!(papyr_get_device_options(device_id.as_ptr()).is_null());
+        assert!(papyr_get_device_options(ptr::null()).is_null());
+        assert!(papyr_set_device_option(device_id.as_ptr(), name.as_ptr(), value.as_ptr()) < 0);
+        assert!(papyr_set_device_option(device_id.as_ptr(), name.as_ptr(), ptr::null()) < 0);
+
+        papyr_cleanup();
+    }
+}
+
+#[test]
+fn test_ffi_sensor_monitor_invalid_device() {
+    unsafe {
+        papyr_init();
+
+        let device_id = CString::new("invalid_device_id_12345").unwrap();
+        assert!(papyr_start_sensor_monitor(device_id.as_ptr(), 100) < 0);
+        assert!(papyr_start_sensor_monitor(ptr::null(), 100) < 0);
+        assert!(papyr_next_sensor_event(99999, 0).is_null());
+
+        // Stopping an unknown monitor is a no-op
+        papyr_stop_sensor_monitor(99999);
+
+        papyr_cleanup();
+    }
+}
+
+#[test]
+fn test_ffi_last_error() {
+    unsafe {
+        papyr_init();
+
+        // NULL arguments are rejected before the registry is consulted
+        assert!(papyr_get_capabilities(ptr::null()).is_null());
+        assert_eq!(papyr_last_error_code(), 2); // PAPYR_ERROR_INVALID_CONFIG
+        assert_eq!(papyr_last_error_retryable(), 0);
+        let message = papyr_last_error_message();
+        assert!(!message.is_null());
+        assert!(std::ffi::CStr::from_ptr(message)
+            .to_string_lossy()
+            .contains("NULL"));
+
+        let device_id = CString::new("invalid_device_id_12345").unwrap();
+        assert!(papyr_get_capabilities(device_id.as_ptr()).is_null());
+        assert_ne!(papyr_last_error_code(), 0);
+
+        // The next call resets the error
+        assert!(papyr_next_sensor_event(99999, 0).is_null());
+        assert_eq!(papyr_last_error_code(), 0);
+        assert!(papyr_last_error_message().is_null());
+
+        papyr_cleanup();
+    }
+}
+
+#[test]
+fn test_ffi_cancel_tokens() {
+    unsafe {
+        papyr_init();
+
+        let token = papyr_cancel_token_new();
+        assert!(token > 0);
+        assert_eq!(papyr_cancel_token_cancel(token), 0);
+        // Cancelling again is harmless
+        assert_eq!(papyr_cancel_token_cancel(token), 0);
+
+        // A cancelled token stops the call before any discovery
+        assert!(papyr_list_scanners_cancellable(token).is_null());
+        assert_eq!(papyr_last_error_code(), 9); // PAPYR_ERROR_CANCELLED
+
+        let device_id = CString::new("escl_invalid_device_id_12345").unwrap();
+        assert!(papyr_get_capabilities_cancellable(device_id.as_ptr(), token).is_null());
+        assert_eq!(papyr_last_error_code(), 9);
+
+        papyr_cancel_token_free(token);
+        assert!(papyr_cancel_token_cancel(token) < 0);
+        assert!(papyr_list_scanners_cancellable(token).is_null());
+        assert_eq!(papyr_last_error_code(), 1); // PAPYR_ERROR_NOT_FOUND
+        assert!(papyr_cancel_scan(99999) < 0);
+
+        papyr_cleanup();
+    }
+}
+
+#[test]
+fn test_ffi_discover_scanners() {
+    unsafe {
+        papyr_init();
+
+        // Keep eSCL's mDNS browse short
+        assert_eq!(papyr_set_enumeration_timeout(-1, 200), 0);
+        let discovery = papyr_discover_scanners(0);
+        assert!(!discovery.is_null());
+        let result = &*discovery;
+        assert!(!result.scanners.is_null());
+        assert!(result.backend_count > 0);
+        let backends = std::slice::from_raw_parts(result.backends, result.backend_count);
+        for backend in backends {
+            assert!((0..=3).contains(&backend.status));
+        }
+        papyr_free_discovery(discovery);
+        papyr_free_discovery(ptr::null_mut());
+
+        let token = papyr_cancel_token_new();
+        papyr_cancel_token_cancel(token);
+        assert!(papyr_discover_scanners(token).is_null());
+        assert_eq!(papyr_last_error_code(), 9); // PAPYR_ERROR_CANCELLED
+        papyr_cancel_token_free(token);
+
+        papyr_cleanup();
+    }
+}
+
+#[test]
+fn test_ffi_init_with_config() {
+    unsafe {
+        let bad = CString::new("{\"escl\": {\"mdns\": \"yes\"}}").unwrap();
+        assert_eq!(papyr_init_with_config_json(bad.as_ptr()), -1);
+        assert_eq!(papyr_last_error_code(), 2); // PAPYR_ERROR_INVALID_CONFIG
+
+        let missing = CString::new("/nonexistent/papyr.toml").unwrap();
+        assert_eq!(papyr_init_with_config(missing.as_ptr()), -1);
+        assert_eq!(papyr_last_error_code(), 2);
+
+        assert_eq!(papyr_init_with_config_json(ptr::null()), -1);
+
+        let json = CString::new(
+            "{\"escl\": {\"mdns\": false}, \"scan\": {\"dpi\": 150, \"max_pages\": 3, \
+             \"page_size\": {\"width_mm\": 215.9, \"height_mm\": 279.4}}}",
+        )
+        .unwrap();
+        assert_eq!(papyr_init_with_config_json(json.as_ptr()), 0);
+
+        let mut config: papyr_core::ffi::CScanConfig = std::mem::zeroed();
+        assert_eq!(papyr_get_default_scan_config(&mut config), 0);
+        assert!(config.dpi > 0);
+        assert_eq!((config.page_width_mm, config.page_height_mm), (216, 279));
+        assert_eq!(papyr_get_default_scan_config(ptr::null_mut()), -1);
+
+        // Only the v2 config has room for max_pages and sub-mm sizes
+        let mut config: papyr_core::ffi::CScanConfigV2 = std::mem::zeroed();
+        assert_eq!(papyr_get_default_scan_config_v2(&mut config), 0);
+        assert_eq!((config.dpi, config.max_pages), (150, 3));
+        assert_eq!(
+            (config.page_width_mm, config.page_height_mm),
+            (215.9, 279.4)
+        );
+        assert_eq!(config.has_area, 0);
+        assert_eq!(papyr_get_default_scan_config_v2(ptr::null_mut()), -1);
+
+        papyr_cleanup();
+    }
+}
+
+#[test]
+fn test_ffi_load_plugins() {
+    unsafe {
+        papyr_init();
+
+        assert_eq!(papyr_load_plugins(ptr::null()), -1);
+        assert_eq!(papyr_last_error_code(), 2); // PAPYR_ERROR_INVALID_CONFIG
+
+        let missing = CString::new("/nonexistent/papyr-plugins").unwrap();
+        assert_eq!(papyr_load_plugins(missing.as_ptr()), -1);
+        assert_eq!(papyr_last_error_code(), 2);
+
+        // A directory without plugins
+        let empty = std::env::temp_dir().join(format!("papyr_no_plugins_{}", std::process::id()));
+        std::fs::create_dir_all(&empty).unwrap();
+        let empty_path = CString::new(empty.to_str().unwrap()).unwrap();
+        assert_eq!(papyr_load_plugins(empty_path.as_ptr()), 0);
+        let _ = std::fs::remove_dir(empty);
+
+        papyr_cleanup();
     }
 }
 
@@ -170,6 +471,49 @@ fn test_ffi_memory_safety() {
     }
 }
 
+#[test]
+fn test_ffi_credentials_callback() {
+    unsafe {
+        papyr_set_credentials_callback(Some(test_credentials_callback));
+
+        let credentials = papyr_core::auth::credentials_for("net:scanbox");
+        assert_eq!(
+            credentials,
+            Some(papyr_core::auth::Credentials::new("scanner", "hunter2"))
+        );
+        assert_eq!(papyr_core::auth::credentials_for("other"), None);
+
+        papyr_set_credentials_callback(None);
+        assert_eq!(papyr_core::auth::credentials_for("net:scanbox"), None);
+    }
+}
+
+#[test]
+fn test_ffi_log_callback() {
+    unsafe {
+        assert_eq!(papyr_set_log_callback(Some(test_log_callback), 6), -1);
+        assert_eq!(
+            papyr_last_error_code(),
+            2,
+            "invalid levels are InvalidConfig"
+        );
+
+        // PAPYR_LOG_INFO
+        assert_eq!(papyr_set_log_callback(Some(test_log_callback), 3), 0);
+        tracing::info_span!("job", id = 7).in_scope(|| {
+            tracing::warn!(page = 2, "feeder is almost empty");
+            tracing::debug!("not forwarded at INFO");
+        });
+        assert_eq!(papyr_set_log_callback(None, 0), 0);
+        tracing::warn!("not forwarded once cleared");
+
+        assert_eq!(
+            *TEST_LOG.lock().unwrap(),
+            [(2, "job{id=7}: feeder is almost empty page=2".to_string())]
+        );
+    }
+}
+
 #[cfg(test)]
 mod integration {
     use super::*;
@@ -210,16 +554,24 @@ mod integration {
                     }
 
                     // 5. Attempt to start scan
-                    let config = papyr_core::ffi::CScanConfig {
+                    let config = papyr_core::ffi::CScanConfigV2 {
                         source: 0,
                         duplex: 0,
                         dpi: 150,
                         color_mode: 0,
-                        page_width_mm: 216,
-                        page_height_mm: 279,
+                        page_width_mm: 215.9,
+                        page_height_mm: 279.4,
+                        dpi_y: 0,
+                        bit_depth: 0,
+                        max_pages: 1,
+                        has_area: 0,
+                        area_x_mm: 0.0,
+                        area_y_mm: 0.0,
+                        area_width_mm: 0.0,
+                        area_height_mm: 0.0,
                     };
 
-                    let session_id = papyr_start_scan(first_scanner.id, &config);
+                    let session_id = papyr_start_scan_v2(first_scanner.id, &config, 0);
                     if session_id > 0 {
                         println!("  Scan session created: {}", session_id);
 
@@ -0,0 +1,174 @@
+//
+//  papyr_core
+//  tests/logging_test.rs - Log sink forwarding, spans and level filtering
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::config::ScanDefaults;
+use papyr_core::logging::{self, LogSink};
+use papyr_core::models::*;
+use papyr_core::registry::BackendRegistry;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+
+/// One scanner whose session logs while reading its page.
+struct LoggingBackend;
+
+impl BackendProvider for LoggingBackend {
+    fn name(&self) -> &'static str {
+        "Logging"
+    }
+
+    fn kind(&self) -> Backend {
+        Backend::Escl
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        vec![ScannerInfo::new(
+            DeviceId::new(Backend::Escl, "a1"),
+            "Logging Scanner",
+        )]
+    }
+
+    fn capabilities(&self, _device_id: &DeviceId) -> Result<Capabilities> {
+        Err(PapyrError::NotImplemented)
+    }
+
+    fn start_scan(&self, _device_id: &DeviceId, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        let meta = PageMeta {
+            index: 0,
+            width_px: 4,
+            height_px: 2,
+            dpi: 150,
+            dpi_y: 150,
+            color_mode: ColorMode::Gray,
+            bit_depth: 8,
+        };
+        Ok(Box::new(LoggingSession {
+            events: vec![
+                ScanEvent::PageStarted(0),
+                ScanEvent::PageData(vec![0; 8]),
+                ScanEvent::warning(WarningCode::ConfigAdjusted, "lamp is dim"),
+                ScanEvent::PageComplete(meta),
+                ScanEvent::JobComplete,
+            ]
+            .into(),
+        }))
+    }
+}
+
+struct LoggingSession {
+    events: std::collections::VecDeque<ScanEvent>,
+}
+
+impl ScanSession for LoggingSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        let event = self.events.pop_front();
+        if let Some(ScanEvent::PageData(data)) = &event {
+            tracing::debug!(bytes = data.len(), "reading page");
+        }
+        Ok(event)
+    }
+}
+
+type Lines = Arc<Mutex<Vec<(Level, String, String)>>>;
+
+/// The sink is process-wide, so tests taking it run one at a time.
+fn capture(max_level: LevelFilter) -> (MutexGuard<'static, ()>, Lines) {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let lines: Lines = Arc::default();
+    let sink = Arc::clone(&lines);
+    logging::set_log_sink(
+        Some(Arc::new(move |level: Level, target: &str, message: &str| {
+            sink.lock()
+                .unwrap()
+                .push((level, target.to_string(), message.to_string()));
+        }) as Arc<dyn LogSink>),
+        max_level,
+    )
+    .unwrap();
+    (guard, lines)
+}
+
+fn scan() {
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(LoggingBackend));
+    let session = registry
+        .start_scan(
+            &DeviceId::new(Backend::Escl, "a1"),
+            ScanDefaults::default().to_config(),
+        )
+        .unwrap();
+    assert!(ScanEvents::new(session).all(|event| !matches!(event, ScanEvent::Error(_))));
+}
+
+fn messages(lines: &Lines) -> Vec<String> {
+    let lines = lines.lock().unwrap();
+    lines
+        .iter()
+        .map(|(_, _, message)| message.clone())
+        .collect()
+}
+
+#[test]
+fn test_scan_logged_within_spans() {
+    let (_guard, lines) = capture(LevelFilter::DEBUG);
+    scan();
+    logging::set_log_sink(None, LevelFilter::OFF).unwrap();
+    let messages = messages(&lines);
+
+    // Discovery runs within the scan, as the device wasn't cached
+    assert!(
+        messages.iter().any(|m| m.starts_with(
+            "scan{device=escl_a1}:discovery: discovery finished devices=1 scanners=1"
+        )),
+        "{:#?}",
+        messages
+    );
+    assert!(messages
+        .iter()
+        .any(|m| m == "scan{device=escl_a1}:page{index=0}: reading page bytes=8"));
+    assert!(messages
+        .iter()
+        .any(|m| m == "scan{device=escl_a1}: scan complete"));
+
+    // Targets are the emitting modules
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|(level, target, message)| {
+        *level == Level::INFO
+            && target == "papyr_core::registry"
+            && message.ends_with("scan complete")
+    }));
+    assert!(lines
+        .iter()
+        .any(|(_, target, message)| target == "logging_test" && message.ends_with("bytes=8")));
+}
+
+#[test]
+fn test_log_level_filter() {
+    let (_guard, lines) = capture(LevelFilter::WARN);
+    scan();
+    assert_eq!(
+        messages(&lines),
+        [
+            "scan{device=escl_a1}: could not check scan config: not implemented device=escl_a1",
+            "scan{device=escl_a1}: lamp is dim code=ConfigAdjusted",
+        ]
+    );
+    assert!(lines
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|(level, _, _)| *level == Level::WARN));
+
+    // Cleared, nothing is forwarded
+    lines.lock().unwrap().clear();
+    logging::set_log_sink(None, LevelFilter::TRACE).unwrap();
+    scan();
+    assert!(lines.lock().unwrap().is_empty());
+}
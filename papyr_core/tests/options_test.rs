@@ -0,0 +1,178 @@
+//
+//  papyr_core
+//  tests/options_test.rs - Device option model and eSCL option parsing tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::backends::escl::options_from_capabilities;
+use papyr_core::models::*;
+
+fn option(value_type: OptionType, constraint: OptionConstraint) -> DeviceOption {
+    DeviceOption {
+        name: "test-option".into(),
+        title: "Test option".into(),
+        description: String::new(),
+        group: None,
+        value_type,
+        unit: OptionUnit::None,
+        constraint,
+        value: None,
+        settable: true,
+        advanced: false,
+    }
+}
+
+const CAPABILITIES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<scan:ScannerCapabilities xmlns:scan="http://schemas.hp.com/imaging/escl/2011/05/03" xmlns:pwg="http://www.pwg.org/schemas/2010/12/sm">
+    <pwg:Version>2.63</pwg:Version>
+    <scan:Platen>
+        <scan:PlatenInputCaps>
+            <scan:SettingProfiles>
+                <scan:SettingProfile>
+                    <scan:SupportedIntents>
+                        <scan:Intent>Document</scan:Intent>
+                        <scan:Intent>Photo</scan:Intent>
+                    </scan:SupportedIntents>
+                </scan:SettingProfile>
+            </scan:SettingProfiles>
+        </scan:PlatenInputCaps>
+    </scan:Platen>
+    <scan:Adf>
+        <scan:AdfSimplexInputCaps>
+            <scan:SettingProfiles>
+                <scan:SettingProfile>
+                    <scan:SupportedIntents>
+                        <scan:Intent>Document</scan:Intent>
+                        <scan:Intent>TextAndGraphic</scan:Intent>
+                    </scan:SupportedIntents>
+                </scan:SettingProfile>
+            </scan:SettingProfiles>
+        </scan:AdfSimplexInputCaps>
+        <scan:BlankPageDetection>true</scan:BlankPageDetection>
+    </scan:Adf>
+    <scan:BrightnessSupport>
+        <scan:Min>0</scan:Min>
+        <scan:Max>100</scan:Max>
+        <scan:Normal>50</scan:Normal>
+        <scan:Step>1</scan:Step>
+    </scan:BrightnessSupport>
+    <scan:GammaSupport>
+        <scan:Min>10</scan:Min>
+        <scan:Max>300</scan:Max>
+    </scan:GammaSupport>
+</scan:ScannerCapabilities>"#;
+
+#[test]
+fn test_option_coerce_parses_text() {
+    let flag = option(OptionType::Bool, OptionConstraint::None);
+    assert_eq!(
+        flag.coerce(OptionValue::String("on".into())).unwrap(),
+        OptionValue::Bool(true)
+    );
+    assert_eq!(
+        flag.coerce(OptionValue::Int(0)).unwrap(),
+        OptionValue::Bool(false)
+    );
+
+    let gamma = option(OptionType::Fixed, OptionConstraint::None);
+    assert_eq!(
+        gamma.coerce(OptionValue::String(" 1.8 ".into())).unwrap(),
+        OptionValue::Fixed(1.8)
+    );
+    assert_eq!(
+        gamma.coerce(OptionValue::Int(2)).unwrap(),
+        OptionValue::Fixed(2.0)
+    );
+
+    let count = option(OptionType::Int, OptionConstraint::None);
+    assert_eq!(
+        count.coerce(OptionValue::Fixed(2.6)).unwrap(),
+        OptionValue::Int(3)
+    );
+    assert!(count.coerce(OptionValue::String("many".into())).is_err());
+}
+
+#[test]
+fn test_option_coerce_checks_constraints() {
+    let range = option(
+        OptionType::Int,
+        OptionConstraint::Range {
+            min: 0.0,
+            max: 60.0,
+            step: None,
+        },
+    );
+    assert!(range.coerce(OptionValue::Int(60)).is_ok());
+    assert!(matches!(
+        range.coerce(OptionValue::Int(61)),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+
+    let list = option(
+        OptionType::String,
+        OptionConstraint::List(vec![
+            OptionValue::String("Red".into()),
+            OptionValue::String("None".into()),
+        ]),
+    );
+    assert!(list.coerce(OptionValue::String("Red".into())).is_ok());
+    assert!(list.coerce(OptionValue::String("Blue".into())).is_err());
+}
+
+#[test]
+fn test_option_coerce_read_only() {
+    let mut read_only = option(OptionType::Int, OptionConstraint::None);
+    read_only.settable = false;
+    assert!(matches!(
+        read_only.coerce(OptionValue::Int(1)),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_escl_options_from_capabilities() {
+    let options = options_from_capabilities(CAPABILITIES_XML);
+    let names: Vec<&str> = options.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["Intent", "Brightness", "Gamma", "BlankPageDetection"]
+    );
+
+    let intent = &options[0];
+    assert_eq!(
+        intent.constraint,
+        OptionConstraint::List(vec![
+            OptionValue::String("Document".into()),
+            OptionValue::String("Photo".into()),
+            OptionValue::String("TextAndGraphic".into()),
+        ])
+    );
+    assert_eq!(intent.value, Some(OptionValue::String("Document".into())));
+
+    let brightness = &options[1];
+    assert_eq!(
+        brightness.constraint,
+        OptionConstraint::Range {
+            min: 0.0,
+            max: 100.0,
+            step: Some(1.0)
+        }
+    );
+    assert_eq!(brightness.value, Some(OptionValue::Int(50)));
+    assert!(!brightness.advanced);
+
+    let gamma = &options[2];
+    assert_eq!(gamma.value, None);
+    assert!(gamma.advanced);
+
+    assert_eq!(options[3].value_type, OptionType::Bool);
+}
+
+#[test]
+fn test_escl_options_without_capabilities() {
+    let options = options_from_capabilities("<scan:ScannerCapabilities/>");
+    assert_eq!(options.len(), 1);
+    assert_eq!(options[0].name, "Intent");
+}
@@ -0,0 +1,122 @@
+//
+//  papyr_core
+//  tests/page_sizes_test.rs - Page-size catalogue and unit conversion tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::models::*;
+
+fn assert_size(format: PageFormat, width_mm: f64, height_mm: f64) {
+    let size = format.size().unwrap();
+    assert!(
+        (size.width_mm - width_mm).abs() < 0.001 && (size.height_mm - height_mm).abs() < 0.001,
+        "{} is {}",
+        format,
+        size
+    );
+}
+
+#[test]
+fn test_iso_series() {
+    assert_size(PageFormat::IsoA(0), 841.0, 1189.0);
+    assert_size(PageFormat::IsoA(4), 210.0, 297.0);
+    assert_size(PageFormat::IsoA(10), 26.0, 37.0);
+    assert_size(PageFormat::IsoB(5), 176.0, 250.0);
+    assert_size(PageFormat::IsoC(5), 162.0, 229.0);
+    assert_size(PageFormat::IsoC(10), 28.0, 40.0);
+    assert_eq!(PageFormat::IsoA(11).size(), None);
+}
+
+#[test]
+fn test_named_sizes() {
+    assert_size(PageFormat::Letter, 215.9, 279.4);
+    assert_size(PageFormat::Legal, 215.9, 355.6);
+    assert_size(PageFormat::Tabloid, 279.4, 431.8);
+    assert_size(PageFormat::Executive, 184.15, 266.7);
+    assert_size(PageFormat::BusinessCard, 50.8, 88.9);
+    assert_size(PageFormat::Id1, 53.98, 85.6);
+    assert_size(PageFormat::Photo4x6, 101.6, 152.4);
+    assert_eq!(PageFormat::MaxArea.size(), None);
+    assert_eq!(PageFormat::Auto.size(), None);
+
+    assert_eq!(PageFormat::IsoB(4).to_string(), "B4");
+    assert_eq!(PageFormat::Id1.to_string(), "ID-1");
+    assert_eq!(PageFormat::catalogue().len(), 42);
+}
+
+#[test]
+fn test_unit_conversions() {
+    let letter = PageFormat::Letter.size().unwrap();
+    let (width_in, height_in) = letter.to_inches();
+    assert!((width_in - 8.5).abs() < 1e-9 && (height_in - 11.0).abs() < 1e-9);
+    let (width_pt, height_pt) = letter.to_points();
+    assert!((width_pt - 612.0).abs() < 1e-9 && (height_pt - 792.0).abs() < 1e-9);
+    assert_eq!(letter.to_three_hundredths(), (2550, 3300));
+    assert_eq!(letter.to_pixels(150, 300), (1275, 3300));
+    assert_eq!(letter.to_string(), "215.9x279.4 mm");
+
+    let a4 = PageSize::from_three_hundredths(2480, 3508);
+    assert!((a4.width_mm - 209.97).abs() < 0.01);
+    assert_eq!(PageFormat::matching(&a4), Some(PageFormat::IsoA(4)));
+    assert_eq!(
+        PageFormat::matching(&PageSize::from_points(612.0, 1008.0)),
+        Some(PageFormat::Legal)
+    );
+    assert_eq!(PageFormat::matching(&PageSize::new(100.0, 100.0)), None);
+}
+
+#[test]
+fn test_fitting_subset() {
+    let a4 = PageFormat::IsoA(4).size().unwrap();
+    let fitting = PageFormat::fitting(&a4);
+    assert!(fitting.contains(&PageFormat::IsoA(4)));
+    assert!(fitting.contains(&PageFormat::IsoB(5)));
+    assert!(fitting.contains(&PageFormat::Photo5x7));
+    assert!(!fitting.contains(&PageFormat::IsoB(4)));
+    assert!(!fitting.contains(&PageFormat::Letter));
+    assert!(fitting.contains(&PageFormat::Photo8x10));
+    assert!(!fitting.contains(&PageFormat::Tabloid));
+
+    // eSCL reports A4 in 1/300 inch, a hair under 210 mm
+    let platen = PageSize::from_three_hundredths(2480, 3508);
+    assert!(PageFormat::fitting(&platen).contains(&PageFormat::IsoA(4)));
+}
+
+#[test]
+fn test_max_area_falls_back_to_page_sizes() {
+    let caps = Capabilities {
+        sources: vec![ScanSource::Flatbed, ScanSource::Adf],
+        dpis: vec![300],
+        dpi_ranges: vec![],
+        independent_dpi: false,
+        color_modes: vec![ColorMode::Color],
+        bit_depths: vec![8],
+        page_sizes: DEFAULT_PAGE_FORMATS.to_vec(),
+        max_areas: vec![SourceArea {
+            source: ScanSource::Adf,
+            max: PageFormat::Legal.size().unwrap(),
+        }],
+        supports_duplex: false,
+    };
+
+    // Letter width by A4 height
+    let flatbed = caps.max_area(ScanSource::Flatbed).unwrap();
+    assert_eq!(flatbed.to_string(), "215.9x297 mm");
+    assert_eq!(
+        caps.page_sizes_for(ScanSource::Flatbed),
+        DEFAULT_PAGE_FORMATS
+    );
+    assert!(caps
+        .page_sizes_for(ScanSource::Adf)
+        .contains(&PageFormat::Legal));
+    assert_eq!(
+        caps.resolve_page(PageFormat::Auto, ScanSource::Adf),
+        PageFormat::Legal.size()
+    );
+    assert_eq!(
+        caps.resolve_page(PageFormat::IsoA(5), ScanSource::Adf),
+        PageFormat::IsoA(5).size()
+    );
+}
@@ -0,0 +1,355 @@
+//
+//  papyr_core
+//  tests/plugin_test.rs - Plugin loading and the example plugin, through the C ABI
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+// Builds examples/plugin/example_plugin.c with the system C compiler
+#![cfg(unix)]
+
+use papyr_core::backends::plugin::PluginBackend;
+use papyr_core::config::ScanDefaults;
+use papyr_core::models::*;
+use papyr_core::registry::{default_backends, BackendRegistry};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+/// Compiles the example plugin into `dir`; `None` when no C compiler is
+/// available.
+fn build_plugin(dir: &str, file: &str, defines: &[&str]) -> Option<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = dir.join(format!("{}.{}", file, std::env::consts::DLL_EXTENSION));
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+
+    match Command::new(&compiler)
+        .args(["-shared", "-fPIC", "-O1", "-I"])
+        .arg(root.join("include"))
+        .args(defines)
+        .arg("-o")
+        .arg(&output)
+        .arg(root.join("examples/plugin/example_plugin.c"))
+        .status()
+    {
+        Ok(status) => {
+            assert!(status.success(), "failed to compile the example plugin");
+            Some(output)
+        }
+        Err(e) => {
+            println!("⚠️  {} not available ({}), skipping", compiler, e);
+            None
+        }
+    }
+}
+
+/// A plugin directory with the example plugin and files that aren't plugins.
+fn plugin_dir() -> Option<&'static Path> {
+    static DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let plugin = build_plugin("plugins", "libexample_plugin", &[])?;
+        let dir = plugin.parent().unwrap().to_path_buf();
+        let junk = format!("libjunk.{}", std::env::consts::DLL_EXTENSION);
+        std::fs::write(dir.join(junk), b"not a library").unwrap();
+        std::fs::write(dir.join("README.txt"), b"ignored").unwrap();
+        Some(dir)
+    })
+    .as_deref()
+}
+
+fn plugin_registry() -> Option<BackendRegistry> {
+    let mut registry = BackendRegistry::empty();
+    assert_eq!(registry.load_plugins(plugin_dir()?).unwrap(), 1);
+    Some(registry)
+}
+
+fn id(local: &str) -> DeviceId {
+    DeviceId::new(Backend::Plugin, format!("example/{}", local))
+}
+
+fn config(color_mode: ColorMode) -> ScanConfig {
+    ScanConfig {
+        source: ScanSource::Adf,
+        dpi: 100,
+        color_mode,
+        page_size: PageSize::new(127.0, 254.0),
+        ..ScanDefaults::default().to_config()
+    }
+}
+
+/// Pages as (data, meta), and the event that ended the job.
+fn collect(session: Box<dyn ScanSession>) -> (Vec<(Vec<u8>, PageMeta)>, ScanEvent) {
+    let mut pages = Vec::new();
+    let mut data = Vec::new();
+    for event in ScanEvents::new(session) {
+        match event {
+            ScanEvent::PageData(chunk) => data.extend(chunk),
+            ScanEvent::PageComplete(meta) => pages.push((std::mem::take(&mut data), meta)),
+            event if event.is_terminal() => return (pages, event),
+            _ => {}
+        }
+    }
+    panic!("the session ended without a terminal event");
+}
+
+#[test]
+fn test_plugin_devices_listed() {
+    let Some(registry) = plugin_registry() else {
+        return;
+    };
+    assert_eq!(
+        registry.availability(),
+        vec![(Backend::Plugin, BackendAvailability::Available)]
+    );
+
+    let devices = registry.list_devices().unwrap();
+    let ids: Vec<String> = devices.iter().map(|device| device.id.to_string()).collect();
+    assert_eq!(
+        ids,
+        [
+            "plugin_example/check",
+            "plugin_example/jammed",
+            "plugin_example/stalled"
+        ]
+    );
+    let check = &devices[0];
+    assert_eq!(check.name, "Example Check Scanner");
+    assert_eq!(check.backend, Backend::Plugin);
+    assert_eq!(check.model.as_deref(), Some("CS-100"));
+    assert_eq!(check.serial.as_deref(), Some("EX0001"));
+    assert_eq!(check.connection, ConnectionKind::Usb);
+    assert_eq!(check.address, None);
+}
+
+#[test]
+fn test_plugin_capabilities() {
+    let Some(registry) = plugin_registry() else {
+        return;
+    };
+    let caps = registry.capabilities(&id("check")).unwrap();
+    assert_eq!(caps.sources, [ScanSource::Adf]);
+    assert_eq!(caps.dpis, [100, 200, 300]);
+    assert_eq!(caps.color_modes, [ColorMode::Color, ColorMode::Gray]);
+    assert_eq!(caps.bit_depths, [8]);
+    assert!(caps.independent_dpi);
+    assert!(!caps.supports_duplex);
+
+    assert!(matches!(
+        registry.capabilities(&id("missing")),
+        Err(PapyrError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_plugin_scan() {
+    let Some(registry) = plugin_registry() else {
+        return;
+    };
+
+    let session = registry
+        .start_scan(&id("check"), config(ColorMode::Gray))
+        .unwrap();
+    let (pages, end) = collect(session);
+    assert!(matches!(end, ScanEvent::JobComplete), "{:?}", end);
+    assert_eq!(pages.len(), 3);
+    for (index, (data, meta)) in pages.iter().enumerate() {
+        assert_eq!(meta.index, index as u32);
+        assert_eq!((meta.width_px, meta.height_px), (500, 1000));
+        assert_eq!(
+            (meta.dpi, meta.color_mode, meta.bit_depth),
+            (100, ColorMode::Gray, 8)
+        );
+        assert_eq!(data.len(), 500 * 1000);
+        // The page number is in the corner
+        assert_eq!(data[0], index as u8 * 64);
+    }
+
+    let session = registry
+        .start_scan(
+            &id("check"),
+            ScanConfig {
+                max_pages: Some(1),
+                ..config(ColorMode::Color)
+            },
+        )
+        .unwrap();
+    let (pages, _) = collect(session);
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].0.len(), 500 * 1000 * 3);
+
+    // The area and vertical resolution reach the plugin and come back
+    let session = registry
+        .start_scan(
+            &id("check"),
+            ScanConfig {
+                dpi_y: Some(200),
+                area: Some(ScanArea {
+                    x_mm: 10.0,
+                    y_mm: 20.0,
+                    width_mm: 127.0,
+                    height_mm: 127.0,
+                }),
+                max_pages: Some(1),
+                ..config(ColorMode::Gray)
+            },
+        )
+        .unwrap();
+    let (pages, _) = collect(session);
+    let meta = &pages[0].1;
+    assert_eq!((meta.width_px, meta.height_px), (500, 1000));
+    assert_eq!((meta.dpi, meta.dpi_y), (100, 200));
+}
+
+#[test]
+fn test_plugin_errors() {
+    let Some(registry) = plugin_registry() else {
+        return;
+    };
+
+    let session = registry
+        .start_scan(&id("jammed"), config(ColorMode::Gray))
+        .unwrap();
+    let (pages, end) = collect(session);
+    assert_eq!(pages.len(), 1);
+    match end {
+        ScanEvent::Error(PapyrError::PaperJam { backend, message }) => {
+            assert_eq!(backend, Backend::Plugin);
+            assert_eq!(message, "sheet 2 is stuck in the feeder");
+        }
+        other => panic!("expected a paper jam, got {:?}", other),
+    }
+
+    // Straight to the plugin, past the registry's config check
+    let plugin = PluginBackend::load(plugin_dir().unwrap().join(format!(
+        "libexample_plugin.{}",
+        std::env::consts::DLL_EXTENSION
+    )))
+    .unwrap();
+    assert!(matches!(
+        plugin.start_scan(&id("check"), config(ColorMode::Bw)),
+        Err(PapyrError::Unsupported {
+            backend: Backend::Plugin,
+            ..
+        })
+    ));
+    // Settings it can't apply are refused rather than dropped
+    match plugin.start_scan(
+        &id("check"),
+        ScanConfig {
+            brightness: Some(10),
+            ..config(ColorMode::Gray)
+        },
+    ) {
+        Err(PapyrError::Unsupported { option, .. }) => {
+            assert_eq!(option, "brightness and contrast")
+        }
+        Err(e) => panic!("expected Unsupported, got {}", e),
+        Ok(_) => panic!("the plugin accepted a brightness"),
+    }
+    // Ids of other plugins and backends never reach it
+    for other in [
+        DeviceId::new(Backend::Plugin, "other/check"),
+        DeviceId::new(Backend::Escl, "example/check"),
+    ] {
+        assert!(matches!(
+            plugin.capabilities(&other),
+            Err(PapyrError::NotFound(_))
+        ));
+    }
+}
+
+#[test]
+fn test_plugin_scan_cancelled() {
+    let Some(registry) = plugin_registry() else {
+        return;
+    };
+
+    let cancel = CancelToken::new();
+    let mut session = registry
+        .start_scan_cancellable(&id("stalled"), config(ColorMode::Gray), &cancel)
+        .unwrap();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        cancel.cancel();
+    });
+
+    assert!(matches!(
+        session.next_event().unwrap(),
+        Some(ScanEvent::Cancelled)
+    ));
+    assert!(session.next_event().unwrap().is_none());
+    canceller.join().unwrap();
+}
+
+#[test]
+fn test_plugin_abi_mismatch_refused() {
+    let Some(plugin) = build_plugin(
+        "plugins_abi",
+        "libold_plugin",
+        &["-DEXAMPLE_ABI_VERSION=99"],
+    ) else {
+        return;
+    };
+    match PluginBackend::load(&plugin) {
+        Err(PapyrError::InvalidConfig(message)) => {
+            assert!(message.contains("plugin ABI 99"), "{}", message)
+        }
+        Err(e) => panic!("expected InvalidConfig, got {}", e),
+        Ok(_) => panic!("a plugin for ABI 99 was loaded"),
+    }
+
+    let mut registry = BackendRegistry::empty();
+    assert_eq!(registry.load_plugins(plugin.parent().unwrap()).unwrap(), 0);
+}
+
+#[test]
+fn test_plugin_truncated_vtable_refused() {
+    let Some(plugin) = build_plugin(
+        "plugins_truncated",
+        "libtruncated_plugin",
+        &["-DEXAMPLE_VTABLE_SIZE=16"],
+    ) else {
+        return;
+    };
+    match PluginBackend::load(&plugin) {
+        Err(PapyrError::InvalidConfig(message)) => {
+            assert!(message.contains("vtable is 16 bytes"), "{}", message)
+        }
+        Err(e) => panic!("expected InvalidConfig, got {}", e),
+        Ok(_) => panic!("a plugin with a 16-byte vtable was loaded"),
+    }
+}
+
+#[test]
+fn test_plugin_names_are_unique() {
+    let Some(dir) = plugin_dir() else {
+        return;
+    };
+    let mut registry = BackendRegistry::empty();
+    assert_eq!(registry.load_plugins(dir).unwrap(), 1);
+    assert_eq!(registry.load_plugins(dir).unwrap(), 0);
+    assert_eq!(registry.availability().len(), 1);
+
+    assert!(matches!(
+        registry.load_plugins(dir.join("missing")),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_plugin_dirs_in_config() {
+    let Some(dir) = plugin_dir() else {
+        return;
+    };
+    let mut builder = BackendRegistry::builder().plugin_dir(dir);
+    for backend in default_backends() {
+        builder = builder.disable(backend);
+    }
+    let registry = builder.build();
+    assert_eq!(registry.list_devices().unwrap().len(), 3);
+}
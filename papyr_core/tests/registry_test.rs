@@ -0,0 +1,477 @@
+//
+//  papyr_core
+//  tests/registry_test.rs - Device cache and routing tests with fake backends
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::models::*;
+use papyr_core::registry::{BackendRegistry, DiscoveryStatus};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A backend with a settable device list that, like eSCL on an HTTP
+/// failure, answers capabilities for any id.
+#[derive(Clone)]
+struct FakeBackend {
+    kind: Backend,
+    dpi: u32,
+    devices: Arc<Mutex<Vec<String>>>,
+    enumerations: Arc<AtomicUsize>,
+    capability_calls: Arc<AtomicUsize>,
+    option_calls: Arc<AtomicUsize>,
+}
+
+impl FakeBackend {
+    fn new(kind: Backend, dpi: u32, devices: &[&str]) -> Self {
+        Self {
+            kind,
+            dpi,
+            devices: Arc::new(Mutex::new(
+                devices.iter().map(|id| id.to_string()).collect(),
+            )),
+            enumerations: Arc::new(AtomicUsize::new(0)),
+            capability_calls: Arc::new(AtomicUsize::new(0)),
+            option_calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn enumerations(&self) -> usize {
+        self.enumerations.load(Ordering::SeqCst)
+    }
+}
+
+impl BackendProvider for FakeBackend {
+    fn name(&self) -> &'static str {
+        "Fake"
+    }
+
+    fn kind(&self) -> Backend {
+        self.kind
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        self.enumerations.fetch_add(1, Ordering::SeqCst);
+        self.devices
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|id| ScannerInfo::new(DeviceId::new(self.kind, id.clone()), id.clone()))
+            .collect()
+    }
+
+    fn capabilities(&self, _device_id: &DeviceId) -> Result<Capabilities> {
+        self.capability_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(Capabilities {
+            sources: vec![ScanSource::Flatbed],
+            dpis: vec![self.dpi],
+            dpi_ranges: vec![],
+            independent_dpi: false,
+            color_modes: vec![ColorMode::Color],
+            bit_depths: vec![8],
+            page_sizes: vec![],
+            max_areas: vec![],
+            supports_duplex: false,
+        })
+    }
+
+    fn options(&self, _device_id: &DeviceId) -> Result<Vec<DeviceOption>> {
+        self.option_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![])
+    }
+
+    fn start_scan(&self, _device_id: &DeviceId, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        Ok(Box::new(FinishedSession))
+    }
+}
+
+/// A backend whose enumeration takes `delay`, or never finishes unless
+/// cancelled, or panics.
+struct SlowBackend {
+    kind: Backend,
+    delay: Option<Duration>,
+    panics: bool,
+    heed_cancel: bool,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SlowBackend {
+    fn new(kind: Backend, delay: Option<Duration>) -> Self {
+        Self {
+            kind,
+            delay,
+            panics: false,
+            heed_cancel: true,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl BackendProvider for SlowBackend {
+    fn name(&self) -> &'static str {
+        "Slow"
+    }
+
+    fn kind(&self) -> Backend {
+        self.kind
+    }
+
+    fn enumerate(&self) -> Vec<ScannerInfo> {
+        self.enumerate_cancellable(&CancelToken::new())
+    }
+
+    fn enumerate_cancellable(&self, cancel: &CancelToken) -> Vec<ScannerInfo> {
+        if self.panics {
+            panic!("probe crashed");
+        }
+        let started = Instant::now();
+        while self.delay.is_none_or(|delay| started.elapsed() < delay) {
+            if self.heed_cancel && cancel.is_cancelled() {
+                self.cancelled.store(true, Ordering::SeqCst);
+                return vec![];
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        vec![ScannerInfo::new(DeviceId::new(self.kind, "slow1"), "Slow")]
+    }
+
+    fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+        Err(PapyrError::NotFound(device_id.to_string()))
+    }
+
+    fn start_scan(&self, device_id: &DeviceId, _cfg: ScanConfig) -> Result<Box<dyn ScanSession>> {
+        Err(PapyrError::NotFound(device_id.to_string()))
+    }
+}
+
+struct FinishedSession;
+
+impl ScanSession for FinishedSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        Ok(None)
+    }
+}
+
+fn config(dpi: u32) -> ScanConfig {
+    ScanConfig {
+        source: ScanSource::Flatbed,
+        duplex: false,
+        dpi,
+        dpi_y: None,
+        color_mode: ColorMode::Color,
+        bit_depth: None,
+        page_size: PageSize {
+            width_mm: 210.0,
+            height_mm: 297.0,
+        },
+        area: None,
+        brightness: None,
+        contrast: None,
+        max_pages: None,
+    }
+}
+
+/// A parsed device id.
+fn id(text: &str) -> DeviceId {
+    text.parse().unwrap()
+}
+
+/// A registry of two fakes: `a` owns device "a1", `b` owns "b1".
+fn registry() -> (BackendRegistry, FakeBackend, FakeBackend) {
+    let a = FakeBackend::new(Backend::Sane, 100, &["a1"]);
+    let b = FakeBackend::new(Backend::Escl, 200, &["b1"]);
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(a.clone()));
+    registry.register(Box::new(b.clone()));
+    (registry, a, b)
+}
+
+#[test]
+fn test_list_devices_uses_cache() {
+    let (registry, a, b) = registry();
+
+    assert_eq!(registry.list_devices().unwrap().len(), 2);
+    assert_eq!(registry.list_devices().unwrap().len(), 2);
+    assert_eq!((a.enumerations(), b.enumerations()), (1, 1));
+
+    a.devices.lock().unwrap().push("a2".into());
+    assert_eq!(registry.list_devices().unwrap().len(), 2);
+    assert_eq!(registry.refresh_devices().unwrap().len(), 3);
+    assert_eq!((a.enumerations(), b.enumerations()), (2, 2));
+}
+
+#[test]
+fn test_lookups_use_cached_owner() {
+    let (registry, a, b) = registry();
+    registry.list_devices().unwrap();
+
+    // `b` is the only eSCL backend, so `a` is never asked
+    let caps = registry.capabilities(&id("escl_b1")).unwrap();
+    assert_eq!(caps.dpis, vec![200]);
+    assert_eq!(a.capability_calls.load(Ordering::SeqCst), 0);
+
+    let mut session = registry.start_scan(&id("escl_b1"), config(200)).unwrap();
+    assert!(session.next_event().unwrap().is_none());
+    assert_eq!((a.enumerations(), b.enumerations()), (1, 1));
+}
+
+#[test]
+fn test_first_lookup_discovers() {
+    let (registry, a, _) = registry();
+
+    assert_eq!(
+        registry.capabilities(&id("sane_a1")).unwrap().dpis,
+        vec![100]
+    );
+    assert_eq!(
+        registry.capabilities(&id("sane_a1")).unwrap().dpis,
+        vec![100]
+    );
+    assert_eq!(a.enumerations(), 1);
+}
+
+#[test]
+fn test_unknown_and_stale_devices_not_found() {
+    let (registry, a, b) = registry();
+    registry.list_devices().unwrap();
+
+    assert!(matches!(
+        registry.capabilities(&id("escl_missing")),
+        Err(PapyrError::NotFound(_))
+    ));
+    assert!(matches!(
+        registry.start_scan(&id("escl_missing"), config(100)),
+        Err(PapyrError::NotFound(_))
+    ));
+    // Unknown ids don't trigger another discovery
+    assert_eq!(a.enumerations(), 1);
+
+    b.devices.lock().unwrap().clear();
+    registry.refresh_devices().unwrap();
+    assert!(matches!(
+        registry.capabilities(&id("escl_b1")),
+        Err(PapyrError::NotFound(message)) if message.contains("b1")
+    ));
+    assert_eq!(b.capability_calls.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_ids_of_unregistered_backends_not_found() {
+    let (registry, a, b) = registry();
+
+    // No TWAIN backend could own it, so nothing is enumerated
+    assert!(matches!(
+        registry.capabilities(&DeviceId::new(Backend::Twain, "1")),
+        Err(PapyrError::NotFound(_))
+    ));
+    assert_eq!((a.enumerations(), b.enumerations()), (0, 0));
+}
+
+#[test]
+fn test_expired_cache_discovers_again() {
+    let (mut registry, a, _) = registry();
+    registry.set_device_cache_ttl(Duration::from_millis(50));
+
+    registry.list_devices().unwrap();
+    std::thread::sleep(Duration::from_millis(80));
+    registry.capabilities(&id("sane_a1")).unwrap();
+    assert_eq!(a.enumerations(), 2);
+
+    registry.set_device_cache_ttl(Duration::ZERO);
+    registry.list_devices().unwrap();
+    registry.list_devices().unwrap();
+    assert_eq!(a.enumerations(), 4);
+}
+
+#[test]
+fn test_register_invalidates_cache() {
+    let (mut registry, a, _) = registry();
+    registry.list_devices().unwrap();
+
+    let c = FakeBackend::new(Backend::Sane, 300, &["c1"]);
+    registry.register(Box::new(c.clone()));
+    assert_eq!(registry.list_devices().unwrap().len(), 3);
+    assert_eq!(a.enumerations(), 2);
+    assert_eq!(
+        registry.capabilities(&id("sane_c1")).unwrap().dpis,
+        vec![300]
+    );
+}
+
+#[test]
+fn test_options_go_to_the_owner() {
+    let (mut registry, a, _) = registry();
+    let c = FakeBackend::new(Backend::Sane, 300, &["c1"]);
+    registry.register(Box::new(c.clone()));
+    registry.set_device_cache_ttl(Duration::from_millis(50));
+    registry.list_devices().unwrap();
+
+    // With the cache expired the owner is found by discovering once, not
+    // by asking every SANE backend in turn
+    std::thread::sleep(Duration::from_millis(80));
+    registry.options(&id("sane_c1")).unwrap();
+    assert_eq!(a.option_calls.load(Ordering::SeqCst), 0);
+    assert_eq!(c.option_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(a.enumerations(), 2);
+
+    assert!(matches!(
+        registry.options(&id("sane_missing")),
+        Err(PapyrError::NotFound(_))
+    ));
+    assert_eq!(a.option_calls.load(Ordering::SeqCst), 0);
+    assert_eq!(a.enumerations(), 2);
+}
+
+#[test]
+fn test_cancelled_refresh_keeps_cache() {
+    let (registry, a, _) = registry();
+    registry.list_devices().unwrap();
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    assert!(matches!(
+        registry.refresh_devices_cancellable(&cancel),
+        Err(PapyrError::Cancelled)
+    ));
+    assert_eq!(registry.list_devices().unwrap().len(), 2);
+    assert_eq!(a.enumerations(), 1);
+}
+
+#[test]
+fn test_backends_enumerate_concurrently() {
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(SlowBackend::new(
+        Backend::Sane,
+        Some(Duration::from_millis(300)),
+    )));
+    registry.register(Box::new(SlowBackend::new(
+        Backend::Escl,
+        Some(Duration::from_millis(300)),
+    )));
+
+    let started = Instant::now();
+    let discovery = registry.discover_devices(&CancelToken::new()).unwrap();
+    assert!(started.elapsed() < Duration::from_millis(550));
+    assert_eq!(discovery.devices.len(), 2);
+    assert!(discovery.is_complete());
+}
+
+#[test]
+fn test_discovery_reports_timeouts_and_panics() {
+    let fake = FakeBackend::new(Backend::Sane, 100, &["a1"]);
+    let hung = SlowBackend::new(Backend::Escl, None);
+    let hung_cancelled = Arc::clone(&hung.cancelled);
+    let mut crashing = SlowBackend::new(Backend::SaneNet, None);
+    crashing.panics = true;
+
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(fake.clone()));
+    registry.register(Box::new(hung));
+    registry.register(Box::new(crashing));
+    registry.set_backend_enumeration_timeout(Backend::Escl, Duration::from_millis(100));
+
+    let started = Instant::now();
+    let discovery = registry.discover_devices(&CancelToken::new()).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(!discovery.is_complete());
+
+    let statuses: Vec<_> = discovery
+        .backends
+        .iter()
+        .map(|backend| (backend.backend, backend.status.clone()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (Backend::Sane, DiscoveryStatus::Ok),
+            (Backend::Escl, DiscoveryStatus::TimedOut),
+            (
+                Backend::SaneNet,
+                DiscoveryStatus::Failed("probe crashed".into())
+            ),
+        ]
+    );
+    assert_eq!(discovery.devices.len(), 1);
+    assert_eq!(discovery.backends[0].devices, 1);
+
+    // The backend that timed out was told to stop
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(hung_cancelled.load(Ordering::SeqCst));
+
+    // Devices that were found are used; the rest is looked for again
+    assert_eq!(
+        registry.capabilities(&id("sane_a1")).unwrap().dpis,
+        vec![100]
+    );
+    assert_eq!(fake.enumerations(), 1);
+    registry.list_devices().unwrap();
+    assert_eq!(fake.enumerations(), 2);
+}
+
+#[test]
+fn test_discovery_skips_unavailable_backends() {
+    struct Unavailable;
+
+    impl BackendProvider for Unavailable {
+        fn name(&self) -> &'static str {
+            "Unavailable"
+        }
+
+        fn kind(&self) -> Backend {
+            Backend::Ica
+        }
+
+        fn availability(&self) -> BackendAvailability {
+            BackendAvailability::Unavailable("not installed".into())
+        }
+
+        fn enumerate(&self) -> Vec<ScannerInfo> {
+            panic!("unavailable backends are not enumerated");
+        }
+
+        fn capabilities(&self, device_id: &DeviceId) -> Result<Capabilities> {
+            Err(PapyrError::NotFound(device_id.to_string()))
+        }
+
+        fn start_scan(
+            &self,
+            device_id: &DeviceId,
+            _cfg: ScanConfig,
+        ) -> Result<Box<dyn ScanSession>> {
+            Err(PapyrError::NotFound(device_id.to_string()))
+        }
+    }
+
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(Unavailable));
+    let discovery = registry.discover_devices(&CancelToken::new()).unwrap();
+    assert_eq!(
+        discovery.backends[0].status,
+        DiscoveryStatus::Unavailable("not installed".into())
+    );
+    assert!(discovery.is_complete());
+}
+
+#[test]
+fn test_cancel_stops_discovery_waiting_on_stuck_backend() {
+    let mut stuck = SlowBackend::new(Backend::Sane, Some(Duration::from_secs(5)));
+    stuck.heed_cancel = false;
+    let mut registry = BackendRegistry::empty();
+    registry.register(Box::new(stuck));
+
+    let cancel = CancelToken::new();
+    let canceller = cancel.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        canceller.cancel();
+    });
+
+    let started = Instant::now();
+    assert!(matches!(
+        registry.discover_devices(&cancel),
+        Err(PapyrError::Cancelled)
+    ));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,966 @@
+//
+//  papyr_core
+//  tests/sane_test.rs - SANE backend integration tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::backends::sane_common::{
+    escape_device_name, sane_device_id, sane_device_name, unescape_device_name,
+};
+use papyr_core::models::DeviceId;
+
+fn id(text: &str) -> DeviceId {
+    text.parse().unwrap()
+}
+
+/// Device names as reported by real SANE backends.
+const REAL_DEVICE_NAMES: &[&str] = &[
+    "airscan:e0:HP LaserJet MFP M426fdn (7E8D12)",
+    "airscan:w1:Canon MF740C/640C",
+    "hpaio:/usb/Officejet_Pro_8600?serial=CN1234567",
+    "hpaio:/net/HP_LaserJet_MFP_M426fdn?ip=192.168.1.20",
+    "epson2:net:192.168.1.50",
+    "epsonds:libusb:001:004",
+    "net:[fe80::1%eth0]:pixma:04A91912_123456",
+    "escl:https://192.168.1.30:443",
+    "brother4:net1;dev0",
+    "pixma:MF4800_192.168.1.10",
+    "v4l:/dev/video0",
+    "test:0",
+    "test_0",
+    "plustek:libusb:002:003",
+    "genesys:libusb:001:005 ünïcode",
+];
+
+#[test]
+fn test_sane_device_id_round_trip() {
+    for name in REAL_DEVICE_NAMES {
+        let id = sane_device_id(name);
+        assert_eq!(
+            sane_device_name(&id).as_deref(),
+            Some(*name),
+            "id {} should decode back to {}",
+            id,
+            name
+        );
+        assert!(
+            !id.to_string().chars().any(char::is_whitespace),
+            "id {} should not contain whitespace",
+            id
+        );
+    }
+}
+
+#[test]
+fn test_sane_device_ids_are_distinct() {
+    let mut ids: Vec<String> = REAL_DEVICE_NAMES
+        .iter()
+        .map(|n| sane_device_id(n).to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), REAL_DEVICE_NAMES.len());
+}
+
+#[test]
+fn test_sane_device_id_stays_readable() {
+    assert_eq!(sane_device_id("test:0").to_string(), "sane_test:0");
+    assert_eq!(
+        sane_device_id("epson2:net:192.168.1.50").to_string(),
+        "sane_epson2:net:192.168.1.50"
+    );
+    assert_eq!(escape_device_name("a b%c"), "a%20b%25c");
+}
+
+#[test]
+fn test_sane_device_id_rejects_malformed() {
+    assert_eq!(sane_device_name(&id("escl_test:0")), None);
+    assert_eq!(sane_device_name(&id("sane_bad%2")), None);
+    assert_eq!(sane_device_name(&id("sane_bad%zz")), None);
+    assert_eq!(unescape_device_name("%FF"), None);
+}
+
+#[cfg(feature = "sane")]
+mod libsane {
+    use super::id;
+    use papyr_core::backends::sane::SaneBackend;
+    use papyr_core::models::*;
+
+    fn missing_library_backend() -> SaneBackend {
+        SaneBackend::with_library_path("/nonexistent/papyr/libsane.so.1")
+    }
+
+    pub fn config(source: ScanSource, dpi: u32, color_mode: ColorMode) -> ScanConfig {
+        ScanConfig {
+            source,
+            duplex: false,
+            dpi,
+            dpi_y: None,
+            color_mode,
+            bit_depth: None,
+            page_size: PageSize::new(210.0, 297.0),
+            area: None,
+            brightness: None,
+            contrast: None,
+            max_pages: None,
+        }
+    }
+
+    /// Pages and the outcome of one scan.
+    pub struct Scan {
+        pub pages: Vec<(Vec<u8>, PageMeta)>,
+        pub started: Vec<u32>,
+        pub job_complete: bool,
+        pub cancelled: bool,
+        /// `(bytes, expected, percent)` of each progress event.
+        pub progress: Vec<(u64, Option<u64>, Option<f32>)>,
+        pub statuses: Vec<ScanStatus>,
+        pub warnings: Vec<(WarningCode, String)>,
+        pub error: Option<PapyrError>,
+    }
+
+    /// Drains `session`, stopping early after `max_pages` pages.
+    pub fn collect(mut session: Box<dyn ScanSession>, max_pages: usize) -> Scan {
+        let mut scan = Scan {
+            pages: Vec::new(),
+            started: Vec::new(),
+            job_complete: false,
+            cancelled: false,
+            progress: Vec::new(),
+            statuses: Vec::new(),
+            warnings: Vec::new(),
+            error: None,
+        };
+        let mut data = Vec::new();
+
+        while scan.pages.len() < max_pages {
+            match session.next_event() {
+                Ok(Some(ScanEvent::PageStarted(index))) => scan.started.push(index),
+                Ok(Some(ScanEvent::PageData(bytes))) => data.extend_from_slice(&bytes),
+                Ok(Some(ScanEvent::PageComplete(meta))) => {
+                    scan.pages.push((std::mem::take(&mut data), meta))
+                }
+                Ok(Some(ScanEvent::JobComplete)) => scan.job_complete = true,
+                Ok(Some(ScanEvent::Progress {
+                    bytes,
+                    expected,
+                    percent,
+                })) => scan.progress.push((bytes, expected, percent)),
+                Ok(Some(ScanEvent::Status(status))) => scan.statuses.push(status),
+                Ok(Some(ScanEvent::Warning { code, message })) => {
+                    scan.warnings.push((code, message))
+                }
+                Ok(Some(ScanEvent::Cancelled)) => scan.cancelled = true,
+                Ok(Some(ScanEvent::Error(e))) => scan.error = Some(e),
+                Ok(None) => break,
+                Err(e) => {
+                    scan.error = Some(e);
+                    break;
+                }
+            }
+        }
+        scan
+    }
+
+    /// A parsed PNM page.
+    #[derive(Debug)]
+    pub struct Pnm {
+        pub magic: String,
+        pub width: usize,
+        pub height: usize,
+        pub maxval: Option<u32>,
+        pub data: Vec<u8>,
+    }
+
+    impl Pnm {
+        pub fn parse(bytes: &[u8]) -> Pnm {
+            // P4 headers carry three fields, P5/P6 a fourth for maxval
+            let fields = if bytes.starts_with(b"P4") { 3 } else { 4 };
+            let mut parts = Vec::new();
+            let mut pos = 0;
+            while parts.len() < fields {
+                let end = pos
+                    + bytes[pos..]
+                        .iter()
+                        .position(|b| b.is_ascii_whitespace())
+                        .expect("truncated PNM header");
+                parts.push(String::from_utf8(bytes[pos..end].to_vec()).unwrap());
+                pos = end + 1;
+            }
+
+            Pnm {
+                magic: parts[0].clone(),
+                width: parts[1].parse().unwrap(),
+                height: parts[2].parse().unwrap(),
+                maxval: parts.get(3).map(|m| m.parse().unwrap()),
+                data: bytes[pos..].to_vec(),
+            }
+        }
+
+        /// Bytes the pixel data should take.
+        pub fn expected_len(&self) -> usize {
+            match (self.magic.as_str(), self.maxval) {
+                ("P4", _) => self.width.div_ceil(8) * self.height,
+                ("P6", Some(65535)) => self.width * self.height * 6,
+                ("P6", _) => self.width * self.height * 3,
+                (_, Some(65535)) => self.width * self.height * 2,
+                _ => self.width * self.height,
+            }
+        }
+    }
+
+    #[test]
+    fn test_sane_missing_library_is_unavailable() {
+        let backend = missing_library_backend();
+
+        match backend.availability() {
+            BackendAvailability::Unavailable(reason) => {
+                assert!(
+                    reason.contains("/nonexistent/papyr/libsane.so.1"),
+                    "Reason should name the library path: {}",
+                    reason
+                );
+            }
+            BackendAvailability::Available => panic!("Missing libsane should be unavailable"),
+        }
+    }
+
+    #[test]
+    fn test_sane_missing_library_degrades_gracefully() {
+        let backend = missing_library_backend();
+
+        assert!(backend.enumerate().is_empty());
+
+        let err = backend
+            .capabilities(&id("sane_test_0"))
+            .expect_err("capabilities should fail without libsane");
+        assert!(err.to_string().contains("unavailable"));
+
+        let cfg = config(ScanSource::Flatbed, 150, ColorMode::Color);
+        assert!(backend.start_scan(&id("sane_test_0"), cfg).is_err());
+    }
+
+    /// Tests against tests/fake_sane/fake_sane.c, compiled on first use.
+    #[cfg(unix)]
+    mod fake {
+        use super::*;
+        use std::path::{Path, PathBuf};
+        use std::process::Command;
+        use std::sync::OnceLock;
+        use std::thread;
+        use std::time::{Duration, Instant};
+
+        /// Compiles the fake libsane; `None` when no C compiler is available.
+        fn fake_library() -> Option<&'static Path> {
+            static LIBRARY: OnceLock<Option<PathBuf>> = OnceLock::new();
+            LIBRARY
+                .get_or_init(|| {
+                    let source =
+                        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fake_sane/fake_sane.c");
+                    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("libfake_sane.so");
+                    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+
+                    match Command::new(&compiler)
+                        .args(["-shared", "-fPIC", "-O1", "-o"])
+                        .arg(&output)
+                        .arg(&source)
+                        .status()
+                    {
+                        Ok(status) => {
+                            assert!(status.success(), "failed to compile {}", source.display());
+                            Some(output)
+                        }
+                        Err(e) => {
+                            println!("⚠️  {} not available ({}), skipping", compiler, e);
+                            None
+                        }
+                    }
+                })
+                .as_deref()
+        }
+
+        fn fake_backend() -> Option<SaneBackend> {
+            fake_library().map(SaneBackend::with_library_path)
+        }
+
+        /// Mirrors the fake's gradient: channel `c` of pixel (x, y).
+        fn gradient(x: usize, y: usize, c: usize) -> u8 {
+            (((y * 16 + x) * 4 + c * 64) & 0xff) as u8
+        }
+
+        fn expected_rgb(width: usize, height: usize) -> Vec<u8> {
+            let mut data = Vec::new();
+            for y in 0..height {
+                for x in 0..width {
+                    data.extend((0..3).map(|c| gradient(x, y, c)));
+                }
+            }
+            data
+        }
+
+        #[test]
+        fn test_fake_sane_enumerates_devices() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            assert_eq!(backend.availability(), BackendAvailability::Available);
+
+            let scanners = backend.enumerate();
+            let scanner = scanners
+                .iter()
+                .find(|s| s.id.to_string() == "sane_fake:scanner")
+                .expect("fake:scanner should be listed");
+            assert_eq!(scanner.name, "Papyr Fake Scanner");
+            assert_eq!(scanner.backend, Backend::Sane);
+            assert_eq!(scanner.manufacturer.as_deref(), Some("Papyr"));
+            assert_eq!(scanner.model.as_deref(), Some("Fake Scanner"));
+            assert_eq!(scanner.device_type.as_deref(), Some("flatbed scanner"));
+            assert_eq!(scanner.connection, ConnectionKind::Unknown);
+            assert_eq!(scanners.len(), 7);
+        }
+
+        #[test]
+        fn test_fake_sane_capabilities() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let caps = backend.capabilities(&id("sane_fake:scanner")).unwrap();
+            assert_eq!(caps.dpis, [75, 100, 150, 200, 300, 600]);
+            assert_eq!(
+                caps.color_modes,
+                [ColorMode::Color, ColorMode::Gray, ColorMode::Bw]
+            );
+            assert_eq!(caps.sources, [ScanSource::Flatbed, ScanSource::Adf]);
+            assert!(!caps.supports_duplex);
+            assert_eq!(
+                caps.dpi_ranges,
+                [DpiRange {
+                    min: 50,
+                    max: 600,
+                    step: 25
+                }]
+            );
+            assert!(caps.supports_dpi(125));
+            assert!(!caps.supports_dpi(130));
+            assert!(!caps.independent_dpi);
+            assert_eq!(caps.bit_depths, [8, 16]);
+        }
+
+        #[test]
+        fn test_fake_sane_options() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            let device = &id("sane_fake:scanner");
+
+            let options = backend.options(device).unwrap();
+            let names: Vec<&str> = options.iter().map(|o| o.name.as_str()).collect();
+            assert_eq!(
+                names,
+                [
+                    "mode",
+                    "depth",
+                    "resolution",
+                    "source",
+                    "three-pass",
+                    "test-picture"
+                ]
+            );
+
+            let resolution = options.iter().find(|o| o.name == "resolution").unwrap();
+            assert_eq!(resolution.group.as_deref(), Some("Scan Mode"));
+            assert_eq!(resolution.unit, OptionUnit::Dpi);
+            assert_eq!(
+                resolution.constraint,
+                OptionConstraint::Range {
+                    min: 50.0,
+                    max: 600.0,
+                    step: Some(25.0)
+                }
+            );
+            assert_eq!(resolution.value, Some(OptionValue::Int(100)));
+
+            // The device rounds to its quantisation and the result is reported
+            let rounded = backend
+                .set_option(device, "resolution", OptionValue::String("160".into()))
+                .unwrap();
+            assert_eq!(rounded.value, Some(OptionValue::Int(150)));
+
+            assert!(matches!(
+                backend.set_option(device, "mode", OptionValue::String("Sepia".into())),
+                Err(PapyrError::InvalidConfig(_))
+            ));
+            assert!(matches!(
+                backend.set_option(device, "resolution", OptionValue::Int(1200)),
+                Err(PapyrError::InvalidConfig(_))
+            ));
+
+            // Lineart reloads the options and deactivates depth and three-pass
+            backend
+                .set_option(device, "mode", OptionValue::String("Lineart".into()))
+                .unwrap();
+            let options = backend.options(device).unwrap();
+            assert!(options.iter().all(|o| o.name != "depth"));
+            assert!(options.iter().all(|o| o.name != "three-pass"));
+            let resolution = options.iter().find(|o| o.name == "resolution").unwrap();
+            assert_eq!(resolution.value, Some(OptionValue::Int(150)));
+        }
+
+        #[test]
+        fn test_fake_sane_color_scan_strips_padding() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:scanner"),
+                    config(ScanSource::Flatbed, 150, ColorMode::Color),
+                )
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.job_complete);
+            assert_eq!(scan.started, [0]);
+            assert_eq!(scan.pages.len(), 1);
+
+            let (page, meta) = &scan.pages[0];
+            assert_eq!((meta.index, meta.width_px, meta.height_px), (0, 6, 4));
+            assert_eq!(meta.dpi, 150);
+
+            let pnm = Pnm::parse(page);
+            assert_eq!(pnm.magic, "P6");
+            assert_eq!((pnm.width, pnm.height, pnm.maxval), (6, 4, Some(255)));
+            assert_eq!(pnm.data, expected_rgb(6, 4));
+        }
+
+        #[test]
+        fn test_fake_sane_gray_16_bit_is_big_endian() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            let device = &id("sane_fake:scanner");
+
+            backend
+                .set_option(device, "depth", OptionValue::Int(16))
+                .unwrap();
+            let session = backend
+                .start_scan(device, config(ScanSource::Flatbed, 100, ColorMode::Gray))
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+
+            let pnm = Pnm::parse(&scan.pages[0].0);
+            assert_eq!(pnm.magic, "P5");
+            assert_eq!((pnm.width, pnm.height, pnm.maxval), (4, 4, Some(65535)));
+            assert_eq!(pnm.data.len(), pnm.expected_len());
+
+            let samples: Vec<u16> = pnm
+                .data
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            assert_eq!(samples[0], gradient(0, 0, 0) as u16 * 257);
+            assert_eq!(samples[5], gradient(1, 1, 0) as u16 * 257);
+        }
+
+        #[test]
+        fn test_fake_sane_48_bit_color_from_config() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            // 125 dpi is only reachable through the resolution range
+            let cfg = ScanConfig {
+                bit_depth: Some(16),
+                ..config(ScanSource::Flatbed, 125, ColorMode::Color)
+            };
+            let session = backend.start_scan(&id("sane_fake:scanner"), cfg).unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+
+            let (page, meta) = &scan.pages[0];
+            let pnm = Pnm::parse(page);
+            assert_eq!(pnm.magic, "P6");
+            assert_eq!((pnm.width, pnm.height, pnm.maxval), (5, 4, Some(65535)));
+            assert_eq!(pnm.data.len(), pnm.expected_len());
+            assert_eq!((meta.dpi, meta.dpi_y, meta.bit_depth), (125, 125, 16));
+        }
+
+        #[test]
+        fn test_fake_sane_lineart_scan() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:scanner"),
+                    config(ScanSource::Flatbed, 200, ColorMode::Bw),
+                )
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+
+            let pnm = Pnm::parse(&scan.pages[0].0);
+            assert_eq!(pnm.magic, "P4");
+            assert_eq!((pnm.width, pnm.height, pnm.maxval), (8, 4, None));
+            assert_eq!(pnm.data, [0xaa, 0x55, 0xaa, 0x55]);
+        }
+
+        #[test]
+        fn test_fake_sane_three_pass_matches_single_pass() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            let device = &id("sane_fake:scanner");
+            let cfg = config(ScanSource::Flatbed, 125, ColorMode::Color);
+
+            backend
+                .set_option(device, "three-pass", OptionValue::Bool(true))
+                .unwrap();
+            let scan = collect(backend.start_scan(device, cfg.clone()).unwrap(), usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.job_complete);
+            assert_eq!(scan.pages.len(), 1);
+
+            let pnm = Pnm::parse(&scan.pages[0].0);
+            assert_eq!(pnm.magic, "P6");
+            assert_eq!((pnm.width, pnm.height), (5, 4));
+            assert_eq!(pnm.data, expected_rgb(5, 4));
+            assert_eq!(
+                (scan.pages[0].1.width_px, scan.pages[0].1.height_px),
+                (5, 4)
+            );
+        }
+
+        #[test]
+        fn test_fake_sane_feeder_scans_every_sheet() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            let device = &id("sane_fake:scanner");
+
+            // Remembered options are applied on top of the scan config
+            backend
+                .set_option(
+                    device,
+                    "test-picture",
+                    OptionValue::String("Solid white".into()),
+                )
+                .unwrap();
+            let session = backend
+                .start_scan(device, config(ScanSource::Adf, 75, ColorMode::Gray))
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.job_complete);
+            assert_eq!(scan.started, [0, 1, 2]);
+
+            let indices: Vec<u32> = scan.pages.iter().map(|(_, meta)| meta.index).collect();
+            assert_eq!(indices, [0, 1, 2]);
+            for (page, _) in &scan.pages {
+                let pnm = Pnm::parse(page);
+                assert_eq!((pnm.magic.as_str(), pnm.width, pnm.height), ("P5", 3, 4));
+                assert!(pnm.data.iter().all(|&b| b == 0xff));
+            }
+        }
+
+        #[test]
+        fn test_fake_sane_paper_jam() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:jammed"),
+                    config(ScanSource::Adf, 100, ColorMode::Gray),
+                )
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+
+            // The first sheet is delivered before the jam is reported
+            assert_eq!(scan.pages.len(), 1);
+            assert!(!scan.job_complete);
+            let err = scan.error.expect("jam should be reported");
+            assert!(err.to_string().contains("jammed"), "{}", err);
+        }
+
+        #[test]
+        fn test_fake_sane_empty_feeder() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let err = backend
+                .start_scan(
+                    &id("sane_fake:empty"),
+                    config(ScanSource::Adf, 100, ColorMode::Gray),
+                )
+                .err()
+                .expect("an empty feeder should fail the scan");
+            assert!(err.to_string().contains("out of documents"), "{}", err);
+        }
+
+        #[test]
+        fn test_fake_sane_device_busy() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let err = backend.capabilities(&id("sane_fake:busy")).unwrap_err();
+            assert!(err.to_string().contains("Device busy"), "{}", err);
+            assert!(backend.options(&id("sane_fake:busy")).is_err());
+        }
+
+        #[test]
+        fn test_fake_sane_io_error() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:faulty"),
+                    config(ScanSource::Flatbed, 100, ColorMode::Color),
+                )
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.pages.is_empty());
+            let err = scan.error.expect("I/O error should be reported");
+            assert!(err.to_string().contains("I/O"), "{}", err);
+        }
+
+        #[test]
+        fn test_fake_sane_read_timeout() {
+            let Some(lib) = fake_library() else {
+                return;
+            };
+            let backend =
+                SaneBackend::with_library_path(lib).with_read_timeout(Duration::from_millis(300));
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:stalled"),
+                    config(ScanSource::Flatbed, 100, ColorMode::Gray),
+                )
+                .unwrap();
+            let started = Instant::now();
+            let scan = collect(session, usize::MAX);
+            let err = scan.error.expect("a stalled read should time out");
+            assert!(err.to_string().contains("timed out"), "{}", err);
+            assert!(started.elapsed() < Duration::from_secs(5));
+        }
+
+        #[test]
+        fn test_fake_sane_cancel_stops_blocked_read() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:stalled"),
+                    config(ScanSource::Flatbed, 100, ColorMode::Gray),
+                )
+                .unwrap();
+            let cancel = session
+                .cancel_handle()
+                .expect("SANE sessions can be cancelled");
+            let canceller = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(200));
+                cancel.cancel();
+            });
+
+            let scan = collect(session, usize::MAX);
+            canceller.join().unwrap();
+            assert!(scan.cancelled);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.pages.is_empty());
+        }
+
+        #[test]
+        fn test_fake_sane_cancel_token_stops_blocked_read() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let cancel = CancelToken::new();
+            let session = backend
+                .start_scan_cancellable(
+                    &id("sane_fake:stalled"),
+                    config(ScanSource::Flatbed, 100, ColorMode::Gray),
+                    &cancel,
+                )
+                .unwrap();
+            let canceller = {
+                let cancel = cancel.clone();
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(200));
+                    cancel.cancel();
+                })
+            };
+
+            let scan = collect(session, usize::MAX);
+            canceller.join().unwrap();
+            assert!(scan.cancelled);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+
+            // A cancelled token stops the next scan before it starts
+            let result = backend.start_scan_cancellable(
+                &id("sane_fake:scanner"),
+                config(ScanSource::Flatbed, 100, ColorMode::Gray),
+                &cancel,
+            );
+            assert!(matches!(result, Err(PapyrError::Cancelled)));
+        }
+
+        #[test]
+        fn test_fake_sane_reports_progress() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:scanner"),
+                    config(ScanSource::Flatbed, 150, ColorMode::Color),
+                )
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert_eq!(scan.statuses, [ScanStatus::Scanning]);
+
+            // 6 pixels of RGB padded to 20 bytes a line, 4 lines, read in
+            // 5-byte chunks
+            assert_eq!(scan.progress.len(), 16);
+            assert!(scan.progress.windows(2).all(|w| w[0].0 < w[1].0));
+            assert_eq!(scan.progress[0], (5, Some(80), Some(6.25)));
+            assert_eq!(scan.progress.last(), Some(&(80, Some(80), Some(100.0))));
+        }
+
+        #[test]
+        fn test_fake_sane_waits_for_warm_up() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:warming"),
+                    config(ScanSource::Flatbed, 100, ColorMode::Gray),
+                )
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.job_complete);
+            assert_eq!(scan.statuses, [ScanStatus::WarmingUp, ScanStatus::Scanning]);
+            assert_eq!(scan.pages.len(), 1);
+        }
+
+        #[test]
+        fn test_fake_sane_warns_about_skipped_options() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            let device = &id("sane_fake:scanner");
+
+            // Three-pass only applies to colour scans
+            backend
+                .set_option(device, "three-pass", OptionValue::Bool(true))
+                .unwrap();
+            let session = backend
+                .start_scan(device, config(ScanSource::Flatbed, 100, ColorMode::Gray))
+                .unwrap();
+            let scan = collect(session, usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.job_complete);
+            assert_eq!(scan.warnings.len(), 1);
+            assert_eq!(scan.warnings[0].0, WarningCode::OptionNotApplied);
+            assert!(
+                scan.warnings[0].1.contains("three-pass"),
+                "{:?}",
+                scan.warnings
+            );
+        }
+
+        #[test]
+        fn test_fake_sane_events_iterator_ends_with_error() {
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(
+                    &id("sane_fake:faulty"),
+                    config(ScanSource::Flatbed, 100, ColorMode::Color),
+                )
+                .unwrap();
+            let events: Vec<ScanEvent> = ScanEvents::new(session).collect();
+            let last = events.last().expect("the failure should be an event");
+            assert!(last.is_terminal());
+            assert!(
+                matches!(last, ScanEvent::Error(PapyrError::Io { .. })),
+                "{:?}",
+                last
+            );
+            assert!(matches!(events[0], ScanEvent::Status(ScanStatus::Scanning)));
+        }
+
+        #[cfg(feature = "async")]
+        #[tokio::test]
+        async fn test_fake_sane_async_scan() {
+            use futures_util::StreamExt;
+            use papyr_core::async_api;
+            use papyr_core::session_guard::SessionLimits;
+            use std::sync::Arc;
+
+            let Some(backend) = fake_backend() else {
+                return;
+            };
+            let backend: Arc<dyn BackendProvider> = Arc::new(backend);
+
+            let scanners = async_api::enumerate(backend.clone()).await;
+            assert!(scanners
+                .iter()
+                .any(|s| s.id.to_string() == "sane_fake:scanner"));
+
+            let stream = async_api::start_scan(
+                backend,
+                &id("sane_fake:scanner"),
+                config(ScanSource::Flatbed, 150, ColorMode::Color),
+                SessionLimits::default(),
+            )
+            .await
+            .unwrap();
+            let events: Vec<ScanEvent> = stream.map(Result::unwrap).collect().await;
+            assert!(matches!(events[0], ScanEvent::Status(ScanStatus::Scanning)));
+            assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+            let pages = events
+                .iter()
+                .filter(|e| matches!(e, ScanEvent::PageComplete(_)))
+                .count();
+            assert_eq!(pages, 1);
+        }
+    }
+
+    /// Tests against SANE's built-in `test` backend. Skipped unless libsane
+    /// is installed with `test` enabled in dll.conf.
+    mod test_backend {
+        use super::*;
+
+        fn test_device() -> Option<(SaneBackend, DeviceId)> {
+            let backend = SaneBackend::new();
+            let device = backend
+                .enumerate()
+                .into_iter()
+                .find(|s| s.id.local().starts_with("test:"))
+                .map(|s| s.id);
+            match device {
+                Some(id) => Some((backend, id)),
+                None => {
+                    println!("⚠️  SANE test backend not available, skipping");
+                    None
+                }
+            }
+        }
+
+        /// Scans one page and checks the PNM is internally consistent.
+        fn scan_page(backend: &SaneBackend, device: &DeviceId, cfg: ScanConfig) -> Pnm {
+            let scan = collect(backend.start_scan(device, cfg).unwrap(), usize::MAX);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.job_complete);
+            assert_eq!(scan.pages.len(), 1);
+
+            let (page, meta) = &scan.pages[0];
+            let pnm = Pnm::parse(page);
+            assert_eq!(
+                (pnm.width, pnm.height),
+                (meta.width_px as usize, meta.height_px as usize)
+            );
+            assert!(pnm.width > 0 && pnm.height > 0);
+            assert_eq!(pnm.data.len(), pnm.expected_len());
+            pnm
+        }
+
+        #[test]
+        fn test_sane_test_backend_modes() {
+            let Some((backend, device)) = test_device() else {
+                return;
+            };
+
+            let color = scan_page(
+                &backend,
+                &device,
+                config(ScanSource::Flatbed, 75, ColorMode::Color),
+            );
+            assert_eq!(color.magic, "P6");
+
+            let gray = scan_page(
+                &backend,
+                &device,
+                config(ScanSource::Flatbed, 75, ColorMode::Gray),
+            );
+            assert_eq!(gray.magic, "P5");
+        }
+
+        #[test]
+        fn test_sane_test_backend_depths() {
+            let Some((backend, device)) = test_device() else {
+                return;
+            };
+            let cfg = config(ScanSource::Flatbed, 75, ColorMode::Gray);
+
+            backend
+                .set_option(&device, "depth", OptionValue::Int(16))
+                .unwrap();
+            let wide = scan_page(&backend, &device, cfg.clone());
+            assert_eq!((wide.magic.as_str(), wide.maxval), ("P5", Some(65535)));
+
+            backend
+                .set_option(&device, "depth", OptionValue::Int(1))
+                .unwrap();
+            let binary = scan_page(&backend, &device, cfg);
+            assert_eq!(binary.magic, "P4");
+        }
+
+        #[test]
+        fn test_sane_test_backend_three_pass() {
+            let Some((backend, device)) = test_device() else {
+                return;
+            };
+            let cfg = config(ScanSource::Flatbed, 75, ColorMode::Color);
+
+            let single = scan_page(&backend, &device, cfg.clone());
+            backend
+                .set_option(&device, "three-pass", OptionValue::Bool(true))
+                .unwrap();
+            let three = scan_page(&backend, &device, cfg);
+            assert_eq!(three.magic, "P6");
+            assert_eq!((three.width, three.height), (single.width, single.height));
+        }
+
+        #[test]
+        fn test_sane_test_backend_feeder() {
+            let Some((backend, device)) = test_device() else {
+                return;
+            };
+
+            let session = backend
+                .start_scan(&device, config(ScanSource::Adf, 75, ColorMode::Gray))
+                .unwrap();
+            let scan = collect(session, 3);
+            assert!(scan.error.is_none(), "{:?}", scan.error);
+            assert!(scan.pages.len() >= 2, "feeder should deliver several pages");
+            for (index, (page, meta)) in scan.pages.iter().enumerate() {
+                assert_eq!(meta.index as usize, index);
+                let pnm = Pnm::parse(page);
+                assert_eq!(pnm.data.len(), pnm.expected_len());
+            }
+        }
+    }
+}
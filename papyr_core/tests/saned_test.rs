@@ -0,0 +1,1077 @@
+//
+//  papyr_core
+//  tests/saned_test.rs - SANE network backend tests against a stand-in saned
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::auth::{self, Credentials};
+use papyr_core::backends::saned::{SaneNetBackend, SaneNetHost};
+use papyr_core::models::*;
+use papyr_core::registry::BackendRegistry;
+use papyr_core::sensors::SensorEvent;
+use std::collections::HashMap;
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const STATUS_GOOD: i32 = 0;
+const STATUS_EOF: u8 = 5;
+const STATUS_NO_DOCS: i32 = 7;
+const STATUS_ACCESS_DENIED: i32 = 11;
+const STATUS_WARMING_UP: i32 = 12;
+
+// 4x2 8-bit gray page
+const PAGE_PIXELS: [u8; 8] = [0, 32, 64, 96, 128, 160, 192, 255];
+
+#[derive(Default)]
+struct ServerState {
+    /// (option index, value) pairs written through CONTROL_OPTION
+    set_options: Vec<(i32, String)>,
+    /// String values as sent, terminator included
+    raw_strings: Vec<Vec<u8>>,
+    source: String,
+    pages_scanned: u32,
+    authorized: Vec<(String, String, String)>,
+    cancelled: bool,
+    /// START calls to answer with STATUS_WARMING_UP before scanning
+    warm_up_polls: u32,
+    /// Send the first data record, then stall until the client hangs up
+    stall_data: bool,
+    /// Hardware sensor states by option index, shared by all connections
+    sensors: HashMap<i32, bool>,
+}
+
+/// Minimal saned speaking just enough of the protocol for the backend.
+struct FakeSaned {
+    port: u16,
+    state: Arc<Mutex<ServerState>>,
+}
+
+impl FakeSaned {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(ServerState {
+            source: "Flatbed".into(),
+            ..Default::default()
+        }));
+
+        let server_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = Arc::clone(&server_state);
+                thread::spawn(move || {
+                    let _ = serve(stream, state);
+                });
+            }
+        });
+
+        Self { port, state }
+    }
+
+    fn host(&self) -> String {
+        format!("127.0.0.1:{}", self.port)
+    }
+}
+
+struct Wire {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Wire {
+    fn word(&mut self) -> std::io::Result<i32> {
+        let mut buf = [0u8; 4];
+        self.reader.read_exact(&mut buf)?;
+        Ok(i32::from_be_bytes(buf))
+    }
+
+    fn string(&mut self) -> std::io::Result<String> {
+        let len = self.word()?;
+        let mut buf = vec![0u8; len.max(0) as usize];
+        self.reader.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf)
+            .trim_end_matches('\0')
+            .to_string())
+    }
+}
+
+#[derive(Default)]
+struct Reply(Vec<u8>);
+
+impl Reply {
+    fn word(mut self, value: i32) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn string(mut self, value: Option<&str>) -> Self {
+        match value {
+            Some(s) => {
+                self = self.word(s.len() as i32 + 1);
+                self.0.extend_from_slice(s.as_bytes());
+                self.0.push(0);
+            }
+            None => self = self.word(0),
+        }
+        self
+    }
+
+    fn string_list(mut self, values: &[&str]) -> Self {
+        self = self.word(values.len() as i32 + 1);
+        for v in values {
+            self = self.string(Some(v));
+        }
+        self.string(None)
+    }
+
+    fn send(self, wire: &mut Wire) -> std::io::Result<()> {
+        wire.writer.write_all(&self.0)
+    }
+}
+
+fn descriptors() -> Reply {
+    let mut reply = Reply::default().word(10);
+
+    // 0: option count
+    reply = reply
+        .word(0)
+        .string(Some(""))
+        .string(Some("Number of options"))
+        .string(Some(""))
+        .word(1) // INT
+        .word(0)
+        .word(4)
+        .word(4) // SOFT_DETECT
+        .word(0);
+
+    // 1: mode
+    reply = reply
+        .word(0)
+        .string(Some("mode"))
+        .string(Some("Scan mode"))
+        .string(Some(""))
+        .word(3) // STRING
+        .word(0)
+        .word(32)
+        .word(5) // SOFT_SELECT | SOFT_DETECT
+        .word(3)
+        .string_list(&["Color", "Gray", "Lineart"]);
+
+    // 2: resolution
+    reply = reply
+        .word(0)
+        .string(Some("resolution"))
+        .string(Some("Scan resolution"))
+        .string(Some(""))
+        .word(1) // INT
+        .word(4) // DPI
+        .word(4)
+        .word(5)
+        .word(2)
+        .word(4)
+        .word(3)
+        .word(75)
+        .word(150)
+        .word(300);
+
+    // 3: source
+    reply = reply
+        .word(0)
+        .string(Some("source"))
+        .string(Some("Scan source"))
+        .string(Some(""))
+        .word(3)
+        .word(0)
+        .word(32)
+        .word(5)
+        .word(3)
+        .string_list(&["Flatbed", "ADF"]);
+
+    // 4: group
+    reply = reply
+        .word(0)
+        .string(Some(""))
+        .string(Some("Advanced"))
+        .string(Some(""))
+        .word(5) // GROUP
+        .word(0)
+        .word(0)
+        .word(0)
+        .word(0);
+
+    // 5: lamp-off-time, rounded by the device to whole minutes of 5
+    reply = reply
+        .word(0)
+        .string(Some("lamp-off-time"))
+        .string(Some("Lamp off time"))
+        .string(Some("Minutes until the lamp turns off"))
+        .word(1) // INT
+        .word(0)
+        .word(4)
+        .word(69) // SOFT_SELECT | SOFT_DETECT | ADVANCED
+        .word(1) // RANGE
+        .word(0)
+        .word(0)
+        .word(60)
+        .word(5);
+
+    // 6: group
+    reply = reply
+        .word(0)
+        .string(Some(""))
+        .string(Some("Sensors"))
+        .string(Some(""))
+        .word(5) // GROUP
+        .word(0)
+        .word(0)
+        .word(0)
+        .word(0);
+
+    // 7-8: read-only sensors
+    for (name, title) in [("scan", "Scan button"), ("page-loaded", "Page loaded")] {
+        reply = reply
+            .word(0)
+            .string(Some(name))
+            .string(Some(title))
+            .string(Some(""))
+            .word(0) // BOOL
+            .word(0)
+            .word(4)
+            .word(4) // SOFT_DETECT
+            .word(0);
+    }
+
+    // 9: free text, 8 bytes with the terminator
+    reply
+        .word(0)
+        .string(Some("label"))
+        .string(Some("Label"))
+        .string(Some("Printed on the page"))
+        .word(3) // STRING
+        .word(0)
+        .word(8)
+        .word(5)
+        .word(0)
+}
+
+/// Values of an open device; reset whenever the device is opened.
+fn default_values() -> HashMap<i32, String> {
+    HashMap::from([
+        (1, "Color".to_string()),
+        (2, "300".to_string()),
+        (3, "Flatbed".to_string()),
+        (5, "15".to_string()),
+    ])
+}
+
+fn serve(stream: TcpStream, state: Arc<Mutex<ServerState>>) -> std::io::Result<()> {
+    let mut wire = Wire {
+        reader: BufReader::new(stream.try_clone()?),
+        writer: stream,
+    };
+
+    let mut values = default_values();
+
+    loop {
+        match wire.word()? {
+            // INIT
+            0 => {
+                let _version = wire.word()?;
+                let _user = wire.string()?;
+                Reply::default()
+                    .word(STATUS_GOOD)
+                    .word((1 << 24) | 3)
+                    .send(&mut wire)?;
+            }
+            // GET_DEVICES
+            1 => {
+                let mut reply = Reply::default().word(STATUS_GOOD).word(3);
+                for (name, model) in [("test:0", "frontend-tester"), ("secure:0", "locked")] {
+                    reply = reply
+                        .word(0)
+                        .string(Some(name))
+                        .string(Some("Noname"))
+                        .string(Some(model))
+                        .string(Some("virtual device"));
+                }
+                reply.word(1).send(&mut wire)?;
+            }
+            // OPEN
+            2 => {
+                let name = wire.string()?;
+                values = default_values();
+                let protected = match name.as_str() {
+                    "secure:0" => Some(("secure", "scanner", "hunter2")),
+                    "md5:0" => Some((
+                        "md5$MD5$abc123",
+                        "scanner",
+                        "$MD5$c81bfabc7214ba20078451cd08f252e3",
+                    )),
+                    "global:0" => Some(("global", "kiosk", "global-secret")),
+                    _ => None,
+                };
+                if let Some((expected_resource, expected_user, expected_pass)) = protected {
+                    Reply::default()
+                        .word(STATUS_GOOD)
+                        .word(0)
+                        .string(Some(expected_resource))
+                        .send(&mut wire)?;
+                    // Clients without credentials give up here
+                    if wire.word()? != 9 {
+                        return Ok(());
+                    }
+                    let resource = wire.string()?;
+                    let user = wire.string()?;
+                    let pass = wire.string()?;
+                    let ok = resource == expected_resource
+                        && user == expected_user
+                        && pass == expected_pass;
+                    state
+                        .lock()
+                        .unwrap()
+                        .authorized
+                        .push((resource, user, pass));
+                    Reply::default().word(0).send(&mut wire)?;
+                    let status = if ok {
+                        STATUS_GOOD
+                    } else {
+                        STATUS_ACCESS_DENIED
+                    };
+                    Reply::default()
+                        .word(status)
+                        .word(1)
+                        .string(None)
+                        .send(&mut wire)?;
+                } else {
+                    Reply::default()
+                        .word(STATUS_GOOD)
+                        .word(0)
+                        .string(None)
+                        .send(&mut wire)?;
+                }
+            }
+            // CLOSE
+            3 => {
+                let _handle = wire.word()?;
+                Reply::default().word(0).send(&mut wire)?;
+            }
+            // GET_OPTION_DESCRIPTORS
+            4 => {
+                let _handle = wire.word()?;
+                descriptors().send(&mut wire)?;
+            }
+            // CONTROL_OPTION
+            5 => {
+                let _handle = wire.word()?;
+                let option = wire.word()?;
+                let action = wire.word()?;
+                let value_type = wire.word()?;
+                let value_size = wire.word()?;
+                let len = wire.word()?;
+                let value = if value_type == 3 {
+                    let mut buf = vec![0u8; len as usize];
+                    wire.reader.read_exact(&mut buf)?;
+                    if action == 1 {
+                        state.lock().unwrap().raw_strings.push(buf.clone());
+                    }
+                    String::from_utf8_lossy(&buf)
+                        .trim_end_matches('\0')
+                        .to_string()
+                } else {
+                    let mut words = Vec::new();
+                    for _ in 0..len {
+                        words.push(wire.word()?.to_string());
+                    }
+                    words.join(",")
+                };
+
+                let value = if action == 1 {
+                    let value = match (option, value.parse::<i32>()) {
+                        (5, Ok(minutes)) => (minutes / 5 * 5).to_string(),
+                        _ => value,
+                    };
+                    let mut state = state.lock().unwrap();
+                    if option == 3 {
+                        state.source = value.clone();
+                    }
+                    state.set_options.push((option, value.clone()));
+                    values.insert(option, value.clone());
+                    value
+                } else if let Some(on) = state.lock().unwrap().sensors.get(&option) {
+                    (*on as i32).to_string()
+                } else {
+                    values.get(&option).cloned().unwrap_or_else(|| {
+                        // Option 0 is the option count; sensors start off
+                        if option == 0 { "10" } else { "0" }.to_string()
+                    })
+                };
+
+                let mut reply = Reply::default()
+                    .word(STATUS_GOOD)
+                    .word(0)
+                    .word(value_type)
+                    .word(value_size)
+                    .word(len);
+                if value_type == 3 {
+                    let mut bytes = value.into_bytes();
+                    bytes.resize(len as usize, 0);
+                    reply.0.extend_from_slice(&bytes);
+                } else {
+                    for word in value.split(',') {
+                        reply = reply.word(word.parse().unwrap_or(0));
+                    }
+                }
+                reply.string(None).send(&mut wire)?;
+            }
+            // GET_PARAMETERS
+            6 => {
+                let _handle = wire.word()?;
+                Reply::default()
+                    .word(STATUS_GOOD)
+                    .word(0) // GRAY
+                    .word(1) // last frame
+                    .word(4)
+                    .word(4)
+                    .word(2)
+                    .word(8)
+                    .send(&mut wire)?;
+            }
+            // START
+            7 => {
+                let _handle = wire.word()?;
+                let (status, scanned, stall) = {
+                    let mut state = state.lock().unwrap();
+                    let adf = state.source == "ADF";
+                    if state.warm_up_polls > 0 {
+                        state.warm_up_polls -= 1;
+                        (STATUS_WARMING_UP, state.pages_scanned, false)
+                    } else if (adf && state.pages_scanned >= 2)
+                        || (!adf && state.pages_scanned >= 1)
+                    {
+                        (STATUS_NO_DOCS, state.pages_scanned, false)
+                    } else {
+                        state.pages_scanned += 1;
+                        (STATUS_GOOD, state.pages_scanned, state.stall_data)
+                    }
+                };
+
+                if status != STATUS_GOOD {
+                    Reply::default()
+                        .word(status)
+                        .word(0)
+                        .word(0x4321)
+                        .string(None)
+                        .send(&mut wire)?;
+                    continue;
+                }
+
+                let data_listener = TcpListener::bind("127.0.0.1:0")?;
+                let data_port = data_listener.local_addr()?.port();
+                thread::spawn(move || {
+                    if let Ok((mut data, _)) = data_listener.accept() {
+                        let mut page = PAGE_PIXELS;
+                        page[0] = scanned as u8;
+                        // Split the page across two records
+                        for chunk in page.chunks(5) {
+                            let _ = data.write_all(&(chunk.len() as u32).to_be_bytes());
+                            let _ = data.write_all(chunk);
+                            if stall {
+                                let _ = data.read(&mut [0u8; 1]);
+                                return;
+                            }
+                        }
+                        let _ = data.write_all(&0xffff_ffffu32.to_be_bytes());
+                        let _ = data.write_all(&[STATUS_EOF]);
+                    }
+                });
+
+                Reply::default()
+                    .word(STATUS_GOOD)
+                    .word(data_port as i32)
+                    .word(0x4321)
+                    .string(None)
+                    .send(&mut wire)?;
+            }
+            // CANCEL
+            8 => {
+                let _handle = wire.word()?;
+                state.lock().unwrap().cancelled = true;
+                Reply::default().word(0).send(&mut wire)?;
+            }
+            // EXIT
+            10 => return Ok(()),
+            other => panic!("unexpected saned procedure {}", other),
+        }
+    }
+}
+
+fn id(text: &str) -> DeviceId {
+    text.parse().unwrap()
+}
+
+fn gray_config(source: ScanSource) -> ScanConfig {
+    ScanConfig {
+        source,
+        duplex: false,
+        dpi: 150,
+        dpi_y: None,
+        color_mode: ColorMode::Gray,
+        bit_depth: None,
+        page_size: PageSize::new(210.0, 297.0),
+        area: None,
+        brightness: None,
+        contrast: None,
+        max_pages: None,
+    }
+}
+
+fn collect_events(session: &mut Box<dyn ScanSession>) -> Vec<ScanEvent> {
+    let mut events = Vec::new();
+    while let Some(event) = session.next_event().expect("scan event") {
+        events.push(event);
+    }
+    events
+}
+
+#[test]
+fn test_saned_host_parsing() {
+    let host = SaneNetHost::parse("scanbox.local").unwrap();
+    assert_eq!((host.host.as_str(), host.port), ("scanbox.local", 6566));
+
+    let host = SaneNetHost::parse("10.0.0.5:7000").unwrap();
+    assert_eq!((host.host.as_str(), host.port), ("10.0.0.5", 7000));
+
+    let host = SaneNetHost::parse("[fe80::1]:7000").unwrap();
+    assert_eq!((host.host.as_str(), host.port), ("fe80::1", 7000));
+    assert_eq!(host.to_string(), "[fe80::1]:7000");
+
+    let host = SaneNetHost::parse("fe80::1").unwrap();
+    assert_eq!((host.host.as_str(), host.port), ("fe80::1", 6566));
+
+    assert!(SaneNetHost::parse("  ").is_none());
+}
+
+#[test]
+fn test_saned_without_hosts_is_unavailable() {
+    let backend = SaneNetBackend::with_hosts(Vec::<String>::new());
+    assert!(matches!(
+        backend.availability(),
+        BackendAvailability::Unavailable(_)
+    ));
+    assert!(backend.enumerate().is_empty());
+}
+
+#[test]
+fn test_saned_enumerate() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    assert_eq!(backend.availability(), BackendAvailability::Available);
+
+    let devices = backend.enumerate();
+    assert_eq!(devices.len(), 2);
+    assert_eq!(
+        devices[0].id,
+        id(&format!("saned_{}/test:0", server.host()))
+    );
+    assert_eq!(devices[0].name, "Noname frontend-tester (127.0.0.1)");
+    assert_eq!(devices[0].backend, Backend::SaneNet);
+    assert_eq!(devices[0].manufacturer.as_deref(), Some("Noname"));
+    assert_eq!(devices[0].model.as_deref(), Some("frontend-tester"));
+    assert_eq!(devices[0].connection, ConnectionKind::Network);
+    assert_eq!(devices[0].device_type.as_deref(), Some("virtual device"));
+    assert_eq!(devices[0].address, Some(server.host()));
+}
+
+#[test]
+fn test_saned_capabilities() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    let caps = backend
+        .capabilities(&id(&format!("saned_{}/test:0", server.host())))
+        .expect("capabilities");
+
+    assert_eq!(caps.dpis, vec![75, 150, 300]);
+    assert_eq!(
+        caps.color_modes,
+        vec![ColorMode::Color, ColorMode::Gray, ColorMode::Bw]
+    );
+    assert_eq!(caps.sources, vec![ScanSource::Flatbed, ScanSource::Adf]);
+    assert!(!caps.supports_duplex);
+}
+
+#[test]
+fn test_saned_flatbed_scan() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    let mut session = backend
+        .start_scan(
+            &id(&format!("saned_{}/test:0", server.host())),
+            gray_config(ScanSource::Flatbed),
+        )
+        .expect("start scan");
+    let events = collect_events(&mut session);
+
+    assert!(matches!(
+        events[..2],
+        [
+            ScanEvent::Status(ScanStatus::Scanning),
+            ScanEvent::PageStarted(0)
+        ]
+    ));
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+
+    let data: Vec<u8> = events
+        .iter()
+        .filter_map(|e| match e {
+            ScanEvent::PageData(d) => Some(d.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let mut expected = b"P5\n4 2\n255\n".to_vec();
+    let mut page = PAGE_PIXELS;
+    page[0] = 1;
+    expected.extend_from_slice(&page);
+    assert_eq!(data, expected);
+
+    let meta = events
+        .iter()
+        .find_map(|e| match e {
+            ScanEvent::PageComplete(meta) => Some(meta.clone()),
+            _ => None,
+        })
+        .expect("page complete");
+    assert_eq!((meta.width_px, meta.height_px, meta.dpi), (4, 2, 150));
+
+    drop(session);
+    let state = server.state.lock().unwrap();
+    assert!(state.set_options.contains(&(1, "Gray".to_string())));
+    assert!(state.set_options.contains(&(2, "150".to_string())));
+    assert!(state.set_options.contains(&(3, "Flatbed".to_string())));
+}
+
+#[test]
+fn test_saned_reports_warm_up_and_progress() {
+    let server = FakeSaned::start();
+    server.state.lock().unwrap().warm_up_polls = 2;
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    let mut session = backend
+        .start_scan(
+            &id(&format!("saned_{}/test:0", server.host())),
+            gray_config(ScanSource::Flatbed),
+        )
+        .expect("start scan");
+    let events = collect_events(&mut session);
+
+    let statuses: Vec<ScanStatus> = events
+        .iter()
+        .filter_map(|e| match e {
+            ScanEvent::Status(status) => Some(*status),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(statuses, [ScanStatus::WarmingUp, ScanStatus::Scanning]);
+
+    // The page arrives as a 5-byte and a 3-byte record
+    let progress: Vec<(u64, Option<u64>)> = events
+        .iter()
+        .filter_map(|e| match e {
+            ScanEvent::Progress {
+                bytes, expected, ..
+            } => Some((*bytes, *expected)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(progress, [(5, Some(8)), (8, Some(8))]);
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+}
+
+#[test]
+fn test_saned_cancel_interrupts_blocked_read() {
+    let server = FakeSaned::start();
+    server.state.lock().unwrap().stall_data = true;
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    let cancel = CancelToken::new();
+    let mut session = backend
+        .start_scan_cancellable(
+            &id(&format!("saned_{}/test:0", server.host())),
+            gray_config(ScanSource::Flatbed),
+            &cancel,
+        )
+        .expect("start scan");
+    let canceller = {
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            cancel.cancel();
+        })
+    };
+
+    let started = Instant::now();
+    let events = collect_events(&mut session);
+    canceller.join().unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, ScanEvent::Progress { bytes: 5, .. })));
+    assert!(matches!(events.last(), Some(ScanEvent::Cancelled)));
+    // Cancelled on the server before the session is dropped
+    assert!(server.state.lock().unwrap().cancelled);
+}
+
+#[test]
+fn test_saned_cancelled_token_stops_start() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+    let device = id(&format!("saned_{}/test:0", server.host()));
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let result = backend.start_scan_cancellable(&device, gray_config(ScanSource::Flatbed), &cancel);
+    assert!(matches!(result, Err(PapyrError::Cancelled)));
+    assert!(matches!(
+        backend.capabilities_cancellable(&device, &cancel),
+        Err(PapyrError::Cancelled)
+    ));
+    assert!(backend.enumerate_cancellable(&cancel).is_empty());
+    assert_eq!(server.state.lock().unwrap().pages_scanned, 0);
+}
+
+#[test]
+fn test_saned_adf_scan_until_empty() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    let mut session = backend
+        .start_scan(
+            &id(&format!("saned_{}/test:0", server.host())),
+            gray_config(ScanSource::Adf),
+        )
+        .expect("start scan");
+    let events = collect_events(&mut session);
+
+    let started: Vec<u32> = events
+        .iter()
+        .filter_map(|e| match e {
+            ScanEvent::PageStarted(i) => Some(*i),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(started, vec![0, 1]);
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, ScanEvent::PageComplete(_)))
+            .count(),
+        2
+    );
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+
+    drop(session);
+    assert!(server.state.lock().unwrap().cancelled);
+}
+
+#[test]
+fn test_saned_authorization() {
+    let server = FakeSaned::start();
+    let device_id = id(&format!("saned_{}/secure:0", server.host()));
+
+    let anonymous = SaneNetBackend::with_hosts([server.host()]);
+    assert!(matches!(
+        anonymous.capabilities(&device_id),
+        Err(PapyrError::Unauthorized { .. })
+    ));
+
+    let authorized =
+        SaneNetBackend::with_hosts([server.host()]).with_credentials("scanner", "hunter2");
+    assert!(authorized.capabilities(&device_id).is_ok());
+
+    let state = server.state.lock().unwrap();
+    assert_eq!(
+        state.authorized,
+        vec![(
+            "secure".to_string(),
+            "scanner".to_string(),
+            "hunter2".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_saned_unreachable_host() {
+    // Bind and drop to get a port nothing listens on
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let backend = SaneNetBackend::with_hosts([format!("127.0.0.1:{}", port)]);
+
+    assert!(backend.enumerate().is_empty());
+    assert!(backend
+        .capabilities(&id(&format!("saned_127.0.0.1:{}/test:0", port)))
+        .is_err());
+}
+
+#[test]
+fn test_saned_md5_authorization() {
+    let server = FakeSaned::start();
+    let device_id = id(&format!("saned_{}/md5:0", server.host()));
+
+    // The provider is asked for the bare resource name, never the salt
+    let backend =
+        SaneNetBackend::with_hosts([server.host()]).with_credentials_provider(std::sync::Arc::new(
+            |resource: &str| (resource == "md5").then(|| Credentials::new("scanner", "hunter2")),
+        ));
+    assert!(backend.capabilities(&device_id).is_ok());
+
+    let state = server.state.lock().unwrap();
+    assert_eq!(state.authorized.len(), 1);
+    assert_eq!(
+        state.authorized[0].2,
+        "$MD5$c81bfabc7214ba20078451cd08f252e3"
+    );
+}
+
+#[test]
+fn test_saned_wrong_password_is_unauthorized() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]).with_credentials("scanner", "wrong");
+
+    assert!(matches!(
+        backend.capabilities(&id(&format!("saned_{}/secure:0", server.host()))),
+        Err(PapyrError::Unauthorized { .. })
+    ));
+}
+
+#[test]
+fn test_saned_uses_global_credentials_provider() {
+    let server = FakeSaned::start();
+
+    // Only answers for "global" so concurrent tests are unaffected
+    auth::set_credentials_provider(Some(std::sync::Arc::new(|resource: &str| {
+        (resource == "global").then(|| Credentials::new("kiosk", "global-secret"))
+    })));
+
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+    let result = backend.capabilities(&id(&format!("saned_{}/global:0", server.host())));
+    auth::set_credentials_provider(None);
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_md5_password_encoding() {
+    assert_eq!(
+        auth::split_md5_resource("md5$MD5$abc123"),
+        ("md5", Some("abc123"))
+    );
+    assert_eq!(auth::split_md5_resource("plain"), ("plain", None));
+    assert_eq!(
+        auth::encode_password("md5$MD5$abc123", "hunter2"),
+        "$MD5$c81bfabc7214ba20078451cd08f252e3"
+    );
+    assert_eq!(auth::encode_password("plain", "hunter2"), "hunter2");
+}
+
+#[test]
+fn test_saned_device_id_escapes_name() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+
+    // Escaped and plain forms name the same remote device
+    let escaped = id(&format!("saned_{}/test%3A0", server.host()));
+    assert!(backend.capabilities(&escaped).is_ok());
+
+    let malformed = id(&format!("saned_{}/test%3", server.host()));
+    assert!(matches!(
+        backend.capabilities(&malformed),
+        Err(PapyrError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_saned_device_options() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+    let device_id = id(&format!("saned_{}/test:0", server.host()));
+
+    let options = backend.options(&device_id).expect("options");
+    let names: Vec<&str> = options.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "mode",
+            "resolution",
+            "source",
+            "lamp-off-time",
+            "scan",
+            "page-loaded",
+            "label"
+        ]
+    );
+
+    let mode = &options[0];
+    assert_eq!(mode.value_type, OptionType::String);
+    assert_eq!(mode.value, Some(OptionValue::String("Color".into())));
+    assert_eq!(mode.group, None);
+    assert!(mode.settable && !mode.advanced);
+
+    let resolution = &options[1];
+    assert_eq!(resolution.unit, OptionUnit::Dpi);
+    assert_eq!(
+        resolution.constraint,
+        OptionConstraint::List(vec![
+            OptionValue::Int(75),
+            OptionValue::Int(150),
+            OptionValue::Int(300)
+        ])
+    );
+
+    let lamp = &options[3];
+    assert_eq!(lamp.title, "Lamp off time");
+    assert_eq!(lamp.group.as_deref(), Some("Advanced"));
+    assert!(lamp.advanced);
+    assert_eq!(lamp.value, Some(OptionValue::Int(15)));
+    assert_eq!(
+        lamp.constraint,
+        OptionConstraint::Range {
+            min: 0.0,
+            max: 60.0,
+            step: Some(5.0)
+        }
+    );
+}
+
+#[test]
+fn test_saned_set_option_is_kept_for_scans() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+    let device_id = id(&format!("saned_{}/test:0", server.host()));
+
+    // Text values are parsed, and the device's rounding is reported back
+    let lamp = backend
+        .set_option(
+            &device_id,
+            "lamp-off-time",
+            OptionValue::String("23".into()),
+        )
+        .expect("set lamp-off-time");
+    assert_eq!(lamp.value, Some(OptionValue::Int(20)));
+
+    // A fresh connection starts from defaults, so this proves the
+    // backend re-applies the setting
+    let options = backend.options(&device_id).expect("options");
+    let lamp = options.iter().find(|o| o.name == "lamp-off-time").unwrap();
+    assert_eq!(lamp.value, Some(OptionValue::Int(20)));
+
+    server.state.lock().unwrap().set_options.clear();
+    let mut session = backend
+        .start_scan(&device_id, gray_config(ScanSource::Flatbed))
+        .expect("start scan");
+    collect_events(&mut session);
+    drop(session);
+
+    let state = server.state.lock().unwrap();
+    assert!(state.set_options.contains(&(5, "20".to_string())));
+}
+
+#[test]
+fn test_saned_string_option_keeps_terminator() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+    let device_id = id(&format!("saned_{}/test:0", server.host()));
+
+    // Exactly the option size: the last byte gives way to the NUL
+    let label = backend
+        .set_option(&device_id, "label", OptionValue::String("ABCDEFGH".into()))
+        .expect("set label");
+    assert_eq!(label.value, Some(OptionValue::String("ABCDEFG".into())));
+    backend
+        .set_option(&device_id, "label", OptionValue::String("short".into()))
+        .expect("set label");
+
+    let state = server.state.lock().unwrap();
+    assert_eq!(state.raw_strings[0], b"ABCDEFG\0");
+    assert_eq!(state.raw_strings.last().unwrap(), b"short\0");
+}
+
+#[test]
+fn test_saned_set_option_rejects_invalid_values() {
+    let server = FakeSaned::start();
+    let backend = SaneNetBackend::with_hosts([server.host()]);
+    let device_id = id(&format!("saned_{}/test:0", server.host()));
+
+    for (name, value) in [
+        ("lamp-off-time", OptionValue::Int(90)),
+        ("lamp-off-time", OptionValue::String("soon".into())),
+        ("mode", OptionValue::String("Infrared".into())),
+        ("no-such-option", OptionValue::Bool(true)),
+    ] {
+        assert!(
+            matches!(
+                backend.set_option(&device_id, name, value.clone()),
+                Err(PapyrError::InvalidConfig(_))
+            ),
+            "{} = {:?} should be rejected",
+            name,
+            value
+        );
+    }
+    assert!(server.state.lock().unwrap().set_options.is_empty());
+}
+
+#[test]
+fn test_saned_sensor_monitor() {
+    let server = FakeSaned::start();
+    let mut registry = BackendRegistry::new();
+    registry.register(Box::new(SaneNetBackend::with_hosts([server.host()])));
+    let device_id = id(&format!("saned_{}/test:0", server.host()));
+
+    // Paper already in the feeder at start is the baseline, not an event
+    server.state.lock().unwrap().sensors.insert(8, true);
+    let monitor = registry
+        .monitor_sensors(&device_id, Duration::from_millis(20))
+        .expect("monitor sensors");
+    assert_eq!(monitor.next_event(Duration::from_millis(200)), None);
+
+    server.state.lock().unwrap().sensors.insert(8, false);
+    assert_eq!(
+        monitor.next_event(Duration::from_secs(2)),
+        Some(SensorEvent::PaperRemoved)
+    );
+
+    server.state.lock().unwrap().sensors.insert(8, true);
+    assert_eq!(
+        monitor.next_event(Duration::from_secs(2)),
+        Some(SensorEvent::PaperLoaded)
+    );
+
+    server.state.lock().unwrap().sensors.insert(7, true);
+    assert_eq!(
+        monitor.next_event(Duration::from_secs(2)),
+        Some(SensorEvent::ButtonPressed("scan".into()))
+    );
+
+    // Holding the button down is a single press
+    assert_eq!(monitor.next_event(Duration::from_millis(200)), None);
+}
+
+#[test]
+fn test_sensor_monitor_unknown_device() {
+    let registry = BackendRegistry::new();
+    assert!(matches!(
+        registry.monitor_sensors(&id("saned_127.0.0.1:1/none"), Duration::from_millis(20)),
+        Err(PapyrError::NotFound(_))
+    ));
+}
@@ -0,0 +1,145 @@
+//
+//  papyr_core
+//  tests/scanner_info_test.rs - Device details reported by the eSCL and SANE backends
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::backends::escl::scanner_info_from_txt;
+use papyr_core::backends::sane_common::*;
+use papyr_core::models::*;
+use std::collections::HashMap;
+
+fn txt(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_escl_info_from_txt() {
+    let record = txt(&[
+        ("ty", "HP OfficeJet Pro 9010 series"),
+        ("uuid", "564e4333-4e30-3435-3139-a0481c1f6bcd"),
+        ("note", "Second floor"),
+        ("adminurl", "http://192.168.1.20/"),
+        ("representation", "http://192.168.1.20/images/printer.png"),
+        ("rs", "eSCL"),
+    ]);
+    let info = scanner_info_from_txt(
+        DeviceId::new(Backend::Escl, "HP"),
+        "HP OfficeJet Pro 9010 series [1F6BCD]._uscan._tcp.local",
+        "192.168.1.20:8080",
+        &record,
+    );
+
+    assert_eq!(info.name, "HP OfficeJet Pro 9010 series [1F6BCD]");
+    assert_eq!(info.backend, Backend::Escl);
+    assert_eq!(info.manufacturer.as_deref(), Some("HP"));
+    assert_eq!(info.model.as_deref(), Some("OfficeJet Pro 9010 series"));
+    assert_eq!(
+        info.serial.as_deref(),
+        Some("564e4333-4e30-3435-3139-a0481c1f6bcd")
+    );
+    assert_eq!(info.connection, ConnectionKind::Network);
+    assert_eq!(info.address.as_deref(), Some("192.168.1.20:8080"));
+    assert_eq!(info.location.as_deref(), Some("Second floor"));
+    assert_eq!(info.admin_url.as_deref(), Some("http://192.168.1.20/"));
+    assert_eq!(
+        info.icon_url.as_deref(),
+        Some("http://192.168.1.20/images/printer.png")
+    );
+}
+
+#[test]
+fn test_escl_info_prefers_usb_make_and_model() {
+    let record = txt(&[
+        ("ty", "Canon MF740C/640C"),
+        ("usb_mfg", "Canon"),
+        ("usb_mdl", "MF741C/743C"),
+        ("note", ""),
+    ]);
+    let info = scanner_info_from_txt(
+        DeviceId::new(Backend::Escl, "canon"),
+        "Canon MF741C",
+        "10.0.0.5",
+        &record,
+    );
+
+    assert_eq!(info.name, "Canon MF741C");
+    assert_eq!(info.manufacturer.as_deref(), Some("Canon"));
+    assert_eq!(info.model.as_deref(), Some("MF741C/743C"));
+    assert_eq!(info.location, None);
+    assert_eq!(info.serial, None);
+
+    // Nothing but the service name
+    let bare = scanner_info_from_txt(
+        DeviceId::new(Backend::Escl, "x"),
+        "Scanner",
+        "10.0.0.6",
+        &HashMap::new(),
+    );
+    assert_eq!(bare.manufacturer, None);
+    assert_eq!(bare.model, None);
+}
+
+#[test]
+fn test_sane_connection_from_device_name() {
+    for (name, expected) in [
+        ("epson2:libusb:001:004", ConnectionKind::Usb),
+        ("hpaio:/usb/Deskjet_3050?serial=CN0", ConnectionKind::Usb),
+        (
+            "hpaio:/net/HP_LaserJet?ip=10.0.0.9",
+            ConnectionKind::Network,
+        ),
+        ("airscan:e0:Brother MFC-L2710DW", ConnectionKind::Network),
+        (
+            "net:scanhost:plustek:libusb:001:002",
+            ConnectionKind::Network,
+        ),
+        ("epson2:net:192.168.1.5", ConnectionKind::Network),
+        ("test:0", ConnectionKind::Virtual),
+        ("pnm:0", ConnectionKind::Virtual),
+        ("genesys:/dev/sg0", ConnectionKind::Unknown),
+    ] {
+        assert_eq!(connection_for_device(name), expected, "{}", name);
+    }
+}
+
+#[test]
+fn test_sane_describe_device() {
+    let info = describe_device(
+        ScannerInfo::new(DeviceId::new(Backend::Sane, "epson2"), "Epson Perfection"),
+        "epson2:net:192.168.1.5",
+        "Epson",
+        "Perfection V600",
+        "flatbed scanner",
+    );
+    assert_eq!(info.manufacturer.as_deref(), Some("Epson"));
+    assert_eq!(info.model.as_deref(), Some("Perfection V600"));
+    assert_eq!(info.device_type.as_deref(), Some("flatbed scanner"));
+    assert_eq!(info.connection, ConnectionKind::Network);
+    assert_eq!(info.address.as_deref(), Some("192.168.1.5"));
+
+    let unnamed = describe_device(
+        ScannerInfo::new(DeviceId::new(Backend::Sane, "x"), "x"),
+        "genesys:/dev/sg0",
+        "",
+        " ",
+        "",
+    );
+    assert_eq!(unnamed.manufacturer, None);
+    assert_eq!(unnamed.model, None);
+    assert_eq!(unnamed.address, None);
+
+    let escl = describe_device(
+        ScannerInfo::new(DeviceId::new(Backend::Sane, "escl"), "HP"),
+        "escl:http://192.168.1.20:80",
+        "HP",
+        "OfficeJet Pro 9010",
+        "flatbed scanner",
+    );
+    assert_eq!(escl.address.as_deref(), Some("http://192.168.1.20:80"));
+}
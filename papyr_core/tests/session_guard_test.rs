@@ -0,0 +1,266 @@
+//
+//  papyr_core
+//  tests/session_guard_test.rs - Session guard tests with scripted backend sessions
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::models::*;
+use papyr_core::session_guard::{GuardedSession, SessionLimits};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Plays back a script, optionally pausing before each event, and records
+/// how often it was polled and whether it was cancelled.
+struct ScriptedSession {
+    script: VecDeque<ScanEvent>,
+    delay: Duration,
+    polls: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScriptedSession {
+    fn new(script: Vec<ScanEvent>) -> Self {
+        Self {
+            script: script.into(),
+            delay: Duration::ZERO,
+            polls: Arc::new(AtomicUsize::new(0)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+impl ScanSession for ScriptedSession {
+    fn next_event(&mut self) -> Result<Option<ScanEvent>> {
+        self.polls.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(self.delay);
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Ok(Some(ScanEvent::Cancelled));
+        }
+        Ok(self.script.pop_front())
+    }
+
+    fn cancel_handle(&self) -> Option<CancelHandle> {
+        let cancelled = Arc::clone(&self.cancelled);
+        Some(CancelHandle::new(move || {
+            cancelled.store(true, Ordering::SeqCst)
+        }))
+    }
+}
+
+fn meta(index: u32) -> PageMeta {
+    PageMeta {
+        index,
+        width_px: 4,
+        height_px: 2,
+        dpi: 150,
+        dpi_y: 150,
+        color_mode: ColorMode::Gray,
+        bit_depth: 8,
+    }
+}
+
+fn page(index: u32) -> Vec<ScanEvent> {
+    vec![
+        ScanEvent::PageStarted(index),
+        ScanEvent::PageData(vec![index as u8; 8]),
+        ScanEvent::PageComplete(meta(index)),
+    ]
+}
+
+fn guard(
+    session: ScriptedSession,
+    max_pages: Option<u32>,
+    limits: SessionLimits,
+) -> GuardedSession {
+    GuardedSession::new(Box::new(session), Backend::Sane, max_pages, limits)
+}
+
+/// Events until the end of the session, and the error that ended it.
+fn drain(session: &mut GuardedSession) -> (Vec<ScanEvent>, Option<PapyrError>) {
+    let mut events = Vec::new();
+    loop {
+        match session.next_event() {
+            Ok(Some(event)) => events.push(event),
+            Ok(None) => return (events, None),
+            Err(e) => {
+                assert!(matches!(session.next_event(), Ok(None)));
+                return (events, Some(e));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_guard_passes_ordered_events_through() {
+    let mut script = page(0);
+    script.extend(page(1));
+    script.push(ScanEvent::JobComplete);
+
+    let mut session = guard(ScriptedSession::new(script), None, SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(events.len(), 7);
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+}
+
+#[test]
+fn test_guard_stops_after_max_pages() {
+    let mut script = Vec::new();
+    for index in 0..5 {
+        script.extend(page(index));
+    }
+    script.push(ScanEvent::JobComplete);
+    let backend = ScriptedSession::new(script);
+    let cancelled = Arc::clone(&backend.cancelled);
+
+    let mut session = guard(backend, Some(2), SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert!(error.is_none(), "{:?}", error);
+    let completed = events
+        .iter()
+        .filter(|e| matches!(e, ScanEvent::PageComplete(_)))
+        .count();
+    assert_eq!(completed, 2);
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+    assert!(
+        cancelled.load(Ordering::SeqCst),
+        "the feeder should be stopped"
+    );
+}
+
+#[test]
+fn test_guard_treats_zero_max_pages_as_unlimited() {
+    let mut script = page(0);
+    script.extend(page(1));
+    script.push(ScanEvent::JobComplete);
+
+    let mut session = guard(
+        ScriptedSession::new(script),
+        Some(0),
+        SessionLimits::default(),
+    );
+    let (events, _) = drain(&mut session);
+    assert_eq!(events.len(), 7);
+}
+
+#[test]
+fn test_guard_rejects_page_complete_without_start() {
+    let script = vec![ScanEvent::PageComplete(meta(0)), ScanEvent::JobComplete];
+
+    let mut session = guard(ScriptedSession::new(script), None, SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert!(events.is_empty());
+    assert!(
+        matches!(&error, Some(PapyrError::Protocol { message, .. }) if message.contains("without being started")),
+        "{:?}",
+        error
+    );
+}
+
+#[test]
+fn test_guard_rejects_data_outside_a_page() {
+    // Before the first page
+    let script = vec![ScanEvent::PageData(vec![1, 2, 3]), ScanEvent::JobComplete];
+    let backend = ScriptedSession::new(script);
+    let cancelled = Arc::clone(&backend.cancelled);
+    let mut session = guard(backend, None, SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert!(events.is_empty());
+    assert!(
+        matches!(&error, Some(PapyrError::Protocol { message, .. }) if message.contains("outside a page")),
+        "{:?}",
+        error
+    );
+    assert!(cancelled.load(Ordering::SeqCst));
+
+    // Between pages
+    let mut script = page(0);
+    script.push(ScanEvent::PageData(vec![9; 4]));
+    script.extend(page(1));
+    let mut session = guard(ScriptedSession::new(script), None, SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert_eq!(events.len(), 3);
+    assert!(matches!(error, Some(PapyrError::Protocol { .. })));
+}
+
+#[test]
+fn test_guard_rejects_overlapping_pages() {
+    let script = vec![ScanEvent::PageStarted(0), ScanEvent::PageStarted(1)];
+
+    let mut session = guard(ScriptedSession::new(script), None, SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(error, Some(PapyrError::Protocol { .. })));
+}
+
+#[test]
+fn test_guard_reads_nothing_after_job_complete() {
+    let mut script = page(0);
+    script.push(ScanEvent::JobComplete);
+    script.push(ScanEvent::PageData(vec![9; 4]));
+    let backend = ScriptedSession::new(script);
+    let polls = Arc::clone(&backend.polls);
+
+    let mut session = guard(backend, None, SessionLimits::default());
+    let (events, error) = drain(&mut session);
+    assert!(error.is_none());
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+    assert!(matches!(session.next_event(), Ok(None)));
+    assert_eq!(polls.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn test_guard_idle_timeout() {
+    let backend = ScriptedSession::new(page(0)).with_delay(Duration::from_secs(2));
+    let cancelled = Arc::clone(&backend.cancelled);
+    let limits = SessionLimits {
+        idle_timeout: Some(Duration::from_millis(100)),
+        job_timeout: None,
+    };
+
+    let started = Instant::now();
+    let mut session = guard(backend, None, limits);
+    let (events, error) = drain(&mut session);
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(events.is_empty());
+    assert!(
+        matches!(&error, Some(PapyrError::Timeout { backend: Backend::Sane, message }) if message.contains("No scan event")),
+        "{:?}",
+        error
+    );
+    assert!(cancelled.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_guard_job_timeout() {
+    // A steady trickle of events that never finishes
+    let script = std::iter::once(ScanEvent::PageStarted(0))
+        .chain(std::iter::repeat_with(|| ScanEvent::PageData(vec![0; 4])).take(1000))
+        .collect();
+    let backend = ScriptedSession::new(script).with_delay(Duration::from_millis(20));
+    let limits = SessionLimits {
+        idle_timeout: Some(Duration::from_secs(1)),
+        job_timeout: Some(Duration::from_millis(300)),
+    };
+
+    let started = Instant::now();
+    let mut session = guard(backend, None, limits);
+    let (events, error) = drain(&mut session);
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(300) && elapsed < Duration::from_secs(2));
+    assert!(!events.is_empty());
+    assert!(
+        matches!(&error, Some(PapyrError::Timeout { message, .. }) if message.contains("did not finish")),
+        "{:?}",
+        error
+    );
+}
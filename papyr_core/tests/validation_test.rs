@@ -0,0 +1,388 @@
+//
+//  papyr_core
+//  tests/validation_test.rs - ScanConfig validation and coercion tests
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+use papyr_core::models::*;
+use papyr_core::validation::{ConfigAdjustment, ConfigIssue};
+
+const A4: PageSize = PageSize::new(210.0, 297.0);
+
+fn capabilities() -> Capabilities {
+    Capabilities {
+        sources: vec![ScanSource::Flatbed, ScanSource::Adf],
+        dpis: vec![75, 150, 300, 600],
+        dpi_ranges: vec![],
+        independent_dpi: false,
+        color_modes: vec![ColorMode::Color, ColorMode::Gray],
+        bit_depths: vec![8],
+        page_sizes: vec![PageFormat::Letter, PageFormat::IsoA(4)],
+        max_areas: vec![],
+        supports_duplex: false,
+    }
+}
+
+fn config() -> ScanConfig {
+    ScanConfig {
+        source: ScanSource::Flatbed,
+        duplex: false,
+        dpi: 300,
+        dpi_y: None,
+        color_mode: ColorMode::Color,
+        bit_depth: None,
+        page_size: A4,
+        area: None,
+        brightness: None,
+        contrast: None,
+        max_pages: None,
+    }
+}
+
+#[test]
+fn test_supported_config_is_valid() {
+    assert!(config().issues(&capabilities()).is_empty());
+    assert!(config().validate(&capabilities()).is_ok());
+
+    let (coerced, adjustments) = config().coerce(&capabilities()).unwrap();
+    assert!(adjustments.is_empty());
+    assert_eq!(coerced.dpi, 300);
+}
+
+#[test]
+fn test_validate_reports_every_issue() {
+    let cfg = ScanConfig {
+        source: ScanSource::AdfDuplex,
+        duplex: true,
+        dpi: 1200,
+        color_mode: ColorMode::Bw,
+        page_size: PageFormat::Legal.size().unwrap(),
+        ..config()
+    };
+
+    let issues = cfg.issues(&capabilities());
+    assert_eq!(issues.len(), 5);
+    assert!(matches!(
+        issues[0],
+        ConfigIssue::UnsupportedSource {
+            requested: ScanSource::AdfDuplex,
+            ..
+        }
+    ));
+    assert_eq!(issues[1], ConfigIssue::DuplexUnsupported);
+    assert!(matches!(
+        issues[2],
+        ConfigIssue::UnsupportedDpi {
+            requested: 1200,
+            ..
+        }
+    ));
+    assert!(matches!(
+        issues[3],
+        ConfigIssue::UnsupportedColorMode {
+            requested: ColorMode::Bw,
+            ..
+        }
+    ));
+    assert!(matches!(
+        issues[4],
+        ConfigIssue::AreaTooLarge { height_mm, .. } if (height_mm - 355.6).abs() < 0.01
+    ));
+
+    match cfg.validate(&capabilities()) {
+        Err(PapyrError::InvalidConfig(message)) => {
+            assert!(message.contains("1200 dpi"));
+            assert!(message.contains("duplex"));
+        }
+        other => panic!("expected InvalidConfig, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_validate_area() {
+    let outside = ScanConfig {
+        area: Some(ScanArea {
+            x_mm: 100.0,
+            y_mm: 0.0,
+            width_mm: 150.0,
+            height_mm: 100.0,
+        }),
+        ..config()
+    };
+    assert!(matches!(
+        outside.issues(&capabilities())[..],
+        [ConfigIssue::AreaTooLarge {
+            width_mm: 250.0,
+            ..
+        }]
+    ));
+
+    let empty = ScanConfig {
+        area: Some(ScanArea {
+            x_mm: 0.0,
+            y_mm: 0.0,
+            width_mm: 0.0,
+            height_mm: 100.0,
+        }),
+        ..config()
+    };
+    assert_eq!(empty.issues(&capabilities()), vec![ConfigIssue::EmptyArea]);
+}
+
+#[test]
+fn test_empty_capabilities_are_not_checked() {
+    let unknown = Capabilities {
+        sources: vec![],
+        dpis: vec![],
+        dpi_ranges: vec![],
+        independent_dpi: false,
+        color_modes: vec![],
+        bit_depths: vec![],
+        page_sizes: vec![],
+        max_areas: vec![],
+        supports_duplex: true,
+    };
+    let cfg = ScanConfig {
+        dpi: 4800,
+        page_size: PageSize::new(1000.0, 1000.0),
+        ..config()
+    };
+    assert!(cfg.issues(&unknown).is_empty());
+    assert!(cfg.coerce(&unknown).unwrap().1.is_empty());
+}
+
+#[test]
+fn test_coerce_snaps_dpi_to_nearest() {
+    let caps = capabilities();
+    for (requested, expected) in [(1200, 600), (200, 150), (225, 300), (10, 75)] {
+        let (cfg, adjustments) = ScanConfig {
+            dpi: requested,
+            ..config()
+        }
+        .coerce(&caps)
+        .unwrap();
+        assert_eq!(cfg.dpi, expected, "{} dpi", requested);
+        assert_eq!(
+            adjustments,
+            vec![ConfigAdjustment::Dpi {
+                from: requested,
+                to: expected
+            }]
+        );
+    }
+}
+
+#[test]
+fn test_coerce_falls_back_to_simplex() {
+    let (cfg, adjustments) = ScanConfig {
+        source: ScanSource::AdfDuplex,
+        duplex: true,
+        ..config()
+    }
+    .coerce(&capabilities())
+    .unwrap();
+
+    assert_eq!(cfg.source, ScanSource::Adf);
+    assert!(!cfg.duplex);
+    assert_eq!(
+        adjustments,
+        vec![
+            ConfigAdjustment::Source {
+                from: ScanSource::AdfDuplex,
+                to: ScanSource::Adf
+            },
+            ConfigAdjustment::DuplexDisabled
+        ]
+    );
+
+    // Without an ADF there is nothing to fall back to
+    let flatbed_only = Capabilities {
+        sources: vec![ScanSource::Flatbed],
+        ..capabilities()
+    };
+    assert!(matches!(
+        ScanConfig {
+            source: ScanSource::AdfDuplex,
+            ..config()
+        }
+        .coerce(&flatbed_only),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_coerce_clamps_page_and_area() {
+    let (cfg, adjustments) = ScanConfig {
+        page_size: PageSize::new(297.0, 420.0), // A3
+        area: Some(ScanArea {
+            x_mm: 100.0,
+            y_mm: 250.0,
+            width_mm: 150.0,
+            height_mm: 100.0,
+        }),
+        ..config()
+    }
+    .coerce(&capabilities())
+    .unwrap();
+
+    // Letter width by A4 height
+    assert_eq!(cfg.page_size, PageSize::new(8.5 * MM_PER_INCH, 297.0));
+    assert_eq!(
+        cfg.area,
+        Some(ScanArea {
+            x_mm: 100.0,
+            y_mm: 250.0,
+            width_mm: 8.5 * MM_PER_INCH - 100.0,
+            height_mm: 47.0,
+        })
+    );
+    assert_eq!(adjustments.len(), 2);
+    assert!(cfg.issues(&capabilities()).is_empty());
+}
+
+#[test]
+fn test_coerce_keeps_unfixable_issues() {
+    let result = ScanConfig {
+        dpi: 1200,
+        color_mode: ColorMode::Bw,
+        ..config()
+    }
+    .coerce(&capabilities());
+
+    match result {
+        Err(PapyrError::InvalidConfig(message)) => {
+            assert!(message.contains("Bw"));
+            assert!(!message.contains("dpi"));
+        }
+        other => panic!("expected InvalidConfig, got {:?}", other.map(|(_, a)| a)),
+    }
+}
+
+fn range_capabilities() -> Capabilities {
+    Capabilities {
+        dpis: vec![100, 200, 300],
+        dpi_ranges: vec![DpiRange {
+            min: 50,
+            max: 1200,
+            step: 25,
+        }],
+        independent_dpi: true,
+        bit_depths: vec![8, 16],
+        ..capabilities()
+    }
+}
+
+#[test]
+fn test_validate_dpi_ranges() {
+    let caps = range_capabilities();
+    for dpi in [50, 125, 1200] {
+        assert!(ScanConfig { dpi, ..config() }.issues(&caps).is_empty());
+    }
+    for dpi in [40, 130, 1225] {
+        assert!(matches!(
+            ScanConfig { dpi, ..config() }.issues(&caps)[..],
+            [ConfigIssue::UnsupportedDpi { .. }]
+        ));
+    }
+
+    let (cfg, adjustments) = ScanConfig {
+        dpi: 138,
+        ..config()
+    }
+    .coerce(&caps)
+    .unwrap();
+    assert_eq!(cfg.dpi, 150);
+    assert_eq!(
+        adjustments,
+        vec![ConfigAdjustment::Dpi { from: 138, to: 150 }]
+    );
+}
+
+#[test]
+fn test_validate_independent_dpi() {
+    let xy = ScanConfig {
+        dpi: 300,
+        dpi_y: Some(600),
+        ..config()
+    };
+    assert!(xy.issues(&range_capabilities()).is_empty());
+    assert_eq!(xy.vertical_dpi(), 600);
+
+    assert_eq!(
+        xy.issues(&capabilities()),
+        vec![ConfigIssue::IndependentDpiUnsupported {
+            dpi: 300,
+            dpi_y: 600
+        }]
+    );
+
+    // Tied devices scan at the horizontal resolution
+    let (cfg, adjustments) = xy.coerce(&capabilities()).unwrap();
+    assert_eq!(cfg.dpi_y, None);
+    assert_eq!(
+        adjustments,
+        vec![ConfigAdjustment::DpiY {
+            from: 600,
+            to: None
+        }]
+    );
+}
+
+#[test]
+fn test_validate_bit_depth() {
+    let deep = ScanConfig {
+        color_mode: ColorMode::Gray,
+        bit_depth: Some(16),
+        ..config()
+    };
+    assert!(deep.issues(&range_capabilities()).is_empty());
+    assert_eq!(deep.bits_per_sample(), 16);
+    assert!(matches!(
+        deep.issues(&capabilities())[..],
+        [ConfigIssue::UnsupportedBitDepth { requested: 16, .. }]
+    ));
+
+    let (cfg, adjustments) = deep.coerce(&capabilities()).unwrap();
+    assert_eq!(cfg.bit_depth, Some(8));
+    assert_eq!(
+        adjustments,
+        vec![ConfigAdjustment::BitDepth {
+            from: 16,
+            to: Some(8)
+        }]
+    );
+
+    // Bw is always one bit per sample
+    let bw = ScanConfig {
+        color_mode: ColorMode::Bw,
+        bit_depth: Some(8),
+        ..config()
+    };
+    let bw_caps = Capabilities {
+        color_modes: vec![ColorMode::Bw],
+        ..capabilities()
+    };
+    assert_eq!(bw.bits_per_sample(), 1);
+    assert!(matches!(
+        bw.issues(&bw_caps)[..],
+        [ConfigIssue::UnsupportedBitDepth { requested: 8, .. }]
+    ));
+    assert_eq!(bw.coerce(&bw_caps).unwrap().0.bit_depth, None);
+}
+
+#[test]
+fn test_dpi_range_nearest() {
+    let range = DpiRange {
+        min: 50,
+        max: 610,
+        step: 25,
+    };
+    assert_eq!(range.nearest(10), 50);
+    assert_eq!(range.nearest(62), 50);
+    assert_eq!(range.nearest(63), 75);
+    assert_eq!(range.nearest(2000), 600);
+    assert!(range.contains(600));
+    assert!(!range.contains(610));
+}
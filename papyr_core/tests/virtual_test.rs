@@ -0,0 +1,561 @@
+//
+//  papyr_core
+//  tests/virtual_test.rs - Virtual scanner devices, rendering, event sequences and file pages
+//
+//  Created by Ngonidzashe Mangudya on 2025/10/22.
+//  Copyright (c) 2025 Codecraft Solutions. All rights reserved.
+//
+
+#![cfg(feature = "virtual")]
+
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use lopdf::{dictionary, Document, Object, Stream};
+use papyr_core::backends::virtual_scanner::VirtualBackend;
+use papyr_core::config::{
+    RegistryBuilder, RegistryConfig, ScanDefaults, VirtualConfig, VirtualDevice,
+};
+use papyr_core::models::*;
+use papyr_core::registry::default_backends;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn backend(devices: Vec<VirtualDevice>) -> VirtualBackend {
+    VirtualBackend::new(&VirtualConfig {
+        enabled: true,
+        devices,
+        page_time_ms: 0,
+    })
+}
+
+fn id(local: &str) -> DeviceId {
+    DeviceId::new(Backend::Virtual, local)
+}
+
+fn config(source: ScanSource, dpi: u32, color_mode: ColorMode) -> ScanConfig {
+    ScanConfig {
+        source,
+        dpi,
+        color_mode,
+        ..ScanDefaults::default().to_config()
+    }
+}
+
+fn scan(backend: &VirtualBackend, local: &str, cfg: ScanConfig) -> Vec<ScanEvent> {
+    let session = backend.start_scan(&id(local), cfg).expect("start scan");
+    ScanEvents::new(session).collect()
+}
+
+/// Each page's metadata and decoded image.
+fn pages(events: &[ScanEvent]) -> Vec<(PageMeta, DynamicImage)> {
+    let mut pages = Vec::new();
+    let mut data = Vec::new();
+    for event in events {
+        match event {
+            ScanEvent::PageStarted(_) => data.clear(),
+            ScanEvent::PageData(bytes) => data.extend_from_slice(bytes),
+            ScanEvent::PageComplete(meta) => {
+                let image = image::load_from_memory_with_format(&data, ImageFormat::Pnm)
+                    .expect("page is a PNM image");
+                pages.push((meta.clone(), image));
+            }
+            _ => {}
+        }
+    }
+    pages
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("papyr_virtual_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn test_default_devices() {
+    let backend = backend(Vec::new());
+    let devices = backend.enumerate();
+    let ids: Vec<String> = devices.iter().map(|device| device.id.to_string()).collect();
+    assert_eq!(ids, ["virtual_flatbed", "virtual_adf", "virtual_duplex"]);
+    assert!(devices
+        .iter()
+        .all(|device| device.connection == ConnectionKind::Virtual
+            && device.backend == Backend::Virtual));
+
+    let flatbed = backend.capabilities(&id("flatbed")).unwrap();
+    assert_eq!(flatbed.sources, [ScanSource::Flatbed]);
+    assert!(!flatbed.supports_duplex);
+    assert!(flatbed.supports_dpi(300) && flatbed.supports_dpi(1200));
+    assert!(!flatbed.supports_dpi(2400));
+
+    let duplex = backend.capabilities(&id("duplex")).unwrap();
+    assert!(duplex.supports_duplex);
+    assert_eq!(
+        duplex.max_area(ScanSource::Adf),
+        Some(PageSize::new(215.9, 355.6))
+    );
+    assert!(duplex.page_sizes.contains(&PageFormat::Legal));
+
+    assert!(matches!(
+        backend.capabilities(&id("missing")),
+        Err(PapyrError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_flatbed_chart() {
+    let backend = backend(Vec::new());
+    let events = scan(
+        &backend,
+        "flatbed",
+        config(ScanSource::Flatbed, 100, ColorMode::Color),
+    );
+
+    assert!(matches!(
+        events[..3],
+        [
+            ScanEvent::Status(ScanStatus::WarmingUp),
+            ScanEvent::Status(ScanStatus::Scanning),
+            ScanEvent::PageStarted(0),
+        ]
+    ));
+    assert!(matches!(events.last(), Some(ScanEvent::JobComplete)));
+    let last_progress = events.iter().rev().find_map(|event| match event {
+        ScanEvent::Progress { percent, .. } => *percent,
+        _ => None,
+    });
+    assert_eq!(last_progress, Some(100.0));
+
+    let pages = pages(&events);
+    assert_eq!(pages.len(), 1);
+    let (meta, image) = &pages[0];
+    // A4 at 100 dpi
+    assert_eq!((meta.width_px, meta.height_px), (827, 1169));
+    assert_eq!((meta.color_mode, meta.bit_depth), (ColorMode::Color, 8));
+    let image = image.to_rgb8();
+    assert_eq!(image.dimensions(), (827, 1169));
+
+    // White margin, black frame, then the red colour bar
+    assert_eq!(image.get_pixel(5, 5), &Rgb([255, 255, 255]));
+    assert_eq!(image.get_pixel(21, 500), &Rgb([0, 0, 0]));
+    assert_eq!(image.get_pixel(551, 106), &Rgb([255, 0, 0]));
+}
+
+#[test]
+fn test_sample_formats() {
+    let backend = backend(Vec::new());
+
+    let mut gray = config(ScanSource::Flatbed, 75, ColorMode::Gray);
+    gray.bit_depth = Some(16);
+    let (meta, image) = pages(&scan(&backend, "flatbed", gray)).remove(0);
+    assert_eq!(meta.bit_depth, 16);
+    assert!(matches!(image, DynamicImage::ImageLuma16(_)));
+
+    let events = scan(
+        &backend,
+        "flatbed",
+        config(ScanSource::Flatbed, 75, ColorMode::Bw),
+    );
+    let data: Vec<u8> = events
+        .iter()
+        .filter_map(|event| match event {
+            ScanEvent::PageData(bytes) => Some(bytes.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let header = b"P4\n620 877\n";
+    assert!(data.starts_with(header));
+    assert_eq!(data.len(), header.len() + 620usize.div_ceil(8) * 877);
+    let (meta, image) = pages(&events).remove(0);
+    assert_eq!(meta.bit_depth, 1);
+    // The black frame stays black, the margin white
+    let image = image.to_luma8();
+    assert_eq!(image.get_pixel(16, 400).0, [0]);
+    assert_eq!(image.get_pixel(4, 400).0, [255]);
+}
+
+#[test]
+fn test_area_and_independent_dpi() {
+    let backend = backend(Vec::new());
+    let mut cfg = config(ScanSource::Flatbed, 200, ColorMode::Gray);
+    cfg.dpi_y = Some(100);
+    cfg.area = Some(ScanArea {
+        x_mm: 12.0,
+        y_mm: 20.0,
+        width_mm: 50.0,
+        height_mm: 14.0,
+    });
+
+    let (meta, image) = pages(&scan(&backend, "flatbed", cfg)).remove(0);
+    assert_eq!((meta.width_px, meta.height_px), (394, 55));
+    assert_eq!((meta.dpi, meta.dpi_y), (200, 100));
+    // The area starts on the white colour bar, then yellow
+    let image = image.to_luma8();
+    assert_eq!(image.get_pixel(0, 20).0, [255]);
+    assert_eq!(image.get_pixel(250, 20).0, [225]);
+}
+
+#[test]
+fn test_feeder_page_counts() {
+    let backend = backend(Vec::new());
+    let count = |local: &str, cfg: ScanConfig| pages(&scan(&backend, local, cfg)).len();
+
+    assert_eq!(
+        count("adf", config(ScanSource::Adf, 50, ColorMode::Gray)),
+        3
+    );
+    assert_eq!(
+        count("duplex", config(ScanSource::AdfDuplex, 50, ColorMode::Gray)),
+        6
+    );
+    let mut duplex = config(ScanSource::Adf, 50, ColorMode::Gray);
+    duplex.duplex = true;
+    assert_eq!(count("duplex", duplex), 6);
+    let mut limited = config(ScanSource::Adf, 50, ColorMode::Gray);
+    limited.max_pages = Some(2);
+    assert_eq!(count("adf", limited), 2);
+
+    // The flatbed has no feeder
+    assert!(matches!(
+        backend.start_scan(&id("flatbed"), config(ScanSource::Adf, 50, ColorMode::Gray)),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_feeder_empty_and_jam() {
+    let mut empty = VirtualDevice::new("empty", "Empty", [ScanSource::Adf]);
+    empty.sheets = 0;
+    let mut jamming = VirtualDevice::new("jam", "Jamming", [ScanSource::Adf]);
+    jamming.jam_at_page = Some(1);
+    let backend = backend(vec![empty, jamming]);
+
+    assert!(matches!(
+        backend.start_scan(&id("empty"), config(ScanSource::Adf, 50, ColorMode::Gray)),
+        Err(PapyrError::FeederEmpty {
+            backend: Backend::Virtual,
+            ..
+        })
+    ));
+
+    let events = scan(
+        &backend,
+        "jam",
+        config(ScanSource::Adf, 50, ColorMode::Gray),
+    );
+    assert_eq!(pages(&events).len(), 1);
+    assert!(matches!(
+        events.last(),
+        Some(ScanEvent::Error(PapyrError::PaperJam { .. }))
+    ));
+}
+
+#[test]
+fn test_cancel() {
+    let backend = VirtualBackend::new(&VirtualConfig {
+        enabled: true,
+        devices: Vec::new(),
+        page_time_ms: 10_000,
+    });
+    let session = backend
+        .start_scan(&id("adf"), config(ScanSource::Adf, 50, ColorMode::Gray))
+        .unwrap();
+    let cancel = session.cancel_handle().expect("cancel handle");
+
+    let started = Instant::now();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        cancel.cancel();
+    });
+    let events: Vec<ScanEvent> = ScanEvents::new(session).collect();
+    canceller.join().unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(matches!(events.last(), Some(ScanEvent::Cancelled)));
+    assert!(pages(&events).is_empty());
+}
+
+#[test]
+fn test_noise_and_skew() {
+    let scan_with = |noise: f32, skew_degrees: f32| {
+        let mut device = VirtualDevice::new("flatbed", "Flatbed", [ScanSource::Flatbed]);
+        device.noise = noise;
+        device.skew_degrees = skew_degrees;
+        let backend = backend(vec![device]);
+        let events = scan(
+            &backend,
+            "flatbed",
+            config(ScanSource::Flatbed, 50, ColorMode::Gray),
+        );
+        pages(&events).remove(0).1.to_luma8()
+    };
+
+    let clean = scan_with(0.0, 0.0);
+    assert_eq!(clean, scan_with(0.0, 0.0));
+
+    // Noise is repeatable but changes some pixels
+    let noisy = scan_with(0.05, 0.0);
+    assert_eq!(noisy, scan_with(0.05, 0.0));
+    let changed = clean
+        .pixels()
+        .zip(noisy.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    let total = (clean.width() * clean.height()) as usize;
+    assert!(changed > total / 100 && changed < total / 10, "{}", changed);
+
+    // The frame's top edge slopes once skewed
+    let skewed = scan_with(0.0, 3.0);
+    let frame_row =
+        |image: &image::GrayImage, x: u32| (0..60).find(|y| image.get_pixel(x, *y).0[0] < 64);
+    assert_eq!(frame_row(&clean, 40), frame_row(&clean, 370));
+    assert_ne!(frame_row(&skewed, 40), frame_row(&skewed, 370));
+}
+
+#[test]
+fn test_image_file_pages() {
+    // Left half red, right half blue
+    let path = temp_path("page.png");
+    RgbImage::from_fn(40, 60, |x, _| {
+        if x < 20 {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([0, 0, 255])
+        }
+    })
+    .save(&path)
+    .unwrap();
+
+    let mut device = VirtualDevice::new("files", "Files", [ScanSource::Flatbed, ScanSource::Adf]);
+    device.files = vec![path.clone(), path.clone()];
+    let backend = backend(vec![device]);
+
+    let (meta, image) = pages(&scan(
+        &backend,
+        "files",
+        config(ScanSource::Flatbed, 50, ColorMode::Color),
+    ))
+    .remove(0);
+    let image = image.to_rgb8();
+    assert_eq!(image.get_pixel(10, 100), &Rgb([255, 0, 0]));
+    assert_eq!(image.get_pixel(meta.width_px - 10, 100), &Rgb([0, 0, 255]));
+
+    // The feeder scans every file
+    let events = scan(
+        &backend,
+        "files",
+        config(ScanSource::Adf, 50, ColorMode::Color),
+    );
+    assert_eq!(pages(&events).len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_pdf_file_pages() {
+    // Two pages: a black image, then a white one
+    let path = temp_path("document.pdf");
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let mut kids: Vec<Object> = Vec::new();
+    for value in [0u8, 255] {
+        let image_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 4,
+                "Height" => 4,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![value; 16],
+        ));
+        let content_id = doc.add_object(Stream::new(
+            dictionary! {},
+            b"q 595 0 0 842 0 0 cm /Im0 Do Q".to_vec(),
+        ));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            "Contents" => content_id,
+            "Resources" => dictionary! { "XObject" => dictionary! { "Im0" => image_id } },
+        });
+        kids.push(page_id.into());
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 2 }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(&path).unwrap();
+
+    let mut device = VirtualDevice::new("pdf", "PDF", [ScanSource::Adf, ScanSource::AdfDuplex]);
+    device.files = vec![path.clone()];
+    let backend = backend(vec![device]);
+
+    let pages = pages(&scan(
+        &backend,
+        "pdf",
+        config(ScanSource::Adf, 50, ColorMode::Gray),
+    ));
+    let centres: Vec<u8> = pages
+        .iter()
+        .map(|(meta, image)| {
+            image
+                .to_luma8()
+                .get_pixel(meta.width_px / 2, meta.height_px / 2)
+                .0[0]
+        })
+        .collect();
+    assert_eq!(centres, [0, 255]);
+
+    std::fs::remove_file(&path).unwrap();
+
+    // Unreadable files fail the scan before it starts
+    assert!(matches!(
+        backend.start_scan(&id("pdf"), config(ScanSource::Adf, 50, ColorMode::Gray)),
+        Err(PapyrError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_enabled_by_config() {
+    // Without other backends, so nothing browses the network
+    let others = default_backends();
+
+    let virtual_config = RegistryConfig::from_toml(
+        r#"
+        [virtual]
+        enabled = true
+        page_time_ms = 0
+
+        [[virtual.devices]]
+        id = "desk"
+        name = "Desk Scanner"
+        sources = ["Flatbed"]
+        text = "Hello"
+        "#,
+    )
+    .unwrap();
+    assert!(virtual_config.backends().contains(&Backend::Virtual));
+
+    let registry = RegistryBuilder::from_config(RegistryConfig {
+        disabled: others.clone(),
+        ..virtual_config
+    })
+    .build();
+    let devices = registry.list_devices().unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].id.to_string(), "virtual_desk");
+    assert_eq!(devices[0].name, "Desk Scanner");
+
+    let session = registry
+        .start_scan(
+            &devices[0].id,
+            config(ScanSource::Flatbed, 50, ColorMode::Gray),
+        )
+        .unwrap();
+    assert!(matches!(
+        ScanEvents::new(session).last(),
+        Some(ScanEvent::JobComplete)
+    ));
+
+    // The builder turns the default devices on
+    let mut builder = RegistryBuilder::new()
+        .enable(Backend::Virtual)
+        .virtual_page_time(Duration::ZERO);
+    for backend in others {
+        builder = builder.disable(backend);
+    }
+    assert_eq!(builder.build().list_devices().unwrap().len(), 3);
+}
+
+#[test]
+fn test_ffi_scan() {
+    use papyr_core::ffi::*;
+    use std::ffi::{CStr, CString};
+
+    let json = serde_json::json!({
+        "disabled": default_backends(),
+        "virtual": { "enabled": true, "page_time_ms": 0 },
+    });
+    let json = CString::new(json.to_string()).unwrap();
+
+    unsafe {
+        assert_eq!(papyr_init_with_config_json(json.as_ptr()), 0);
+
+        let list = papyr_list_scanners();
+        assert!(!list.is_null());
+        let scanners = std::slice::from_raw_parts((*list).scanners, (*list).count);
+        assert_eq!(scanners.len(), 3);
+        let flatbed = &scanners[0];
+        assert_eq!(CStr::from_ptr(flatbed.id).to_str(), Ok("virtual_flatbed"));
+        assert_eq!(flatbed.backend, 7); // BACKEND_VIRTUAL
+        assert_eq!(flatbed.connection, 3); // CONNECTION_VIRTUAL
+        let device_id = CString::from(CStr::from_ptr(flatbed.id));
+        papyr_free_scanner_list(list);
+
+        let config = CScanConfig {
+            source: 0, // Flatbed
+            duplex: 0,
+            dpi: 50,
+            color_mode: 1, // Gray
+            page_width_mm: 210,
+            page_height_mm: 297,
+        };
+        // Event types, and the last progress as (bytes, expected)
+        let run = |session_id: i32| {
+            assert!(session_id >= 0);
+            let mut event_types = Vec::new();
+            let mut progress = (0, 0);
+            loop {
+                let event = papyr_next_scan_event(session_id);
+                if event.is_null() {
+                    break;
+                }
+                let c_event = &*event;
+                event_types.push(c_event.event_type);
+                if c_event.event_type == 4 {
+                    progress = (c_event.bytes, c_event.expected);
+                }
+                papyr_free_scan_event(event);
+            }
+            (event_types, progress)
+        };
+        let (event_types, progress) = run(papyr_start_scan(device_id.as_ptr(), &config));
+
+        // Status (warming up, scanning), page started ... job complete
+        assert_eq!(event_types[..3], [5, 5, 0]);
+        assert!(event_types.contains(&2));
+        assert_eq!(event_types.last(), Some(&3));
+        // A 413x585 gray PNM
+        let page_bytes = b"P5\n413 585\n255\n".len() as u64 + 413 * 585;
+        assert_eq!(progress, (page_bytes, page_bytes as i64));
+
+        // The v2 config keeps sub-millimetre sizes: 25.6 mm is 50 pixels at
+        // 50 dpi, where 26 mm would be 51
+        let config = CScanConfigV2 {
+            source: 0,
+            duplex: 0,
+            dpi: 50,
+            color_mode: 1,
+            page_width_mm: 215.9,
+            page_height_mm: 279.4,
+            dpi_y: 0,
+            bit_depth: 0,
+            max_pages: 0,
+            has_area: 1,
+            area_x_mm: 10.5,
+            area_y_mm: 20.5,
+            area_width_mm: 25.6,
+            area_height_mm: 12.9,
+        };
+        let (event_types, progress) = run(papyr_start_scan_v2(device_id.as_ptr(), &config, 0));
+        assert_eq!(event_types.last(), Some(&3));
+        let page_bytes = b"P5\n50 25\n255\n".len() as u64 + 50 * 25;
+        assert_eq!(progress, (page_bytes, page_bytes as i64));
+
+        papyr_cleanup();
+    }
+}